@@ -0,0 +1,21 @@
+#![no_main]
+
+use core::num::NonZeroUsize;
+
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+use negentropy::storage::copy::Sink as _;
+use negentropy::storage::sink::chunked::Chunked;
+use negentropy::storage::sink::memory::Memory;
+
+const MIME: &str = "application/vnd.negentropy.chunked+json";
+
+fuzz_target!(|stored: &[u8]| {
+    let key = "fuzz".to_owned();
+    let mut memory = Memory::default();
+    block_on(memory.put_bytes_copy(&key, MIME.to_owned(), stored.to_vec())).ok();
+    let chunked = Chunked::new(NonZeroUsize::MIN, memory);
+
+    let _ = block_on(chunked.get_bytes_copy(&key));
+    let _ = block_on(chunked.get_range_copy(&key, 0..u64::MAX));
+});
@@ -0,0 +1,28 @@
+#![no_main]
+
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+use negentropy::storage::copy::Sink as _;
+use negentropy::storage::sink::compressed::{Compressed, Deflate};
+use negentropy::storage::sink::memory::Memory;
+
+const MIME: &str = "application/octet-stream";
+
+fuzz_target!(|stored: &[u8]| {
+    let mut memory = Memory::default();
+    block_on(memory.put_bytes_copy(&"raw".to_owned(), MIME.to_owned(), stored.to_vec())).ok();
+
+    // Most inputs are no envelope at all, wrap them so the inflate path is reached too.
+    if let [high, low, body @ ..] = stored {
+        let mut envelope = b"NGC1\x07deflate".to_vec();
+        envelope.extend(u64::from(u16::from_be_bytes([*high, *low])).to_be_bytes());
+        envelope.extend_from_slice(body);
+        block_on(memory.put_bytes_copy(&"deflate".to_owned(), MIME.to_owned(), envelope)).ok();
+    }
+    let compressed = Compressed::new(Deflate::default(), memory);
+
+    for key in ["raw".to_owned(), "deflate".to_owned()] {
+        let _ = block_on(compressed.get_bytes_copy(&key));
+        let _ = block_on(compressed.head_copy(&key));
+    }
+});
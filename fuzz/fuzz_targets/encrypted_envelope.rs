@@ -0,0 +1,19 @@
+#![no_main]
+
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+use negentropy::storage::copy::Sink as _;
+use negentropy::storage::sink::encrypted::{Encrypted, KeyRing};
+use negentropy::storage::sink::memory::Memory;
+
+const MIME: &str = "application/octet-stream";
+
+fuzz_target!(|stored: &[u8]| {
+    let key = "fuzz".to_owned();
+    let mut memory = Memory::default();
+    block_on(memory.put_bytes_copy(&key, MIME.to_owned(), stored.to_vec())).ok();
+    let encrypted = Encrypted::new(KeyRing::new("k1", &[7; 32]), memory);
+
+    assert!(block_on(encrypted.get_bytes_copy(&key)).is_err());
+    let _ = block_on(encrypted.head_copy(&key));
+});
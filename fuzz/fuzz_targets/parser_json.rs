@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use negentropy::storage::copy::parser::{GuardedParser as _, Json};
+use serde_json::Value;
+
+fuzz_target!(|content: &[u8]| {
+    if let Ok(value) = Json.deserialize_guarded::<Value>(content) {
+        let serialized = Json.serialize_guarded(&value).expect("reserialize decoded json");
+        assert_eq!(Json.deserialize_guarded::<Value>(&serialized).ok(), Some(value));
+    }
+});
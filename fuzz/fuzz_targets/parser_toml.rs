@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use negentropy::storage::copy::parser::{GuardedParser as _, Toml};
+use serde_json::Value;
+
+fuzz_target!(|content: &[u8]| {
+    let _ = Toml.deserialize_guarded::<Value>(content);
+});
@@ -0,0 +1,113 @@
+use core::hash::{BuildHasher, Hasher};
+
+const ROTATE: u32 = 5;
+const MULTIPLIER: u64 = 0x517c_c1b7_2722_0a95;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    #[inline]
+    #[must_use]
+    pub const fn with_seed(seed: u64) -> Self {
+        Self { hash: seed }
+    }
+
+    const fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(ROTATE) ^ word).wrapping_mul(MULTIPLIER);
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let (chunks, remainder) = bytes.as_chunks::<8>();
+        for &chunk in chunks {
+            self.add(u64::from_le_bytes(chunk));
+        }
+
+        let mut rest = [0; 8];
+        if let Some(target) = rest
+            .get_mut(..remainder.len())
+            .filter(|_| !remainder.is_empty())
+        {
+            target.copy_from_slice(remainder);
+            self.add(u64::from_le_bytes(rest));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, value: u8) {
+        self.add(u64::from(value));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, value: u32) {
+        self.add(u64::from(value));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, value: u64) {
+        self.add(value);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, value: usize) {
+        self.add(value as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FxBuildHasher {
+    seed: u64,
+}
+
+impl FxBuildHasher {
+    #[inline]
+    #[must_use]
+    pub const fn with_seed(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl BuildHasher for FxBuildHasher {
+    type Hasher = FxHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> Self::Hasher {
+        FxHasher::with_seed(self.seed)
+    }
+}
+
+pub type HashMap<KEY, VALUE> = std::collections::HashMap<KEY, VALUE, FxBuildHasher>;
+pub type HashSet<KEY> = std::collections::HashSet<KEY, FxBuildHasher>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(seed: u64, value: &str) -> u64 {
+        FxBuildHasher::with_seed(seed).hash_one(value)
+    }
+
+    #[test]
+    fn reproducible() {
+        assert_eq!(hash(0, "instances/welcome"), hash(0, "instances/welcome"));
+        assert_ne!(hash(0, "instances/welcome"), hash(1, "instances/welcome"));
+        assert_ne!(hash(0, "a"), hash(0, "b"));
+
+        let order = |seed| {
+            let mut set = HashSet::with_hasher(FxBuildHasher::with_seed(seed));
+            set.extend((0..64).map(|index| format!("key/{index}")));
+            set.into_iter().collect::<Vec<_>>()
+        };
+        assert_eq!(order(7), order(7), "iteration order must be reproducible");
+    }
+}
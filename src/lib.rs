@@ -21,29 +21,39 @@
 )]
 #![expect(clippy::exhaustive_structs, reason = "Accept breaking struct")]
 
+#[cfg(feature = "deterministic")]
+pub mod hash;
+pub mod prelude;
 pub mod storage;
 
-#[cfg(not(feature = "prod"))]
+use std::borrow::Cow;
+#[cfg(not(any(feature = "prod", feature = "deterministic")))]
 pub use std::collections::{HashMap, HashSet};
 
-#[cfg(feature = "prod")]
+#[cfg(all(feature = "prod", not(feature = "deterministic")))]
 pub use gxhash::{HashMap, HashSet};
+#[cfg(feature = "deterministic")]
+pub use hash::{HashMap, HashSet};
 use storage::DKey;
 
 #[derive(Debug, Clone)]
 pub enum InstanceKey {
     Welcome,
     Initialize(String),
+    Record(String),
     Alive(String, String),
 }
 
 impl DKey for InstanceKey {
     #[inline]
-    fn name(&self) -> String {
+    fn name(&self) -> Cow<'_, str> {
         match *self {
-            Self::Welcome => "instances/welcome".to_owned(),
-            Self::Initialize(ref id) => format!("instances/{id}/new"),
-            Self::Alive(ref id, ref timestamp) => format!("instances/{id}/alive/{timestamp}"),
+            Self::Welcome => Cow::Borrowed("instances/welcome"),
+            Self::Initialize(ref id) => Cow::Owned(format!("instances/{id}/new")),
+            Self::Record(ref id) => Cow::Owned(format!("instances/{id}/record")),
+            Self::Alive(ref id, ref timestamp) => {
+                Cow::Owned(format!("instances/{id}/alive/{timestamp}"))
+            }
         }
     }
 }
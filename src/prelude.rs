@@ -0,0 +1,53 @@
+pub use crate::storage::cache::lru::Lru;
+pub use crate::storage::clock::{Clock, SystemClock};
+#[cfg(feature = "copy")]
+pub use crate::storage::copy::direct::DKeyWithParserCopy;
+#[cfg(feature = "copy")]
+pub use crate::storage::copy::instance::{Configuration, Instance, InstanceIdPolicy};
+#[cfg(feature = "copy")]
+pub use crate::storage::copy::parser::{GuardedParser, Json, Parser, Toml};
+#[cfg(feature = "copy")]
+pub use crate::storage::copy::{Cache, ParserWhere, Required, Sink, ValueWhere};
+pub use crate::storage::layer::Layer;
+pub use crate::storage::sink::memory::Memory;
+pub use crate::storage::sink::s3::S3;
+pub use crate::storage::{DKey, DKeyWhere, PrefixedKey};
+pub use crate::InstanceKey;
+
+#[cfg(all(test, feature = "copy"))]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct Note {
+        body: String,
+    }
+
+    #[tokio::test]
+    async fn example_with_prelude_only() {
+        let lru = Lru::new(NonZeroUsize::new(8).unwrap(), Memory::default());
+        let mut instance = Instance::new(lru, Configuration::default()).await.unwrap();
+        let key = "notes/first".to_owned();
+        let note = Note {
+            body: "hello".to_owned(),
+        };
+
+        instance
+            .cache()
+            .put_object_copy(&DKeyWithParserCopy::new(&key, &Json), &note)
+            .await
+            .unwrap();
+        assert_eq!(
+            instance
+                .cache()
+                .get_object_copy::<Note, _, _>(&DKeyWithParserCopy::new(&key, &Json))
+                .await
+                .unwrap(),
+            Some(note)
+        );
+    }
+}
@@ -1,10 +1,28 @@
 pub mod cache;
+pub mod clock;
+pub mod codec;
+pub mod compat;
 #[cfg(feature = "copy")]
 pub mod copy;
+pub mod cost;
+pub mod health;
+pub mod intern;
+pub mod layer;
+pub mod meta;
+pub mod metrics;
+pub mod progress;
 pub mod sink;
+pub mod task;
+#[cfg(feature = "tracing")]
+pub mod telemetry;
+pub mod tls;
 
 use core::error::Error;
 use core::fmt;
+use core::ops::Range;
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::time::SystemTime;
 
 use crate::HashSet;
 
@@ -16,7 +34,46 @@ pub trait DeserializeWhere<RETURN, ERROR> = Send + Sync + Fn(&[u8]) -> Result<RE
 pub type ListKeyObjects = HashSet<String>;
 
 pub trait DKey {
-    fn name(&self) -> String;
+    fn name(&self) -> Cow<'_, str>;
+}
+
+pub trait Throttled {
+    fn is_throttled(&self) -> bool;
+}
+
+impl DKey for String {
+    #[inline]
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+pub struct PrefixedKey<'key, DKEY> {
+    prefix: &'key str,
+    key: &'key DKEY,
+}
+
+impl<'key, DKEY> PrefixedKey<'key, DKEY> {
+    pub(crate) const fn new(prefix: &'key str, key: &'key DKEY) -> Self {
+        Self { prefix, key }
+    }
+}
+
+impl<DKEY> DKey for PrefixedKey<'_, DKEY>
+where
+    DKEY: DKey,
+{
+    #[inline]
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("{}{}", self.prefix, self.key.name()))
+    }
+}
+
+/// `x-amz-request-id` and `x-amz-id-2` of the response a failed S3 request got back.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestIds {
+    pub request_id: Option<String>,
+    pub extended_request_id: Option<String>,
 }
 
 #[derive(Debug)]
@@ -26,25 +83,64 @@ pub enum S3Error {
         operation: String,
         bucket: String,
         internal: String,
+        request: Option<Box<RequestIds>>,
     },
     S3Object {
         operation: String,
         key: String,
         internal: String,
+        request: Option<Box<RequestIds>>,
     },
     S3List {
         operation: String,
         prefix: String,
         internal: Option<String>,
+        request: Option<Box<RequestIds>>,
     },
     S3Exists {
         operation: String,
         key: String,
         internal: String,
+        request: Option<Box<RequestIds>>,
     },
     S3ListHandle,
+    #[deprecated(note = "a missing object is `Ok(None)` on every get, never an error")]
     NotExistsObject(String),
+    NotExistsBucket(String),
+    PermissionDenied {
+        operation: String,
+        key: String,
+        request: Option<Box<RequestIds>>,
+    },
+    Throttled {
+        operation: String,
+        key: String,
+        request: Option<Box<RequestIds>>,
+    },
     EnvConfig(String),
+    Layer(LayerError),
+    Locked {
+        key: String,
+        until: SystemTime,
+    },
+    Tls(TlsError),
+}
+
+impl S3Error {
+    /// Identifiers of the failed AWS request, to quote when opening a support case.
+    #[inline]
+    #[must_use]
+    pub fn request_ids(&self) -> Option<&RequestIds> {
+        match *self {
+            Self::S3Bucket { ref request, .. }
+            | Self::S3Object { ref request, .. }
+            | Self::S3List { ref request, .. }
+            | Self::S3Exists { ref request, .. }
+            | Self::PermissionDenied { ref request, .. }
+            | Self::Throttled { ref request, .. } => request.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for S3Error {
@@ -60,6 +156,13 @@ impl fmt::Display for S3Error {
 
 impl Error for S3Error {}
 
+impl Throttled for S3Error {
+    #[inline]
+    fn is_throttled(&self) -> bool {
+        matches!(*self, Self::Throttled { .. })
+    }
+}
+
 impl From<ParserError> for S3Error {
     #[inline]
     fn from(value: ParserError) -> Self {
@@ -67,9 +170,170 @@ impl From<ParserError> for S3Error {
     }
 }
 
+impl From<LayerError> for S3Error {
+    #[inline]
+    fn from(value: LayerError) -> Self {
+        Self::Layer(value)
+    }
+}
+
+impl From<TlsError> for S3Error {
+    #[inline]
+    fn from(value: TlsError) -> Self {
+        Self::Tls(value)
+    }
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+    Serde(ParserError),
+    Url(String),
+    Request {
+        operation: String,
+        key: String,
+        internal: String,
+    },
+    Status {
+        operation: String,
+        key: String,
+        status: u16,
+    },
+    Throttled {
+        operation: String,
+        key: String,
+    },
+    Layer(LayerError),
+    Tls(TlsError),
+}
+
+impl fmt::Display for HttpError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Error for HttpError {}
+
+impl Throttled for HttpError {
+    #[inline]
+    fn is_throttled(&self) -> bool {
+        matches!(*self, Self::Throttled { .. })
+    }
+}
+
+impl From<ParserError> for HttpError {
+    #[inline]
+    fn from(value: ParserError) -> Self {
+        Self::Serde(value)
+    }
+}
+
+impl From<LayerError> for HttpError {
+    #[inline]
+    fn from(value: LayerError) -> Self {
+        Self::Layer(value)
+    }
+}
+
+impl From<TlsError> for HttpError {
+    #[inline]
+    fn from(value: TlsError) -> Self {
+        Self::Tls(value)
+    }
+}
+
+#[cfg(feature = "postgres")]
+#[derive(Debug)]
+pub enum PostgresError {
+    Serde(ParserError),
+    Table(String),
+    Query {
+        operation: String,
+        key: String,
+        internal: String,
+    },
+    Unsupported {
+        operation: String,
+        key: String,
+    },
+    Layer(LayerError),
+}
+
+#[cfg(feature = "postgres")]
+impl fmt::Display for PostgresError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Serde(ref err) => write!(f, "ParsePostgres: {err}"),
+            Self::Table(ref table) => write!(f, "Invalid Postgres table name: {table}"),
+            Self::Query {
+                ref operation,
+                ref key,
+                ref internal,
+            } => write!(f, "Postgres {operation} on {key}: {internal}"),
+            Self::Unsupported {
+                ref operation,
+                ref key,
+            } => write!(f, "Postgres does not support {operation} on {key}"),
+            Self::Layer(ref err) => write!(f, "LayerPostgres: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl Error for PostgresError {}
+
+#[cfg(feature = "postgres")]
+impl From<ParserError> for PostgresError {
+    #[inline]
+    fn from(value: ParserError) -> Self {
+        Self::Serde(value)
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<LayerError> for PostgresError {
+    #[inline]
+    fn from(value: LayerError) -> Self {
+        Self::Layer(value)
+    }
+}
+
+#[derive(Debug)]
+pub enum TlsError {
+    Read { path: PathBuf, internal: String },
+    Certificate { path: PathBuf, internal: String },
+    Config(String),
+    Disabled,
+}
+
+impl fmt::Display for TlsError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Read {
+                ref path,
+                ref internal,
+            } => write!(f, "Cannot read {}: {internal}", path.display()),
+            Self::Certificate {
+                ref path,
+                ref internal,
+            } => write!(f, "Invalid certificate in {}: {internal}", path.display()),
+            Self::Config(ref internal) => write!(f, "Invalid TLS configuration: {internal}"),
+            Self::Disabled => write!(f, "TLS and connection pool settings need the `tls` feature"),
+        }
+    }
+}
+
+impl Error for TlsError {}
+
 #[derive(Debug)]
 pub enum MemoryError {
     Serde(ParserError),
+    Layer(LayerError),
+    Locked { key: String, until: SystemTime },
+    NotExists { key: String },
 }
 
 impl fmt::Display for MemoryError {
@@ -81,6 +345,9 @@ impl fmt::Display for MemoryError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Self::Serde(ref err) => write!(f, "ParseMemory: {err}"),
+            Self::Layer(ref err) => write!(f, "LayerMemory: {err}"),
+            Self::Locked { ref key, .. } => write!(f, "LockedMemory: {key}"),
+            Self::NotExists { ref key } => write!(f, "NotExistsMemory: {key}"),
         }
     }
 }
@@ -92,11 +359,45 @@ impl From<ParserError> for MemoryError {
     }
 }
 
+impl From<LayerError> for MemoryError {
+    #[inline]
+    fn from(value: LayerError) -> Self {
+        Self::Layer(value)
+    }
+}
+
 impl Error for MemoryError {}
 
+impl Throttled for MemoryError {
+    #[inline]
+    fn is_throttled(&self) -> bool {
+        false
+    }
+}
+
 #[derive(Debug)]
 pub enum ParserError {
-    Serde { internal: String },
+    Serde {
+        internal: String,
+    },
+    UnknownMime(String),
+    Corrupted(String),
+    Validation(Vec<ValidationError>),
+    Panicked {
+        operation: String,
+        message: String,
+    },
+    PayloadTooLarge {
+        dimension: String,
+        size: u64,
+        limit: u64,
+    },
+    Context {
+        backend: String,
+        operation: String,
+        key: String,
+        internal: Box<ParserError>,
+    },
 }
 
 impl fmt::Display for ParserError {
@@ -108,17 +409,271 @@ impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Self::Serde { ref internal } => write!(f, "Can not serde : {internal}"),
+            Self::UnknownMime(ref mime) => write!(f, "No parser for mime {mime:?}"),
+            Self::Corrupted(ref reason) => write!(f, "Corrupted payload: {reason}"),
+            Self::Panicked {
+                ref operation,
+                ref message,
+            } => write!(f, "Parser panicked on {operation} : {message}"),
+            Self::PayloadTooLarge {
+                ref dimension,
+                size,
+                limit,
+            } => write!(f, "Payload has {size} {dimension}, limit is {limit}"),
+            Self::Validation(ref errors) => {
+                write!(f, "Invalid payload:")?;
+                for error in errors {
+                    write!(f, " {error};")?;
+                }
+                Ok(())
+            }
+            Self::Context {
+                ref backend,
+                ref operation,
+                ref key,
+                ref internal,
+            } => write!(f, "{backend} {operation} {key:?}: {internal}"),
         }
     }
 }
 
-impl Error for ParserError {}
+impl Error for ParserError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Self::Context { ref internal, .. } => Some(&**internal),
+            _ => None,
+        }
+    }
+}
+
+impl ParserError {
+    /// Attach where the error happened, an error already in context keeps the innermost one.
+    #[inline]
+    #[must_use]
+    pub fn in_context(self, backend: &str, operation: &str, key: &str) -> Self {
+        match self {
+            Self::Context { .. } => self,
+            _ => Self::Context {
+                backend: backend.to_owned(),
+                operation: operation.to_owned(),
+                key: key.to_owned(),
+                internal: Box::new(self),
+            },
+        }
+    }
+
+    /// The error without its context, to match on what actually failed.
+    #[inline]
+    #[must_use]
+    pub fn root(&self) -> &Self {
+        match *self {
+            Self::Context { ref internal, .. } => internal.root(),
+            _ => self,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn key(&self) -> Option<&str> {
+        match *self {
+            Self::Context { ref key, .. } => Some(key),
+            _ => None,
+        }
+    }
+}
+
+pub trait ResultExt<OK> {
+    fn context(self, backend: &str, operation: &str, key: &str) -> Result<OK, ParserError>;
+}
+
+impl<OK> ResultExt<OK> for Result<OK, ParserError> {
+    #[inline]
+    fn context(self, backend: &str, operation: &str, key: &str) -> Self {
+        self.map_err(|err| err.in_context(backend, operation, key))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub pointer: String,
+    pub keyword: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.pointer, self.keyword, self.message)
+    }
+}
+
+impl Error for ValidationError {}
+
+#[derive(Debug)]
+pub enum LayerError {
+    Disk {
+        operation: String,
+        key: String,
+        internal: String,
+    },
+    Crypto {
+        operation: String,
+        key: String,
+        internal: String,
+    },
+    Compression {
+        operation: String,
+        key: String,
+        internal: String,
+    },
+    ObjectTooLarge {
+        key: String,
+        size: u64,
+        limit: u64,
+    },
+    Queue {
+        operation: String,
+        key: String,
+        internal: String,
+    },
+}
+
+impl fmt::Display for LayerError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Disk {
+                ref operation,
+                ref key,
+                ref internal,
+            } => write!(f, "Disk {operation} on {key}: {internal}"),
+            Self::Crypto {
+                ref operation,
+                ref key,
+                ref internal,
+            } => write!(f, "Crypto {operation} on {key}: {internal}"),
+            Self::Compression {
+                ref operation,
+                ref key,
+                ref internal,
+            } => write!(f, "Compression {operation} on {key}: {internal}"),
+            Self::ObjectTooLarge {
+                ref key,
+                size,
+                limit,
+            } => write!(f, "ObjectTooLarge {key}: {size} bytes over {limit}"),
+            Self::Queue {
+                ref operation,
+                ref key,
+                ref internal,
+            } => write!(f, "Queue {operation} on {key}: {internal}"),
+        }
+    }
+}
+
+impl Error for LayerError {}
+
+#[derive(Debug)]
+pub enum TenantError {
+    InvalidId(String),
+    MissingKey(String),
+}
+
+impl fmt::Display for TenantError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidId(ref id) => write!(f, "Invalid tenant id: {id:?}"),
+            Self::MissingKey(ref id) => write!(f, "No encryption key for tenant: {id:?}"),
+        }
+    }
+}
+
+impl Error for TenantError {}
+
+#[derive(Debug)]
+pub enum AnyError {
+    S3(S3Error),
+    Memory(MemoryError),
+    #[cfg(feature = "http")]
+    Http(HttpError),
+    Parser(ParserError),
+    Layer(LayerError),
+}
+
+impl fmt::Display for AnyError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::S3(ref err) => write!(f, "S3Error: {err}"),
+            Self::Memory(ref err) => write!(f, "MemoryError: {err}"),
+            #[cfg(feature = "http")]
+            Self::Http(ref err) => write!(f, "HttpError: {err}"),
+            Self::Parser(ref err) => write!(f, "ParserError: {err}"),
+            Self::Layer(ref err) => write!(f, "LayerError: {err}"),
+        }
+    }
+}
+
+impl Error for AnyError {}
+
+impl Throttled for AnyError {
+    #[inline]
+    fn is_throttled(&self) -> bool {
+        match *self {
+            Self::S3(ref err) => err.is_throttled(),
+            Self::Memory(ref err) => err.is_throttled(),
+            #[cfg(feature = "http")]
+            Self::Http(ref err) => err.is_throttled(),
+            Self::Parser(_) | Self::Layer(_) => false,
+        }
+    }
+}
+
+impl From<S3Error> for AnyError {
+    #[inline]
+    fn from(value: S3Error) -> Self {
+        Self::S3(value)
+    }
+}
+
+impl From<MemoryError> for AnyError {
+    #[inline]
+    fn from(value: MemoryError) -> Self {
+        Self::Memory(value)
+    }
+}
+
+#[cfg(feature = "http")]
+impl From<HttpError> for AnyError {
+    #[inline]
+    fn from(value: HttpError) -> Self {
+        Self::Http(value)
+    }
+}
+
+impl From<ParserError> for AnyError {
+    #[inline]
+    fn from(value: ParserError) -> Self {
+        Self::Parser(value)
+    }
+}
+
+impl From<LayerError> for AnyError {
+    #[inline]
+    fn from(value: LayerError) -> Self {
+        Self::Layer(value)
+    }
+}
 
 #[derive(Debug)]
 pub enum LruError {
     S3(S3Error),
     Memory(MemoryError),
     Parser(ParserError),
+    Layer(LayerError),
+    Any(Box<AnyError>),
 }
 
 impl fmt::Display for LruError {
@@ -132,6 +687,20 @@ impl fmt::Display for LruError {
             Self::S3(ref err) => write!(f, "LruError: {err}"),
             Self::Parser(ref err) => write!(f, "ParserError: {err}"),
             Self::Memory(ref err) => write!(f, "MemoryError: {err}"),
+            Self::Layer(ref err) => write!(f, "LayerError: {err}"),
+            Self::Any(ref err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Throttled for LruError {
+    #[inline]
+    fn is_throttled(&self) -> bool {
+        match *self {
+            Self::S3(ref err) => err.is_throttled(),
+            Self::Memory(ref err) => err.is_throttled(),
+            Self::Any(ref err) => err.is_throttled(),
+            Self::Parser(_) | Self::Layer(_) => false,
         }
     }
 }
@@ -157,20 +726,34 @@ impl From<ParserError> for LruError {
     }
 }
 
-fn radix_key(prefix: &str, key: &String) -> Option<String> {
-    let delimiter = '/';
-    let prefix_len = prefix.len();
-    let (_, radical) = key.split_at(prefix_len);
-    let radical_key = radical.split_once(delimiter);
-
-    match radical_key {
-        None => Some(key.to_owned()),
-        Some((radical_without_suffix, _)) => {
-            if radical_without_suffix.is_empty() {
-                None
-            } else {
-                Some(format!("{prefix}{radical_without_suffix}{delimiter}"))
-            }
-        }
+impl From<LayerError> for LruError {
+    #[inline]
+    fn from(value: LayerError) -> Self {
+        Self::Layer(value)
     }
 }
+
+impl From<AnyError> for LruError {
+    #[inline]
+    fn from(value: AnyError) -> Self {
+        Self::Any(Box::new(value))
+    }
+}
+
+fn radix_key(prefix: &str, key: &str) -> Option<String> {
+    let delimiter = '/';
+    let radical = key.strip_prefix(prefix)?;
+
+    // Same rule as S3 common prefixes: cut right after the first delimiter past the prefix.
+    Some(match radical.find(delimiter) {
+        None => key.to_owned(),
+        Some(index) => format!("{prefix}{}", radical.get(..=index).unwrap_or_default()),
+    })
+}
+
+fn slice_range(value: &[u8], range: &Range<u64>) -> Vec<u8> {
+    let len = value.len();
+    let start = usize::try_from(range.start).unwrap_or(len).min(len);
+    let end = usize::try_from(range.end).unwrap_or(len).clamp(start, len);
+    value.get(start..end).unwrap_or_default().to_vec()
+}
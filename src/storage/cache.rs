@@ -1 +1,5 @@
+pub mod disk;
+pub mod filter;
+pub mod heat;
 pub mod lru;
+pub mod policy;
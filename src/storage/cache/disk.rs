@@ -0,0 +1,346 @@
+use core::num::NonZeroUsize;
+use core::ops::Range;
+use core::time::Duration;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, SeekFrom, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use lru::LruCache;
+use tokio::io::{AsyncReadExt as _, AsyncSeekExt as _};
+use uuid::Uuid;
+
+use crate::storage::layer::Layer;
+use crate::storage::LayerError;
+
+const INDEX_FILE: &str = "index";
+const TMP_SUFFIX: &str = ".tmp";
+
+/// The index file is a journal of `{file}\t{key}` lines, an empty file removes the key.
+/// Every change appends a line, the journal is compacted once it outgrows the cache.
+/// Cached files are written aside then renamed, a crash never leaves a truncated value behind.
+pub struct DiskCache<STORAGE> {
+    root: PathBuf,
+    index: Mutex<LruCache<String, String>>,
+    journal: AtomicUsize,
+    storage: STORAGE,
+}
+
+impl<STORAGE> DiskCache<STORAGE>
+where
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub fn new(root: PathBuf, size: NonZeroUsize, storage: STORAGE) -> Result<Self, LayerError> {
+        fs::create_dir_all(&root).map_err(|err| disk_error("new", &root, &err))?;
+        remove_partial_writes(&root)?;
+        let index = load_index(&root, size)?;
+
+        let cache = Self {
+            root,
+            index: Mutex::new(index),
+            journal: AtomicUsize::new(0),
+            storage,
+        };
+        cache.compact_index(&cache.index())?;
+        Ok(cache)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.index().len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.index().is_empty()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    #[inline]
+    pub fn invalidate(&self, key: &str) -> Result<bool, LayerError> {
+        let mut index = self.index();
+        let Some(file) = index.pop(key) else {
+            return Ok(false);
+        };
+
+        let path = self.root.join(file);
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(disk_error("invalidate", &path, &err)),
+        }
+        self.journal_index(&index, &[("", key)])?;
+        Ok(true)
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn exists_inner(&self, key: &str) -> bool {
+        self.index().contains(key)
+    }
+
+    pub(crate) async fn get_bytes_inner(
+        &self,
+        key: &str,
+        max_age: Option<Duration>,
+    ) -> Result<Option<Vec<u8>>, LayerError> {
+        let Some(file) = self.index().get(key).cloned() else {
+            return Ok(None);
+        };
+        let path = self.root.join(&file);
+        if let Some(max_age) = max_age {
+            let age = tokio::fs::metadata(&path)
+                .await
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| modified.elapsed().unwrap_or_default())
+                .map_err(|err| disk_error("get_bytes", &path, &err))?;
+            if age >= max_age {
+                return Ok(None);
+            }
+        }
+
+        match tokio::fs::read(&path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                self.forget_inner(key, &file)?;
+                Ok(None)
+            }
+            Err(err) => Err(disk_error("get_bytes", &path, &err)),
+        }
+    }
+
+    pub(crate) async fn get_range_inner(
+        &self,
+        key: &str,
+        range: &Range<u64>,
+    ) -> Result<Option<Vec<u8>>, LayerError> {
+        let Some(file) = self.index().get(key).cloned() else {
+            return Ok(None);
+        };
+        let path = self.root.join(file);
+
+        let mut content = match tokio::fs::File::open(&path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(disk_error("get_range", &path, &err)),
+        };
+        let mut value = vec![];
+        content
+            .seek(SeekFrom::Start(range.start))
+            .await
+            .map_err(|err| disk_error("get_range", &path, &err))?;
+        content
+            .take(range.end.saturating_sub(range.start))
+            .read_to_end(&mut value)
+            .await
+            .map_err(|err| disk_error("get_range", &path, &err))?;
+
+        Ok(Some(value))
+    }
+
+    /// Rewrites the whole file, appending in place could leave half of `value` after a crash.
+    pub(crate) async fn append_bytes_inner(
+        &self,
+        key: &str,
+        value: &[u8],
+    ) -> Result<(), LayerError> {
+        let Some(file) = self.index().peek(key).cloned() else {
+            return Ok(());
+        };
+        let path = self.root.join(&file);
+
+        let mut content = match tokio::fs::read(&path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(disk_error("append_bytes", &path, &err)),
+        };
+        content.extend_from_slice(value);
+        self.write_file(&file, &content).await
+    }
+
+    pub(crate) async fn put_bytes_inner(
+        &self,
+        key: String,
+        value: &[u8],
+    ) -> Result<(), LayerError> {
+        if key.contains('\n') {
+            return Ok(());
+        }
+
+        let file = self
+            .index()
+            .peek(&key)
+            .cloned()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        self.write_file(&file, value).await?;
+
+        let mut index = self.index();
+        let evicted = index
+            .push(key.clone(), file.clone())
+            .filter(|(evicted_key, _)| *evicted_key != key);
+        let mut lines = vec![(file.as_str(), key.as_str())];
+        if let Some((ref evicted_key, ref evicted)) = evicted {
+            remove_evicted(&self.root.join(evicted));
+            lines.push(("", evicted_key));
+        }
+
+        self.journal_index(&index, &lines)
+    }
+
+    async fn write_file(&self, file: &str, value: &[u8]) -> Result<(), LayerError> {
+        let tmp = self.root.join(format!("{}{TMP_SUFFIX}", Uuid::new_v4()));
+        let path = self.root.join(file);
+        tokio::fs::write(&tmp, value)
+            .await
+            .map_err(|err| disk_error("put_bytes", &tmp, &err))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|err| disk_error("put_bytes", &path, &err))
+    }
+
+    /// Drops `key` when its file vanished, unless a concurrent write already replaced it.
+    fn forget_inner(&self, key: &str, file: &str) -> Result<(), LayerError> {
+        let mut index = self.index();
+        if index.peek(key).is_some_and(|current| current == file) {
+            index.pop(key);
+            self.journal_index(&index, &[("", key)])?;
+        }
+        Ok(())
+    }
+
+    fn index(&self) -> MutexGuard<'_, LruCache<String, String>> {
+        self.index.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn journal_index(
+        &self,
+        index: &LruCache<String, String>,
+        lines: &[(&str, &str)],
+    ) -> Result<(), LayerError> {
+        if self.journal.fetch_add(lines.len(), Ordering::Relaxed) >= index.cap().get() {
+            return self.compact_index(index);
+        }
+
+        let content = lines
+            .iter()
+            .map(|(file, key)| format!("{file}\t{key}\n"))
+            .collect::<String>();
+        let path = self.root.join(INDEX_FILE);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut journal| journal.write_all(content.as_bytes()))
+            .map_err(|err| disk_error("write_index", &path, &err))
+    }
+
+    fn compact_index(&self, index: &LruCache<String, String>) -> Result<(), LayerError> {
+        let content = index
+            .iter()
+            .rev()
+            .map(|(key, file)| format!("{file}\t{key}\n"))
+            .collect::<String>();
+        let tmp = self.root.join(format!("{INDEX_FILE}{TMP_SUFFIX}"));
+        let path = self.root.join(INDEX_FILE);
+        fs::write(&tmp, content).map_err(|err| disk_error("write_index", &tmp, &err))?;
+        fs::rename(&tmp, &path).map_err(|err| disk_error("write_index", &path, &err))?;
+        self.journal.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl<STORAGE> Layer for DiskCache<STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
+
+#[expect(clippy::single_call_fn, reason = "code readability")]
+fn load_index(root: &Path, size: NonZeroUsize) -> Result<LruCache<String, String>, LayerError> {
+    let mut index = LruCache::new(size);
+    let path = root.join(INDEX_FILE);
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(index),
+        Err(err) => return Err(disk_error("load_index", &path, &err)),
+    };
+
+    for line in content.lines() {
+        let Some((file, key)) = line.split_once('\t') else {
+            continue;
+        };
+        if file.is_empty() || !root.join(file).is_file() {
+            index.pop(key);
+            continue;
+        }
+        if let Some((evicted_key, evicted)) = index.push(key.to_owned(), file.to_owned()) {
+            if evicted_key != key || evicted != file {
+                remove_evicted(&root.join(evicted));
+            }
+        }
+    }
+
+    Ok(index)
+}
+
+#[expect(clippy::single_call_fn, reason = "code readability")]
+fn remove_partial_writes(root: &Path) -> Result<(), LayerError> {
+    let entries = fs::read_dir(root).map_err(|err| disk_error("new", root, &err))?;
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().ends_with(TMP_SUFFIX) {
+            remove_evicted(&entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// An evicted file left behind only wastes space, it is not worth failing the write.
+fn remove_evicted(path: &Path) {
+    match fs::remove_file(path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        #[cfg(feature = "tracing")]
+        Err(err) => {
+            tracing::warn!(path = %path.display(), %err, "failed to remove an evicted file")
+        }
+        #[cfg(not(feature = "tracing"))]
+        Err(_) => {}
+    }
+}
+
+fn disk_error(operation: &str, path: &Path, err: &io::Error) -> LayerError {
+    LayerError::Disk {
+        operation: operation.to_owned(),
+        key: path.display().to_string(),
+        internal: err.to_string(),
+    }
+}
@@ -0,0 +1,200 @@
+use core::hash::{Hash, Hasher};
+use core::time::Duration;
+use std::hash::DefaultHasher;
+use std::time::Instant;
+
+use crate::storage::clock::{Clock, SystemClock};
+use crate::storage::layer::Layer;
+use crate::HashMap;
+
+const BITS_PER_KEY: usize = 10;
+const HASHES: u64 = 7;
+/// Writes made around the filter, by another process or on the sink directly, are hidden for at
+/// most this long.
+pub(crate) const DEFAULT_MAX_AGE: Duration = Duration::from_secs(300);
+
+struct Bloom {
+    bits: Vec<u64>,
+    built_at: Instant,
+}
+
+impl Bloom {
+    fn with_capacity(keys: usize, built_at: Instant) -> Self {
+        let words = (keys.max(1) * BITS_PER_KEY).div_ceil(64);
+        Self {
+            bits: vec![0; words],
+            built_at,
+        }
+    }
+
+    fn positions(&self, key: &str) -> impl Iterator<Item = usize> {
+        let len = (self.bits.len() * 64) as u64;
+        let first = seeded_hash(0, key);
+        let second = seeded_hash(1, key) | 1;
+
+        (0..HASHES).map(move |index| {
+            let position = first.wrapping_add(index.wrapping_mul(second)) % len;
+            usize::try_from(position).unwrap_or_default()
+        })
+    }
+
+    fn insert(&mut self, key: &str) {
+        for position in self.positions(key).collect::<Vec<_>>() {
+            if let Some(word) = self.bits.get_mut(position / 64) {
+                *word |= 1 << (position % 64);
+            }
+        }
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.positions(key).all(|position| {
+            self.bits
+                .get(position / 64)
+                .is_some_and(|word| word & (1 << (position % 64)) != 0)
+        })
+    }
+}
+
+pub struct ExistenceFilter<STORAGE, CLOCK = SystemClock> {
+    filters: HashMap<String, Bloom>,
+    max_age: Duration,
+    clock: CLOCK,
+    storage: STORAGE,
+}
+
+impl<STORAGE> ExistenceFilter<STORAGE>
+where
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub fn new(storage: STORAGE) -> Self {
+        Self {
+            filters: HashMap::default(),
+            max_age: DEFAULT_MAX_AGE,
+            clock: SystemClock,
+            storage,
+        }
+    }
+}
+
+impl<STORAGE, CLOCK> ExistenceFilter<STORAGE, CLOCK>
+where
+    STORAGE: Send + Sync,
+    CLOCK: Clock,
+{
+    #[inline]
+    pub fn with_clock<OTHER>(self, clock: OTHER) -> ExistenceFilter<STORAGE, OTHER>
+    where
+        OTHER: Clock,
+    {
+        ExistenceFilter {
+            filters: self.filters,
+            max_age: self.max_age,
+            clock,
+            storage: self.storage,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    #[inline]
+    pub fn invalidate(&mut self, prefix: &str) {
+        self.filters.remove(prefix);
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn rebuild_inner<'key, KEYS>(&mut self, prefix: &str, keys: KEYS)
+    where
+        KEYS: ExactSizeIterator<Item = &'key String>,
+    {
+        let mut bloom = Bloom::with_capacity(keys.len(), self.clock.now());
+        for key in keys {
+            bloom.insert(key);
+        }
+        self.filters.insert(prefix.to_owned(), bloom);
+    }
+
+    pub(crate) fn might_exist_inner(&self, key: &str) -> Option<bool> {
+        let bloom = self.filters.get(parent(key))?;
+
+        if self.is_stale(bloom) {
+            None
+        } else {
+            Some(bloom.contains(key))
+        }
+    }
+
+    pub(crate) fn stale_prefixes_inner(&self) -> Vec<String> {
+        self.filters
+            .iter()
+            .filter(|&(_, bloom)| self.is_stale(bloom))
+            .map(|(prefix, _)| prefix.clone())
+            .collect()
+    }
+
+    fn is_stale(&self, bloom: &Bloom) -> bool {
+        self.clock.now().saturating_duration_since(bloom.built_at) > self.max_age
+    }
+
+    pub(crate) fn insert_inner(&mut self, key: &str) {
+        if let Some(bloom) = self.filters.get_mut(parent(key)) {
+            bloom.insert(key);
+        }
+    }
+}
+
+fn parent(key: &str) -> &str {
+    key.rfind('/')
+        .and_then(|index| key.get(..=index))
+        .unwrap_or_default()
+}
+
+fn seeded_hash(seed: u64, key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<STORAGE, CLOCK> Layer for ExistenceFilter<STORAGE, CLOCK> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
@@ -0,0 +1,106 @@
+use core::num::{NonZeroU64, NonZeroUsize};
+use std::time::SystemTime;
+
+use crate::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyHeat {
+    pub key: String,
+    pub reads: u64,
+    pub last_access: SystemTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeatMap {
+    capacity: NonZeroUsize,
+    sample_every: NonZeroU64,
+    seen: u64,
+    keys: HashMap<String, (u64, SystemTime)>,
+}
+
+impl HeatMap {
+    #[inline]
+    #[must_use]
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            capacity,
+            sample_every: NonZeroU64::MIN,
+            seen: 0,
+            keys: HashMap::default(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_sample_every(mut self, sample_every: NonZeroU64) -> Self {
+        self.sample_every = sample_every;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn reads(&self, key: &str) -> Option<u64> {
+        self.keys.get(key).map(|&(reads, _)| reads)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn report(&self, prefix: &str, top_n: usize) -> Vec<KeyHeat> {
+        let mut report = self
+            .keys
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, &(reads, last_access))| KeyHeat {
+                key: key.clone(),
+                reads,
+                last_access,
+            })
+            .collect::<Vec<_>>();
+        report.sort_unstable_by(|left, right| {
+            right
+                .reads
+                .cmp(&left.reads)
+                .then_with(|| left.key.cmp(&right.key))
+        });
+        report.truncate(top_n);
+        report
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub(crate) fn record_inner(&mut self, key: &str, now: SystemTime) {
+        self.seen += 1;
+        if !self.seen.is_multiple_of(self.sample_every.get()) {
+            return;
+        }
+
+        if let Some(&mut (ref mut reads, ref mut last_access)) = self.keys.get_mut(key) {
+            *reads = reads.saturating_add(self.sample_every.get());
+            *last_access = now;
+            return;
+        }
+
+        if self.keys.len() >= self.capacity.get() {
+            let coldest = self
+                .keys
+                .iter()
+                .min_by_key(|&(_, &(reads, last_access))| (reads, last_access))
+                .map(|(coldest, _)| coldest.clone());
+            if let Some(coldest) = coldest {
+                self.keys.remove(&coldest);
+            }
+        }
+        self.keys
+            .insert(key.to_owned(), (self.sample_every.get(), now));
+    }
+}
@@ -1,13 +1,178 @@
 use core::num::NonZeroUsize;
+use core::time::Duration;
+use std::sync::Arc;
+use std::time::Instant;
 
-use lru::LruCache;
-
+use super::heat::{HeatMap, KeyHeat};
+use super::policy::{Entries, EvictionPolicy};
+use crate::storage::clock::{Clock, SystemClock};
+use crate::storage::intern::{InternStats, Interner};
+use crate::storage::layer::Layer;
 use crate::storage::{radix_key, DeserializeWhere, ListKeyObjects, LruError, ReturnWhere};
-use crate::HashSet;
+use crate::{HashMap, HashSet};
+
+const DEFAULT_LIST_TTL: Duration = Duration::from_secs(30);
+const PRESSURE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSizing {
+    pub min: NonZeroUsize,
+    pub max: NonZeroUsize,
+    pub target_hit_ratio: f64,
+    pub max_bytes: Option<usize>,
+    pub interval: Duration,
+}
+
+impl AdaptiveSizing {
+    #[inline]
+    #[must_use]
+    pub const fn new(min: NonZeroUsize, max: NonZeroUsize) -> Self {
+        Self {
+            min,
+            max,
+            target_hit_ratio: 0.9,
+            max_bytes: None,
+            interval: Duration::from_secs(60),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_target_hit_ratio(mut self, target_hit_ratio: f64) -> Self {
+        self.target_hit_ratio = target_hit_ratio;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    fn next_cap(&self, cap: usize, hit_ratio: f64, bytes: usize, entries: usize) -> usize {
+        let average = bytes.checked_div(entries).unwrap_or_default();
+        let affordable = self.max_bytes.map_or(usize::MAX, |max_bytes| {
+            max_bytes.checked_div(average).unwrap_or(usize::MAX)
+        });
+
+        let next = if affordable < cap {
+            affordable
+        } else if hit_ratio < self.target_hit_ratio {
+            cap.saturating_add(cap.div_ceil(4)).min(affordable)
+        } else {
+            cap.saturating_sub(cap / 8)
+        };
+        next.clamp(self.min.get(), self.max.get().max(self.min.get()))
+    }
+}
 
-pub struct Lru<STORAGE> {
-    exists: HashSet<String>,
-    cache: LruCache<String, Vec<u8>>,
+#[derive(Debug, Clone, Copy)]
+struct Adaptive {
+    sizing: AdaptiveSizing,
+    evaluated_at: Instant,
+    hits: u64,
+    misses: u64,
+}
+
+type PressureHook = Box<dyn Fn(&CacheStats) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub evictions_per_sec: f64,
+    pub bytes: usize,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+impl CacheStats {
+    #[inline]
+    #[must_use]
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Counters {
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    bytes: usize,
+    window_start: Instant,
+    window_evictions: u64,
+    alerted: bool,
+}
+
+impl Counters {
+    fn new(now: Instant) -> Self {
+        Self {
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            bytes: 0,
+            window_start: now,
+            window_evictions: 0,
+            alerted: false,
+        }
+    }
+
+    fn evictions_per_sec(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        self.window_evictions as f64 / elapsed.as_secs_f64().max(1.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedEntry {
+    pub key: Arc<str>,
+    pub size: usize,
+    pub age: Duration,
+    pub idle: Duration,
+}
+
+struct Cached {
+    stored_at: Instant,
+    accessed_at: Instant,
+    value: Vec<u8>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ListMode {
+    #[default]
+    Merged,
+    CacheOnly,
+}
+
+pub struct Lru<STORAGE, CLOCK = SystemClock> {
+    keys: Interner,
+    exists: HashSet<Arc<str>>,
+    dirty: HashSet<Arc<str>>,
+    cache: Entries<Cached>,
+    lists: HashMap<String, (Instant, ListKeyObjects)>,
+    list_ttl: Duration,
+    list_mode: ListMode,
+    soft_limit: Option<usize>,
+    counters: Counters,
+    pressure: Option<(f64, PressureHook)>,
+    adaptive: Option<Adaptive>,
+    heat: Option<HeatMap>,
+    clock: CLOCK,
     storage: STORAGE,
 }
 
@@ -17,14 +182,236 @@ where
 {
     #[inline]
     pub fn new(size: NonZeroUsize, storage: STORAGE) -> Self {
+        Self::with_policy(size, EvictionPolicy::Lru, storage)
+    }
+
+    #[inline]
+    pub fn with_policy(size: NonZeroUsize, policy: EvictionPolicy, storage: STORAGE) -> Self {
         Self {
-            exists: HashSet::new(),
-            cache: LruCache::new(size),
+            keys: Interner::default(),
+            exists: HashSet::default(),
+            dirty: HashSet::default(),
+            cache: Entries::new(size, policy),
+            lists: HashMap::default(),
+            list_ttl: DEFAULT_LIST_TTL,
+            list_mode: ListMode::default(),
+            soft_limit: None,
+            counters: Counters::new(SystemClock.now()),
+            pressure: None,
+            adaptive: None,
+            heat: None,
+            clock: SystemClock,
             storage,
         }
     }
+}
+
+impl<STORAGE, CLOCK> Lru<STORAGE, CLOCK>
+where
+    STORAGE: Send + Sync,
+    CLOCK: Clock,
+{
+    #[inline]
+    pub fn with_clock<OTHER>(self, clock: OTHER) -> Lru<STORAGE, OTHER>
+    where
+        OTHER: Clock,
+    {
+        Lru {
+            keys: self.keys,
+            exists: self.exists,
+            dirty: self.dirty,
+            cache: self.cache,
+            lists: self.lists,
+            list_ttl: self.list_ttl,
+            list_mode: self.list_mode,
+            soft_limit: self.soft_limit,
+            // The cached entries and the adaptive snapshot stay, only the eviction window restarts
+            // on the new clock.
+            counters: Counters {
+                window_start: clock.now(),
+                ..self.counters
+            },
+            pressure: self.pressure,
+            adaptive: self.adaptive.map(|adaptive| Adaptive {
+                evaluated_at: clock.now(),
+                ..adaptive
+            }),
+            heat: self.heat,
+            clock,
+            storage: self.storage,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_list_ttl(mut self, list_ttl: Duration) -> Self {
+        self.list_ttl = list_ttl;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_list_mode(mut self, list_mode: ListMode) -> Self {
+        self.list_mode = list_mode;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn list_mode(&self) -> ListMode {
+        self.list_mode
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_soft_limit(mut self, bytes: usize) -> Self {
+        self.soft_limit = Some(bytes);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_pressure_hook<HOOK>(mut self, max_evictions_per_sec: f64, hook: HOOK) -> Self
+    where
+        HOOK: Fn(&CacheStats) + Send + Sync + 'static,
+    {
+        self.pressure = Some((max_evictions_per_sec, Box::new(hook)));
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_adaptive_sizing(mut self, sizing: AdaptiveSizing) -> Self {
+        self.adaptive = Some(Adaptive {
+            sizing,
+            evaluated_at: self.clock.now(),
+            hits: self.counters.hits,
+            misses: self.counters.misses,
+        });
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_heat_map(mut self, heat: HeatMap) -> Self {
+        self.heat = Some(heat);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn heat_map(&self) -> Option<&HeatMap> {
+        self.heat.as_ref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn heat_report(&self, prefix: &str, top_n: usize) -> Vec<KeyHeat> {
+        self.heat
+            .as_ref()
+            .map(|heat| heat.report(prefix, top_n))
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.counters.hits,
+            misses: self.counters.misses,
+            evictions: self.counters.evictions,
+            evictions_per_sec: self.counters.evictions_per_sec(self.clock.now()),
+            bytes: self.counters.bytes,
+            entries: self.cache.len(),
+            capacity: self.cache.cap().get(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn intern_stats(&self) -> InternStats {
+        self.keys.stats()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn cap(&self) -> NonZeroUsize {
+        self.cache.cap()
+    }
+
+    #[inline]
+    pub fn invalidate(&mut self, key: &str) -> bool {
+        self.lists
+            .retain(|prefix, _| !key.starts_with(prefix.as_str()));
+        self.dirty.remove(key);
+        let existed = self.exists.remove(key);
+        let cached = self.cache.pop(key);
+        if let Some(ref old) = cached {
+            self.counters.bytes = self.counters.bytes.saturating_sub(old.value.len());
+        }
+        existed || cached.is_some()
+    }
+
+    /// Read a cached value without touching its recency, its last access or the hit counters.
+    #[inline]
+    #[must_use]
+    pub fn peek(&self, key: &str) -> Option<&[u8]> {
+        self.cache.peek(key).map(|cached| cached.value.as_slice())
+    }
 
-    pub(crate) fn storage(&mut self) -> &mut STORAGE {
+    /// Mark a cached entry as just used, without counting a hit, `false` when it is not cached.
+    #[inline]
+    pub fn promote(&mut self, key: &str) -> bool {
+        let now = self.clock.now();
+        self.cache
+            .get_mut(key)
+            .map(|cached| cached.accessed_at = now)
+            .is_some()
+    }
+
+    /// Most recently used first, ages are measured on the cache clock.
+    #[inline]
+    pub fn entries(&self) -> impl Iterator<Item = CachedEntry> + '_ {
+        let now = self.clock.now();
+        self.cache.iter().map(move |(key, cached)| CachedEntry {
+            key: Arc::clone(key),
+            size: cached.value.len(),
+            age: now.saturating_duration_since(cached.stored_at),
+            idle: now.saturating_duration_since(cached.accessed_at),
+        })
+    }
+
+    /// What the soft limit and the adaptive byte budget still allow, `None` when unbounded.
+    pub(crate) fn bytes_left_inner(&self) -> Option<usize> {
+        let adaptive = self.adaptive.and_then(|adaptive| adaptive.sizing.max_bytes);
+        let budget = match (self.soft_limit, adaptive) {
+            (Some(soft), Some(adaptive)) => Some(soft.min(adaptive)),
+            (soft, adaptive) => soft.or(adaptive),
+        };
+        budget.map(|budget| budget.saturating_sub(self.counters.bytes))
+    }
+
+    pub(crate) fn contains_inner(&self, key: &str) -> bool {
+        self.cache.contains(key)
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
         &mut self.storage
     }
 
@@ -32,27 +419,183 @@ where
         self.exists.contains(key)
     }
 
-    pub(crate) fn put_bytes_inner(&mut self, key: String, value: Vec<u8>) {
-        self.cache.put(key.clone(), value);
+    pub(crate) fn mark_exists_inner(&mut self, key: &str) {
+        let key = self.keys.intern(key);
         self.exists.insert(key);
     }
 
-    pub(crate) fn get_bytes_inner(&mut self, key: &str) -> Option<Vec<u8>> {
-        // TODO: Get from sink
-        self.cache.get(key).cloned()
+    pub(crate) fn put_bytes_inner(&mut self, key: &str, value: Vec<u8>) {
+        self.lists
+            .retain(|prefix, _| !key.starts_with(prefix.as_str()));
+        self.counters.bytes += value.len();
+        let key = self.keys.intern(key);
+        let now = self.clock.now();
+        let cached = Cached {
+            stored_at: now,
+            accessed_at: now,
+            value,
+        };
+        if let Some((pushed, old)) = self.cache.push(Arc::clone(&key), cached) {
+            self.counters.bytes = self.counters.bytes.saturating_sub(old.value.len());
+            if pushed != key {
+                self.evicted_inner();
+            }
+        }
+        self.exists.insert(key);
+
+        while self
+            .soft_limit
+            .is_some_and(|limit| self.counters.bytes > limit && self.cache.len() > 1)
+        {
+            let Some((_, old)) = self.cache.pop_lru() else {
+                break;
+            };
+            self.counters.bytes = self.counters.bytes.saturating_sub(old.value.len());
+            self.evicted_inner();
+        }
     }
 
-    pub(crate) fn list_objects_inner(&self, prefix: &str) -> ListKeyObjects {
-        self.cache
+    pub(crate) fn get_bytes_inner(
+        &mut self,
+        key: &str,
+        max_age: Option<Duration>,
+    ) -> Option<Vec<u8>> {
+        let value = self.fresh_inner(key, max_age).cloned();
+        self.record_inner(key, value.is_some());
+        value
+    }
+
+    fn fresh_inner(&mut self, key: &str, max_age: Option<Duration>) -> Option<&Vec<u8>> {
+        let now = self.clock.now();
+        let cached = self.cache.get_mut(key)?;
+        cached.accessed_at = now;
+        max_age
+            .is_none_or(|max_age| now.saturating_duration_since(cached.stored_at) < max_age)
+            .then_some(&cached.value)
+    }
+
+    fn record_inner(&mut self, key: &str, hit: bool) {
+        if let Some(ref mut heat) = self.heat {
+            heat.record_inner(key, self.clock.system_time());
+        }
+        if hit {
+            self.counters.hits += 1;
+        } else {
+            self.counters.misses += 1;
+        }
+        self.adapt_inner();
+    }
+
+    fn adapt_inner(&mut self) {
+        let now = self.clock.now();
+        let Some(adaptive) = self.adaptive else {
+            return;
+        };
+        if now.saturating_duration_since(adaptive.evaluated_at) < adaptive.sizing.interval {
+            return;
+        }
+
+        let hit_ratio = CacheStats {
+            hits: self.counters.hits.saturating_sub(adaptive.hits),
+            misses: self.counters.misses.saturating_sub(adaptive.misses),
+            ..self.stats()
+        }
+        .hit_ratio();
+        self.adaptive = Some(Adaptive {
+            evaluated_at: now,
+            hits: self.counters.hits,
+            misses: self.counters.misses,
+            ..adaptive
+        });
+
+        let cap = adaptive.sizing.next_cap(
+            self.cache.cap().get(),
+            hit_ratio,
+            self.counters.bytes,
+            self.cache.len(),
+        );
+        if let Some(cap) = NonZeroUsize::new(cap) {
+            while self.cache.len() > cap.get() {
+                let Some((_, old)) = self.cache.pop_lru() else {
+                    break;
+                };
+                self.counters.bytes = self.counters.bytes.saturating_sub(old.value.len());
+                self.evicted_inner();
+            }
+            self.cache.resize(cap);
+        }
+    }
+
+    fn evicted_inner(&mut self) {
+        let now = self.clock.now();
+        if now.saturating_duration_since(self.counters.window_start) >= PRESSURE_WINDOW {
+            self.counters.window_start = now;
+            self.counters.window_evictions = 0;
+            self.counters.alerted = false;
+        }
+        self.counters.evictions += 1;
+        self.counters.window_evictions += 1;
+
+        if let Some((max_evictions_per_sec, ref hook)) = self.pressure {
+            if !self.counters.alerted
+                && self.counters.evictions_per_sec(now) > max_evictions_per_sec
+            {
+                self.counters.alerted = true;
+                hook(&self.stats());
+            }
+        }
+    }
+
+    pub(crate) fn list_objects_inner(&self, prefix: &str) -> Option<ListKeyObjects> {
+        self.lists
+            .get(prefix)
+            .filter(|&&(listed_at, _)| {
+                self.clock.now().saturating_duration_since(listed_at) < self.list_ttl
+            })
+            .map(|(_, list)| list.clone())
+    }
+
+    pub(crate) fn put_list_inner(&mut self, prefix: String, list: ListKeyObjects) {
+        self.lists.insert(prefix, (self.clock.now(), list));
+    }
+
+    pub(crate) fn mark_dirty_inner(&mut self, key: &str) {
+        let key = self.keys.intern(key);
+        self.dirty.insert(key);
+    }
+
+    pub(crate) fn list_cached_inner(&self, prefix: &str) -> ListKeyObjects {
+        self.exists
+            .iter()
+            .filter_map(|key| radix_key(prefix, key))
+            .collect()
+    }
+
+    /// The keys written through this cache under `prefix` that `list` does not show, the sink
+    /// listing lags behind them or they were deleted behind the cache.
+    pub(crate) fn unlisted_dirty_inner(&self, prefix: &str, list: &ListKeyObjects) -> Vec<String> {
+        self.dirty
             .iter()
-            .filter(|&(key, _)| key.starts_with(prefix))
-            .filter_map(|(key, _)| radix_key(prefix, key))
+            .filter(|key| radix_key(prefix, key).is_some_and(|entry| !list.contains(&entry)))
+            .map(ToString::to_string)
             .collect()
     }
 
+    pub(crate) fn forget_dirty_inner(&mut self, key: &str) {
+        self.dirty.remove(key);
+    }
+
+    /// Add the keys written through this cache that the sink listing does not show yet, and stop
+    /// tracking the ones it now does.
+    pub(crate) fn merge_dirty_inner(&mut self, prefix: &str, list: &mut ListKeyObjects) {
+        self.dirty.retain(|key| !list.contains(key.as_ref()));
+        list.extend(self.dirty.iter().filter_map(|key| radix_key(prefix, key)));
+    }
+
     pub(crate) fn get_object_cache_inner<RETURN, PARSER>(
         &mut self,
         key: &str,
+        max_age: Option<Duration>,
         parser: PARSER,
     ) -> Result<Option<RETURN>, LruError>
     where
@@ -62,16 +605,21 @@ where
         let exists = self.exists_inner(key);
 
         if exists {
-            let value = self.cache.get(key).map(|value| parser(value)).transpose()?;
+            let value = self
+                .fresh_inner(key, max_age)
+                .map(|value| parser(value))
+                .transpose()?;
+            self.record_inner(key, value.is_some());
             Ok(value)
         } else {
+            self.record_inner(key, false);
             Ok(None)
         }
     }
 
     pub(crate) fn put_object_inner<VALUE, PARSER>(
         &mut self,
-        key: String,
+        key: &str,
         value: &VALUE,
         parser: PARSER,
     ) -> Result<Vec<u8>, LruError>
@@ -83,3 +631,22 @@ where
         Ok(serialize)
     }
 }
+
+impl<STORAGE, CLOCK> Layer for Lru<STORAGE, CLOCK> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
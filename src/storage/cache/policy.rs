@@ -0,0 +1,181 @@
+use core::num::NonZeroUsize;
+use std::sync::Arc;
+
+use lru::LruCache;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    #[default]
+    Lru,
+    Segmented,
+}
+
+pub(crate) enum Entries<VALUE> {
+    Lru(LruCache<Arc<str>, VALUE>),
+    Segmented(Segmented<VALUE>),
+}
+
+pub(crate) struct Segmented<VALUE> {
+    probation: LruCache<Arc<str>, VALUE>,
+    protected: LruCache<Arc<str>, VALUE>,
+    cap: NonZeroUsize,
+}
+
+impl<VALUE> Segmented<VALUE> {
+    fn protected_cap(&self) -> usize {
+        self.cap.get() * 4 / 5
+    }
+
+    fn len(&self) -> usize {
+        self.probation.len() + self.protected.len()
+    }
+
+    fn push(&mut self, key: Arc<str>, value: VALUE) -> Option<(Arc<str>, VALUE)> {
+        if self.protected.contains(&key) {
+            return self.protected.push(key, value);
+        }
+
+        if let Some(replaced) = self.probation.push(key, value) {
+            return Some(replaced);
+        }
+
+        (self.len() > self.cap.get())
+            .then(|| self.pop_lru())
+            .flatten()
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut VALUE> {
+        if self.protected.contains(key) {
+            return self.protected.get_mut(key);
+        }
+
+        let (key, value) = self.probation.pop_entry(key)?;
+        self.protected.put(Arc::clone(&key), value);
+        self.demote();
+        self.protected
+            .peek_mut(&key)
+            .or_else(|| self.probation.peek_mut(&key))
+    }
+
+    fn demote(&mut self) {
+        while self.protected.len() > self.protected_cap() {
+            let Some((key, value)) = self.protected.pop_lru() else {
+                break;
+            };
+            self.probation.put(key, value);
+        }
+    }
+
+    fn pop_lru(&mut self) -> Option<(Arc<str>, VALUE)> {
+        self.probation
+            .pop_lru()
+            .or_else(|| self.protected.pop_lru())
+    }
+}
+
+impl<VALUE> Entries<VALUE> {
+    pub(crate) fn new(size: NonZeroUsize, policy: EvictionPolicy) -> Self {
+        match policy {
+            EvictionPolicy::Lru => Self::Lru(LruCache::new(size)),
+            EvictionPolicy::Segmented => Self::Segmented(Segmented {
+                probation: LruCache::unbounded(),
+                protected: LruCache::unbounded(),
+                cap: size,
+            }),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match *self {
+            Self::Lru(ref cache) => cache.len(),
+            Self::Segmented(ref segmented) => segmented.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn cap(&self) -> NonZeroUsize {
+        match *self {
+            Self::Lru(ref cache) => cache.cap(),
+            Self::Segmented(ref segmented) => segmented.cap,
+        }
+    }
+
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        match *self {
+            Self::Lru(ref cache) => cache.contains(key),
+            Self::Segmented(ref segmented) => {
+                segmented.protected.contains(key) || segmented.probation.contains(key)
+            }
+        }
+    }
+
+    pub(crate) fn push(&mut self, key: Arc<str>, value: VALUE) -> Option<(Arc<str>, VALUE)> {
+        match *self {
+            Self::Lru(ref mut cache) => cache.push(key, value),
+            Self::Segmented(ref mut segmented) => segmented.push(key, value),
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, key: &str) -> Option<&mut VALUE> {
+        match *self {
+            Self::Lru(ref mut cache) => cache.get_mut(key),
+            Self::Segmented(ref mut segmented) => segmented.get_mut(key),
+        }
+    }
+
+    pub(crate) fn peek(&self, key: &str) -> Option<&VALUE> {
+        match *self {
+            Self::Lru(ref cache) => cache.peek(key),
+            Self::Segmented(ref segmented) => segmented
+                .protected
+                .peek(key)
+                .or_else(|| segmented.probation.peek(key)),
+        }
+    }
+
+    /// Most recently used first, the protected segment before the probation one.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Arc<str>, &VALUE)> {
+        let (first, second) = match *self {
+            Self::Lru(ref cache) => (cache.iter(), None),
+            Self::Segmented(ref segmented) => {
+                (segmented.protected.iter(), Some(segmented.probation.iter()))
+            }
+        };
+        first.chain(second.into_iter().flatten())
+    }
+
+    pub(crate) fn pop(&mut self, key: &str) -> Option<VALUE> {
+        match *self {
+            Self::Lru(ref mut cache) => cache.pop(key),
+            Self::Segmented(ref mut segmented) => segmented
+                .protected
+                .pop(key)
+                .or_else(|| segmented.probation.pop(key)),
+        }
+    }
+
+    pub(crate) fn pop_lru(&mut self) -> Option<(Arc<str>, VALUE)> {
+        match *self {
+            Self::Lru(ref mut cache) => cache.pop_lru(),
+            Self::Segmented(ref mut segmented) => segmented.pop_lru(),
+        }
+    }
+
+    pub(crate) fn resize(&mut self, cap: NonZeroUsize) {
+        match *self {
+            Self::Lru(ref mut cache) => cache.resize(cap),
+            Self::Segmented(ref mut segmented) => {
+                segmented.cap = cap;
+                segmented.demote();
+                while segmented.len() > cap.get() {
+                    if segmented.pop_lru().is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
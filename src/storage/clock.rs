@@ -0,0 +1,73 @@
+use core::fmt::Debug;
+use core::time::Duration;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Instant, SystemTime};
+
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+
+    fn system_time(&self) -> SystemTime;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    #[inline]
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    instant: Instant,
+    system_time: SystemTime,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl MockClock {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            instant: Instant::now(),
+            system_time: SystemTime::UNIX_EPOCH,
+            elapsed: Arc::default(),
+        }
+    }
+
+    #[inline]
+    pub fn advance(&self, duration: Duration) {
+        let mut elapsed = self.elapsed.lock().unwrap_or_else(PoisonError::into_inner);
+        *elapsed = elapsed.saturating_add(duration);
+    }
+
+    fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for MockClock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        self.instant + self.elapsed()
+    }
+
+    #[inline]
+    fn system_time(&self) -> SystemTime {
+        self.system_time + self.elapsed()
+    }
+}
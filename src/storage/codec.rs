@@ -0,0 +1,178 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hmac;
+use unicode_normalization::UnicodeNormalization as _;
+
+use super::meta::checksum;
+
+pub trait KeyCodec: Send + Sync {
+    fn encode(&self, segment: &str) -> String;
+
+    fn decode(&self, encoded: &str) -> Option<String>;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Identity;
+
+impl KeyCodec for Identity {
+    #[inline]
+    fn encode(&self, segment: &str) -> String {
+        segment.to_owned()
+    }
+
+    #[inline]
+    fn decode(&self, encoded: &str) -> Option<String> {
+        Some(encoded.to_owned())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Normalized;
+
+impl KeyCodec for Normalized {
+    #[inline]
+    fn encode(&self, segment: &str) -> String {
+        segment.nfc().collect()
+    }
+
+    #[inline]
+    fn decode(&self, encoded: &str) -> Option<String> {
+        Some(encoded.to_owned())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Hashed {
+    salt: Vec<u8>,
+}
+
+impl Hashed {
+    #[inline]
+    #[must_use]
+    pub const fn new(salt: Vec<u8>) -> Self {
+        Self { salt }
+    }
+}
+
+impl KeyCodec for Hashed {
+    #[inline]
+    fn encode(&self, segment: &str) -> String {
+        let mut salted = self.salt.clone();
+        salted.extend_from_slice(segment.as_bytes());
+        checksum(&salted)
+    }
+
+    #[inline]
+    fn decode(&self, _encoded: &str) -> Option<String> {
+        None
+    }
+}
+
+pub struct Deterministic {
+    cipher: LessSafeKey,
+    nonce: hmac::Key,
+}
+
+impl Deterministic {
+    #[inline]
+    #[must_use]
+    pub fn new(secret: &[u8; 32]) -> Self {
+        let derive = hmac::Key::new(hmac::HMAC_SHA256, secret);
+        let cipher_key = hmac::sign(&derive, b"negentropy key cipher");
+        let nonce_key = hmac::sign(&derive, b"negentropy key nonce");
+
+        Self {
+            cipher: LessSafeKey::new(
+                UnboundKey::new(&AES_256_GCM, cipher_key.as_ref())
+                    .unwrap_or_else(|_| unreachable!("hmac-sha256 output is 32 bytes")),
+            ),
+            nonce: hmac::Key::new(hmac::HMAC_SHA256, nonce_key.as_ref()),
+        }
+    }
+
+    fn nonce(&self, segment: &[u8]) -> [u8; NONCE_LEN] {
+        let mut nonce = [0; NONCE_LEN];
+        let tag = hmac::sign(&self.nonce, segment);
+        nonce.copy_from_slice(tag.as_ref().get(..NONCE_LEN).unwrap_or_default());
+        nonce
+    }
+}
+
+impl KeyCodec for Deterministic {
+    #[inline]
+    fn encode(&self, segment: &str) -> String {
+        let nonce = self.nonce(segment.as_bytes());
+        let mut sealed = segment.as_bytes().to_vec();
+        if self
+            .cipher
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut sealed,
+            )
+            .is_err()
+        {
+            return String::new();
+        }
+
+        let mut encoded = nonce.to_vec();
+        encoded.extend(sealed);
+        URL_SAFE_NO_PAD.encode(encoded)
+    }
+
+    #[inline]
+    fn decode(&self, encoded: &str) -> Option<String> {
+        let decoded = URL_SAFE_NO_PAD.decode(encoded).ok()?;
+        let (nonce, sealed) = decoded.split_at_checked(NONCE_LEN)?;
+        let nonce = <[u8; NONCE_LEN]>::try_from(nonce).ok()?;
+        let mut sealed = sealed.to_vec();
+        let opened = self
+            .cipher
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut sealed,
+            )
+            .ok()?;
+
+        (self.nonce(opened) == nonce)
+            .then(|| String::from_utf8(opened.to_vec()).ok())
+            .flatten()
+    }
+}
+
+#[inline]
+pub fn encode_key<CODEC>(codec: &CODEC, key: &str) -> String
+where
+    CODEC: KeyCodec + ?Sized,
+{
+    key.split('/')
+        .map(|segment| {
+            if segment.is_empty() {
+                String::new()
+            } else {
+                codec.encode(segment)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[inline]
+pub fn decode_key<CODEC>(codec: &CODEC, encoded: &str) -> Option<String>
+where
+    CODEC: KeyCodec + ?Sized,
+{
+    encoded
+        .split('/')
+        .map(|segment| {
+            if segment.is_empty() {
+                Some(String::new())
+            } else {
+                codec.decode(segment)
+            }
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|segments| segments.join("/"))
+}
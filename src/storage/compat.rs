@@ -0,0 +1,211 @@
+//! Stored formats this crate keeps reading across upgrades.
+//!
+//! Every envelope written by a layer starts with a magic carrying its version. A layout change
+//! gets a new magic and a new reader next to the previous ones, which are never removed: data
+//! written by any released version stays readable. Bytes without a known magic are plain values,
+//! the format of everything stored before envelopes existed.
+
+/// The newest envelope version written by this crate.
+pub const FORMAT_VERSION: u8 = 2;
+
+pub(crate) const COMPRESSED_V1: &[u8; 4] = b"NGC1";
+pub(crate) const ENCRYPTED_V1: &[u8; 4] = b"NGE1";
+pub(crate) const ENCRYPTED_V2: &[u8; 4] = b"NGE2";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Envelope<'value> {
+    Plain(&'value [u8]),
+    Compressed {
+        version: u8,
+        encoding: &'value str,
+        size: u64,
+        body: &'value [u8],
+    },
+    /// `wrapped_key` is the data key as wrapped by a `KeyProvider`, empty before version 2.
+    Encrypted {
+        version: u8,
+        key_id: &'value str,
+        wrapped_key: &'value [u8],
+        sealed: &'value [u8],
+    },
+}
+
+impl<'value> Envelope<'value> {
+    /// A truncated or malformed envelope reads as a plain value, like the layers always did.
+    #[inline]
+    #[must_use]
+    pub fn read(value: &'value [u8]) -> Self {
+        if let Some((encoding, size, body)) = read_compressed_v1(value) {
+            return Self::Compressed {
+                version: 1,
+                encoding,
+                size,
+                body,
+            };
+        }
+        if let Some((key_id, sealed)) = read_encrypted_v1(value) {
+            return Self::Encrypted {
+                version: 1,
+                key_id,
+                wrapped_key: &[],
+                sealed,
+            };
+        }
+        if let Some((key_id, wrapped_key, sealed)) = read_encrypted_v2(value) {
+            return Self::Encrypted {
+                version: 2,
+                key_id,
+                wrapped_key,
+                sealed,
+            };
+        }
+        Self::Plain(value)
+    }
+
+    /// `0` for plain values.
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> u8 {
+        match *self {
+            Self::Plain(_) => 0,
+            Self::Compressed { version, .. } | Self::Encrypted { version, .. } => version,
+        }
+    }
+}
+
+/// `NGC1`, encoding length, encoding, uncompressed size as big endian `u64`, body.
+fn read_compressed_v1(value: &[u8]) -> Option<(&str, u64, &[u8])> {
+    let rest = value.strip_prefix(COMPRESSED_V1)?;
+    let (&len, rest) = rest.split_first()?;
+    let (encoding, rest) = rest.split_at_checked(usize::from(len))?;
+    let (size, body) = rest.split_first_chunk::<8>()?;
+    Some((
+        core::str::from_utf8(encoding).ok()?,
+        u64::from_be_bytes(*size),
+        body,
+    ))
+}
+
+/// `NGE1`, key id length, key id, then the nonce followed by the sealed value.
+fn read_encrypted_v1(value: &[u8]) -> Option<(&str, &[u8])> {
+    let rest = value.strip_prefix(ENCRYPTED_V1)?;
+    let (&len, rest) = rest.split_first()?;
+    let (key_id, sealed) = rest.split_at_checked(usize::from(len))?;
+    Some((core::str::from_utf8(key_id).ok()?, sealed))
+}
+
+/// `NGE2`, wrapped key length as big endian `u16`, key id length, key id, wrapped key, then the
+/// nonce followed by the sealed value.
+fn read_encrypted_v2(value: &[u8]) -> Option<(&str, &[u8], &[u8])> {
+    let rest = value.strip_prefix(ENCRYPTED_V2)?;
+    let (wrapped_len, rest) = rest.split_first_chunk::<2>()?;
+    let (&len, rest) = rest.split_first()?;
+    let (key_id, rest) = rest.split_at_checked(usize::from(len))?;
+    let (wrapped_key, sealed) =
+        rest.split_at_checked(usize::from(u16::from_be_bytes(*wrapped_len)))?;
+    Some((core::str::from_utf8(key_id).ok()?, wrapped_key, sealed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sink::encrypted::{Encrypted, KeyRing};
+    use crate::storage::sink::memory::Memory;
+
+    const PLAIN: &[u8] = b"hello";
+    const COMPRESSED_IDENTITY_V1: &[u8] = b"NGC1\x08identity\0\0\0\0\0\0\0\x05hello";
+    const COMPRESSED_RLE_V1: &[u8] = b"NGC1\x03rle\0\0\0\0\0\0\0\x05\x01h\x01e\x02l\x01o";
+    // "hello" sealed for the key "fixture" with the key id "k1" and a secret of 32 bytes of 7.
+    const ENCRYPTED_V1_FIXTURE: &[u8] = &[
+        78, 71, 69, 49, 2, 107, 49, 45, 49, 123, 46, 199, 204, 49, 144, 83, 148, 21, 130, 4, 86,
+        192, 70, 243, 225, 105, 217, 157, 157, 231, 73, 154, 164, 111, 53, 93, 181, 121, 78, 159,
+    ];
+    // "hello" sealed for the key "fixture" with the key id "k2", wrapped as "wrapped", and a
+    // secret of 32 bytes of 7.
+    const ENCRYPTED_V2_FIXTURE: &[u8] = &[
+        78, 71, 69, 50, 0, 7, 2, 107, 50, 119, 114, 97, 112, 112, 101, 100, 194, 95, 101, 142, 13,
+        34, 132, 221, 30, 212, 43, 174, 211, 191, 44, 230, 99, 74, 75, 166, 180, 8, 81, 24, 101,
+        103, 255, 151, 22, 62, 140, 189, 79,
+    ];
+
+    #[test]
+    fn read_every_version() {
+        assert_eq!(Envelope::read(PLAIN), Envelope::Plain(PLAIN));
+        assert_eq!(Envelope::read(PLAIN).version(), 0);
+        assert_eq!(
+            Envelope::read(b"NGC1\x08iden"),
+            Envelope::Plain(b"NGC1\x08iden"),
+            "a truncated envelope is a plain value"
+        );
+
+        assert_eq!(
+            Envelope::read(COMPRESSED_IDENTITY_V1),
+            Envelope::Compressed {
+                version: 1,
+                encoding: "identity",
+                size: 5,
+                body: PLAIN,
+            }
+        );
+        assert!(matches!(
+            Envelope::read(COMPRESSED_RLE_V1),
+            Envelope::Compressed {
+                version: 1,
+                encoding: "rle",
+                size: 5,
+                ..
+            }
+        ));
+
+        let Envelope::Encrypted {
+            version, key_id, ..
+        } = Envelope::read(ENCRYPTED_V1_FIXTURE)
+        else {
+            panic!("the fixture must read as an encrypted envelope");
+        };
+        assert_eq!((version, key_id), (1, "k1"));
+        assert_eq!(
+            Encrypted::new(KeyRing::new("k1", &[7; 32]), Memory::default())
+                .open_inner("fixture", ENCRYPTED_V1_FIXTURE)
+                .unwrap(),
+            PLAIN
+        );
+
+        let Envelope::Encrypted {
+            version,
+            key_id,
+            wrapped_key,
+            ..
+        } = Envelope::read(ENCRYPTED_V2_FIXTURE)
+        else {
+            panic!("the fixture must read as an encrypted envelope");
+        };
+        assert_eq!((version, key_id, wrapped_key), (2, "k2", &b"wrapped"[..]));
+        assert_eq!(
+            Encrypted::new(KeyRing::new("k2", &[7; 32]), Memory::default())
+                .open_inner("fixture", ENCRYPTED_V2_FIXTURE)
+                .unwrap(),
+            PLAIN
+        );
+    }
+
+    #[cfg(feature = "copy")]
+    #[test]
+    fn read_chunk_manifest_v1() {
+        use crate::storage::copy::sink::chunked::ChunkManifest;
+
+        let manifest: ChunkManifest = serde_json::from_str(
+            r#"{"size":10,"chunk_size":4,"chunks":3,"mime":"application/octet-stream"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest,
+            ChunkManifest {
+                size: 10,
+                chunk_size: 4,
+                chunks: 3,
+                mime: "application/octet-stream".to_owned(),
+            }
+        );
+    }
+}
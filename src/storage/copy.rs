@@ -1,20 +1,48 @@
+use core::ops::Range;
+use core::time::Duration;
+
+use diff::walk_page;
 use direct::DKeyWithParserCopy;
+use futures::future::join_all;
 use futures::Future;
+use handle::ObjectHandle;
 use parser::Parser;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use super::{DKeyWhere, ListKeyObjects};
+use super::health::HealthReport;
+use super::meta::{ListEntry, ListPage, ObjectLock, ObjectMeta};
+use super::{slice_range, DKeyWhere, ListKeyObjects, ParserError};
+use crate::HashMap;
 
+pub mod bulk;
 pub mod cache;
+pub mod collection;
+pub mod diff;
 pub mod direct;
+pub mod handle;
 pub mod instance;
+pub mod journal;
+pub mod listing;
+pub mod map;
+pub mod model;
+pub mod notify;
 pub mod parser;
+pub mod retention;
+pub mod schema;
+pub mod secret;
+#[cfg(feature = "sim")]
+pub mod sim;
 pub mod sink;
+pub mod sync;
+pub mod tiering;
+pub mod transcode;
 
 pub trait ParserWhere = Parser + Send + Sync;
 pub trait ValueWhere = Serialize + Send + Sync;
 
+const FLAT_PAGE_SIZE: usize = 1000;
+
 pub trait Sink {
     type Error;
 
@@ -58,6 +86,51 @@ pub trait Sink {
         DKEY: DKeyWhere,
         PARSER: ParserWhere;
 
+    #[inline]
+    fn put_object_returning_previous_copy<RETURN, VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<DKEY, PARSER>,
+        value: &VALUE,
+    ) -> impl Future<Output = Result<Option<RETURN>, Self::Error>> + Send
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+        Self: Send,
+    {
+        async {
+            let previous = self.get_object_copy(key_with_parser).await?;
+            self.put_object_copy(key_with_parser, value).await?;
+            Ok(previous)
+        }
+    }
+
+    #[inline]
+    fn preview_put_object_copy<VALUE, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<DKEY, PARSER>,
+        value: &VALUE,
+    ) -> impl Future<Output = Result<PutPreview, Self::Error>> + Send
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+        Self: Sync,
+        Self::Error: From<ParserError>,
+    {
+        async move {
+            let size = key_with_parser.parser().serialized_size(value)?;
+            let previous = self.head_copy(key_with_parser.key()).await?;
+            Ok(PutPreview {
+                key: key_with_parser.key().name().into_owned(),
+                mime: key_with_parser.parser().mime(),
+                size,
+                replaces: previous.map(|meta| meta.size),
+            })
+        }
+    }
+
     fn put_bytes_copy<DKEY>(
         &mut self,
         key: &DKEY,
@@ -67,6 +140,127 @@ pub trait Sink {
     where
         DKEY: DKeyWhere;
 
+    #[inline]
+    fn put_bytes_with_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+        options: PutOptions,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send
+    where
+        DKEY: DKeyWhere,
+        Self: Send,
+    {
+        async move {
+            self.put_bytes_copy(key, mime, value).await?;
+            match options.lock {
+                Some(lock) => self.lock_copy(key, lock).await,
+                None => Ok(()),
+            }
+        }
+    }
+
+    fn lock_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        lock: ObjectLock,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send
+    where
+        DKEY: DKeyWhere;
+
+    fn delete_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send
+    where
+        DKEY: DKeyWhere;
+
+    #[inline]
+    fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send
+    where
+        DKEY: DKeyWhere,
+        Self: Send + Sync,
+    {
+        async move {
+            let mime = self
+                .head_copy(key)
+                .await?
+                .and_then(|meta| meta.mime)
+                .unwrap_or_default();
+            let mut content = self.get_bytes_copy(key).await?.unwrap_or_default();
+            content.extend(value);
+            self.put_bytes_copy(key, mime, content).await
+        }
+    }
+
+    /// Writes like `put_bytes_copy` and returns what the write reported about the stored object.
+    #[inline]
+    fn put_bytes_meta_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> impl Future<Output = Result<ObjectMeta, Self::Error>> + Send
+    where
+        DKEY: DKeyWhere,
+        Self: Send,
+    {
+        async move {
+            let meta = ObjectMeta {
+                mime: (!mime.is_empty()).then(|| mime.clone()),
+                ..ObjectMeta::from_bytes(&value)
+            };
+            self.put_bytes_copy(key, mime, value).await?;
+            Ok(meta)
+        }
+    }
+
+    /// Appends like `append_bytes_copy` and returns the metadata of the whole object.
+    #[inline]
+    fn append_bytes_meta_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> impl Future<Output = Result<ObjectMeta, Self::Error>> + Send
+    where
+        DKEY: DKeyWhere,
+        Self: Send + Sync,
+    {
+        async move {
+            self.append_bytes_copy(key, value).await?;
+            Ok(self.head_copy(key).await?.unwrap_or_default())
+        }
+    }
+
+    /// Bump the last-modified date of an object without changing its content, `false` when absent.
+    #[inline]
+    fn touch_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+    ) -> impl Future<Output = Result<bool, Self::Error>> + Send
+    where
+        DKEY: DKeyWhere,
+        Self: Send + Sync,
+    {
+        async move {
+            let Some(content) = self.get_bytes_copy(key).await? else {
+                return Ok(false);
+            };
+            let mime = self
+                .head_copy(key)
+                .await?
+                .and_then(|meta| meta.mime)
+                .unwrap_or_default();
+            self.put_bytes_copy(key, mime, content).await?;
+            Ok(true)
+        }
+    }
+
     fn get_object_copy<RETURN, DKEY, PARSER>(
         &self,
         key_with_parser: &DKeyWithParserCopy<DKEY, PARSER>,
@@ -76,17 +270,238 @@ pub trait Sink {
         DKEY: DKeyWhere,
         PARSER: ParserWhere;
 
+    fn get_bytes_copy<DKEY>(
+        &self,
+        key: &DKEY,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, Self::Error>> + Send
+    where
+        DKEY: DKeyWhere;
+
+    /// Fetch every key concurrently, missing objects are left out of the map and a failed key
+    /// only fails its own entry.
+    #[inline]
+    fn get_map_copy<RETURN, DKEY, PARSER>(
+        &self,
+        keys: &[DKEY],
+        parser: &PARSER,
+    ) -> impl Future<Output = HashMap<String, Result<RETURN, Self::Error>>> + Send
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+        Self: Sync,
+        Self::Error: Send,
+    {
+        async move {
+            let keys_with_parser = keys
+                .iter()
+                .map(|key| DKeyWithParserCopy::new(key, parser))
+                .collect::<Vec<_>>();
+            let fetched = join_all(
+                keys_with_parser
+                    .iter()
+                    .map(|key_with_parser| self.get_object_copy(key_with_parser)),
+            )
+            .await;
+
+            let mut objects: HashMap<_, _> =
+                HashMap::with_capacity_and_hasher(fetched.len(), Default::default());
+            for (key, object) in keys.iter().zip(fetched) {
+                if let Some(object) = object.transpose() {
+                    objects.insert(key.name().into_owned(), object);
+                }
+            }
+            objects
+        }
+    }
+
+    #[inline]
+    fn head_copy<DKEY>(
+        &self,
+        key: &DKEY,
+    ) -> impl Future<Output = Result<Option<ObjectMeta>, Self::Error>> + Send
+    where
+        DKEY: DKeyWhere,
+        Self: Sync,
+    {
+        async {
+            Ok(self
+                .get_bytes_copy(key)
+                .await?
+                .map(|value| ObjectMeta::from_bytes(&value)))
+        }
+    }
+
+    #[inline]
+    fn get_lazy_copy<DKEY>(
+        &self,
+        key: &DKEY,
+    ) -> impl Future<Output = Result<Option<ObjectHandle>, Self::Error>> + Send
+    where
+        DKEY: DKeyWhere,
+        Self: Sync,
+    {
+        async {
+            let Some(bytes) = self.get_bytes_copy(key).await? else {
+                return Ok(None);
+            };
+            let meta = match self.head_copy(key).await? {
+                Some(meta) => meta,
+                None => ObjectMeta::from_bytes(&bytes),
+            };
+            Ok(Some(ObjectHandle::new(
+                key.name().into_owned(),
+                meta,
+                bytes,
+            )))
+        }
+    }
+
+    #[inline]
+    fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, Self::Error>> + Send
+    where
+        DKEY: DKeyWhere,
+        Self: Sync,
+    {
+        async move {
+            Ok(self
+                .get_bytes_copy(key)
+                .await?
+                .map(|value| slice_range(&value, &range)))
+        }
+    }
+
     fn list_objects_copy(
         &self,
         prefix: &str,
     ) -> impl Future<Output = Result<ListKeyObjects, Self::Error>> + Send;
+
+    /// Walks the whole prefix then heads one page of keys after `continuation`.
+    #[inline]
+    fn list_flat_page_copy(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> impl Future<Output = Result<ListPage, Self::Error>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let mut keys = walk_page(self, prefix, continuation.as_deref(), FLAT_PAGE_SIZE + 1)
+                .await?
+                .into_iter()
+                .peekable();
+            let mut entries = vec![];
+            for key in keys.by_ref().take(FLAT_PAGE_SIZE) {
+                let meta = self.head_copy(&key).await?;
+                entries.push(ListEntry {
+                    key,
+                    size: meta.map_or(0, |meta| meta.size),
+                });
+            }
+            let next = keys
+                .peek()
+                .is_some()
+                .then(|| entries.last().map(|entry| entry.key.clone()))
+                .flatten();
+            Ok(ListPage { entries, next })
+        }
+    }
+
+    fn health_copy(&self) -> impl Future<Output = HealthReport> + Send;
+}
+
+/// Turn the `Ok(None)` of a missing object into the caller's error, for code written against
+/// backends that used to fail on absence.
+pub trait Required<VALUE, ERROR> {
+    fn required<MISSING>(self, missing: MISSING) -> Result<VALUE, ERROR>
+    where
+        MISSING: FnOnce() -> ERROR;
+}
+
+impl<VALUE, ERROR> Required<VALUE, ERROR> for Result<Option<VALUE>, ERROR> {
+    #[inline]
+    fn required<MISSING>(self, missing: MISSING) -> Result<VALUE, ERROR>
+    where
+        MISSING: FnOnce() -> ERROR,
+    {
+        self?.ok_or_else(missing)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    #[default]
+    Use,
+    Bypass,
+    RefreshAfter(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PutPreview {
+    pub key: String,
+    pub mime: String,
+    pub size: u64,
+    pub replaces: Option<u64>,
+}
+
+impl PutPreview {
+    #[inline]
+    #[must_use]
+    pub const fn growth(&self) -> i64 {
+        let previous = match self.replaces {
+            Some(previous) => previous,
+            None => 0,
+        };
+        self.size as i64 - previous as i64
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PutOptions {
+    pub lock: Option<ObjectLock>,
+}
+
+impl PutOptions {
+    #[inline]
+    #[must_use]
+    pub const fn locked(lock: ObjectLock) -> Self {
+        Self { lock: Some(lock) }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GetOptions {
+    pub cache: CacheMode,
+}
+
+impl GetOptions {
+    #[inline]
+    #[must_use]
+    pub const fn bypass() -> Self {
+        Self {
+            cache: CacheMode::Bypass,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn refresh_after(max_age: Duration) -> Self {
+        Self {
+            cache: CacheMode::RefreshAfter(max_age),
+        }
+    }
 }
 
 pub trait Cache {
     type Error;
 
     fn exists_copy<DKEY, PARSER>(
-        &self,
+        &mut self,
         key_with_parser: &DKeyWithParserCopy<DKEY, PARSER>,
     ) -> impl Future<Output = Result<bool, Self::Error>> + Send
     where
@@ -125,6 +540,26 @@ pub trait Cache {
         DKEY: DKeyWhere,
         PARSER: ParserWhere;
 
+    #[inline]
+    fn put_object_returning_previous_copy<RETURN, VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<DKEY, PARSER>,
+        value: &VALUE,
+    ) -> impl Future<Output = Result<Option<RETURN>, Self::Error>> + Send
+    where
+        RETURN: Serialize + DeserializeOwned + Send + Sync,
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+        Self: Send,
+    {
+        async {
+            let previous = self.get_object_copy(key_with_parser).await?;
+            self.put_object_copy(key_with_parser, value).await?;
+            Ok(previous)
+        }
+    }
+
     fn put_bytes_copy<DKEY>(
         &mut self,
         key: &DKEY,
@@ -134,19 +569,111 @@ pub trait Cache {
     where
         DKEY: DKeyWhere;
 
+    /// Return the stored object, or compute it and store it only if nobody stored one meanwhile.
+    /// A writer beating us between the get and the conditional put wins, its value is returned.
+    #[inline]
+    fn get_or_put_with_copy<RETURN, DKEY, PARSER, COMPUTE, FUTURE>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<DKEY, PARSER>,
+        compute: COMPUTE,
+    ) -> impl Future<Output = Result<RETURN, Self::Error>> + Send
+    where
+        RETURN: Serialize + DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+        COMPUTE: FnOnce() -> FUTURE + Send,
+        FUTURE: Future<Output = RETURN> + Send,
+        Self: Send,
+    {
+        async {
+            if let Some(existing) = self.get_object_copy(key_with_parser).await? {
+                return Ok(existing);
+            }
+
+            let value = compute().await;
+            if self
+                .put_object_if_not_exists_copy(key_with_parser, &value)
+                .await?
+            {
+                return Ok(value);
+            }
+            Ok(self
+                .get_object_copy(key_with_parser)
+                .await?
+                .unwrap_or(value))
+        }
+    }
+
+    #[inline]
     fn get_object_copy<RETURN, DKEY, PARSER>(
         &mut self,
         key_with_parser: &DKeyWithParserCopy<DKEY, PARSER>,
     ) -> impl Future<Output = Result<Option<RETURN>, Self::Error>> + Send
+    where
+        RETURN: Serialize + DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+        Self: Send,
+    {
+        self.get_object_with_copy(key_with_parser, GetOptions::default())
+    }
+
+    fn get_object_with_copy<RETURN, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<DKEY, PARSER>,
+        options: GetOptions,
+    ) -> impl Future<Output = Result<Option<RETURN>, Self::Error>> + Send
     where
         RETURN: Serialize + DeserializeOwned + Send + Sync,
         DKEY: DKeyWhere,
         PARSER: ParserWhere;
 
+    /// Missing objects are left out of the map and a failed key only fails its own entry.
+    #[inline]
+    fn get_map_copy<RETURN, DKEY, PARSER>(
+        &mut self,
+        keys: &[DKEY],
+        parser: &PARSER,
+    ) -> impl Future<Output = HashMap<String, Result<RETURN, Self::Error>>> + Send
+    where
+        RETURN: Serialize + DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+        Self: Send,
+        Self::Error: Send,
+    {
+        async move {
+            let mut objects: HashMap<_, _> =
+                HashMap::with_capacity_and_hasher(keys.len(), Default::default());
+            for key in keys {
+                let object = self
+                    .get_object_copy(&DKeyWithParserCopy::new(key, parser))
+                    .await;
+                if let Some(object) = object.transpose() {
+                    objects.insert(key.name().into_owned(), object);
+                }
+            }
+            objects
+        }
+    }
+
+    #[inline]
     fn get_bytes_copy<DKEY>(
         &mut self,
         key: &DKEY,
     ) -> impl Future<Output = Result<Option<Vec<u8>>, Self::Error>> + Send
+    where
+        DKEY: DKeyWhere,
+        Self: Send,
+    {
+        self.get_bytes_with_copy(key, GetOptions::default())
+    }
+
+    fn get_bytes_with_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        options: GetOptions,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, Self::Error>> + Send
     where
         DKEY: DKeyWhere;
 
@@ -154,4 +681,6 @@ pub trait Cache {
         &mut self,
         prefix: &str,
     ) -> impl Future<Output = Result<ListKeyObjects, Self::Error>> + Send;
+
+    fn health_copy(&self) -> impl Future<Output = HealthReport> + Send;
 }
@@ -0,0 +1,213 @@
+use core::future::Future;
+use core::num::NonZeroUsize;
+
+use futures::stream::{self, StreamExt as _};
+
+use crate::storage::progress::{Progress, ProgressTracker};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorMode {
+    #[default]
+    CollectAll,
+    FailFast,
+}
+
+#[derive(Debug)]
+pub struct BulkReport<OUTPUT, ERROR> {
+    pub succeeded: Vec<(usize, OUTPUT)>,
+    pub failed: Vec<(usize, ERROR)>,
+    pub skipped: usize,
+}
+
+impl<OUTPUT, ERROR> BulkReport<OUTPUT, ERROR> {
+    #[inline]
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.skipped == 0
+    }
+
+    #[inline]
+    pub fn into_result(self) -> Result<Vec<OUTPUT>, ERROR> {
+        match self.failed.into_iter().next() {
+            Some((_, err)) => Err(err),
+            None => Ok(self
+                .succeeded
+                .into_iter()
+                .map(|(_, output)| output)
+                .collect()),
+        }
+    }
+}
+
+pub struct BulkExecutor<PROGRESS = ()> {
+    concurrency: NonZeroUsize,
+    mode: ErrorMode,
+    progress: PROGRESS,
+}
+
+impl BulkExecutor {
+    #[inline]
+    #[must_use]
+    pub const fn new(concurrency: NonZeroUsize) -> Self {
+        Self {
+            concurrency,
+            mode: ErrorMode::CollectAll,
+            progress: (),
+        }
+    }
+}
+
+impl<PROGRESS> BulkExecutor<PROGRESS>
+where
+    PROGRESS: Progress,
+{
+    #[inline]
+    #[must_use]
+    pub const fn with_mode(mut self, mode: ErrorMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Report each finished item, failed or not, with its index as the key.
+    #[inline]
+    #[must_use]
+    pub fn with_progress<OTHER>(self, progress: OTHER) -> BulkExecutor<OTHER>
+    where
+        OTHER: Progress,
+    {
+        BulkExecutor {
+            concurrency: self.concurrency,
+            mode: self.mode,
+            progress,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn concurrency(&self) -> NonZeroUsize {
+        self.concurrency
+    }
+
+    #[inline]
+    pub async fn run<ITEM, OPERATION, FUTURE, OUTPUT, ERROR>(
+        &self,
+        items: Vec<ITEM>,
+        operation: OPERATION,
+    ) -> BulkReport<OUTPUT, ERROR>
+    where
+        OPERATION: Fn(ITEM) -> FUTURE,
+        FUTURE: Future<Output = Result<OUTPUT, ERROR>>,
+    {
+        let total = items.len();
+        let mut tracker = ProgressTracker::new(&self.progress, Some(total as u64));
+        let mut report = BulkReport {
+            succeeded: vec![],
+            failed: vec![],
+            skipped: 0,
+        };
+        let mut pending = stream::iter(items.into_iter().enumerate())
+            .map(|(index, item)| {
+                let operation = operation(item);
+                async move { (index, operation.await) }
+            })
+            .buffer_unordered(self.concurrency.get());
+
+        while let Some((index, result)) = pending.next().await {
+            match result {
+                Ok(output) => report.succeeded.push((index, output)),
+                Err(err) => report.failed.push((index, err)),
+            }
+            tracker.advance(&index.to_string(), 0);
+            if self.mode == ErrorMode::FailFast && !report.failed.is_empty() {
+                break;
+            }
+        }
+
+        report.skipped = total - report.succeeded.len() - report.failed.len();
+        report.succeeded.sort_unstable_by_key(|&(index, _)| index);
+        report.failed.sort_unstable_by_key(|&(index, _)| index);
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::storage::copy::Sink;
+    use crate::storage::progress::ChannelProgress;
+    use crate::storage::sink::memory::Memory;
+
+    #[tokio::test]
+    async fn bounded_concurrency_in_order() {
+        let mut memory = Memory::default();
+        for index in 0..10_u8 {
+            memory
+                .put_bytes_copy(&format!("key/{index}"), String::new(), vec![index])
+                .await
+                .unwrap();
+        }
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let (progress, updates) = ChannelProgress::new();
+        let executor = BulkExecutor::new(NonZeroUsize::new(3).unwrap()).with_progress(progress);
+
+        let report = executor
+            .run((0..10).collect(), |index| {
+                let memory = &memory;
+                let running = &running;
+                let peak = &peak;
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                    let value = memory.get_bytes_copy(&format!("key/{index}")).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    value
+                }
+            })
+            .await;
+
+        assert!(report.is_success());
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        drop(executor);
+        let updates = updates.collect::<Vec<_>>().await;
+        assert_eq!(updates.len(), 10);
+        assert_eq!(
+            updates.last().map(|update| (update.done, update.total)),
+            Some((10, Some(10)))
+        );
+        assert_eq!(
+            report.into_result().unwrap(),
+            (0..10).map(|index| Some(vec![index])).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn collect_all_or_fail_fast() {
+        let operation = |index: usize| async move {
+            if index % 4 == 1 {
+                Err(index)
+            } else {
+                Ok(index)
+            }
+        };
+        let items = (0..8).collect::<Vec<_>>();
+
+        let all = BulkExecutor::new(NonZeroUsize::MIN)
+            .run(items.clone(), operation)
+            .await;
+        assert_eq!(all.succeeded.len(), 6);
+        assert_eq!(all.failed, vec![(1, 1), (5, 5)]);
+        assert_eq!(all.skipped, 0);
+
+        let fast = BulkExecutor::new(NonZeroUsize::MIN)
+            .with_mode(ErrorMode::FailFast)
+            .run(items, operation)
+            .await;
+        assert_eq!(fast.failed, vec![(1, 1)]);
+        assert_eq!(fast.skipped, 6);
+        assert_eq!(fast.into_result().unwrap_err(), 1);
+    }
+}
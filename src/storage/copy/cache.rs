@@ -1 +1,3 @@
+pub mod disk;
+pub mod filter;
 pub mod lru;
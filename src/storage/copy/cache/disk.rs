@@ -0,0 +1,529 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::storage::cache::disk::DiskCache;
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::{Cache, CacheMode, GetOptions, ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ListPage, ObjectLock, ObjectMeta};
+use crate::storage::{DKeyWhere, LayerError, ListKeyObjects, ParserError, ResultExt as _};
+
+impl<STORAGE> Sink for DiskCache<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+    <STORAGE as Sink>::Error: From<LayerError> + From<ParserError>,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        if self.exists_inner(&key_with_parser.key().name()) {
+            Ok(true)
+        } else {
+            self.storage().exists_copy(key_with_parser).await
+        }
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "disk",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        Sink::put_bytes_copy(
+            self,
+            key_with_parser.key(),
+            key_with_parser.parser().mime(),
+            serialize,
+        )
+        .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut()
+            .put_bytes_copy(key, mime, value.clone())
+            .await?;
+        Ok(self
+            .put_bytes_inner(key.name().into_owned(), &value)
+            .await?)
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().delete_copy(key).await?;
+        self.invalidate(&key.name())?;
+        Ok(())
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().lock_copy(key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().touch_copy(key).await
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut()
+            .append_bytes_copy(key, value.clone())
+            .await?;
+        Ok(self.append_bytes_inner(&key.name(), &value).await?)
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let content = Sink::get_bytes_copy(self, key_with_parser.key()).await?;
+        Ok(content
+            .map(|value| {
+                key_with_parser
+                    .parser()
+                    .deserialize_guarded(&value)
+                    .context("disk", "get_object", &key_with_parser.key().name())
+            })
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let name = key.name();
+
+        if let Some(from_disk) = self.get_bytes_inner(&name, None).await? {
+            Ok(Some(from_disk))
+        } else {
+            let from_storage = self.storage().get_bytes_copy(key).await?;
+
+            if let Some(ref value) = from_storage {
+                self.put_bytes_inner(name.into_owned(), value).await?;
+            }
+
+            Ok(from_storage)
+        }
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage().head_copy(key).await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        if let Some(from_disk) = self.get_range_inner(&key.name(), &range).await? {
+            Ok(Some(from_disk))
+        } else {
+            self.storage().get_range_copy(key, range).await
+        }
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.storage().list_objects_copy(prefix).await
+    }
+
+    #[inline]
+    async fn list_flat_page_copy(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<ListPage, Self::Error> {
+        self.storage()
+            .list_flat_page_copy(prefix, continuation)
+            .await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        let mut report = HealthReport::new("disk");
+        if !self.root().is_dir() {
+            report.reachable = false;
+            report.error = Some(format!("{} is not a directory", self.root().display()));
+        }
+        report.with_inner(self.storage().health_copy().await)
+    }
+}
+
+impl<STORAGE> Cache for DiskCache<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+    <STORAGE as Sink>::Error: From<LayerError> + From<ParserError>,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        Sink::exists_copy(self, key_with_parser).await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<&Self, Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        Sink::put_object_copy(self, key_with_parser, value).await?;
+        Ok(self)
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<&Self, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Sink::put_bytes_copy(self, key, mime, value).await?;
+        Ok(self)
+    }
+
+    #[inline]
+    async fn get_object_with_copy<RETURN, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        options: GetOptions,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: Serialize + DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let content = Cache::get_bytes_with_copy(self, key_with_parser.key(), options).await?;
+        Ok(content
+            .map(|value| {
+                key_with_parser
+                    .parser()
+                    .deserialize_guarded(&value)
+                    .context("disk", "get_object", &key_with_parser.key().name())
+            })
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn get_bytes_with_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        options: GetOptions,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let max_age = match options.cache {
+            CacheMode::Use => None,
+            CacheMode::Bypass => return self.storage().get_bytes_copy(key).await,
+            CacheMode::RefreshAfter(max_age) => Some(max_age),
+        };
+        let name = key.name();
+
+        if let Some(from_disk) = self.get_bytes_inner(&name, max_age).await? {
+            Ok(Some(from_disk))
+        } else {
+            let from_storage = self.storage().get_bytes_copy(key).await?;
+
+            if let Some(ref value) = from_storage {
+                self.put_bytes_inner(name.into_owned(), value).await?;
+            }
+
+            Ok(from_storage)
+        }
+    }
+
+    #[inline]
+    async fn list_objects_copy(&mut self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        Sink::list_objects_copy(self, prefix).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        Sink::health_copy(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::{env, fs};
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::storage::cache::lru::Lru;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::sink::memory::Memory;
+
+    fn root() -> PathBuf {
+        env::temp_dir().join(format!("negentropy-disk-{}", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn survives_restart() {
+        let root = root();
+        let size = NonZeroUsize::new(10).unwrap();
+        let mut disk = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        Sink::put_bytes_copy(&mut disk, &"one".to_owned(), String::new(), vec![4, 2])
+            .await
+            .unwrap();
+        drop(disk);
+
+        let reopened = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(
+            Sink::get_bytes_copy(&reopened, &"one".to_owned())
+                .await
+                .unwrap(),
+            Some(vec![4, 2]),
+            "must be served from disk, the new sink is empty"
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replay_and_compact_the_journal() {
+        let root = root();
+        let size = NonZeroUsize::new(2).unwrap();
+        let mut disk = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        for key in ["one", "two", "three", "two"] {
+            Sink::put_bytes_copy(&mut disk, &key.to_owned(), String::new(), vec![1])
+                .await
+                .unwrap();
+        }
+        assert!(disk.invalidate("three").unwrap());
+        let lines = |root: &PathBuf| {
+            fs::read_to_string(root.join("index"))
+                .unwrap()
+                .lines()
+                .count()
+        };
+        assert!(lines(&root) <= 2 * size.get(), "the journal is compacted");
+        drop(disk);
+
+        let reopened = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert!(reopened.exists_inner("two"));
+        assert_eq!(lines(&root), 1, "reopening compacts the journal");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn drop_partial_writes() {
+        let root = root();
+        let size = NonZeroUsize::new(4).unwrap();
+        let mut disk = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        Sink::put_bytes_copy(&mut disk, &"one".to_owned(), String::new(), vec![1; 64])
+            .await
+            .unwrap();
+        fs::write(root.join("crashed.tmp"), [1; 3]).unwrap();
+        drop(disk);
+
+        let reopened = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        let mut files = fs::read_dir(&root)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort_unstable();
+        assert_eq!(
+            files.len(),
+            2,
+            "only the index and one value remain: {files:?}"
+        );
+        assert!(files.iter().all(|file| !file.ends_with(".tmp")));
+        assert_eq!(
+            reopened.get_bytes_inner("one", None).await.unwrap(),
+            Some(vec![1; 64])
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn evict_removes_file() {
+        let root = root();
+        let size = NonZeroUsize::new(1).unwrap();
+        let mut disk = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        Sink::put_bytes_copy(&mut disk, &"one".to_owned(), String::new(), vec![1])
+            .await
+            .unwrap();
+        Sink::put_bytes_copy(&mut disk, &"two".to_owned(), String::new(), vec![2])
+            .await
+            .unwrap();
+
+        assert_eq!(disk.len(), 1);
+        assert!(!disk.exists_inner("one"));
+        assert_eq!(
+            fs::read_dir(&root).unwrap().count(),
+            2,
+            "only the index and `two` must stay on disk"
+        );
+        assert_eq!(
+            Sink::get_bytes_copy(&disk, &"one".to_owned())
+                .await
+                .unwrap(),
+            Some(vec![1]),
+            "evicted value must be read back from the sink"
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn append_to_cached_file() {
+        let root = root();
+        let size = NonZeroUsize::new(10).unwrap();
+        let mut disk = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        let key = "log".to_owned();
+        Sink::put_bytes_copy(&mut disk, &key, String::new(), vec![1])
+            .await
+            .unwrap();
+        disk.append_bytes_copy(&key, vec![2]).await.unwrap();
+
+        assert_eq!(
+            disk.get_bytes_inner("log", None).await.unwrap(),
+            Some(vec![1, 2])
+        );
+        assert_eq!(
+            disk.storage().get_bytes_inner("log"),
+            Some(vec![1, 2]),
+            "append must reach the sink"
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn range_from_disk() {
+        let root = root();
+        let size = NonZeroUsize::new(10).unwrap();
+        let mut disk = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        let key = "one".to_owned();
+        Sink::put_bytes_copy(&mut disk, &key, String::new(), vec![0, 1, 2, 3])
+            .await
+            .unwrap();
+        drop(disk);
+
+        let reopened = DiskCache::new(root.clone(), size, Memory::default()).unwrap();
+        assert_eq!(
+            reopened.get_range_copy(&key, 2..10).await.unwrap(),
+            Some(vec![2, 3]),
+            "must be served from disk, the new sink is empty"
+        );
+        assert_eq!(
+            reopened
+                .get_range_copy(&"two".to_owned(), 0..1)
+                .await
+                .unwrap(),
+            None
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn under_lru() {
+        let root = root();
+        let disk = DiskCache::new(
+            root.clone(),
+            NonZeroUsize::new(10).unwrap(),
+            Memory::default(),
+        )
+        .unwrap();
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), disk);
+        let key = "one".to_owned();
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+
+        Cache::put_object_copy(&mut lru, &key_with_parser, &42_u32)
+            .await
+            .unwrap();
+        assert_eq!(
+            Cache::get_object_copy::<u32, _, _>(&mut lru, &key_with_parser)
+                .await
+                .unwrap(),
+            Some(42)
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,300 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::cache::filter::ExistenceFilter;
+use crate::storage::clock::Clock;
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::{DKeyWhere, ListKeyObjects};
+
+impl<STORAGE, CLOCK> ExistenceFilter<STORAGE, CLOCK>
+where
+    STORAGE: Sink + Send + Sync,
+    CLOCK: Clock,
+{
+    #[inline]
+    pub async fn rebuild(&mut self, prefix: &str) -> Result<usize, STORAGE::Error> {
+        let keys = self.storage().list_objects_copy(prefix).await?;
+        self.rebuild_inner(prefix, keys.iter());
+        Ok(keys.len())
+    }
+
+    /// Rebuild every filter older than the max age, returning how many were rebuilt. Meant to run
+    /// periodically, for instance as an instance task, so stale prefixes get a filter back.
+    #[inline]
+    pub async fn rebuild_stale(&mut self) -> Result<usize, STORAGE::Error> {
+        let stale = self.stale_prefixes_inner();
+        for prefix in &stale {
+            self.rebuild(prefix).await?;
+        }
+        Ok(stale.len())
+    }
+}
+
+impl<STORAGE, CLOCK> Sink for ExistenceFilter<STORAGE, CLOCK>
+where
+    STORAGE: Sink + Send + Sync,
+    CLOCK: Clock,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        if self.might_exist_inner(&key_with_parser.key().name()) == Some(false) {
+            Ok(false)
+        } else {
+            self.storage().exists_copy(key_with_parser).await
+        }
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.storage_mut()
+            .put_object_copy(key_with_parser, value)
+            .await?;
+        self.insert_inner(&key_with_parser.key().name());
+        Ok(())
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().put_bytes_copy(key, mime, value).await?;
+        self.insert_inner(&key.name());
+        Ok(())
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().delete_copy(key).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().lock_copy(key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().touch_copy(key).await
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().append_bytes_copy(key, value).await?;
+        self.insert_inner(&key.name());
+        Ok(())
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.storage().get_object_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage().get_bytes_copy(key).await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        if self.might_exist_inner(&key.name()) == Some(false) {
+            Ok(None)
+        } else {
+            self.storage().head_copy(key).await
+        }
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        if self.might_exist_inner(&key.name()) == Some(false) {
+            Ok(None)
+        } else {
+            self.storage().get_range_copy(key, range).await
+        }
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.storage().list_objects_copy(prefix).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("existence_filter").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::storage::cache::filter::DEFAULT_MAX_AGE;
+    use crate::storage::clock::MockClock;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::sink::memory::Memory;
+
+    async fn exists<CLOCK>(filter: &ExistenceFilter<Memory, CLOCK>, key: &str) -> bool
+    where
+        CLOCK: Clock,
+    {
+        filter
+            .exists_copy(&DKeyWithParserCopy::new(&key.to_owned(), &Json))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn answers_from_filter() {
+        let mut memory = Memory::default();
+        for key in ["logs/a", "logs/b", "other"] {
+            memory
+                .put_bytes_copy(&key.to_owned(), String::new(), vec![])
+                .await
+                .unwrap();
+        }
+        let mut filter = ExistenceFilter::new(memory);
+
+        assert_eq!(filter.rebuild("logs/").await.unwrap(), 2);
+        assert!(exists(&filter, "logs/a").await);
+
+        filter
+            .storage_mut()
+            .put_bytes_copy(&"logs/c".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+        assert!(
+            !exists(&filter, "logs/c").await,
+            "absent at rebuild time, the sink must not be asked"
+        );
+        assert!(
+            exists(&filter, "other").await,
+            "prefix without filter must ask the sink"
+        );
+
+        filter
+            .put_bytes_copy(&"logs/d".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+        assert!(exists(&filter, "logs/d").await);
+    }
+
+    #[tokio::test]
+    async fn stale_filter_is_ignored() {
+        let mut filter = ExistenceFilter::new(Memory::default()).with_max_age(Duration::ZERO);
+        filter.rebuild("logs/").await.unwrap();
+        filter
+            .storage_mut()
+            .put_bytes_copy(&"logs/a".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+
+        assert!(exists(&filter, "logs/a").await);
+    }
+
+    #[tokio::test]
+    async fn max_age_with_clock() {
+        let clock = MockClock::new();
+        let mut filter = ExistenceFilter::new(Memory::default())
+            .with_max_age(Duration::from_secs(60))
+            .with_clock(clock.clone());
+        filter.rebuild("logs/").await.unwrap();
+        filter
+            .storage_mut()
+            .put_bytes_copy(&"logs/a".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+
+        assert!(!exists(&filter, "logs/a").await);
+        clock.advance(Duration::from_secs(61));
+        assert!(exists(&filter, "logs/a").await);
+    }
+
+    #[tokio::test]
+    async fn rebuild_stale_filters() {
+        let clock = MockClock::new();
+        let mut filter = ExistenceFilter::new(Memory::default()).with_clock(clock.clone());
+        filter.rebuild("logs/").await.unwrap();
+        filter
+            .storage_mut()
+            .put_bytes_copy(&"logs/a".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+        assert_eq!(filter.rebuild_stale().await.unwrap(), 0);
+        assert!(!exists(&filter, "logs/a").await);
+
+        clock.advance(DEFAULT_MAX_AGE + Duration::from_secs(1));
+        filter.rebuild("tmp/").await.unwrap();
+        assert_eq!(
+            filter.rebuild_stale().await.unwrap(),
+            1,
+            "the default max age expires the filter"
+        );
+        assert!(exists(&filter, "logs/a").await);
+        assert!(filter.might_exist_inner("logs/a").is_some());
+    }
+}
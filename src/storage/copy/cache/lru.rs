@@ -1,28 +1,110 @@
+use futures::future::join_all;
+use futures::FutureExt as _;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use crate::storage::cache::lru::Lru;
+use crate::storage::cache::lru::{ListMode, Lru};
+use crate::storage::clock::Clock;
 use crate::storage::copy::direct::DKeyWithParserCopy;
-use crate::storage::copy::{Cache, ParserWhere, Sink, ValueWhere};
-use crate::storage::{DKeyWhere, ListKeyObjects, LruError};
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::tiering::AccessFrequency;
+use crate::storage::copy::{Cache, CacheMode, GetOptions, ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::{DKeyWhere, ListKeyObjects, LruError, ResultExt as _};
+use crate::HashMap;
 
-impl<STORAGE> Cache for Lru<STORAGE>
+impl<STORAGE, CLOCK> Lru<STORAGE, CLOCK>
 where
     STORAGE: Sink + Send + Sync,
+    CLOCK: Clock,
+    LruError: From<<STORAGE as Sink>::Error>,
+{
+    #[inline]
+    pub async fn warm(&mut self, prefix: &str, limit: usize) -> Result<usize, LruError> {
+        self.warm_with(prefix, limit, |_| true).await
+    }
+
+    #[inline]
+    pub async fn warm_with<PREDICATE>(
+        &mut self,
+        prefix: &str,
+        limit: usize,
+        predicate: PREDICATE,
+    ) -> Result<usize, LruError>
+    where
+        PREDICATE: Fn(&str) -> bool,
+    {
+        let budget = limit.min(self.cap().get().saturating_sub(self.len()));
+        let listed = self.storage().list_objects_copy(prefix).await?;
+        let mut keys = listed
+            .into_iter()
+            .filter(|key| !key.ends_with('/') && !self.contains_inner(key) && predicate(key))
+            .collect::<Vec<_>>();
+        keys.sort_unstable();
+
+        let mut warmed = 0;
+        for key in keys.into_iter().take(budget) {
+            if self.bytes_left_inner() == Some(0) {
+                break;
+            }
+            let Some(value) = self.storage().get_bytes_copy(&key).await? else {
+                continue;
+            };
+            // Warming only fills free room, a value over the byte budget would evict hot entries.
+            if self
+                .bytes_left_inner()
+                .is_none_or(|left| value.len() <= left)
+            {
+                self.put_bytes_inner(&key, value);
+                warmed += 1;
+            }
+        }
+
+        Ok(warmed)
+    }
+}
+
+impl<STORAGE, CLOCK> AccessFrequency for Lru<STORAGE, CLOCK>
+where
+    STORAGE: Send + Sync,
+    CLOCK: Clock,
+{
+    #[inline]
+    fn reads(&self, key: &str) -> Option<u64> {
+        self.heat_map().and_then(|heat| heat.reads(key))
+    }
+}
+
+impl<STORAGE, CLOCK> Cache for Lru<STORAGE, CLOCK>
+where
+    STORAGE: Sink + Send + Sync,
+    CLOCK: Clock,
     LruError: From<<STORAGE as Sink>::Error>,
 {
     type Error = LruError;
 
     #[inline]
     async fn exists_copy<DKEY, PARSER>(
-        &self,
+        &mut self,
         key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
     ) -> Result<bool, Self::Error>
     where
         DKEY: DKeyWhere,
         PARSER: ParserWhere,
     {
-        Ok(self.exists_inner(&key_with_parser.key().name()))
+        let name = key_with_parser.key().name();
+
+        if self.exists_inner(&name) {
+            Ok(true)
+        } else {
+            let exists = self.storage().exists_copy(key_with_parser).await?;
+
+            if exists {
+                self.mark_exists_inner(&name);
+            }
+
+            Ok(exists)
+        }
     }
 
     #[inline]
@@ -37,19 +119,21 @@ where
         PARSER: ParserWhere,
     {
         let serialize =
-            self.put_object_inner(key_with_parser.key().name(), value, |value_to_serialize| {
+            self.put_object_inner(&key_with_parser.key().name(), value, |value_to_serialize| {
                 Ok(key_with_parser
                     .parser()
-                    .serialize_value(value_to_serialize)?)
+                    .serialize_guarded(value_to_serialize)
+                    .context("lru", "put_object", &key_with_parser.key().name())?)
             })?;
 
-        self.storage()
+        self.storage_mut()
             .put_bytes_copy(
                 key_with_parser.key(),
                 key_with_parser.parser().mime(),
                 serialize,
             )
             .await?;
+        self.mark_dirty_inner(&key_with_parser.key().name());
 
         Ok(self)
     }
@@ -64,48 +148,957 @@ where
     where
         DKEY: DKeyWhere,
     {
-        self.put_bytes_inner(key.name(), value.clone());
-        self.storage().put_bytes_copy(key, mime, value).await?;
+        let name = key.name();
+        self.put_bytes_inner(&name, value.clone());
+        self.storage_mut().put_bytes_copy(key, mime, value).await?;
+        self.mark_dirty_inner(&name);
         Ok(self)
     }
 
     #[inline]
-    async fn get_object_copy<RETURN, DKEY, PARSER>(
+    async fn get_object_with_copy<RETURN, DKEY, PARSER>(
         &mut self,
         key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        options: GetOptions,
     ) -> Result<Option<RETURN>, Self::Error>
     where
         RETURN: Serialize + DeserializeOwned + Send + Sync,
         DKEY: DKeyWhere,
         PARSER: ParserWhere,
     {
-        let from_cache = self.get_object_cache_inner(&key_with_parser.key().name(), |value| {
-            Ok(key_with_parser.parser().deserialize_value(value)?)
-        })?;
+        let max_age = match options.cache {
+            CacheMode::Use => None,
+            CacheMode::Bypass => {
+                return Ok(self.storage().get_object_copy(key_with_parser).await?)
+            }
+            CacheMode::RefreshAfter(max_age) => Some(max_age),
+        };
+        let from_cache =
+            self.get_object_cache_inner(&key_with_parser.key().name(), max_age, |value| {
+                Ok(key_with_parser
+                    .parser()
+                    .deserialize_guarded(value)
+                    .context("lru", "get_object", &key_with_parser.key().name())?)
+            })?;
 
         if let Some(value_from_cache) = from_cache {
-            Ok(Some(value_from_cache))
-        } else {
-            let get_object_copy = self.storage().get_object_copy(key_with_parser).await?;
+            return Ok(Some(value_from_cache));
+        }
+
+        // A miss only fills the cache, writing back would touch the sink on a read.
+        let name = key_with_parser.key().name();
+        let Some(bytes) = self.storage().get_bytes_copy(key_with_parser.key()).await? else {
+            return Ok(None);
+        };
+        let value = key_with_parser
+            .parser()
+            .deserialize_guarded(&bytes)
+            .context("lru", "get_object", &name)?;
+        self.put_bytes_inner(&name, bytes);
+        Ok(Some(value))
+    }
 
-            if let Some(ref value) = get_object_copy {
-                self.put_object_copy(key_with_parser, value).await?;
+    #[inline]
+    async fn get_map_copy<RETURN, DKEY, PARSER>(
+        &mut self,
+        keys: &[DKEY],
+        parser: &PARSER,
+    ) -> HashMap<String, Result<RETURN, Self::Error>>
+    where
+        RETURN: Serialize + DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let mut objects: HashMap<_, _> =
+            HashMap::with_capacity_and_hasher(keys.len(), Default::default());
+        let mut misses = vec![];
+        for key in keys {
+            let name = key.name();
+            match self.get_object_cache_inner(&name, None, |value| {
+                Ok(parser
+                    .deserialize_guarded(value)
+                    .context("lru", "get_objects", &name)?)
+            }) {
+                Ok(Some(value)) => {
+                    objects.insert(name.into_owned(), Ok(value));
+                }
+                Ok(None) => misses.push(key),
+                Err(err) => {
+                    objects.insert(name.into_owned(), Err(err));
+                }
             }
+        }
 
-            Ok(get_object_copy)
+        // Only the misses reach the sink, all at once; hits were served without awaiting.
+        let fetched = join_all(misses.iter().map(|&key| {
+            self.storage()
+                .get_bytes_copy(key)
+                .map(|bytes| bytes.map_err(LruError::from))
+        }))
+        .await;
+        for (key, bytes) in misses.into_iter().zip(fetched) {
+            let name = key.name().into_owned();
+            match bytes {
+                Ok(Some(bytes)) => {
+                    match parser
+                        .deserialize_guarded(&bytes)
+                        .context("lru", "get_objects", &name)
+                    {
+                        Ok(value) => {
+                            self.put_bytes_inner(&name, bytes);
+                            objects.insert(name, Ok(value));
+                        }
+                        Err(err) => {
+                            objects.insert(name, Err(err.into()));
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    objects.insert(name, Err(err));
+                }
+            }
         }
+        objects
     }
 
     #[inline]
     async fn list_objects_copy(&mut self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
-        Ok(self.list_objects_inner(prefix))
+        if self.list_mode() == ListMode::CacheOnly {
+            return Ok(self.list_cached_inner(prefix));
+        }
+
+        let mut list = if let Some(from_cache) = self.list_objects_inner(prefix) {
+            from_cache
+        } else {
+            let list = self.storage().list_objects_copy(prefix).await?;
+            self.put_list_inner(prefix.to_owned(), list.clone());
+            list
+        };
+        // A listing may lag behind a write but a read does not, whatever the sink no longer
+        // has was deleted behind the cache.
+        for key in self.unlisted_dirty_inner(prefix, &list) {
+            if self.storage().head_copy(&key).await?.is_none() {
+                self.forget_dirty_inner(&key);
+            }
+        }
+        self.merge_dirty_inner(prefix, &mut list);
+        Ok(list)
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("lru").with_inner(self.storage().health_copy().await)
     }
 
     #[inline]
-    async fn get_bytes_copy<DKEY>(&mut self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    async fn get_bytes_with_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        options: GetOptions,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
     where
         DKEY: DKeyWhere,
     {
-        Ok(self.get_bytes_inner(key.name().as_str()))
+        let max_age = match options.cache {
+            CacheMode::Use => None,
+            CacheMode::Bypass => return Ok(self.storage().get_bytes_copy(key).await?),
+            CacheMode::RefreshAfter(max_age) => Some(max_age),
+        };
+        let name = key.name();
+
+        if let Some(from_cache) = self.get_bytes_inner(&name, max_age) {
+            Ok(Some(from_cache))
+        } else {
+            let from_storage = self.storage().get_bytes_copy(key).await?;
+
+            if let Some(ref value) = from_storage {
+                self.put_bytes_inner(&name, value.clone());
+            }
+
+            Ok(from_storage)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use core::time::Duration;
+    use std::num::{NonZeroU64, NonZeroUsize};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::storage::cache::heat::HeatMap;
+    use crate::storage::cache::lru::AdaptiveSizing;
+    use crate::storage::cache::policy::EvictionPolicy;
+    use crate::storage::clock::MockClock;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::layer::Layer;
+    use crate::storage::sink::chunked::Chunked;
+    use crate::storage::sink::memory::Memory;
+    use crate::HashSet;
+
+    async fn memory_with(keys: &[&str]) -> Memory {
+        let mut memory = Memory::default();
+        for key in keys {
+            memory
+                .put_bytes_copy(&(*key).to_owned(), String::new(), key.as_bytes().to_vec())
+                .await
+                .unwrap();
+        }
+        memory
+    }
+
+    #[tokio::test]
+    async fn warm() {
+        let memory = memory_with(&["logs/a", "logs/b", "logs/sub/c", "other"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory);
+
+        assert_eq!(lru.warm("logs/", 10).await.unwrap(), 2);
+        assert_eq!(lru.len(), 2);
+        assert_eq!(
+            lru.get_bytes_copy(&"logs/a".to_owned()).await.unwrap(),
+            Some(b"logs/a".to_vec())
+        );
+        assert_eq!(
+            lru.warm("logs/", 10).await.unwrap(),
+            0,
+            "already cached keys must not be fetched again"
+        );
+    }
+
+    #[tokio::test]
+    async fn read_locked_objects() {
+        use std::time::SystemTime;
+
+        use crate::storage::meta::ObjectLock;
+
+        let mut memory = Memory::default();
+        memory
+            .put_bytes_inner("locked", "application/json".to_owned(), b"42".to_vec())
+            .unwrap();
+        memory
+            .lock_copy(
+                &"locked".to_owned(),
+                ObjectLock::governance(SystemTime::now() + Duration::from_secs(3600)),
+            )
+            .await
+            .unwrap();
+        let mut lru = Lru::new(NonZeroUsize::new(4).unwrap(), memory);
+        let key = "locked".to_owned();
+
+        for _ in 0..2 {
+            assert_eq!(
+                lru.get_object_copy::<u32, _, _>(&DKeyWithParserCopy::new(&key, &Json))
+                    .await
+                    .unwrap(),
+                Some(42)
+            );
+        }
+        assert_eq!(lru.stats().hits, 1, "the miss must fill the cache");
+        assert!(lru
+            .unlisted_dirty_inner("", &ListKeyObjects::default())
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn warm_respects_capacity() {
+        let memory = memory_with(&["logs/a", "logs/b", "logs/c"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(2).unwrap(), memory);
+        lru.put_bytes_copy(&"hot".to_owned(), String::new(), vec![1])
+            .await
+            .unwrap();
+
+        assert_eq!(lru.warm("logs/", 10).await.unwrap(), 1);
+        assert_eq!(
+            lru.get_bytes_copy(&"hot".to_owned()).await.unwrap(),
+            Some(vec![1]),
+            "warm must not evict entries already cached"
+        );
+    }
+
+    #[tokio::test]
+    async fn warm_respects_byte_budget() {
+        let memory = memory_with(&["logs/a", "logs/bb", "logs/c"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory).with_soft_limit(13);
+        lru.put_bytes_copy(&"hot".to_owned(), String::new(), vec![1])
+            .await
+            .unwrap();
+
+        assert_eq!(lru.warm("logs/", 10).await.unwrap(), 2);
+        assert!(lru.contains_inner("hot"));
+        assert!(!lru.contains_inner("logs/bb"), "logs/bb does not fit");
+        assert_eq!(lru.stats().bytes, 13);
+    }
+
+    #[tokio::test]
+    async fn health() {
+        let lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let report = lru.health_copy().await;
+
+        assert!(report.is_healthy());
+        assert_eq!(report.backend, "lru");
+        assert_eq!(report.inner.first().unwrap().backend, "memory");
+    }
+
+    #[tokio::test]
+    async fn exists_from_sink() {
+        let memory = memory_with(&["one"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory);
+        let key = "one".to_owned();
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+
+        assert!(lru.exists_copy(&key_with_parser).await.unwrap());
+        assert!(lru.exists_inner("one"), "positive answer must be cached");
+        lru.get_bytes_copy(&key).await.unwrap();
+        assert_eq!(
+            lru.intern_stats().references,
+            2,
+            "exists set and entries must share the key"
+        );
+        assert!(!lru
+            .exists_copy(&DKeyWithParserCopy::new(&"two".to_owned(), &Json))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn put_if_not_exists_keeps_foreign_write() {
+        let memory = memory_with(&["one"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory);
+        let key = "one".to_owned();
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+
+        assert!(!lru
+            .put_object_if_not_exists_copy(&key_with_parser, &42_u32)
+            .await
+            .unwrap());
+        assert_eq!(
+            lru.get_bytes_copy(&key).await.unwrap(),
+            Some(b"one".to_vec()),
+            "object written by another process must not be overwritten"
+        );
+    }
+
+    #[tokio::test]
+    async fn get_or_put_with_computes_once() {
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let key = "answer".to_owned();
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+
+        assert_eq!(
+            lru.get_or_put_with_copy(&key_with_parser, || async { 42_u32 })
+                .await
+                .unwrap(),
+            42
+        );
+        assert_eq!(
+            lru.get_or_put_with_copy(&key_with_parser, || async { 7_u32 })
+                .await
+                .unwrap(),
+            42,
+            "stored value must be reused"
+        );
+        assert_eq!(
+            lru.storage()
+                .get_object_copy::<u32, _, _>(&key_with_parser)
+                .await
+                .unwrap(),
+            Some(42)
+        );
+    }
+
+    #[tokio::test]
+    async fn get_map_fetches_misses_only() {
+        let memory = memory_with(&["two", "three"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory);
+        lru.put_object_copy(
+            &DKeyWithParserCopy::new(&"one".to_owned(), &Json),
+            &"cached",
+        )
+        .await
+        .unwrap();
+        lru.storage_mut()
+            .put_object_copy(
+                &DKeyWithParserCopy::new(&"two".to_owned(), &Json),
+                &"stored",
+            )
+            .await
+            .unwrap();
+
+        let objects = lru
+            .get_map_copy::<String, _, _>(
+                &["one".to_owned(), "two".to_owned(), "missing".to_owned()],
+                &Json,
+            )
+            .await;
+
+        assert_eq!(
+            objects
+                .into_iter()
+                .map(|(key, value)| (key, value.unwrap()))
+                .collect::<HashMap<_, _>>(),
+            HashMap::from_iter([
+                ("one".to_owned(), "cached".to_owned()),
+                ("two".to_owned(), "stored".to_owned()),
+            ])
+        );
+        assert_eq!(lru.stats().hits, 1);
+        assert!(lru.peek("two").is_some(), "a fetched miss must be cached");
+    }
+
+    #[tokio::test]
+    async fn get_bytes_miss_then_hit() {
+        let memory = memory_with(&["one"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory);
+        assert!(lru.is_empty());
+
+        assert_eq!(
+            lru.get_bytes_copy(&"one".to_owned()).await.unwrap(),
+            Some(b"one".to_vec()),
+            "miss must fall through to the sink"
+        );
+        assert_eq!(lru.len(), 1, "miss must populate the cache");
+
+        lru.storage_mut()
+            .put_bytes_copy(&"one".to_owned(), String::new(), vec![0])
+            .await
+            .unwrap();
+        assert_eq!(
+            lru.get_bytes_copy(&"one".to_owned()).await.unwrap(),
+            Some(b"one".to_vec()),
+            "hit must be served from the cache"
+        );
+    }
+
+    #[tokio::test]
+    async fn get_bytes_missing() {
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+
+        assert_eq!(lru.get_bytes_copy(&"one".to_owned()).await.unwrap(), None);
+        assert!(lru.is_empty());
+    }
+
+    #[tokio::test]
+    async fn put_returning_previous() {
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let key = "counter".to_owned();
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+
+        assert_eq!(
+            Cache::put_object_returning_previous_copy::<u32, _, _, _>(
+                &mut lru,
+                &key_with_parser,
+                &1_u32
+            )
+            .await
+            .unwrap(),
+            None
+        );
+        assert_eq!(
+            Cache::put_object_returning_previous_copy::<u32, _, _, _>(
+                &mut lru,
+                &key_with_parser,
+                &2_u32
+            )
+            .await
+            .unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            lru.storage()
+                .get_object_copy::<u32, _, _>(&key_with_parser)
+                .await
+                .unwrap(),
+            Some(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn list_from_sink() {
+        let memory = memory_with(&["logs/a", "logs/b"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory);
+
+        assert_eq!(
+            lru.list_objects_copy("logs/").await.unwrap(),
+            vec!["logs/a".to_owned(), "logs/b".to_owned()]
+                .into_iter()
+                .collect::<HashSet<_>>(),
+            "must list keys never read through the cache"
+        );
+
+        lru.storage_mut()
+            .put_bytes_copy(&"logs/c".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+        assert_eq!(
+            lru.list_objects_copy("logs/").await.unwrap().len(),
+            2,
+            "must be served from the list cache within the ttl"
+        );
+
+        lru.put_bytes_copy(&"logs/d".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+        assert_eq!(
+            lru.list_objects_copy("logs/").await.unwrap().len(),
+            4,
+            "a write under the prefix must invalidate its listing"
+        );
+    }
+
+    #[tokio::test]
+    async fn list_merges_dirty_keys() {
+        let memory = memory_with(&["logs/a"]).await;
+        let mut lagging =
+            Lru::new(NonZeroUsize::new(10).unwrap(), memory).with_list_ttl(Duration::from_secs(60));
+        lagging.list_objects_copy("logs/").await.unwrap();
+        // Stand-in for a sink whose listing lags behind its writes: the stale listing is cached.
+        lagging
+            .storage_mut()
+            .put_bytes_copy(&"logs/b".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+        lagging.mark_dirty_inner("logs/b");
+        lagging.mark_dirty_inner("other/c");
+        assert_eq!(
+            lagging.list_objects_copy("logs/").await.unwrap(),
+            ["logs/a", "logs/b"]
+                .map(ToOwned::to_owned)
+                .into_iter()
+                .collect::<HashSet<_>>(),
+            "a key written through the cache must be listed before the sink shows it"
+        );
+
+        lagging
+            .storage_mut()
+            .delete_copy(&"logs/b".to_owned())
+            .await
+            .unwrap();
+        assert!(
+            !lagging
+                .list_objects_copy("logs/")
+                .await
+                .unwrap()
+                .contains("logs/b"),
+            "a key deleted behind the cache is not listed"
+        );
+
+        let memory = memory_with(&["logs/a"]).await;
+        let mut lru =
+            Lru::new(NonZeroUsize::new(10).unwrap(), memory).with_list_ttl(Duration::ZERO);
+        lru.put_bytes_copy(&"logs/deep/c".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+        lru.storage_mut()
+            .delete_copy(&"logs/deep/c".to_owned())
+            .await
+            .unwrap();
+        assert!(
+            lru.list_objects_copy("logs/deep/")
+                .await
+                .unwrap()
+                .is_empty(),
+            "the phantom of a deleted key is not listed"
+        );
+        lru.put_bytes_copy(&"logs/e".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+        lru.list_objects_copy("logs/").await.unwrap();
+        lru.storage_mut()
+            .delete_copy(&"logs/e".to_owned())
+            .await
+            .unwrap();
+        assert!(
+            !lru.list_objects_copy("logs/")
+                .await
+                .unwrap()
+                .contains("logs/e"),
+            "a key seen by the sink is no longer tracked as dirty"
+        );
+    }
+
+    #[tokio::test]
+    async fn list_cache_only() {
+        let memory = memory_with(&["logs/a", "logs/b"]).await;
+        let mut lru =
+            Lru::new(NonZeroUsize::new(10).unwrap(), memory).with_list_mode(ListMode::CacheOnly);
+        assert!(lru.list_objects_copy("logs/").await.unwrap().is_empty());
+
+        lru.get_bytes_copy(&"logs/a".to_owned()).await.unwrap();
+        assert_eq!(
+            lru.list_objects_copy("logs/").await.unwrap(),
+            ["logs/a".to_owned()].into_iter().collect::<HashSet<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn list_ttl_expire() {
+        let memory = memory_with(&["logs/a"]).await;
+        let mut lru =
+            Lru::new(NonZeroUsize::new(10).unwrap(), memory).with_list_ttl(Duration::ZERO);
+        lru.list_objects_copy("logs/").await.unwrap();
+
+        lru.storage_mut()
+            .put_bytes_copy(&"logs/b".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+        assert_eq!(lru.list_objects_copy("logs/").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn list_ttl_with_clock() {
+        let clock = MockClock::new();
+        let memory = memory_with(&["logs/a"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory)
+            .with_list_ttl(Duration::from_secs(30))
+            .with_clock(clock.clone());
+        lru.list_objects_copy("logs/").await.unwrap();
+        lru.storage_mut()
+            .put_bytes_copy(&"logs/b".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_secs(29));
+        assert_eq!(lru.list_objects_copy("logs/").await.unwrap().len(), 1);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(lru.list_objects_copy("logs/").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn peek_promote_and_entries() {
+        let clock = MockClock::new();
+        let mut lru =
+            Lru::new(NonZeroUsize::new(2).unwrap(), Memory::default()).with_clock(clock.clone());
+        lru.put_bytes_copy(&"one".to_owned(), String::new(), vec![1])
+            .await
+            .unwrap();
+        clock.advance(Duration::from_secs(10));
+        lru.put_bytes_copy(&"two".to_owned(), String::new(), vec![2, 2])
+            .await
+            .unwrap();
+        clock.advance(Duration::from_secs(5));
+
+        assert_eq!(lru.peek("one"), Some([1_u8].as_slice()));
+        assert_eq!((lru.stats().hits, lru.stats().misses), (0, 0));
+        let entries = lru.entries().collect::<Vec<_>>();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.key.as_ref(), entry.size, entry.idle))
+                .collect::<Vec<_>>(),
+            [
+                ("two", 2, Duration::from_secs(5)),
+                ("one", 1, Duration::from_secs(15))
+            ],
+            "peek must not change the recency"
+        );
+
+        assert!(lru.promote("one"));
+        assert!(!lru.promote("missing"));
+        let one = lru.entries().next().unwrap();
+        assert_eq!(one.key.as_ref(), "one");
+        assert_eq!(
+            (one.age, one.idle),
+            (Duration::from_secs(15), Duration::ZERO)
+        );
+
+        lru.put_bytes_copy(&"three".to_owned(), String::new(), vec![3])
+            .await
+            .unwrap();
+        assert!(
+            lru.peek("two").is_none(),
+            "\"two\" was the least recently used"
+        );
+    }
+
+    #[tokio::test]
+    async fn stats() {
+        let memory = memory_with(&["one"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(2).unwrap(), memory);
+        lru.get_bytes_copy(&"one".to_owned()).await.unwrap();
+        lru.get_bytes_copy(&"one".to_owned()).await.unwrap();
+        lru.put_bytes_copy(&"two".to_owned(), String::new(), vec![0; 10])
+            .await
+            .unwrap();
+        lru.put_bytes_copy(&"three".to_owned(), String::new(), vec![0; 5])
+            .await
+            .unwrap();
+
+        let stats = lru.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert!((stats.hit_ratio() - 0.5).abs() < f64::EPSILON);
+        assert_eq!(stats.evictions, 1, "\"one\" must be evicted by capacity");
+        assert_eq!(stats.bytes, 15);
+        assert_eq!((stats.entries, stats.capacity), (2, 2));
+    }
+
+    #[tokio::test]
+    async fn soft_limit_and_pressure_hook() {
+        let alerts = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&alerts);
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default())
+            .with_soft_limit(8)
+            .with_pressure_hook(1.0, move |stats| {
+                assert!(stats.evictions_per_sec > 1.0);
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+
+        for key in ["a", "b", "c", "d"] {
+            lru.put_bytes_copy(&key.to_owned(), String::new(), vec![0; 4])
+                .await
+                .unwrap();
+        }
+
+        let stats = lru.stats();
+        assert_eq!(stats.bytes, 8, "soft limit must evict down to the budget");
+        assert_eq!(stats.evictions, 2);
+        assert_eq!(
+            alerts.load(Ordering::SeqCst),
+            1,
+            "hook must fire once per window"
+        );
+    }
+
+    #[tokio::test]
+    async fn adaptive_sizing() {
+        let clock = MockClock::new();
+        let memory = memory_with(&["a", "b", "c", "d"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(8).unwrap(), memory)
+            .with_clock(clock.clone())
+            .with_adaptive_sizing(
+                AdaptiveSizing::new(
+                    NonZeroUsize::new(2).unwrap(),
+                    NonZeroUsize::new(10).unwrap(),
+                )
+                .with_interval(Duration::from_secs(10)),
+            );
+
+        for key in ["a", "b", "c", "d"] {
+            lru.get_bytes_copy(&key.to_owned()).await.unwrap();
+        }
+        assert_eq!(lru.cap().get(), 8, "must wait for the interval");
+        clock.advance(Duration::from_secs(10));
+        lru.get_bytes_copy(&"missing".to_owned()).await.unwrap();
+        assert_eq!(lru.cap().get(), 10, "misses must grow the cache up to max");
+
+        for _ in 0..10 {
+            lru.get_bytes_copy(&"a".to_owned()).await.unwrap();
+        }
+        clock.advance(Duration::from_secs(10));
+        lru.get_bytes_copy(&"a".to_owned()).await.unwrap();
+        assert_eq!(
+            lru.cap().get(),
+            9,
+            "hits above target must shrink the cache"
+        );
+    }
+
+    #[tokio::test]
+    async fn clock_after_adaptive_sizing() {
+        let clock = MockClock::new();
+        let memory = memory_with(&["a", "b"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(4).unwrap(), memory).with_adaptive_sizing(
+            AdaptiveSizing::new(NonZeroUsize::new(2).unwrap(), NonZeroUsize::new(8).unwrap())
+                .with_interval(Duration::ZERO),
+        );
+        for key in ["a", "b", "a"] {
+            lru.get_bytes_copy(&key.to_owned()).await.unwrap();
+        }
+        let stats = lru.stats();
+
+        let mut lru = lru.with_clock(clock.clone());
+        assert_eq!(lru.stats(), stats, "swapping the clock keeps the counters");
+        lru.get_bytes_copy(&"missing".to_owned()).await.unwrap();
+        assert_eq!(lru.stats().bytes, stats.bytes);
+        assert_eq!(lru.stats().misses, stats.misses + 1);
+    }
+
+    #[tokio::test]
+    async fn adaptive_sizing_respects_headroom() {
+        let clock = MockClock::new();
+        let memory = memory_with(&["aaaa", "bbbb", "cccc"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(3).unwrap(), memory)
+            .with_clock(clock.clone())
+            .with_adaptive_sizing(
+                AdaptiveSizing::new(
+                    NonZeroUsize::new(1).unwrap(),
+                    NonZeroUsize::new(10).unwrap(),
+                )
+                .with_max_bytes(8)
+                .with_interval(Duration::ZERO),
+            );
+
+        for key in ["aaaa", "bbbb", "cccc"] {
+            lru.get_bytes_copy(&key.to_owned()).await.unwrap();
+        }
+        lru.get_bytes_copy(&"missing".to_owned()).await.unwrap();
+
+        assert_eq!(lru.cap().get(), 2, "must shrink to the memory headroom");
+        assert_eq!(lru.stats().bytes, 8);
+        assert_eq!(
+            lru.stats().evictions,
+            1,
+            "shrinking must count its evictions"
+        );
+    }
+
+    #[tokio::test]
+    async fn segmented_resists_scan() {
+        let keys = ["a", "b", "c", "d", "e", "f", "g", "h"];
+
+        for (policy, survives) in [
+            (EvictionPolicy::Lru, false),
+            (EvictionPolicy::Segmented, true),
+        ] {
+            let memory = memory_with(&keys).await;
+            let mut lru = Lru::with_policy(NonZeroUsize::new(4).unwrap(), policy, memory);
+            for _ in 0..2 {
+                lru.get_bytes_copy(&"a".to_owned()).await.unwrap();
+                lru.get_bytes_copy(&"b".to_owned()).await.unwrap();
+            }
+            for key in keys.get(2..).unwrap() {
+                lru.get_bytes_copy(&(*key).to_owned()).await.unwrap();
+            }
+
+            assert_eq!(lru.len(), 4);
+            assert_eq!(
+                lru.contains_inner("a") && lru.contains_inner("b"),
+                survives,
+                "{policy:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn get_options() {
+        let clock = MockClock::new();
+        let memory = memory_with(&["one"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory).with_clock(clock.clone());
+        let key = "one".to_owned();
+        lru.get_bytes_copy(&key).await.unwrap();
+        lru.storage_mut()
+            .put_bytes_copy(&key, String::new(), b"1".to_vec())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            lru.get_bytes_copy(&key).await.unwrap(),
+            Some(b"one".to_vec())
+        );
+        assert_eq!(
+            lru.get_bytes_with_copy(&key, GetOptions::bypass())
+                .await
+                .unwrap(),
+            Some(b"1".to_vec()),
+            "bypass must read the sink"
+        );
+        assert_eq!(
+            lru.get_bytes_copy(&key).await.unwrap(),
+            Some(b"one".to_vec()),
+            "bypass must leave the cache untouched"
+        );
+
+        let refresh = GetOptions::refresh_after(Duration::from_secs(10));
+        assert_eq!(
+            lru.get_bytes_with_copy(&key, refresh).await.unwrap(),
+            Some(b"one".to_vec())
+        );
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(
+            lru.get_bytes_with_copy(&key, refresh).await.unwrap(),
+            Some(b"1".to_vec()),
+            "stale entry must be refreshed"
+        );
+        assert_eq!(
+            lru.get_object_with_copy::<u32, _, _>(
+                &DKeyWithParserCopy::new(&key, &Json),
+                GetOptions::default()
+            )
+            .await
+            .unwrap(),
+            Some(1),
+        );
+    }
+
+    #[test]
+    fn reach_inner_layers() {
+        let lru = Lru::new(
+            NonZeroUsize::new(10).unwrap(),
+            Chunked::new(NonZeroUsize::new(4).unwrap(), Memory::default()),
+        );
+
+        assert!(lru.downcast_inner::<Chunked<Memory>>().is_some());
+        assert!(lru.downcast_inner::<Memory>().is_none());
+        assert!(lru
+            .as_any()
+            .downcast_ref::<Lru<Chunked<Memory>>>()
+            .is_some());
+        assert!(lru.inner().inner().is_empty());
+        assert!(lru.into_inner().into_inner().is_empty());
+    }
+
+    #[tokio::test]
+    async fn warm_with_predicate() {
+        let memory = memory_with(&["logs/a.json", "logs/b.bin", "logs/c.json"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory);
+
+        assert_eq!(
+            lru.warm_with("logs/", 1, |key| key.ends_with(".json"))
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            lru.get_bytes_copy(&"logs/a.json".to_owned()).await.unwrap(),
+            Some(b"logs/a.json".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn heat_report() {
+        let memory = memory_with(&["logs/a", "logs/b", "logs/c", "other"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory)
+            .with_clock(MockClock::new())
+            .with_heat_map(HeatMap::new(NonZeroUsize::new(3).unwrap()));
+
+        for key in ["logs/a", "logs/b", "logs/a", "other", "logs/a", "logs/b"] {
+            lru.get_bytes_copy(&key.to_owned()).await.unwrap();
+        }
+        let report = lru.heat_report("logs/", 5);
+
+        assert_eq!(
+            report
+                .iter()
+                .map(|heat| (heat.key.as_str(), heat.reads))
+                .collect::<Vec<_>>(),
+            [("logs/a", 3), ("logs/b", 2)]
+        );
+        assert_eq!(lru.reads("other"), Some(1));
+
+        lru.get_bytes_copy(&"logs/c".to_owned()).await.unwrap();
+        assert_eq!(
+            lru.reads("other"),
+            None,
+            "bounded map drops the coldest key"
+        );
+        assert_eq!(lru.heat_report("", 1).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn heat_sampling() {
+        let memory = memory_with(&["one"]).await;
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory).with_heat_map(
+            HeatMap::new(NonZeroUsize::new(10).unwrap())
+                .with_sample_every(NonZeroU64::new(4).unwrap()),
+        );
+
+        for _ in 0..9 {
+            lru.get_bytes_copy(&"one".to_owned()).await.unwrap();
+        }
+
+        assert_eq!(lru.reads("one"), Some(8), "two samples weighted by four");
     }
 }
@@ -0,0 +1,246 @@
+use core::marker::PhantomData;
+use core::num::NonZeroUsize;
+use std::collections::VecDeque;
+
+use futures::stream::{self, Stream};
+use serde::de::DeserializeOwned;
+
+use super::direct::DKeyWithParserCopy;
+use super::parser::Json;
+use super::{Sink, ValueWhere};
+
+const COLLECTION_PREFIX: &str = "col/";
+const PART_PREFIX: &str = "part-";
+
+pub trait CollectionValue = ValueWhere + DeserializeOwned;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collection<VALUE> {
+    name: String,
+    part_size: NonZeroUsize,
+    value: PhantomData<fn() -> VALUE>,
+}
+
+impl<VALUE> Collection<VALUE>
+where
+    VALUE: CollectionValue,
+{
+    #[inline]
+    #[must_use]
+    pub fn new(name: &str, part_size: NonZeroUsize) -> Self {
+        Self {
+            name: name.to_owned(),
+            part_size,
+            value: PhantomData,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn part_size(&self) -> NonZeroUsize {
+        self.part_size
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn prefix(&self) -> String {
+        format!("{COLLECTION_PREFIX}{}/", self.name)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn part_key(&self, part: u64) -> String {
+        // Zero padded so the listing order is the push order.
+        format!("{}{PART_PREFIX}{part:08}", self.prefix())
+    }
+
+    #[inline]
+    pub async fn parts<SINK>(&self, sink: &SINK) -> Result<Vec<u64>, SINK::Error>
+    where
+        SINK: Sink + Sync,
+    {
+        let prefix = format!("{}{PART_PREFIX}", self.prefix());
+        let mut parts = sink
+            .list_objects_copy(&prefix)
+            .await?
+            .iter()
+            .filter_map(|key| key.strip_prefix(prefix.as_str())?.parse().ok())
+            .collect::<Vec<u64>>();
+        parts.sort_unstable();
+        Ok(parts)
+    }
+
+    #[inline]
+    pub async fn get_part<SINK>(&self, sink: &SINK, part: u64) -> Result<Vec<VALUE>, SINK::Error>
+    where
+        SINK: Sink + Sync,
+    {
+        Ok(sink
+            .get_object_copy(&DKeyWithParserCopy::new(&self.part_key(part), &Json))
+            .await?
+            .unwrap_or_default())
+    }
+
+    #[inline]
+    pub async fn push<SINK>(&self, sink: &mut SINK, value: VALUE) -> Result<(), SINK::Error>
+    where
+        SINK: Sink + Send + Sync,
+    {
+        self.extend(sink, vec![value]).await
+    }
+
+    #[inline]
+    pub async fn extend<SINK>(&self, sink: &mut SINK, values: Vec<VALUE>) -> Result<(), SINK::Error>
+    where
+        SINK: Sink + Send + Sync,
+    {
+        let (mut part, mut current) = match self.parts(sink).await?.last() {
+            Some(&last) => (last, self.get_part(sink, last).await?),
+            None => (0, vec![]),
+        };
+        let mut values = values.into_iter().peekable();
+
+        while values.peek().is_some() {
+            if current.len() >= self.part_size.get() {
+                part += 1;
+                current = vec![];
+            }
+            let room = self.part_size.get() - current.len();
+            current.extend(values.by_ref().take(room));
+            sink.put_object_copy(
+                &DKeyWithParserCopy::new(&self.part_key(part), &Json),
+                &current,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub async fn len<SINK>(&self, sink: &SINK) -> Result<usize, SINK::Error>
+    where
+        SINK: Sink + Sync,
+    {
+        let mut len = 0;
+        for part in self.parts(sink).await? {
+            len += self.get_part(sink, part).await?.len();
+        }
+        Ok(len)
+    }
+
+    #[inline]
+    pub fn iter<'sink, SINK>(
+        &'sink self,
+        sink: &'sink SINK,
+    ) -> impl Stream<Item = Result<VALUE, SINK::Error>> + 'sink
+    where
+        SINK: Sink + Sync,
+    {
+        stream::unfold(
+            (None, VecDeque::new()),
+            move |(mut parts, mut ready): (Option<VecDeque<u64>>, VecDeque<VALUE>)| async move {
+                loop {
+                    if let Some(value) = ready.pop_front() {
+                        return Some((Ok(value), (parts, ready)));
+                    }
+                    let pending = match parts {
+                        Some(ref mut pending) => pending,
+                        None => match self.parts(sink).await {
+                            Ok(listed) => parts.insert(listed.into()),
+                            Err(err) => return Some((Err(err), (Some(VecDeque::new()), ready))),
+                        },
+                    };
+                    let part = pending.pop_front()?;
+                    match self.get_part(sink, part).await {
+                        Ok(values) => ready.extend(values),
+                        Err(err) => return Some((Err(err), (Some(VecDeque::new()), ready))),
+                    }
+                }
+            },
+        )
+    }
+
+    /// Rewrite every value into full parts, dropping the trailing parts left empty.
+    #[inline]
+    pub async fn compact<SINK>(&self, sink: &mut SINK) -> Result<usize, SINK::Error>
+    where
+        SINK: Sink + Send + Sync,
+    {
+        let parts = self.parts(sink).await?;
+        let mut values = vec![];
+        for &part in &parts {
+            values.extend(self.get_part(sink, part).await?);
+        }
+
+        let mut written = 0;
+        for chunk in values.chunks(self.part_size.get()) {
+            sink.put_object_copy(
+                &DKeyWithParserCopy::new(&self.part_key(written), &Json),
+                &chunk,
+            )
+            .await?;
+            written += 1;
+        }
+        for part in parts.into_iter().filter(|&part| part >= written) {
+            sink.delete_copy(&self.part_key(part)).await?;
+        }
+
+        Ok(usize::try_from(written).unwrap_or(usize::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt as _;
+
+    use super::*;
+    use crate::storage::sink::memory::Memory;
+
+    #[tokio::test]
+    async fn push_iter_and_compact() {
+        let mut memory = Memory::default();
+        let collection = Collection::<u32>::new("events", NonZeroUsize::new(3).unwrap());
+
+        for value in 0..4 {
+            collection.push(&mut memory, value).await.unwrap();
+        }
+        collection
+            .extend(&mut memory, (4..7).collect())
+            .await
+            .unwrap();
+
+        assert_eq!(collection.parts(&memory).await.unwrap(), [0, 1, 2]);
+        assert_eq!(collection.get_part(&memory, 2).await.unwrap(), [6]);
+        assert_eq!(collection.len(&memory).await.unwrap(), 7);
+        assert_eq!(
+            collection
+                .iter(&memory)
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap(),
+            (0..7).collect::<Vec<_>>()
+        );
+
+        let wider = Collection::<u32>::new("events", NonZeroUsize::new(4).unwrap());
+        assert_eq!(wider.compact(&mut memory).await.unwrap(), 2);
+        assert_eq!(wider.parts(&memory).await.unwrap(), [0, 1]);
+        assert_eq!(wider.get_part(&memory, 0).await.unwrap(), [0, 1, 2, 3]);
+        assert_eq!(
+            wider.iter(&memory).try_collect::<Vec<_>>().await.unwrap(),
+            (0..7).collect::<Vec<_>>()
+        );
+        assert!(Collection::<u32>::new("other", NonZeroUsize::MIN)
+            .iter(&memory)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::BTreeSet;
+
+use futures::stream::{self, Stream, StreamExt as _};
+
+use super::Sink;
+use crate::storage::meta::checksum;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiffEntry {
+    Added(String),
+    Removed(String),
+    Changed(String),
+}
+
+#[inline]
+pub async fn diff<'sink, ERROR, SOURCE, TARGET>(
+    source: &'sink SOURCE,
+    source_prefix: &'sink str,
+    target: &'sink TARGET,
+    target_prefix: &'sink str,
+) -> Result<impl Stream<Item = Result<DiffEntry, ERROR>> + 'sink, ERROR>
+where
+    SOURCE: Sink + Sync,
+    TARGET: Sink + Sync,
+    ERROR: From<SOURCE::Error> + From<TARGET::Error> + 'sink,
+{
+    let source_keys = walk(source, source_prefix).await?;
+    let target_keys = walk(target, target_prefix).await?;
+
+    let removed = source_keys
+        .difference(&target_keys)
+        .cloned()
+        .map(DiffEntry::Removed);
+    let added = target_keys
+        .difference(&source_keys)
+        .cloned()
+        .map(DiffEntry::Added);
+    let missing = removed.chain(added).map(Ok).collect::<Vec<_>>();
+    let common = source_keys
+        .intersection(&target_keys)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let changed = stream::iter(common).filter_map(move |key| async move {
+        match is_changed(source, source_prefix, target, target_prefix, &key).await {
+            Ok(true) => Some(Ok(DiffEntry::Changed(key))),
+            Ok(false) => None,
+            Err(err) => Some(Err(err)),
+        }
+    });
+
+    Ok(stream::iter(missing).chain(changed))
+}
+
+pub(crate) async fn walk<SINK>(sink: &SINK, prefix: &str) -> Result<BTreeSet<String>, SINK::Error>
+where
+    SINK: Sink + Sync + ?Sized,
+{
+    let mut keys = BTreeSet::new();
+    let mut pending = vec![prefix.to_owned()];
+
+    while let Some(current) = pending.pop() {
+        for entry in sink.list_objects_copy(&current).await? {
+            if entry.ends_with('/') {
+                if entry != current {
+                    pending.push(entry);
+                }
+            } else if let Some(relative) = entry.strip_prefix(prefix) {
+                keys.insert(relative.to_owned());
+            }
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Full keys under `prefix` sorted and strictly after `after`, at most `limit` of them. A
+/// directory sorting wholly before `after` is not listed, so paging through a tree lists each
+/// directory about once instead of walking it again per page.
+pub(crate) async fn walk_page<SINK>(
+    sink: &SINK,
+    prefix: &str,
+    after: Option<&str>,
+    limit: usize,
+) -> Result<Vec<String>, SINK::Error>
+where
+    SINK: Sink + Sync + ?Sized,
+{
+    let is_before = |entry: &str| {
+        after.is_some_and(|after| {
+            if entry.ends_with('/') {
+                entry < after && !after.starts_with(entry)
+            } else {
+                entry <= after
+            }
+        })
+    };
+    let mut keys = vec![];
+    let mut pending = vec![];
+    let mut listing = Some(prefix.to_owned());
+
+    loop {
+        if let Some(current) = listing.take() {
+            let mut entries = sink
+                .list_objects_copy(&current)
+                .await?
+                .into_iter()
+                .filter(|entry| *entry != current && !is_before(entry))
+                .collect::<Vec<_>>();
+            entries.sort_unstable_by(|left, right| right.cmp(left));
+            pending.extend(entries);
+        }
+        let Some(entry) = pending.pop() else {
+            break;
+        };
+        if entry.ends_with('/') {
+            listing = Some(entry);
+        } else if entry.starts_with(prefix) {
+            keys.push(entry);
+            if keys.len() >= limit {
+                break;
+            }
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Full keys under any of `prefixes`, a prefix covered by a shorter one is not listed again.
+pub(crate) async fn walk_all<SINK>(
+    sink: &SINK,
+    mut prefixes: Vec<&str>,
+) -> Result<BTreeSet<String>, SINK::Error>
+where
+    SINK: Sink + Sync + ?Sized,
+{
+    prefixes.sort_unstable();
+    prefixes.dedup_by(|prefix, root| prefix.starts_with(&**root));
+
+    let mut keys = BTreeSet::new();
+    for root in prefixes {
+        let relatives = walk(sink, root).await?;
+        keys.extend(
+            relatives
+                .into_iter()
+                .map(|relative| format!("{root}{relative}")),
+        );
+    }
+    Ok(keys)
+}
+
+async fn is_changed<ERROR, SOURCE, TARGET>(
+    source: &SOURCE,
+    source_prefix: &str,
+    target: &TARGET,
+    target_prefix: &str,
+    key: &str,
+) -> Result<bool, ERROR>
+where
+    SOURCE: Sink + Sync,
+    TARGET: Sink + Sync,
+    ERROR: From<SOURCE::Error> + From<TARGET::Error>,
+{
+    let source_key = format!("{source_prefix}{key}");
+    let target_key = format!("{target_prefix}{key}");
+
+    match (
+        source.head_copy(&source_key).await?,
+        target.head_copy(&target_key).await?,
+    ) {
+        (Some(source_meta), Some(target_meta)) => {
+            if let Some(same) = source_meta.same_content(&target_meta) {
+                Ok(!same)
+            } else {
+                let source_checksum = source
+                    .get_bytes_copy(&source_key)
+                    .await?
+                    .map(|value| checksum(&value));
+                let target_checksum = target
+                    .get_bytes_copy(&target_key)
+                    .await?
+                    .map(|value| checksum(&value));
+                Ok(source_checksum != target_checksum)
+            }
+        }
+        (None, None) => Ok(false),
+        _ => Ok(true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    async fn put(memory: &mut Memory, key: &str, value: &[u8]) {
+        memory
+            .put_bytes_copy(&key.to_owned(), String::new(), value.to_vec())
+            .await
+            .unwrap();
+    }
+
+    async fn collect(
+        source: &Memory,
+        source_prefix: &str,
+        target: &Memory,
+        target_prefix: &str,
+    ) -> Vec<DiffEntry> {
+        let mut entries = diff::<MemoryError, _, _>(source, source_prefix, target, target_prefix)
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn compare_checksums_across_encodings() {
+        use crate::storage::meta::{checksum_from_base64, ObjectMeta};
+
+        let local = ObjectMeta::from_bytes(b"hello");
+        let remote = ObjectMeta {
+            size: 5,
+            checksum: checksum_from_base64("LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ="),
+            ..ObjectMeta::default()
+        };
+
+        assert_eq!(local.same_content(&remote), Some(true));
+        assert_eq!(
+            checksum_from_base64("LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=-3"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn walk_only_the_prefixes() {
+        let mut memory = Memory::default();
+        for key in ["logs/a", "logs/old/b", "tmp/c", "other"] {
+            put(&mut memory, key, b"1").await;
+        }
+
+        assert_eq!(
+            walk_all(&memory, vec!["logs/old/", "tmp/", "logs/"])
+                .await
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ["logs/a", "logs/old/b", "tmp/c"]
+        );
+    }
+
+    #[tokio::test]
+    async fn walk_past_one_listing_page() {
+        let mut memory = Memory::default();
+        for index in 0..1200 {
+            put(&mut memory, &format!("wide/{index:04}"), b"1").await;
+        }
+        put(&mut memory, "wide/deep/last", b"1").await;
+
+        let keys = walk(&memory, "wide/").await.unwrap();
+        assert_eq!(keys.len(), 1201);
+        assert!(keys.contains("1199") && keys.contains("deep/last"));
+    }
+
+    #[tokio::test]
+    async fn walk_pages_in_order() {
+        let mut memory = Memory::default();
+        let mut expected = vec![];
+        for key in [
+            "tree/a",
+            "tree/b/1",
+            "tree/b/2",
+            "tree/b-c",
+            "tree/b0",
+            "tree/c/d/3",
+            "tree/c/e",
+            "tree/z",
+        ] {
+            put(&mut memory, key, b"1").await;
+            expected.push(key.to_owned());
+        }
+        put(&mut memory, "other", b"1").await;
+
+        let mut keys = vec![];
+        let mut after = None;
+        loop {
+            let page = walk_page(&memory, "tree/", after.as_deref(), 3)
+                .await
+                .unwrap();
+            after = page.last().cloned();
+            keys.extend(page);
+            if after.is_none() {
+                break;
+            }
+        }
+        expected.sort();
+        assert_eq!(keys, expected);
+        assert_eq!(
+            walk_page(&memory, "tree/", Some("tree/b0"), 2)
+                .await
+                .unwrap(),
+            ["tree/c/d/3", "tree/c/e"]
+        );
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn walk_page_skips_listed_directories() {
+        use crate::storage::sink::recording::RecordingSink;
+
+        let mut memory = Memory::default();
+        for key in ["tree/a/1", "tree/a/2", "tree/b/1", "tree/c/1"] {
+            put(&mut memory, key, b"1").await;
+        }
+        let recording = RecordingSink::new(memory);
+
+        assert_eq!(
+            walk_page(&recording, "tree/", Some("tree/b/1"), 10)
+                .await
+                .unwrap(),
+            ["tree/c/1"]
+        );
+        assert_eq!(
+            recording
+                .calls()
+                .iter()
+                .map(|call| call.key().to_owned())
+                .collect::<Vec<_>>(),
+            ["tree/", "tree/b/", "tree/c/"]
+        );
+    }
+
+    #[tokio::test]
+    async fn between_sinks() {
+        let mut source = Memory::default();
+        let mut target = Memory::default();
+        put(&mut source, "same", b"1").await;
+        put(&mut target, "same", b"1").await;
+        put(&mut source, "deep/changed", b"1").await;
+        put(&mut target, "deep/changed", b"2").await;
+        put(&mut source, "deep/deeper/removed", b"1").await;
+        put(&mut target, "added", b"1").await;
+
+        assert_eq!(
+            collect(&source, "", &target, "").await,
+            vec![
+                DiffEntry::Added("added".to_owned()),
+                DiffEntry::Removed("deep/deeper/removed".to_owned()),
+                DiffEntry::Changed("deep/changed".to_owned()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn between_prefixes() {
+        let mut memory = Memory::default();
+        put(&mut memory, "v1/a", b"1").await;
+        put(&mut memory, "v2/a", b"1").await;
+        put(&mut memory, "v2/b", b"1").await;
+
+        assert_eq!(
+            collect(&memory, "v1/", &memory, "v2/").await,
+            vec![DiffEntry::Added("b".to_owned())]
+        );
+    }
+
+    #[tokio::test]
+    async fn diff_past_one_listing_page() {
+        let mut source = Memory::default();
+        let mut target = Memory::default();
+        for index in 0..1100 {
+            let key = format!("bulk/{index:04}");
+            put(&mut source, &key, b"1").await;
+            put(&mut target, &key, if index == 1050 { b"2" } else { b"1" }).await;
+        }
+        put(&mut target, "bulk/1100", b"1").await;
+
+        assert_eq!(
+            collect(&source, "bulk/", &target, "bulk/").await,
+            vec![
+                DiffEntry::Added("1100".to_owned()),
+                DiffEntry::Changed("1050".to_owned()),
+            ]
+        );
+    }
+}
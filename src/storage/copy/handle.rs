@@ -0,0 +1,134 @@
+use serde::de::DeserializeOwned;
+
+use super::parser::{GuardedParser as _, Parser, ParserRegistry};
+use super::ParserWhere;
+use crate::storage::meta::ObjectMeta;
+use crate::storage::{ParserError, ResultExt as _};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectHandle {
+    key: String,
+    meta: ObjectMeta,
+    bytes: Vec<u8>,
+}
+
+impl ObjectHandle {
+    #[inline]
+    #[must_use]
+    pub const fn new(key: String, meta: ObjectMeta, bytes: Vec<u8>) -> Self {
+        Self { key, meta, bytes }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn meta(&self) -> &ObjectMeta {
+        &self.meta
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn mime(&self) -> Option<&str> {
+        self.meta.mime.as_deref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    #[inline]
+    pub fn parse<RETURN, PARSER>(&self, parser: &PARSER) -> Result<RETURN, ParserError>
+    where
+        RETURN: DeserializeOwned,
+        PARSER: Parser,
+    {
+        parser
+            .deserialize_guarded(&self.bytes)
+            .context("handle", "parse", &self.key)
+    }
+
+    #[inline]
+    pub fn parse_auto<RETURN, PARSER>(
+        &self,
+        registry: &ParserRegistry<PARSER>,
+    ) -> Result<RETURN, ParserError>
+    where
+        RETURN: DeserializeOwned,
+        PARSER: ParserWhere,
+    {
+        self.parse(registry.parser(self.mime())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::storage::copy::direct::DKeyWithParserCopy;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::copy::Sink as _;
+    use crate::storage::sink::memory::Memory;
+
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    struct Header {
+        kind: String,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    struct Full {
+        kind: String,
+        size: u32,
+    }
+
+    #[tokio::test]
+    async fn parse_later_as_different_types() {
+        let key = "event".to_owned();
+        let mut memory = Memory::default();
+        memory
+            .put_object_copy(
+                &DKeyWithParserCopy::new(&key, &Json),
+                &serde_json::json!({ "kind": "blob", "size": 3 }),
+            )
+            .await
+            .unwrap();
+
+        let handle = memory.get_lazy_copy(&key).await.unwrap().unwrap();
+        assert_eq!(handle.key(), "event");
+        assert_eq!(handle.mime(), Some(Json.mime().as_str()));
+
+        assert_eq!(
+            handle.parse::<Header, _>(&Json).unwrap(),
+            Header {
+                kind: "blob".to_owned()
+            }
+        );
+        assert_eq!(
+            handle
+                .parse_auto::<Full, _>(&ParserRegistry::default().with_parser(Json))
+                .unwrap(),
+            Full {
+                kind: "blob".to_owned(),
+                size: 3
+            }
+        );
+        assert!(memory
+            .get_lazy_copy(&"missing".to_owned())
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,50 +1,104 @@
-use core::fmt::Debug;
-use std::path::Path;
-use std::{env, fs};
+use core::error::Error;
+use core::fmt::{self, Debug, Display};
+use core::future::Future;
+use core::time::Duration;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
 
 use directories::ProjectDirs;
-use semver::{BuildMetadata, Version};
+use semver::Version;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use toml::Table;
 use uuid::Uuid;
 
 use super::direct::DKeyWithParserCopy;
-use super::parser::Json;
-use super::{Cache, ValueWhere};
-use crate::storage::DKey;
+use super::model::{unix_time, SkewPolicy, VersionReport};
+pub use super::model::{Initialize, InstanceRecord, Welcome};
+use super::parser::{GuardedParser as _, Json};
+use super::secret::{SecretRef, SecretSource};
+use super::{Cache, GetOptions, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::sink::any::Backend;
+use crate::storage::sink::s3::BucketMode;
+use crate::storage::sink::tenant::{TenantId, TENANT_ROOT};
+use crate::storage::task::{CancellationToken, RestartPolicy, ShutdownReport, Supervisor, TaskSet};
+use crate::storage::tls::TlsConfig;
+use crate::storage::{radix_key, DKey, ListKeyObjects, ParserError, PrefixedKey, ResultExt as _};
 use crate::InstanceKey;
 
-#[derive(Serialize, Deserialize)]
-pub struct Welcome {
-    version: Version,
+const INSTANCES_PREFIX: &str = "instances/";
+const MAX_PENDING_WRITES: usize = 1024;
+
+#[derive(Debug)]
+pub enum BuilderError {
+    MissingVar(String),
+    InvalidVar { name: String, value: String },
+    Serde(String),
+    Io(String),
+    Secret(String),
 }
 
-impl Default for Welcome {
+#[derive(Debug)]
+pub enum InstanceError<ERROR> {
+    Storage(ERROR),
+    Configuration(BuilderError),
+    Incompatible { bucket: Version, instance: Version },
+}
+
+impl<ERROR> Display for InstanceError<ERROR>
+where
+    ERROR: Display,
+{
     #[inline]
-    fn default() -> Self {
-        Self {
-            version: Version {
-                major: env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or_default(),
-                minor: env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or_default(),
-                patch: env!("CARGO_PKG_VERSION_PATCH").parse().unwrap_or_default(),
-                pre: env!("CARGO_PKG_VERSION_PRE").parse().unwrap_or_default(),
-                build: BuildMetadata::EMPTY,
-            },
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Storage(ref err) => write!(f, "Instance storage error: {err}"),
+            Self::Configuration(ref err) => write!(f, "Instance configuration error: {err:?}"),
+            Self::Incompatible {
+                ref bucket,
+                ref instance,
+            } => write!(
+                f,
+                "Instance {instance} is incompatible with bucket welcome {bucket}"
+            ),
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct Initialize;
+impl<ERROR> Error for InstanceError<ERROR> where ERROR: Debug + Display {}
 
-#[derive(Debug)]
-pub enum BuilderError {
-    MissingVar(String),
-    Serde(String),
+impl<ERROR> From<ERROR> for InstanceError<ERROR> {
+    #[inline]
+    fn from(value: ERROR) -> Self {
+        Self::Storage(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InstanceIdPolicy {
+    #[default]
+    GenerateAndPersist,
+    RequireConfigured,
+    UseNil,
 }
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct Configuration {
     pub instance_id: Option<Uuid>,
+    pub tenant_id: Option<TenantId>,
+    pub tenant_root: Option<String>,
+    pub encryption_key: Option<SecretRef>,
+    pub key_prefix: Option<String>,
+    pub backend: Option<Backend>,
+    pub tls: Option<TlsConfig>,
+    #[serde(skip)]
+    pub instance_id_policy: InstanceIdPolicy,
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
 }
 
 impl Configuration {
@@ -63,22 +117,59 @@ impl Configuration {
         );
 
         if let Some(project_dirs) = ProjectDirs::from(qualifier, organization, application) {
-            Ok(self
-                .load_from_file(project_dirs.config_dir().join("negentropy.toml").as_path())?
-                .load_from_env(&prefix))
+            let path = project_dirs.config_dir().join("negentropy.toml");
+            let loaded = self.load_from_file(&path)?.load_from_env(&prefix)?;
+            Ok(Self {
+                path: loaded.path.or(Some(path)),
+                ..loaded
+            })
         } else {
-            Ok(self.load_from_env(&prefix))
+            self.load_from_env(&prefix)
         }
     }
 
     #[inline]
-    #[must_use]
-    pub fn load_from_env(self, prefix: &str) -> Self {
+    pub fn load_from_env(self, prefix: &str) -> Result<Self, BuilderError> {
         let key = format!("{prefix}_NEGENTROPY_INSTANCE_ID");
         let instance_id = env::var(key)
             .ok()
-            .and_then(|value| Uuid::parse_str(&value).ok());
-        Self { instance_id }
+            .and_then(|value| Uuid::parse_str(&value).ok())
+            .or(self.instance_id);
+        let tenant_key = format!("{prefix}_NEGENTROPY_TENANT_ID");
+        let tenant_id = env::var(&tenant_key)
+            .ok()
+            .map(|value| {
+                TenantId::new(&value).map_err(|_| BuilderError::InvalidVar {
+                    name: tenant_key,
+                    value,
+                })
+            })
+            .transpose()?
+            .or(self.tenant_id);
+        let tenant_root = env::var(format!("{prefix}_NEGENTROPY_TENANT_ROOT"))
+            .ok()
+            .or(self.tenant_root);
+        let key_prefix = env::var(format!("{prefix}_NEGENTROPY_KEY_PREFIX"))
+            .ok()
+            .or(self.key_prefix);
+        let backend = env::var(format!("{prefix}_NEGENTROPY_BUCKET"))
+            .ok()
+            .map(|bucket| Backend::S3 {
+                mode: BucketMode::from_bucket(&bucket),
+                accelerate: false,
+                dual_stack: false,
+                anonymous: false,
+                bucket,
+            })
+            .or(self.backend);
+        Ok(Self {
+            instance_id,
+            tenant_id,
+            tenant_root,
+            key_prefix,
+            backend,
+            ..self
+        })
     }
 
     #[inline]
@@ -91,15 +182,121 @@ impl Configuration {
 
             Ok(Self {
                 instance_id: config.instance_id.or(self.instance_id),
+                tenant_id: config.tenant_id.or(self.tenant_id),
+                tenant_root: config.tenant_root.or(self.tenant_root),
+                encryption_key: config.encryption_key.or(self.encryption_key),
+                key_prefix: config.key_prefix.or(self.key_prefix),
+                backend: config.backend.or(self.backend),
+                tls: config.tls.or(self.tls),
+                ..self
             })
         } else {
             Ok(self)
         }
     }
+
+    #[inline]
+    pub fn save(
+        &self,
+        qualifier: &str,
+        organization: &str,
+        application: &str,
+    ) -> Result<PathBuf, BuilderError> {
+        let project_dirs = ProjectDirs::from(qualifier, organization, application)
+            .ok_or_else(|| BuilderError::MissingVar("HOME".to_owned()))?;
+        let path = project_dirs.config_dir().join("negentropy.toml");
+        self.save_to_file(&path)?;
+        Ok(path)
+    }
+
+    #[inline]
+    pub fn save_to_file(&self, path: &Path) -> Result<(), BuilderError> {
+        let mut stored = match fs::read_to_string(path) {
+            Ok(content) => toml::from_str::<Table>(&content)
+                .map_err(|err| BuilderError::Serde(err.to_string()))?,
+            Err(_) => Table::new(),
+        };
+        stored.extend(Table::try_from(self).map_err(|err| BuilderError::Serde(err.to_string()))?);
+        let content =
+            toml::to_string(&stored).map_err(|err| BuilderError::Serde(err.to_string()))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| BuilderError::Io(err.to_string()))?;
+        }
+        let tmp = path.with_extension(format!("toml.{}.tmp", process::id()));
+        write_private(&tmp, content.as_bytes())
+            .and_then(|()| fs::rename(&tmp, path))
+            .map_err(|err| {
+                let _removed = fs::remove_file(&tmp);
+                BuilderError::Io(err.to_string())
+            })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn prefix(&self) -> String {
+        match self.key_prefix.as_deref() {
+            None | Some("") => String::new(),
+            Some(prefix) if prefix.ends_with('/') => prefix.to_owned(),
+            Some(prefix) => format!("{prefix}/"),
+        }
+    }
+
+    #[inline]
+    pub fn encryption_key<SOURCE>(&self, source: &SOURCE) -> Result<Option<String>, BuilderError>
+    where
+        SOURCE: SecretSource,
+    {
+        self.encryption_key
+            .as_ref()
+            .map(|secret| secret.resolve(source))
+            .transpose()
+    }
+
+    #[inline]
+    pub fn resolve_instance_id(self) -> Result<Self, BuilderError> {
+        if self.instance_id.is_some() {
+            return Ok(self);
+        }
+
+        match self.instance_id_policy {
+            InstanceIdPolicy::UseNil => Ok(Self {
+                instance_id: Some(Uuid::nil()),
+                ..self
+            }),
+            InstanceIdPolicy::RequireConfigured => {
+                Err(BuilderError::MissingVar("instance_id".to_owned()))
+            }
+            InstanceIdPolicy::GenerateAndPersist => {
+                let configuration = Self {
+                    instance_id: Some(Uuid::new_v4()),
+                    ..self
+                };
+                if let Some(ref path) = configuration.path {
+                    configuration.save_to_file(path)?;
+                }
+                Ok(configuration)
+            }
+        }
+    }
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionMode {
+    #[default]
+    Eventual,
+    ReadYourWrites,
+}
+
 pub struct Instance<CACHE: Cache + Send + Sync> {
     storage: CACHE,
     configuration: Configuration,
+    prefix: String,
+    record: InstanceRecord,
+    supervisor: Supervisor,
+    session: SessionMode,
+    written: BTreeMap<String, Vec<u8>>,
+    max_pending_writes: usize,
 }
 
 impl<CACHE> Instance<CACHE>
@@ -108,39 +305,230 @@ where
     <CACHE as Cache>::Error: Send + Sync,
 {
     #[inline]
-    pub async fn new(storage: CACHE, configuration: Configuration) -> Result<Self, CACHE::Error> {
+    pub async fn new(
+        storage: CACHE,
+        configuration: Configuration,
+    ) -> Result<Self, InstanceError<CACHE::Error>> {
+        let configuration = configuration
+            .resolve_instance_id()
+            .map_err(InstanceError::Configuration)?;
+        let record = InstanceRecord::new(configuration.instance_id.unwrap_or_default());
         let instance = Self {
             storage,
+            prefix: configuration.prefix(),
             configuration,
+            record,
+            supervisor: Supervisor::new(),
+            session: SessionMode::default(),
+            written: BTreeMap::new(),
+            max_pending_writes: MAX_PENDING_WRITES,
         };
 
-        instance.welcome().await?.initialize().await
+        Ok(instance
+            .welcome()
+            .await?
+            .initialize()
+            .await?
+            .register()
+            .await?)
+    }
+
+    #[inline]
+    pub async fn discover(
+        storage: &mut CACHE,
+        configuration: &Configuration,
+    ) -> Result<Vec<InstanceRecord>, CACHE::Error> {
+        let prefix = configuration.prefix();
+        let instances = format!("{prefix}{INSTANCES_PREFIX}");
+        let mut records = vec![];
+
+        for entry in storage.list_objects_copy(&instances).await? {
+            let Some(id) = entry
+                .strip_prefix(instances.as_str())
+                .and_then(|id| id.strip_suffix('/'))
+            else {
+                continue;
+            };
+            let key = InstanceKey::Record(id.to_owned());
+            let key = PrefixedKey::new(&prefix, &key);
+            if let Some(record) = storage
+                .get_object_copy::<InstanceRecord, _, _>(&DKeyWithParserCopy::new(&key, &Json))
+                .await?
+            {
+                records.push(record);
+            }
+        }
+
+        records.sort_by_key(|record| record.id);
+        Ok(records)
+    }
+
+    #[inline]
+    pub async fn version_report(
+        storage: &mut CACHE,
+        configuration: &Configuration,
+        policy: &SkewPolicy,
+    ) -> Result<VersionReport, CACHE::Error> {
+        let records = Self::discover(storage, configuration).await?;
+        Ok(VersionReport::from_records(&records, policy, unix_time()))
+    }
+
+    #[inline]
+    pub async fn heartbeat(&mut self) -> Result<&InstanceRecord, CACHE::Error> {
+        self.record.last_heartbeat = unix_time();
+        self.write_record().await?;
+        Ok(&self.record)
+    }
+
+    #[inline]
+    pub async fn heartbeat_until(
+        &mut self,
+        interval: Duration,
+        token: &CancellationToken,
+    ) -> Result<(), CACHE::Error> {
+        while !token.is_cancelled() {
+            self.heartbeat().await?;
+            if !token.sleep(interval).await {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn spawn<TASK, FUTURE>(&mut self, name: &str, task: TASK)
+    where
+        TASK: FnOnce(CancellationToken) -> FUTURE,
+        FUTURE: Future<Output = ()> + Send + 'static,
+    {
+        self.supervisor.spawn(name, task);
     }
 
-    async fn welcome(mut self) -> Result<Self, CACHE::Error> {
+    #[inline]
+    pub fn supervise<TASK, FUTURE, ERROR>(&mut self, name: &str, policy: RestartPolicy, task: TASK)
+    where
+        TASK: Fn(CancellationToken) -> FUTURE + Send + 'static,
+        FUTURE: Future<Output = Result<(), ERROR>> + Send + 'static,
+        ERROR: Display,
+    {
+        self.supervisor.supervise(name, policy, task);
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn tasks(&self) -> &TaskSet {
+        self.supervisor.tasks()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn supervisor(&self) -> &Supervisor {
+        &self.supervisor
+    }
+
+    #[inline]
+    pub async fn health(&self) -> HealthReport {
+        HealthReport::new("instance")
+            .with_inner(self.storage.health_copy().await)
+            .with_inner(self.supervisor.health())
+    }
+
+    #[inline]
+    pub async fn shutdown(&mut self, grace: Duration) -> ShutdownReport {
+        self.supervisor.shutdown(grace).await
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn record(&self) -> &InstanceRecord {
+        &self.record
+    }
+
+    async fn welcome(mut self) -> Result<Self, InstanceError<CACHE::Error>> {
         let welcome = Welcome::default();
-        let key_with_parser = DKeyWithParserCopy::new(&InstanceKey::Welcome, &Json);
-        self.storage
+        let key = PrefixedKey::new(&self.prefix, &InstanceKey::Welcome);
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+        let created = self
+            .storage
             .put_object_if_not_exists_copy(&key_with_parser, &welcome)
             .await?;
+
+        if !created {
+            if let Some(existing) = self
+                .storage
+                .get_object_copy::<Welcome, _, _>(&key_with_parser)
+                .await?
+            {
+                if !existing.is_compatible(&welcome) {
+                    return Err(InstanceError::Incompatible {
+                        bucket: existing.version,
+                        instance: welcome.version,
+                    });
+                }
+            }
+        }
+
         Ok(self)
     }
 
     async fn initialize(mut self) -> Result<Self, CACHE::Error> {
-        let initialize = Initialize;
-        let key = &InstanceKey::Initialize(
+        let initialize = Initialize::new();
+        let key = InstanceKey::Initialize(
             self.configuration
                 .instance_id
                 .unwrap_or_default()
                 .to_string(),
         );
-        let key_with_parser = DKeyWithParserCopy::new(key, &Json);
+        let key = PrefixedKey::new(&self.prefix, &key);
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
         self.storage
             .put_object_if_not_exists_copy(&key_with_parser, &initialize)
             .await?;
         Ok(self)
     }
 
+    async fn register(mut self) -> Result<Self, CACHE::Error> {
+        self.write_record().await?;
+        Ok(self)
+    }
+
+    async fn write_record(&mut self) -> Result<(), CACHE::Error> {
+        let key = InstanceKey::Record(self.record.id.to_string());
+        let key = PrefixedKey::new(&self.prefix, &key);
+        self.storage
+            .put_object_copy(&DKeyWithParserCopy::new(&key, &Json), &self.record)
+            .await?;
+        Ok(())
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_session_mode(mut self, session: SessionMode) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Keep at most `max` local copies of writes the sink may not serve yet. Past the bound the
+    /// oldest keys are forgotten, so read your writes is best effort under heavy write load.
+    #[inline]
+    #[must_use]
+    pub fn with_max_pending_writes(mut self, max: usize) -> Self {
+        self.max_pending_writes = max;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn session_mode(&self) -> SessionMode {
+        self.session
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn pending_writes(&self) -> usize {
+        self.written.len()
+    }
+
     #[inline]
     pub async fn put_object<DKEY, VALUE>(
         &mut self,
@@ -152,25 +540,159 @@ where
         VALUE: ValueWhere,
         <CACHE as Cache>::Error: Debug,
     {
-        self.storage
-            .put_object_if_not_exists_copy(&DKeyWithParserCopy::new(key, &Json), value)
+        let prefix = self.data_prefix();
+        let prefixed_key = PrefixedKey::new(&prefix, key);
+        let created = self
+            .storage
+            .put_object_if_not_exists_copy(&DKeyWithParserCopy::new(&prefixed_key, &Json), value)
             .await?;
 
+        if created && self.session == SessionMode::ReadYourWrites {
+            // The cache already serialized the same value with the same parser.
+            if let Ok(content) = Json.serialize_guarded(value) {
+                self.written
+                    .insert(prefixed_key.name().into_owned(), content);
+            }
+            if self.written.len() > self.max_pending_writes {
+                self.confirm_writes().await?;
+            }
+            while self.written.len() > self.max_pending_writes {
+                self.written.pop_first();
+            }
+        }
+
         Ok(self)
     }
 
+    #[inline]
+    pub async fn get_object<DKEY, RETURN>(
+        &mut self,
+        key: &DKEY,
+    ) -> Result<Option<RETURN>, CACHE::Error>
+    where
+        DKEY: DKey + Send + Sync,
+        RETURN: Serialize + DeserializeOwned + Send + Sync,
+        <CACHE as Cache>::Error: From<ParserError>,
+    {
+        let prefix = self.data_prefix();
+        let prefixed_key = PrefixedKey::new(&prefix, key);
+        let name = prefixed_key.name();
+
+        if self.written.contains_key(name.as_ref()) {
+            let visible = self
+                .storage
+                .get_bytes_with_copy(&prefixed_key, GetOptions::bypass())
+                .await?;
+            if visible.is_some() {
+                self.written.remove(name.as_ref());
+            } else if let Some(content) = self.written.get(name.as_ref()) {
+                return Ok(Some(Json.deserialize_guarded(content).context(
+                    "instance",
+                    "get_object",
+                    &name,
+                )?));
+            }
+        }
+
+        self.storage
+            .get_object_copy(&DKeyWithParserCopy::new(&prefixed_key, &Json))
+            .await
+    }
+
+    /// List `prefix` relative to the instance data prefix, merging the writes not yet confirmed
+    /// when the session reads its own writes.
+    #[inline]
+    pub async fn list_objects(&mut self, prefix: &str) -> Result<ListKeyObjects, CACHE::Error> {
+        let data_prefix = self.data_prefix();
+        let full_prefix = format!("{data_prefix}{prefix}");
+        let mut listed = self.storage.list_objects_copy(&full_prefix).await?;
+        listed.extend(
+            self.written
+                .keys()
+                .filter_map(|key| radix_key(&full_prefix, key)),
+        );
+
+        Ok(listed
+            .into_iter()
+            .filter_map(|key| {
+                key.strip_prefix(data_prefix.as_str())
+                    .map(ToOwned::to_owned)
+            })
+            .collect())
+    }
+
+    /// Forget the local copy of every write the sink now serves, returning how many are still
+    /// pending.
+    #[inline]
+    pub async fn confirm_writes(&mut self) -> Result<usize, CACHE::Error> {
+        let mut confirmed = vec![];
+        for key in self.written.keys() {
+            let visible = self
+                .storage
+                .get_bytes_with_copy(key, GetOptions::bypass())
+                .await?;
+            if visible.is_some() {
+                confirmed.push(key.clone());
+            }
+        }
+        for key in confirmed {
+            self.written.remove(&key);
+        }
+
+        Ok(self.written.len())
+    }
+
+    fn data_prefix(&self) -> String {
+        match self.configuration.tenant_id {
+            Some(ref tenant_id) => {
+                let root = self
+                    .configuration
+                    .tenant_root
+                    .as_deref()
+                    .unwrap_or(TENANT_ROOT);
+                format!("{}{}", self.prefix, tenant_id.prefix_in(root))
+            }
+            None => self.prefix.clone(),
+        }
+    }
+
+    /// Hand out the underlying cache. Writes and deletes made through it bypass the session, so
+    /// the local copies of pending writes are dropped rather than left to shadow them.
     #[inline]
     pub fn cache(&mut self) -> &mut CACHE {
+        self.written.clear();
         &mut self.storage
     }
 }
 
+#[cfg(unix)]
+fn write_private(path: &Path, content: &[u8]) -> io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt as _;
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
 #[cfg(test)]
 mod tests {
     use std::num::NonZeroUsize;
 
     use super::*;
     use crate::storage::cache::lru::Lru;
+    use crate::storage::copy::secret::FileSecretSource;
     use crate::storage::sink::memory::Memory;
 
     #[tokio::test]
@@ -186,4 +708,375 @@ mod tests {
             .await
             .unwrap();
     }
+
+    #[tokio::test]
+    async fn tenant_isolation() {
+        let memory = Memory::default();
+        let lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory);
+        let configuration = Configuration {
+            tenant_id: Some(TenantId::new("acme").unwrap()),
+            ..Configuration::default()
+        };
+        let mut instance = Instance::new(lru, configuration).await.unwrap();
+        instance
+            .put_object(&"doc".to_owned(), &42_u32)
+            .await
+            .unwrap();
+
+        assert!(instance.cache().contains_inner("tenants/acme/doc"));
+        assert!(!instance.cache().contains_inner("doc"));
+    }
+
+    #[tokio::test]
+    async fn tenant_root_is_configurable() {
+        let lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let configuration = Configuration {
+            tenant_id: Some(TenantId::new("acme").unwrap()),
+            tenant_root: Some("orgs/".to_owned()),
+            ..Configuration::default()
+        };
+        let mut instance = Instance::new(lru, configuration).await.unwrap();
+        instance
+            .put_object(&"doc".to_owned(), &42_u32)
+            .await
+            .unwrap();
+
+        assert!(instance.cache().contains_inner("orgs/acme/doc"));
+        assert!(!instance.cache().contains_inner("tenants/acme/doc"));
+    }
+
+    #[test]
+    fn invalid_tenant_from_env() {
+        env::set_var("TEST_INVALID_TENANT_NEGENTROPY_TENANT_ID", "acme/other");
+
+        assert!(matches!(
+            Configuration::default().load_from_env("TEST_INVALID_TENANT"),
+            Err(BuilderError::InvalidVar { ref name, ref value })
+                if name == "TEST_INVALID_TENANT_NEGENTROPY_TENANT_ID" && value == "acme/other"
+        ));
+    }
+
+    #[tokio::test]
+    async fn read_your_writes() {
+        let lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let configuration = Configuration {
+            tenant_id: Some(TenantId::new("acme").unwrap()),
+            ..Configuration::default()
+        };
+        let mut instance = Instance::new(lru, configuration)
+            .await
+            .unwrap()
+            .with_session_mode(SessionMode::ReadYourWrites);
+        assert!(instance.list_objects("docs/").await.unwrap().is_empty());
+
+        instance
+            .put_object(&"docs/a".to_owned(), &1_u32)
+            .await
+            .unwrap();
+        assert_eq!(instance.pending_writes(), 1);
+        assert_eq!(
+            instance
+                .get_object::<_, u32>(&"docs/a".to_owned())
+                .await
+                .unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            instance.list_objects("docs/").await.unwrap(),
+            ["docs/a".to_owned()].into_iter().collect(),
+            "the listing cached before the write must not hide it"
+        );
+        assert_eq!(
+            instance.list_objects("").await.unwrap(),
+            ["docs/".to_owned()].into_iter().collect()
+        );
+
+        assert_eq!(instance.confirm_writes().await.unwrap(), 0);
+        assert_eq!(
+            instance
+                .get_object::<_, u32>(&"docs/a".to_owned())
+                .await
+                .unwrap(),
+            Some(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn bound_pending_writes() {
+        let lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let mut instance = Instance::new(lru, Configuration::default())
+            .await
+            .unwrap()
+            .with_session_mode(SessionMode::ReadYourWrites)
+            .with_max_pending_writes(1);
+        let prefix = instance.data_prefix();
+        for key in ["hidden/a", "hidden/b"] {
+            instance
+                .written
+                .insert(format!("{prefix}{key}"), b"7".to_vec());
+        }
+        assert_eq!(
+            instance
+                .get_object::<_, u32>(&"hidden/a".to_owned())
+                .await
+                .unwrap(),
+            Some(7),
+            "a write the sink does not serve yet stays readable"
+        );
+
+        instance
+            .put_object(&"docs/a".to_owned(), &1_u32)
+            .await
+            .unwrap();
+        assert_eq!(
+            instance.written.keys().collect::<Vec<_>>(),
+            [&format!("{prefix}hidden/b")],
+            "the visible write is confirmed and the oldest hidden one evicted"
+        );
+
+        instance.written.clear();
+        instance = instance.with_max_pending_writes(8);
+        instance
+            .put_object(&"docs/b".to_owned(), &2_u32)
+            .await
+            .unwrap();
+        assert_eq!(instance.pending_writes(), 1);
+        assert_eq!(
+            instance
+                .get_object::<_, u32>(&"docs/b".to_owned())
+                .await
+                .unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            instance.pending_writes(),
+            0,
+            "a read served by the sink drops the local copy"
+        );
+
+        instance
+            .written
+            .insert(format!("{prefix}hidden/c"), b"3".to_vec());
+        instance.cache().invalidate(&format!("{prefix}docs/b"));
+        assert_eq!(instance.pending_writes(), 0);
+        assert_eq!(
+            instance
+                .get_object::<_, u32>(&"hidden/c".to_owned())
+                .await
+                .unwrap(),
+            None,
+            "changes made through the cache are not shadowed by the session"
+        );
+    }
+
+    #[tokio::test]
+    async fn discover_instances() {
+        let memory = Memory::default();
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), memory);
+        let mut ids = vec![Uuid::new_v4(), Uuid::new_v4()];
+        ids.sort();
+
+        for &id in &ids {
+            let configuration = Configuration {
+                instance_id: Some(id),
+                ..Configuration::default()
+            };
+            lru = Instance::new(lru, configuration).await.unwrap().storage;
+        }
+
+        let records = Instance::discover(&mut lru, &Configuration::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            records.iter().map(|record| record.id).collect::<Vec<_>>(),
+            ids
+        );
+        assert_eq!(records[0].version, Welcome::default().version);
+        assert_eq!(records[0].pid, process::id());
+
+        let report =
+            Instance::version_report(&mut lru, &Configuration::default(), &SkewPolicy::default())
+                .await
+                .unwrap();
+        assert_eq!(report.live(), 2);
+        assert!(!report.skewed);
+    }
+
+    #[tokio::test]
+    async fn incompatible_welcome() {
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let key_with_parser = DKeyWithParserCopy::new(&InstanceKey::Welcome, &Json);
+        let mut welcome = Welcome::default();
+        welcome.version.major += 1;
+        lru.put_object_copy(&key_with_parser, &welcome)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            Instance::new(lru, Configuration::default()).await,
+            Err(InstanceError::Incompatible { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn legacy_welcome_is_compatible() {
+        let mut lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let key_with_parser = DKeyWithParserCopy::new(&InstanceKey::Welcome, &Json);
+        lru.put_object_copy(
+            &key_with_parser,
+            &serde_json::json!({ "version": Welcome::default().version }),
+        )
+        .await
+        .unwrap();
+
+        let mut instance = Instance::new(lru, Configuration::default()).await.unwrap();
+        let legacy = instance
+            .cache()
+            .get_object_copy::<Welcome, _, _>(&key_with_parser)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(legacy.hostname(), None);
+        assert_eq!(legacy.envelope_versions(), &[1]);
+    }
+
+    #[tokio::test]
+    async fn instance_id_policy() {
+        let lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let required = Configuration {
+            instance_id_policy: InstanceIdPolicy::RequireConfigured,
+            ..Configuration::default()
+        };
+        assert!(matches!(
+            Instance::new(lru, required).await,
+            Err(InstanceError::Configuration(BuilderError::MissingVar(_)))
+        ));
+
+        let nil = Configuration {
+            instance_id_policy: InstanceIdPolicy::UseNil,
+            ..Configuration::default()
+        }
+        .resolve_instance_id()
+        .unwrap();
+        assert_eq!(nil.instance_id, Some(Uuid::nil()));
+    }
+
+    #[test]
+    fn generate_and_persist_instance_id() {
+        let path = env::temp_dir()
+            .join(format!("negentropy-config-{}", Uuid::new_v4()))
+            .join("negentropy.toml");
+        let generated = Configuration {
+            path: Some(path.clone()),
+            ..Configuration::default()
+        }
+        .resolve_instance_id()
+        .unwrap();
+        let reloaded = Configuration::default().load_from_file(&path).unwrap();
+
+        assert!(generated.instance_id.is_some());
+        assert_eq!(reloaded.instance_id, generated.instance_id);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn save_is_private_and_keeps_unknown_settings() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let root = env::temp_dir().join(format!("negentropy-config-{}", Uuid::new_v4()));
+        let path = root.join("nested").join("negentropy.toml");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "future_setting = true\n").unwrap();
+
+        let configuration = Configuration {
+            instance_id: Some(Uuid::new_v4()),
+            tenant_id: Some(TenantId::new("acme").unwrap()),
+            ..Configuration::default()
+        };
+        configuration.save_to_file(&path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("future_setting = true"));
+        let reloaded = Configuration::default().load_from_file(&path).unwrap();
+        assert_eq!(reloaded.instance_id, configuration.instance_id);
+        assert_eq!(reloaded.tenant_id, configuration.tenant_id);
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn secret_reference_is_saved_not_value() {
+        let root = env::temp_dir().join(format!("negentropy-config-{}", Uuid::new_v4()));
+        let path = root.join("negentropy.toml");
+        fs::create_dir_all(root.join("secrets")).unwrap();
+        fs::write(root.join("secrets").join("key"), "plaintext").unwrap();
+        fs::write(&path, "encryption_key = \"secret://key\"\n").unwrap();
+
+        let configuration = Configuration::default().load_from_file(&path).unwrap();
+        let source = FileSecretSource::new(root.join("secrets"));
+        assert_eq!(
+            configuration.encryption_key(&source).unwrap().as_deref(),
+            Some("plaintext")
+        );
+
+        configuration.save_to_file(&path).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("plaintext"));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn key_prefix_separates_environments() {
+        let lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let staging = Configuration {
+            key_prefix: Some("staging".to_owned()),
+            tenant_id: Some(TenantId::new("acme").unwrap()),
+            ..Configuration::default()
+        };
+        let mut instance = Instance::new(lru, staging).await.unwrap();
+        instance
+            .put_object(&"doc".to_owned(), &42_u32)
+            .await
+            .unwrap();
+
+        assert!(instance.cache().contains_inner("staging/tenants/acme/doc"));
+        assert!(instance.cache().contains_inner("staging/instances/welcome"));
+        assert!(!instance.cache().contains_inner("instances/welcome"));
+
+        let prod = Configuration {
+            key_prefix: Some("prod/".to_owned()),
+            ..Configuration::default()
+        };
+        assert!(Instance::discover(instance.cache(), &prod)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn heartbeat_stops_and_tasks_drain_on_shutdown() {
+        let lru = Lru::new(NonZeroUsize::new(10).unwrap(), Memory::default());
+        let mut instance = Instance::new(lru, Configuration::default()).await.unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        instance
+            .heartbeat_until(Duration::from_secs(60), &token)
+            .await
+            .unwrap();
+
+        instance.spawn("flush", |token| async move { token.cancelled().await });
+        assert_eq!(instance.tasks().names(), ["flush"]);
+        let report = instance.shutdown(Duration::from_secs(1)).await;
+
+        assert_eq!(report.drained, ["flush"]);
+        assert!(report.is_clean());
+        assert!(instance.tasks().is_empty());
+    }
 }
@@ -0,0 +1,63 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::Sink;
+use crate::storage::ParserError;
+
+const MIME: &str = "application/x-ndjson";
+
+/// Appends one JSON line per entry to `key`, the existing lines are never read back.
+#[inline]
+pub async fn append<SINK, ENTRY>(
+    sink: &mut SINK,
+    key: &str,
+    entries: &[ENTRY],
+) -> Result<(), SINK::Error>
+where
+    SINK: Sink + Send + Sync,
+    SINK::Error: From<ParserError>,
+    ENTRY: Serialize,
+{
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let mut lines = vec![];
+    for entry in entries {
+        serde_json::to_writer(&mut lines, entry).map_err(|err| ParserError::Serde {
+            internal: err.to_string(),
+        })?;
+        lines.push(b'\n');
+    }
+    let key = key.to_owned();
+    if sink.head_copy(&key).await?.is_none() {
+        return sink.put_bytes_copy(&key, MIME.to_owned(), lines).await;
+    }
+    sink.append_bytes_copy(&key, lines).await
+}
+
+/// Every entry appended to `key`, oldest first, empty when the journal does not exist.
+#[inline]
+pub async fn read<SINK, ENTRY>(sink: &SINK, key: &str) -> Result<Vec<ENTRY>, SINK::Error>
+where
+    SINK: Sink + Send + Sync,
+    SINK::Error: From<ParserError>,
+    ENTRY: DeserializeOwned,
+{
+    let content = sink
+        .get_bytes_copy(&key.to_owned())
+        .await?
+        .unwrap_or_default();
+    content
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            serde_json::from_slice(line).map_err(|err| {
+                ParserError::Serde {
+                    internal: err.to_string(),
+                }
+                .into()
+            })
+        })
+        .collect()
+}
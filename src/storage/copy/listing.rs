@@ -0,0 +1,826 @@
+use core::future::Future;
+use core::num::NonZeroUsize;
+use core::pin::pin;
+use std::collections::{BTreeSet, VecDeque};
+
+use futures::future::{self, Either};
+use futures::stream::{self, Stream, StreamExt as _};
+#[cfg(feature = "regex")]
+use regex_lite::Regex;
+use serde::de::DeserializeOwned;
+
+use super::bulk::{BulkReport, ErrorMode};
+use super::direct::DKeyWithParserCopy;
+use super::{ParserWhere, Sink};
+use crate::storage::meta::ListEntry;
+
+const DEFAULT_CONCURRENCY: NonZeroUsize = NonZeroUsize::new(8).unwrap();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    One,
+    Any,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Deep,
+    Tokens(Vec<Token>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountEstimate {
+    pub count: u64,
+    pub exact: bool,
+    pub listed: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: String,
+    segments: Vec<Segment>,
+}
+
+impl Glob {
+    #[inline]
+    #[must_use]
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            segments: pattern
+                .split('/')
+                .map(|segment| {
+                    if segment == "**" {
+                        Segment::Deep
+                    } else {
+                        Segment::Tokens(tokenize(segment))
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn literal_prefix(&self) -> String {
+        let directories = self.segments.len().saturating_sub(1);
+        self.segments
+            .iter()
+            .take(directories)
+            .map_while(|segment| match *segment {
+                Segment::Tokens(ref tokens) => tokens
+                    .iter()
+                    .map(|token| match *token {
+                        Token::Literal(char) => Some(char),
+                        Token::One | Token::Any | Token::Class { .. } => None,
+                    })
+                    .collect::<Option<String>>(),
+                Segment::Deep => None,
+            })
+            .map(|segment| format!("{segment}/"))
+            .collect()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn matches(&self, key: &str) -> bool {
+        match_segments(&self.segments, &key.split('/').collect::<Vec<_>>())
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn could_contain(&self, directory: &str) -> bool {
+        let directory = directory.strip_suffix('/').unwrap_or(directory);
+        directory.is_empty()
+            || contain_segments(&self.segments, &directory.split('/').collect::<Vec<_>>())
+    }
+}
+
+#[inline]
+pub async fn list_glob<SINK>(sink: &SINK, pattern: &str) -> Result<BTreeSet<String>, SINK::Error>
+where
+    SINK: Sink + Sync,
+{
+    let glob = Glob::new(pattern);
+    let mut keys = BTreeSet::new();
+    let mut pending = vec![glob.literal_prefix()];
+
+    while let Some(current) = pending.pop() {
+        for entry in sink.list_objects_copy(&current).await? {
+            if entry.ends_with('/') {
+                if entry != current && glob.could_contain(&entry) {
+                    pending.push(entry);
+                }
+            } else if glob.matches(&entry) {
+                keys.insert(entry);
+            }
+        }
+    }
+
+    Ok(keys)
+}
+
+#[inline]
+pub async fn estimate_count<SINK>(
+    sink: &SINK,
+    prefix: &str,
+    samples: NonZeroUsize,
+) -> Result<CountEstimate, SINK::Error>
+where
+    SINK: Sink + Sync,
+{
+    let mut count = 0.0_f64;
+    let mut exact = true;
+    let mut listed = 0;
+    let mut pending = vec![(prefix.to_owned(), 1.0_f64)];
+
+    while let Some((current, weight)) = pending.pop() {
+        let entries = sink.list_objects_copy(&current).await?;
+        listed += 1;
+
+        let mut directories = vec![];
+        for entry in entries {
+            if !entry.ends_with('/') {
+                count += weight;
+            } else if entry != current {
+                directories.push(entry);
+            }
+        }
+        directories.sort_unstable();
+
+        let stride = directories.len().div_ceil(samples.get()).max(1);
+        let sampled = directories.len().div_ceil(stride);
+        if stride > 1 {
+            exact = false;
+        }
+        let weight = weight * directories.len() as f64 / sampled.max(1) as f64;
+        pending.extend(
+            directories
+                .into_iter()
+                .step_by(stride)
+                .map(|directory| (directory, weight)),
+        );
+    }
+
+    Ok(CountEstimate {
+        count: count.round() as u64,
+        exact,
+        listed,
+    })
+}
+
+#[inline]
+pub fn list_flat<'sink, SINK>(
+    sink: &'sink SINK,
+    prefix: &'sink str,
+) -> impl Stream<Item = Result<ListEntry, SINK::Error>> + 'sink
+where
+    SINK: Sink + Sync,
+{
+    stream::unfold(
+        (Some(None), VecDeque::new()),
+        move |(mut next, mut ready): (Option<Option<String>>, VecDeque<ListEntry>)| async move {
+            loop {
+                if let Some(entry) = ready.pop_front() {
+                    return Some((Ok(entry), (next, ready)));
+                }
+                let continuation = next.take()?;
+                match sink.list_flat_page_copy(prefix, continuation).await {
+                    Ok(page) => {
+                        ready.extend(page.entries);
+                        next = page.next.map(Some);
+                    }
+                    Err(err) => return Some((Err(err), (None, VecDeque::new()))),
+                }
+            }
+        },
+    )
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Ordering {
+    #[default]
+    Listing,
+    Completion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcurrentOptions {
+    pub concurrency: NonZeroUsize,
+    pub ordering: Ordering,
+    pub mode: ErrorMode,
+}
+
+impl Default for ConcurrentOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            ordering: Ordering::default(),
+            mode: ErrorMode::default(),
+        }
+    }
+}
+
+#[inline]
+pub fn get_concurrent<'sink, RETURN, PARSER, SINK>(
+    sink: &'sink SINK,
+    prefix: &'sink str,
+    parser: &'sink PARSER,
+    options: ConcurrentOptions,
+) -> impl Stream<Item = Result<(String, RETURN), SINK::Error>> + 'sink
+where
+    RETURN: DeserializeOwned + Send + Sync + 'sink,
+    PARSER: ParserWhere,
+    SINK: Sink + Sync,
+{
+    let loads = list_flat(sink, prefix).map(move |entry| async move {
+        let key = entry?.key;
+        let value = sink
+            .get_object_copy(&DKeyWithParserCopy::new(&key, parser))
+            .await?;
+        Ok(value.map(|value| (key, value)))
+    });
+
+    // Keys deleted between the listing and the get are silently dropped.
+    ordered(loads, options).filter_map(|loaded| future::ready(loaded.transpose()))
+}
+
+/// Run `operation` on every object under `prefix`, `skipped` stays at zero as the listing is
+/// streamed and stops at the first failure in `ErrorMode::FailFast`.
+#[inline]
+pub async fn for_each_concurrent<RETURN, PARSER, SINK, OPERATION, FUTURE, OUTPUT, ERROR>(
+    sink: &SINK,
+    prefix: &str,
+    parser: &PARSER,
+    options: ConcurrentOptions,
+    operation: OPERATION,
+) -> BulkReport<OUTPUT, ERROR>
+where
+    RETURN: DeserializeOwned + Send + Sync,
+    PARSER: ParserWhere,
+    SINK: Sink + Sync,
+    OPERATION: Fn(String, RETURN) -> FUTURE,
+    FUTURE: Future<Output = Result<OUTPUT, ERROR>>,
+    ERROR: From<SINK::Error>,
+{
+    let operation = &operation;
+    let tasks = list_flat(sink, prefix)
+        .enumerate()
+        .map(|(index, entry)| async move {
+            let result = async {
+                let key = entry?.key;
+                let Some(value) = sink
+                    .get_object_copy(&DKeyWithParserCopy::new(&key, parser))
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                operation(key, value).await.map(Some)
+            }
+            .await;
+            result.transpose().map(|result| (index, result))
+        });
+    let mut tasks = pin!(ordered(tasks, options).filter_map(future::ready));
+    let mut report = BulkReport {
+        succeeded: vec![],
+        failed: vec![],
+        skipped: 0,
+    };
+
+    while let Some((index, result)) = tasks.next().await {
+        match result {
+            Ok(output) => report.succeeded.push((index, output)),
+            Err(err) => {
+                report.failed.push((index, err));
+                if options.mode == ErrorMode::FailFast {
+                    break;
+                }
+            }
+        }
+    }
+
+    report.succeeded.sort_unstable_by_key(|&(index, _)| index);
+    report.failed.sort_unstable_by_key(|&(index, _)| index);
+    report
+}
+
+fn ordered<TASKS>(
+    tasks: TASKS,
+    options: ConcurrentOptions,
+) -> impl Stream<Item = <TASKS::Item as Future>::Output>
+where
+    TASKS: Stream,
+    TASKS::Item: Future,
+{
+    match options.ordering {
+        Ordering::Listing => Either::Left(tasks.buffered(options.concurrency.get())),
+        Ordering::Completion => Either::Right(tasks.buffer_unordered(options.concurrency.get())),
+    }
+}
+
+#[cfg(feature = "regex")]
+#[inline]
+pub fn list_matching<'sink, SINK>(
+    sink: &'sink SINK,
+    prefix: &str,
+    regex: &'sink Regex,
+) -> impl Stream<Item = Result<String, SINK::Error>> + 'sink
+where
+    SINK: Sink + Sync,
+{
+    list_matching_with(sink, prefix, regex, false)
+}
+
+/// Same as `list_matching`, for a regex built with `RegexBuilder::case_insensitive`. The flag is
+/// not visible from the built regex, a literal prefix of its pattern would skip matching keys.
+#[cfg(feature = "regex")]
+#[inline]
+pub fn list_matching_with<'sink, SINK>(
+    sink: &'sink SINK,
+    prefix: &str,
+    regex: &'sink Regex,
+    case_insensitive: bool,
+) -> impl Stream<Item = Result<String, SINK::Error>> + 'sink
+where
+    SINK: Sink + Sync,
+{
+    let literal = if case_insensitive {
+        String::new()
+    } else {
+        regex_prefix(regex.as_str())
+    };
+    let literal = if literal.starts_with(prefix) {
+        Some(literal)
+    } else {
+        prefix
+            .starts_with(literal.as_str())
+            .then(|| prefix.to_owned())
+    };
+    let pending = literal
+        .as_deref()
+        .map(|literal| {
+            let directory = literal.rfind('/').map_or(0, |index| index + 1);
+            vec![literal.get(..directory).unwrap_or_default().to_owned()]
+        })
+        .unwrap_or_default();
+    let literal = literal.unwrap_or_default();
+
+    stream::unfold(
+        (pending, VecDeque::new()),
+        move |(mut pending, mut ready)| {
+            let literal = literal.clone();
+            async move {
+                loop {
+                    if let Some(key) = ready.pop_front() {
+                        return Some((Ok(key), (pending, ready)));
+                    }
+                    let current = pending.pop()?;
+                    let mut entries = match sink.list_objects_copy(&current).await {
+                        Ok(entries) => entries.into_iter().collect::<Vec<_>>(),
+                        Err(err) => return Some((Err(err), (vec![], VecDeque::new()))),
+                    };
+                    entries.sort_unstable_by(|left, right| right.cmp(left));
+
+                    for entry in entries {
+                        if entry.ends_with('/') {
+                            if entry != current
+                                && (entry.starts_with(literal.as_str())
+                                    || literal.starts_with(entry.as_str()))
+                            {
+                                pending.push(entry);
+                            }
+                        } else if entry.starts_with(literal.as_str()) && regex.is_match(&entry) {
+                            ready.push_front(entry);
+                        }
+                    }
+                }
+            }
+        },
+    )
+}
+
+#[cfg(feature = "regex")]
+fn regex_prefix(pattern: &str) -> String {
+    let Some(pattern) = pattern.strip_prefix('^').filter(|_| !unprefixable(pattern)) else {
+        return String::new();
+    };
+    let mut literal = String::new();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        let next = match char {
+            '\\' => match chars.next() {
+                Some(escaped) if !escaped.is_ascii_alphanumeric() => escaped,
+                _ => break,
+            },
+            '.' | '[' | ']' | '(' | ')' | '{' | '}' | '*' | '+' | '?' | '|' | '^' | '$' => break,
+            _ => char,
+        };
+        if matches!(chars.peek(), Some(&('*' | '?' | '{' | '|'))) {
+            break;
+        }
+        literal.push(next);
+    }
+
+    literal
+}
+
+/// Whether the pattern alternates at its top level or sets a flag changing how its literal
+/// characters match, either way a key matching it may not start with its literal prefix.
+#[cfg(feature = "regex")]
+fn unprefixable(pattern: &str) -> bool {
+    let mut depth = 0_usize;
+    let mut class = false;
+    let mut chars = pattern.char_indices().peekable();
+
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '\\' => {
+                chars.next();
+            }
+            ']' if class => class = false,
+            _ if class => {}
+            '[' => {
+                class = true;
+                chars.next_if(|&(_, next)| next == '^');
+                chars.next_if(|&(_, next)| next == ']');
+            }
+            '(' => {
+                // `(?flags)` or `(?flags:...)`, a named group `(?P<name>...)` sets none.
+                let group = pattern.get(index + 1..).unwrap_or_default();
+                if let Some(group) = group.strip_prefix('?') {
+                    let flags = group
+                        .find(|flag: char| !flag.is_ascii_alphabetic() && flag != '-')
+                        .and_then(|end| group.get(..end).zip(group.get(end..)));
+                    if flags.is_some_and(|(flags, rest)| {
+                        rest.starts_with([':', ')']) && flags.contains(['i', 'm', 'x'])
+                    }) {
+                        return true;
+                    }
+                }
+                depth += 1;
+            }
+            ')' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+fn tokenize(segment: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = segment.chars();
+
+    while let Some(char) = chars.next() {
+        tokens.push(match char {
+            '*' => Token::Any,
+            '?' => Token::One,
+            '[' => {
+                let class = chars
+                    .clone()
+                    .take_while(|&char| char != ']')
+                    .collect::<Vec<_>>();
+                if chars.clone().nth(class.len()).is_none() {
+                    Token::Literal('[')
+                } else {
+                    chars.nth(class.len());
+                    class_token(&class)
+                }
+            }
+            '\\' => Token::Literal(chars.next().unwrap_or('\\')),
+            _ => Token::Literal(char),
+        });
+    }
+
+    tokens
+}
+
+fn class_token(class: &[char]) -> Token {
+    let (negated, class) = match class.split_first() {
+        Some((&'!', rest)) => (true, rest),
+        _ => (false, class),
+    };
+    let mut ranges = vec![];
+    let mut index = 0;
+
+    while let Some(&start) = class.get(index) {
+        if let (Some(&'-'), Some(&end)) = (class.get(index + 1), class.get(index + 2)) {
+            ranges.push((start, end));
+            index += 3;
+        } else {
+            ranges.push((start, start));
+            index += 1;
+        }
+    }
+
+    Token::Class { negated, ranges }
+}
+
+fn match_tokens(tokens: &[Token], text: &[char]) -> bool {
+    match tokens.split_first() {
+        None => text.is_empty(),
+        Some((&Token::Any, rest)) => (0..=text.len()).any(|skip| {
+            text.get(skip..)
+                .is_some_and(|text| match_tokens(rest, text))
+        }),
+        Some((token, rest)) => text.split_first().is_some_and(|(&char, text)| {
+            let matched = match *token {
+                Token::Literal(literal) => literal == char,
+                Token::One | Token::Any => true,
+                Token::Class {
+                    negated,
+                    ref ranges,
+                } => {
+                    ranges
+                        .iter()
+                        .any(|&(start, end)| (start..=end).contains(&char))
+                        != negated
+                }
+            };
+            matched && match_tokens(rest, text)
+        }),
+    }
+}
+
+fn match_segment(tokens: &[Token], segment: &str) -> bool {
+    match_tokens(tokens, &segment.chars().collect::<Vec<_>>())
+}
+
+fn match_segments(segments: &[Segment], key: &[&str]) -> bool {
+    match segments.split_first() {
+        None => key.is_empty(),
+        Some((&Segment::Deep, rest)) => {
+            (0..=key.len()).any(|skip| key.get(skip..).is_some_and(|key| match_segments(rest, key)))
+        }
+        Some((Segment::Tokens(tokens), rest)) => key
+            .split_first()
+            .is_some_and(|(first, key)| match_segment(tokens, first) && match_segments(rest, key)),
+    }
+}
+
+fn contain_segments(segments: &[Segment], directory: &[&str]) -> bool {
+    match (segments.split_first(), directory.split_first()) {
+        (_, None) => !segments.is_empty(),
+        (None, Some(_)) => false,
+        (Some((&Segment::Deep, _)), Some(_)) => true,
+        (Some((Segment::Tokens(tokens), rest)), Some((first, directory))) => {
+            match_segment(tokens, first) && contain_segments(rest, directory)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    #[test]
+    fn glob_matching() {
+        let glob = Glob::new("logs/2024-*/errors/*.json");
+
+        assert_eq!(glob.literal_prefix(), "logs/");
+        assert!(glob.matches("logs/2024-01/errors/a.json"));
+        assert!(!glob.matches("logs/2024-01/errors/deep/a.json"));
+        assert!(!glob.matches("logs/2023-01/errors/a.json"));
+        assert!(glob.could_contain("logs/2024-02/"));
+        assert!(!glob.could_contain("logs/2023-02/"));
+
+        let deep = Glob::new("data/**/[a-c]?.bin");
+        assert_eq!(deep.literal_prefix(), "data/");
+        assert!(deep.matches("data/b1.bin"));
+        assert!(deep.matches("data/x/y/c2.bin"));
+        assert!(!deep.matches("data/x/d2.bin"));
+        assert!(Glob::new("[!a]*").matches("b"));
+        assert_eq!(Glob::new("exact/key").literal_prefix(), "exact/");
+    }
+
+    async fn memory() -> Memory {
+        let mut memory = Memory::default();
+        for key in [
+            "logs/2024-01/errors/a.json",
+            "logs/2024-01/errors/b.txt",
+            "logs/2024-02/errors/c.json",
+            "logs/2024-02/info/d.json",
+            "logs/2023-12/errors/e.json",
+        ] {
+            memory
+                .put_bytes_copy(&key.to_owned(), String::new(), vec![])
+                .await
+                .unwrap();
+        }
+        memory
+    }
+
+    #[tokio::test]
+    async fn list_with_glob() {
+        let memory = memory().await;
+
+        assert_eq!(
+            list_glob(&memory, "logs/2024-*/errors/*.json")
+                .await
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![
+                "logs/2024-01/errors/a.json".to_owned(),
+                "logs/2024-02/errors/c.json".to_owned()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn estimate_by_sampling() {
+        let mut memory = Memory::default();
+        for shard in 0..40 {
+            for index in 0..3 {
+                memory
+                    .put_bytes_copy(&format!("events/{shard:02}/{index}"), String::new(), vec![])
+                    .await
+                    .unwrap();
+            }
+        }
+        memory
+            .put_bytes_copy(&"events/index".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+
+        let sampled = estimate_count(&memory, "events/", NonZeroUsize::new(8).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(sampled.count, 121);
+        assert!(!sampled.exact);
+        assert_eq!(sampled.listed, 9, "root and eight sampled shards");
+
+        let full = estimate_count(&memory, "events/", NonZeroUsize::new(64).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            full,
+            CountEstimate {
+                count: 121,
+                exact: true,
+                listed: 41,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn process_prefix_concurrently() {
+        let mut memory = Memory::default();
+        for index in 0..6_u32 {
+            memory
+                .put_object_copy(
+                    &DKeyWithParserCopy::new(&format!("scores/{index}"), &Json),
+                    &index,
+                )
+                .await
+                .unwrap();
+        }
+        memory
+            .put_bytes_copy(&"scores/broken".to_owned(), String::new(), b"{".to_vec())
+            .await
+            .unwrap();
+        let options = ConcurrentOptions {
+            concurrency: NonZeroUsize::new(3).unwrap(),
+            ..ConcurrentOptions::default()
+        };
+
+        let loaded = get_concurrent::<u32, _, _>(&memory, "scores/", &Json, options)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            loaded
+                .iter()
+                .filter_map(|loaded| loaded.as_ref().ok().map(|&(_, value)| value))
+                .collect::<Vec<_>>(),
+            (0..6).collect::<Vec<_>>()
+        );
+        assert!(loaded.last().unwrap().is_err());
+
+        let all = for_each_concurrent(
+            &memory,
+            "scores/",
+            &Json,
+            ConcurrentOptions {
+                ordering: Ordering::Completion,
+                ..options
+            },
+            |_, value: u32| async move { Ok::<_, MemoryError>(value * 2) },
+        )
+        .await;
+        assert_eq!(
+            all.succeeded,
+            (0..6)
+                .map(|index| (index as usize, index * 2))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(all.failed.len(), 1);
+
+        let fast = for_each_concurrent(
+            &memory,
+            "scores/",
+            &Json,
+            ConcurrentOptions {
+                mode: ErrorMode::FailFast,
+                ..options
+            },
+            |key, value: u32| async move {
+                if value == 2 {
+                    Err(MemoryError::Locked {
+                        key,
+                        until: SystemTime::UNIX_EPOCH,
+                    })
+                } else {
+                    Ok(value)
+                }
+            },
+        )
+        .await;
+        assert_eq!(fast.succeeded, [(0, 0), (1, 1)]);
+        assert!(matches!(
+            fast.failed.as_slice(),
+            [(2, MemoryError::Locked { .. })]
+        ));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn literal_prefix_of_regex() {
+        assert_eq!(regex_prefix(r"^logs/2024-0\d/"), "logs/2024-0");
+        assert_eq!(regex_prefix(r"^logs/a\.json$"), "logs/a.json");
+        assert_eq!(regex_prefix(r"^logs/ab*"), "logs/a");
+        assert_eq!(regex_prefix(r"^a|b"), "");
+        assert_eq!(regex_prefix(r"^logs/a|b"), "", "top level alternation");
+        assert_eq!(regex_prefix(r"^logs/(a|b)"), "logs/");
+        assert_eq!(regex_prefix(r"^logs/[|]a"), "logs/");
+        assert_eq!(regex_prefix(r"^logs/\|a"), "logs/|a");
+        assert_eq!(regex_prefix(r"^(?i)logs/"), "");
+        assert_eq!(regex_prefix(r"^logs/(?i:a)"), "");
+        assert_eq!(regex_prefix(r"^logs/(?P<id>\d)"), "logs/");
+        assert_eq!(regex_prefix(r"logs/"), "", "unanchored regex has no prefix");
+    }
+
+    #[cfg(feature = "regex")]
+    #[tokio::test]
+    async fn list_with_regex() {
+        use futures::StreamExt as _;
+        use regex_lite::RegexBuilder;
+
+        let memory = memory().await;
+        let regex = Regex::new(r"^logs/2024-\d+/(errors|info)/[cd]\.json$").unwrap();
+
+        assert_eq!(
+            list_matching(&memory, "logs/", &regex)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+                .await,
+            vec![
+                "logs/2024-02/errors/c.json".to_owned(),
+                "logs/2024-02/info/d.json".to_owned()
+            ]
+        );
+        assert!(list_matching(&memory, "other/", &regex)
+            .collect::<Vec<_>>()
+            .await
+            .is_empty());
+
+        let expected = vec!["logs/2024-02/errors/c.json".to_owned()];
+        let inline = Regex::new(r"^(?i)LOGS/2024-\d+/ERRORS/C\.json$").unwrap();
+        assert_eq!(
+            list_matching(&memory, "", &inline)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+                .await,
+            expected
+        );
+        let built = RegexBuilder::new(r"^LOGS/2024-\d+/ERRORS/C\.json$")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            list_matching_with(&memory, "", &built, true)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+                .await,
+            expected
+        );
+    }
+}
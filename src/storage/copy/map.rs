@@ -0,0 +1,245 @@
+use core::marker::PhantomData;
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::direct::DKeyWithParserCopy;
+use super::parser::{GuardedParser as _, Json};
+use super::Cache;
+use crate::storage::meta::checksum;
+use crate::storage::{ParserError, ResultExt as _};
+
+const MAP_PREFIX: &str = "map/";
+const INDEX_NAME: &str = "index";
+
+pub trait MapValue = Serialize + DeserializeOwned + Send + Sync;
+
+type Index<KEY> = BTreeMap<String, KEY>;
+
+#[derive(Serialize, Deserialize)]
+struct Entry<KEY, VALUE> {
+    key: KEY,
+    // `None` is a tombstone, the cache has no delete to forget the object.
+    value: Option<VALUE>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyedMap<KEY, VALUE> {
+    name: String,
+    entry: PhantomData<fn() -> (KEY, VALUE)>,
+}
+
+impl<KEY, VALUE> KeyedMap<KEY, VALUE>
+where
+    KEY: MapValue + Clone + PartialEq,
+    VALUE: MapValue,
+{
+    #[inline]
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            entry: PhantomData,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn prefix(&self) -> String {
+        format!("{MAP_PREFIX}{}/", self.name)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn index_key(&self) -> String {
+        format!("{}{INDEX_NAME}", self.prefix())
+    }
+
+    #[inline]
+    pub fn entry_key(&self, key: &KEY) -> Result<String, ParserError> {
+        Ok(format!(
+            "{}{}",
+            self.prefix(),
+            checksum(
+                &Json
+                    .serialize_guarded(key)
+                    .context("map", "entry_key", &self.name)?
+            )
+        ))
+    }
+
+    #[inline]
+    pub async fn insert<CACHE>(
+        &self,
+        cache: &mut CACHE,
+        key: KEY,
+        value: VALUE,
+    ) -> Result<(), CACHE::Error>
+    where
+        CACHE: Cache + Send,
+        CACHE::Error: From<ParserError>,
+    {
+        let entry_key = self.entry_key(&key)?;
+        cache
+            .put_object_copy(
+                &DKeyWithParserCopy::new(&entry_key, &Json),
+                &Entry {
+                    key: key.clone(),
+                    value: Some(value),
+                },
+            )
+            .await?;
+
+        let mut index = self.index(cache).await?;
+        if index.insert(entry_key, key).is_none() {
+            self.put_index(cache, &index).await?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub async fn get<CACHE>(
+        &self,
+        cache: &mut CACHE,
+        key: &KEY,
+    ) -> Result<Option<VALUE>, CACHE::Error>
+    where
+        CACHE: Cache + Send,
+        CACHE::Error: From<ParserError>,
+    {
+        let entry = cache
+            .get_object_copy::<Entry<KEY, VALUE>, _, _>(&DKeyWithParserCopy::new(
+                &self.entry_key(key)?,
+                &Json,
+            ))
+            .await?;
+        Ok(entry
+            .filter(|entry| entry.key == *key)
+            .and_then(|entry| entry.value))
+    }
+
+    #[inline]
+    pub async fn remove<CACHE>(&self, cache: &mut CACHE, key: &KEY) -> Result<bool, CACHE::Error>
+    where
+        CACHE: Cache + Send,
+        CACHE::Error: From<ParserError>,
+    {
+        let entry_key = self.entry_key(key)?;
+        let mut index = self.index(cache).await?;
+        if index.remove(&entry_key).is_none() {
+            return Ok(false);
+        }
+
+        cache
+            .put_object_copy(
+                &DKeyWithParserCopy::new(&entry_key, &Json),
+                &Entry::<&KEY, VALUE> { key, value: None },
+            )
+            .await?;
+        self.put_index(cache, &index).await?;
+        Ok(true)
+    }
+
+    #[inline]
+    pub async fn keys<CACHE>(&self, cache: &mut CACHE) -> Result<Vec<KEY>, CACHE::Error>
+    where
+        CACHE: Cache + Send,
+    {
+        Ok(self.index(cache).await?.into_values().collect())
+    }
+
+    #[inline]
+    pub async fn len<CACHE>(&self, cache: &mut CACHE) -> Result<usize, CACHE::Error>
+    where
+        CACHE: Cache + Send,
+    {
+        Ok(self.index(cache).await?.len())
+    }
+
+    #[inline]
+    pub async fn iter<CACHE>(&self, cache: &mut CACHE) -> Result<Vec<(KEY, VALUE)>, CACHE::Error>
+    where
+        CACHE: Cache + Send,
+        CACHE::Error: From<ParserError>,
+    {
+        let mut entries = vec![];
+        for key in self.keys(cache).await? {
+            // An index updated by another writer may still list an entry already removed.
+            if let Some(value) = self.get(cache, &key).await? {
+                entries.push((key, value));
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn index<CACHE>(&self, cache: &mut CACHE) -> Result<Index<KEY>, CACHE::Error>
+    where
+        CACHE: Cache + Send,
+    {
+        Ok(cache
+            .get_object_copy(&DKeyWithParserCopy::new(&self.index_key(), &Json))
+            .await?
+            .unwrap_or_default())
+    }
+
+    async fn put_index<CACHE>(
+        &self,
+        cache: &mut CACHE,
+        index: &Index<KEY>,
+    ) -> Result<(), CACHE::Error>
+    where
+        CACHE: Cache + Send,
+    {
+        cache
+            .put_object_copy(&DKeyWithParserCopy::new(&self.index_key(), &Json), index)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::storage::cache::lru::Lru;
+    use crate::storage::copy::Sink as _;
+    use crate::storage::layer::Layer as _;
+    use crate::storage::sink::memory::Memory;
+
+    #[tokio::test]
+    async fn insert_get_remove_iter() {
+        let mut lru = Lru::new(NonZeroUsize::new(16).unwrap(), Memory::default());
+        let map = KeyedMap::<(String, u8), u32>::new("scores");
+        let alice = ("alice".to_owned(), 1);
+        let bob = ("bob".to_owned(), 2);
+
+        map.insert(&mut lru, alice.clone(), 10).await.unwrap();
+        map.insert(&mut lru, bob.clone(), 20).await.unwrap();
+        map.insert(&mut lru, alice.clone(), 11).await.unwrap();
+
+        assert_eq!(map.get(&mut lru, &alice).await.unwrap(), Some(11));
+        assert_eq!(map.len(&mut lru).await.unwrap(), 2);
+        assert!(map.entry_key(&alice).unwrap().starts_with("map/scores/"));
+        assert!(lru
+            .storage()
+            .exists_copy(&DKeyWithParserCopy::new(&map.index_key(), &Json))
+            .await
+            .unwrap());
+
+        assert!(map.remove(&mut lru, &bob).await.unwrap());
+        assert!(!map.remove(&mut lru, &bob).await.unwrap());
+        assert_eq!(map.get(&mut lru, &bob).await.unwrap(), None);
+        assert_eq!(map.iter(&mut lru).await.unwrap(), [(alice, 11)]);
+
+        let mut reopened = Lru::new(NonZeroUsize::MIN, lru.into_inner());
+        assert_eq!(map.keys(&mut reopened).await.unwrap().len(), 1);
+    }
+}
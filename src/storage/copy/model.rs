@@ -0,0 +1,345 @@
+use core::time::Duration;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, process};
+
+use semver::{BuildMetadata, Version};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const MODEL_VERSION: u32 = 1;
+const ENVELOPE_VERSIONS: &[u32] = &[1];
+const SCHEMA_VERSIONS: &[u32] = &[1];
+const DEFAULT_STALE_AFTER: u64 = 5 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Welcome {
+    #[serde(default = "legacy_model_version")]
+    pub(crate) model_version: u32,
+    pub(crate) version: Version,
+    #[serde(default)]
+    pub(crate) build_hash: Option<String>,
+    #[serde(default)]
+    pub(crate) hostname: Option<String>,
+    #[serde(default)]
+    pub(crate) started_at: u64,
+    #[serde(default = "legacy_versions")]
+    pub(crate) envelope_versions: Vec<u32>,
+    #[serde(default = "legacy_versions")]
+    pub(crate) schema_versions: Vec<u32>,
+}
+
+impl Welcome {
+    #[inline]
+    #[must_use]
+    pub const fn model_version(&self) -> u32 {
+        self.model_version
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> &Version {
+        &self.version
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn build_hash(&self) -> Option<&str> {
+        self.build_hash.as_deref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hostname(&self) -> Option<&str> {
+        self.hostname.as_deref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn started_at(&self) -> u64 {
+        self.started_at
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn envelope_versions(&self) -> &[u32] {
+        &self.envelope_versions
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn schema_versions(&self) -> &[u32] {
+        &self.schema_versions
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        let same_release = self.version.major == other.version.major
+            && (self.version.major != 0 || self.version.minor == other.version.minor);
+        let shared =
+            |left: &[u32], right: &[u32]| left.iter().any(|version| right.contains(version));
+
+        same_release
+            && shared(&self.envelope_versions, &other.envelope_versions)
+            && shared(&self.schema_versions, &other.schema_versions)
+    }
+}
+
+impl Default for Welcome {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            model_version: MODEL_VERSION,
+            version: Version {
+                major: env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or_default(),
+                minor: env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or_default(),
+                patch: env!("CARGO_PKG_VERSION_PATCH").parse().unwrap_or_default(),
+                pre: env!("CARGO_PKG_VERSION_PRE").parse().unwrap_or_default(),
+                build: BuildMetadata::EMPTY,
+            },
+            build_hash: option_env!("NEGENTROPY_BUILD_HASH").map(ToOwned::to_owned),
+            hostname: hostname(),
+            started_at: unix_time(),
+            envelope_versions: ENVELOPE_VERSIONS.to_vec(),
+            schema_versions: SCHEMA_VERSIONS.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "InitializeShim")]
+pub struct Initialize {
+    pub model_version: u32,
+    pub created_at: u64,
+}
+
+impl Initialize {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            model_version: MODEL_VERSION,
+            created_at: unix_time(),
+        }
+    }
+}
+
+impl Default for Initialize {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Objects written before the model module stored `Initialize` as a unit struct, i.e. `null`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InitializeShim {
+    Legacy(()),
+    Current {
+        #[serde(default = "legacy_model_version")]
+        model_version: u32,
+        #[serde(default)]
+        created_at: u64,
+    },
+}
+
+impl From<InitializeShim> for Initialize {
+    #[inline]
+    fn from(value: InitializeShim) -> Self {
+        match value {
+            InitializeShim::Legacy(()) => Self {
+                model_version: legacy_model_version(),
+                created_at: 0,
+            },
+            InitializeShim::Current {
+                model_version,
+                created_at,
+            } => Self {
+                model_version,
+                created_at,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceRecord {
+    #[serde(default = "legacy_model_version")]
+    pub model_version: u32,
+    pub id: Uuid,
+    pub version: Version,
+    pub started_at: u64,
+    pub last_heartbeat: u64,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default)]
+    pub pid: u32,
+}
+
+impl InstanceRecord {
+    #[inline]
+    #[must_use]
+    pub fn new(id: Uuid) -> Self {
+        let now = unix_time();
+        Self {
+            model_version: MODEL_VERSION,
+            id,
+            version: Welcome::default().version,
+            started_at: now,
+            last_heartbeat: now,
+            hostname: hostname(),
+            pid: process::id(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkewPolicy {
+    pub stale_after: Duration,
+    pub max_minor_skew: u64,
+}
+
+impl Default for SkewPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            stale_after: Duration::from_secs(DEFAULT_STALE_AFTER),
+            max_minor_skew: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionReport {
+    pub versions: BTreeMap<Version, Vec<Uuid>>,
+    pub stale: Vec<Uuid>,
+    pub skewed: bool,
+}
+
+impl VersionReport {
+    #[inline]
+    #[must_use]
+    pub fn from_records(records: &[InstanceRecord], policy: &SkewPolicy, now: u64) -> Self {
+        let mut report = Self::default();
+        for record in records {
+            if now.saturating_sub(record.last_heartbeat) > policy.stale_after.as_secs() {
+                report.stale.push(record.id);
+            } else {
+                report
+                    .versions
+                    .entry(record.version.clone())
+                    .or_default()
+                    .push(record.id);
+            }
+        }
+
+        report.skewed = match (report.oldest(), report.newest()) {
+            (Some(oldest), Some(newest)) => {
+                oldest.major != newest.major || newest.minor - oldest.minor > policy.max_minor_skew
+            }
+            _ => false,
+        };
+        report
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn oldest(&self) -> Option<&Version> {
+        self.versions.keys().next()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn newest(&self) -> Option<&Version> {
+        self.versions.keys().next_back()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn live(&self) -> usize {
+        self.versions.values().map(Vec::len).sum()
+    }
+}
+
+pub(crate) fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn legacy_versions() -> Vec<u32> {
+    vec![1]
+}
+
+fn hostname() -> Option<String> {
+    env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+}
+
+const fn legacy_model_version() -> u32 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_objects_still_parse() {
+        let initialize = serde_json::from_slice::<Initialize>(b"null").unwrap();
+        assert_eq!(initialize.model_version, 1);
+        assert_eq!(initialize.created_at, 0);
+
+        let current = Initialize::new();
+        assert_eq!(
+            serde_json::from_slice::<Initialize>(&serde_json::to_vec(&current).unwrap()).unwrap(),
+            current
+        );
+
+        let record = serde_json::from_str::<InstanceRecord>(
+            r#"{"id":"00000000-0000-0000-0000-000000000000","version":"0.1.0","started_at":1,"last_heartbeat":2}"#,
+        )
+        .unwrap();
+        assert_eq!(record.model_version, 1);
+        assert_eq!(record.hostname, None);
+
+        let welcome = serde_json::from_str::<Welcome>(r#"{"version":"0.1.0"}"#).unwrap();
+        assert_eq!(welcome.model_version(), 1);
+        assert!(welcome.is_compatible(&Welcome::default()));
+    }
+
+    #[test]
+    fn group_live_instances_by_version() {
+        let record = |id: u128, version: &str, last_heartbeat: u64| InstanceRecord {
+            version: Version::parse(version).unwrap(),
+            last_heartbeat,
+            ..InstanceRecord::new(Uuid::from_u128(id))
+        };
+        let policy = SkewPolicy::default();
+        let records = [
+            record(1, "1.2.0", 1_000),
+            record(2, "1.3.1", 1_000),
+            record(3, "1.3.1", 990),
+            record(4, "1.0.0", 10),
+        ];
+
+        let report = VersionReport::from_records(&records, &policy, 1_000);
+        assert_eq!(report.live(), 3);
+        assert_eq!(report.stale, [Uuid::from_u128(4)]);
+        assert_eq!(report.oldest(), Some(&Version::new(1, 2, 0)));
+        assert_eq!(
+            report.versions.get(&Version::new(1, 3, 1)).map(Vec::len),
+            Some(2)
+        );
+        assert!(!report.skewed, "stale instances do not count");
+
+        let report = VersionReport::from_records(&records, &policy, 100);
+        assert!(report.skewed, "1.0 is still writing next to 1.3");
+    }
+}
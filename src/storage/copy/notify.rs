@@ -0,0 +1,203 @@
+#[cfg(feature = "sqs")]
+pub mod sqs;
+
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+
+use crate::storage::cache::disk::DiskCache;
+use crate::storage::cache::lru::Lru;
+use crate::storage::clock::Clock;
+use crate::storage::{LayerError, ParserError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum S3EventKind {
+    Created,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Event {
+    pub bucket: String,
+    pub key: String,
+    pub kind: S3EventKind,
+}
+
+#[derive(Deserialize)]
+struct Notification {
+    #[serde(rename = "Records", default)]
+    records: Vec<Record>,
+}
+
+#[derive(Deserialize)]
+struct Record {
+    #[serde(rename = "eventName")]
+    event_name: String,
+    s3: RecordS3,
+}
+
+#[derive(Deserialize)]
+struct RecordS3 {
+    bucket: RecordBucket,
+    object: RecordObject,
+}
+
+#[derive(Deserialize)]
+struct RecordBucket {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RecordObject {
+    key: String,
+}
+
+pub trait Invalidate {
+    type Error;
+
+    fn invalidate_key(&mut self, key: &str) -> Result<bool, Self::Error>;
+}
+
+impl<STORAGE, CLOCK> Invalidate for Lru<STORAGE, CLOCK>
+where
+    STORAGE: Send + Sync,
+    CLOCK: Clock,
+{
+    type Error = LayerError;
+
+    #[inline]
+    fn invalidate_key(&mut self, key: &str) -> Result<bool, Self::Error> {
+        Ok(self.invalidate(key))
+    }
+}
+
+impl<STORAGE> Invalidate for DiskCache<STORAGE>
+where
+    STORAGE: Send + Sync,
+{
+    type Error = LayerError;
+
+    #[inline]
+    fn invalidate_key(&mut self, key: &str) -> Result<bool, Self::Error> {
+        self.invalidate(key)
+    }
+}
+
+#[inline]
+pub fn parse_s3_events(body: &[u8]) -> Result<Vec<S3Event>, ParserError> {
+    let notification =
+        serde_json::from_slice::<Notification>(body).map_err(|err| ParserError::Serde {
+            internal: err.to_string(),
+        })?;
+
+    Ok(notification
+        .records
+        .into_iter()
+        .filter_map(|record| {
+            let kind = if record.event_name.starts_with("ObjectCreated:") {
+                S3EventKind::Created
+            } else if record.event_name.starts_with("ObjectRemoved:") {
+                S3EventKind::Removed
+            } else {
+                return None;
+            };
+            Some(S3Event {
+                bucket: record.s3.bucket.name,
+                key: decode_event_key(&record.s3.object.key),
+                kind,
+            })
+        })
+        .collect())
+}
+
+/// Only the events of `bucket` are applied, one queue may carry the notifications of several.
+#[inline]
+pub fn invalidate_events<CACHE>(
+    cache: &mut CACHE,
+    bucket: &str,
+    events: &[S3Event],
+) -> Result<usize, CACHE::Error>
+where
+    CACHE: Invalidate,
+{
+    let mut invalidated = 0;
+    for event in events.iter().filter(|event| event.bucket == bucket) {
+        if cache.invalidate_key(&event.key)? {
+            invalidated += 1;
+        }
+    }
+    Ok(invalidated)
+}
+
+/// Event keys are form encoded, `+` stands for a space and a literal plus is `%2B`.
+fn decode_event_key(key: &str) -> String {
+    percent_decode_str(&key.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::storage::copy::Cache;
+    use crate::storage::sink::memory::Memory;
+
+    const NOTIFICATION: &[u8] = br#"{
+        "Records": [
+            {
+                "eventName": "ObjectCreated:Put",
+                "s3": { "bucket": { "name": "assets" }, "object": { "key": "docs/a+b%2Bc.json" } }
+            },
+            {
+                "eventName": "ObjectRemoved:Delete",
+                "s3": { "bucket": { "name": "assets" }, "object": { "key": "docs/gone" } }
+            },
+            {
+                "eventName": "ObjectRestore:Completed",
+                "s3": { "bucket": { "name": "assets" }, "object": { "key": "docs/cold" } }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn parse_notification() {
+        assert_eq!(
+            parse_s3_events(NOTIFICATION).unwrap(),
+            vec![
+                S3Event {
+                    bucket: "assets".to_owned(),
+                    key: "docs/a b+c.json".to_owned(),
+                    kind: S3EventKind::Created,
+                },
+                S3Event {
+                    bucket: "assets".to_owned(),
+                    key: "docs/gone".to_owned(),
+                    kind: S3EventKind::Removed,
+                },
+            ]
+        );
+        assert!(parse_s3_events(br#"{"Event":"s3:TestEvent"}"#)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalidate_lru_from_events() {
+        let mut lru = Lru::new(NonZeroUsize::new(8).unwrap(), Memory::default());
+        for key in ["docs/a b+c.json", "docs/gone", "docs/kept"] {
+            lru.put_bytes_copy(&key.to_owned(), String::new(), vec![1])
+                .await
+                .unwrap();
+        }
+
+        let events = parse_s3_events(NOTIFICATION).unwrap();
+        assert_eq!(invalidate_events(&mut lru, "other", &events).unwrap(), 0);
+        assert!(lru.contains_inner("docs/gone"));
+        assert_eq!(invalidate_events(&mut lru, "assets", &events).unwrap(), 2);
+        assert!(!lru.contains_inner("docs/gone"));
+        assert!(!lru.exists_inner("docs/a b+c.json"));
+        assert!(lru.contains_inner("docs/kept"));
+        assert_eq!(lru.stats().bytes, 1);
+    }
+}
@@ -0,0 +1,189 @@
+use core::time::Duration;
+
+use aws_config::BehaviorVersion;
+use aws_sdk_sqs::Client;
+
+use super::{invalidate_events, parse_s3_events, Invalidate};
+use crate::storage::task::CancellationToken;
+use crate::storage::LayerError;
+
+const MAX_MESSAGES: i32 = 10;
+const MAX_WAIT: Duration = Duration::from_secs(20);
+
+/// Long polls the SQS queue fed by the S3 event notifications of `bucket` and invalidates the
+/// cached keys, a message is deleted only once its events are applied.
+#[derive(Debug, Clone)]
+pub struct SqsListener {
+    client: Client,
+    queue_url: String,
+    bucket: String,
+    wait: Duration,
+}
+
+impl SqsListener {
+    #[inline]
+    #[must_use]
+    pub fn new(client: Client, queue_url: &str, bucket: &str) -> Self {
+        Self {
+            client,
+            queue_url: queue_url.to_owned(),
+            bucket: bucket.to_owned(),
+            wait: MAX_WAIT,
+        }
+    }
+
+    /// Client from the environment, like the S3 sink loads its own.
+    #[inline]
+    pub async fn from_env(queue_url: &str, bucket: &str) -> Self {
+        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+        Self::new(Client::new(&config), queue_url, bucket)
+    }
+
+    /// Long poll wait, SQS caps it at 20 seconds.
+    #[inline]
+    #[must_use]
+    pub fn with_wait_time(mut self, wait: Duration) -> Self {
+        self.wait = wait.min(MAX_WAIT);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn queue_url(&self) -> &str {
+        &self.queue_url
+    }
+
+    /// Receive one batch of messages, returning how many cached keys were invalidated.
+    #[inline]
+    pub async fn poll<CACHE>(&self, cache: &mut CACHE) -> Result<usize, CACHE::Error>
+    where
+        CACHE: Invalidate,
+        CACHE::Error: From<LayerError>,
+    {
+        let output = self
+            .client
+            .receive_message()
+            .queue_url(&self.queue_url)
+            .max_number_of_messages(MAX_MESSAGES)
+            .wait_time_seconds(i32::try_from(self.wait.as_secs()).unwrap_or(i32::MAX))
+            .send()
+            .await
+            .map_err(|err| self.queue_error("receive", &err.to_string()))?;
+
+        let mut invalidated = 0;
+        for message in output.messages() {
+            invalidated += apply_message(cache, &self.bucket, message.body())?;
+            if let Some(receipt) = message.receipt_handle() {
+                self.client
+                    .delete_message()
+                    .queue_url(&self.queue_url)
+                    .receipt_handle(receipt)
+                    .send()
+                    .await
+                    .map_err(|err| self.queue_error("delete", &err.to_string()))?;
+            }
+        }
+        Ok(invalidated)
+    }
+
+    /// Poll until `token` is cancelled, an in flight long poll is abandoned on cancellation and
+    /// its messages are redelivered once their visibility timeout expires.
+    #[inline]
+    pub async fn listen<CACHE>(
+        &self,
+        cache: &mut CACHE,
+        token: &CancellationToken,
+    ) -> Result<(), CACHE::Error>
+    where
+        CACHE: Invalidate + Send,
+        CACHE::Error: From<LayerError>,
+    {
+        while !token.is_cancelled() {
+            tokio::select! {
+                polled = self.poll(cache) => {
+                    polled?;
+                }
+                () = token.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn queue_error(&self, operation: &str, internal: &str) -> LayerError {
+        LayerError::Queue {
+            operation: operation.to_owned(),
+            key: self.queue_url.clone(),
+            internal: internal.to_owned(),
+        }
+    }
+}
+
+/// A body that is not an S3 notification is dropped rather than redelivered forever.
+fn apply_message<CACHE>(
+    cache: &mut CACHE,
+    bucket: &str,
+    body: Option<&str>,
+) -> Result<usize, CACHE::Error>
+where
+    CACHE: Invalidate,
+{
+    match parse_s3_events(body.unwrap_or_default().as_bytes()) {
+        Ok(events) => invalidate_events(cache, bucket, &events),
+        #[cfg(feature = "tracing")]
+        Err(err) => {
+            tracing::warn!(%err, "Drop a queue message that is not an S3 notification");
+            Ok(0)
+        }
+        #[cfg(not(feature = "tracing"))]
+        Err(_) => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::storage::cache::lru::Lru;
+    use crate::storage::copy::Cache as _;
+    use crate::storage::sink::memory::Memory;
+
+    #[tokio::test]
+    async fn apply_queue_messages() {
+        let mut lru = Lru::new(NonZeroUsize::new(8).unwrap(), Memory::default());
+        lru.put_bytes_copy(&"docs/a".to_owned(), String::new(), vec![1])
+            .await
+            .unwrap();
+
+        assert_eq!(apply_message(&mut lru, "assets", None).unwrap(), 0);
+        assert_eq!(
+            apply_message(&mut lru, "assets", Some("not json")).unwrap(),
+            0
+        );
+        assert!(lru.contains_inner("docs/a"));
+        assert_eq!(
+            apply_message(
+                &mut lru,
+                "assets",
+                Some(
+                    r#"{"Records":[{"eventName":"ObjectRemoved:Delete",
+                    "s3":{"bucket":{"name":"assets"},"object":{"key":"docs/a"}}}]}"#
+                )
+            )
+            .unwrap(),
+            1
+        );
+        assert!(!lru.contains_inner("docs/a"));
+    }
+
+    #[test]
+    fn cap_wait_time() {
+        let config = aws_sdk_sqs::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .build();
+        let listener = SqsListener::new(Client::from_conf(config), "queue", "assets")
+            .with_wait_time(Duration::from_secs(60));
+        assert_eq!(listener.wait, MAX_WAIT);
+        assert_eq!(listener.queue_url(), "queue");
+    }
+}
@@ -1,7 +1,18 @@
-use serde::Deserialize;
+use core::any::Any;
+use std::io;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 
-use super::ValueWhere;
-use crate::storage::ParserError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::direct::DKeyWithParserCopy;
+use super::{ParserWhere, Sink, ValueWhere};
+use crate::storage::{DKeyWhere, ParserError};
+use crate::HashMap;
+
+pub mod limited;
+pub mod metered;
+pub mod validated;
 
 pub trait Parser {
     fn serialize_value<VALUE>(&self, value: &VALUE) -> Result<Vec<u8>, ParserError>
@@ -13,6 +24,64 @@ pub trait Parser {
         CONTENT: for<'content> Deserialize<'content>;
 
     fn mime(&self) -> String;
+
+    #[inline]
+    fn serialized_size<VALUE>(&self, value: &VALUE) -> Result<u64, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        Ok(self.serialize_value(value)?.len() as u64)
+    }
+}
+
+pub trait GuardedParser: Parser {
+    #[inline]
+    fn serialize_guarded<VALUE>(&self, value: &VALUE) -> Result<Vec<u8>, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        catch_unwind(AssertUnwindSafe(|| self.serialize_value(value)))
+            .map_err(|payload| panicked("serialize", &*payload))?
+    }
+
+    #[inline]
+    fn deserialize_guarded<CONTENT>(&self, content: &[u8]) -> Result<CONTENT, ParserError>
+    where
+        CONTENT: for<'content> Deserialize<'content>,
+    {
+        catch_unwind(AssertUnwindSafe(|| self.deserialize_value(content)))
+            .map_err(|payload| panicked("deserialize", &*payload))?
+    }
+}
+
+impl<PARSER> GuardedParser for PARSER where PARSER: Parser + ?Sized {}
+
+fn panicked(operation: &str, payload: &(dyn Any + Send)) -> ParserError {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| (*message).to_owned())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default();
+    ParserError::Panicked {
+        operation: operation.to_owned(),
+        message,
+    }
+}
+
+#[derive(Default)]
+struct Counter(u64);
+
+impl io::Write for Counter {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Default)]
@@ -43,4 +112,525 @@ impl Parser for Json {
     fn mime(&self) -> String {
         "application/json".to_owned()
     }
+
+    #[inline]
+    fn serialized_size<VALUE>(&self, value: &VALUE) -> Result<u64, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        let mut counter = Counter::default();
+        serde_json::to_writer(&mut counter, value).map_err(|err| ParserError::Serde {
+            internal: err.to_string(),
+        })?;
+        Ok(counter.0)
+    }
+}
+
+#[derive(Default)]
+pub struct Toml;
+
+impl Parser for Toml {
+    #[inline]
+    fn serialize_value<VALUE>(&self, value: &VALUE) -> Result<Vec<u8>, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        toml::to_string(value)
+            .map(String::into_bytes)
+            .map_err(|err| ParserError::Serde {
+                internal: err.to_string(),
+            })
+    }
+
+    #[inline]
+    fn deserialize_value<RETURN>(&self, content: &[u8]) -> Result<RETURN, ParserError>
+    where
+        RETURN: for<'content> Deserialize<'content>,
+    {
+        core::str::from_utf8(content)
+            .map_err(|err| err.to_string())
+            .and_then(|text| toml::from_str(text).map_err(|err| err.to_string()))
+            .map_err(|internal| ParserError::Serde { internal })
+    }
+
+    #[inline]
+    fn mime(&self) -> String {
+        "application/toml".to_owned()
+    }
+}
+
+pub struct ParserRegistry<PARSER> {
+    parsers: HashMap<String, PARSER>,
+    fallback: Option<String>,
+}
+
+impl<PARSER> Default for ParserRegistry<PARSER> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            parsers: HashMap::default(),
+            fallback: None,
+        }
+    }
+}
+
+impl<PARSER> ParserRegistry<PARSER>
+where
+    PARSER: ParserWhere,
+{
+    #[inline]
+    #[must_use]
+    pub fn with_parser(mut self, parser: PARSER) -> Self {
+        self.parsers.insert(parser.mime(), parser);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_fallback(mut self, mime: &str) -> Self {
+        self.fallback = Some(mime.to_owned());
+        self
+    }
+
+    #[inline]
+    pub fn parser(&self, mime: Option<&str>) -> Result<&PARSER, ParserError> {
+        let essence = mime
+            .map(|value| value.split(';').next().unwrap_or_default().trim())
+            .filter(|value| !value.is_empty());
+
+        essence
+            .and_then(|value| self.parsers.get(value))
+            .or_else(|| {
+                self.fallback
+                    .as_ref()
+                    .filter(|_| essence.is_none())
+                    .and_then(|fallback| self.parsers.get(fallback))
+            })
+            .ok_or_else(|| ParserError::UnknownMime(essence.unwrap_or_default().to_owned()))
+    }
+
+    #[inline]
+    pub async fn get_object_auto<RETURN, DKEY, SINK>(
+        &self,
+        sink: &SINK,
+        key: &DKEY,
+    ) -> Result<Option<RETURN>, SINK::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        SINK: Sink + Sync,
+        SINK::Error: From<ParserError>,
+    {
+        let Some(meta) = sink.head_copy(key).await? else {
+            return Ok(None);
+        };
+        let parser = self.parser(meta.mime.as_deref())?;
+
+        sink.get_object_copy(&DKeyWithParserCopy::new(key, parser))
+            .await
+    }
+}
+
+pub struct MigratingParser<OLD, NEW> {
+    old: OLD,
+    new: NEW,
+    rewrite: bool,
+}
+
+impl<OLD, NEW> MigratingParser<OLD, NEW>
+where
+    OLD: ParserWhere,
+    NEW: ParserWhere,
+{
+    #[inline]
+    pub const fn new(old: OLD, new: NEW) -> Self {
+        Self {
+            old,
+            new,
+            rewrite: false,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_rewrite(mut self, rewrite: bool) -> Self {
+        self.rewrite = rewrite;
+        self
+    }
+
+    #[inline]
+    pub async fn get_object_migrating<RETURN, DKEY, SINK>(
+        &self,
+        sink: &mut SINK,
+        key: &DKEY,
+    ) -> Result<Option<RETURN>, SINK::Error>
+    where
+        RETURN: Serialize + DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        SINK: Sink + Send + Sync,
+        SINK::Error: From<ParserError>,
+    {
+        let Some(content) = sink.get_bytes_copy(key).await? else {
+            return Ok(None);
+        };
+        let mime = sink.head_copy(key).await?.and_then(|meta| meta.mime);
+        let (value, from_old) = self.decode::<RETURN>(mime.as_deref(), &content)?;
+
+        if from_old && self.rewrite {
+            let rewritten = self.new.serialize_guarded(&value)?;
+            sink.put_bytes_copy(key, self.new.mime(), rewritten).await?;
+        }
+
+        Ok(Some(value))
+    }
+
+    fn decode<RETURN>(
+        &self,
+        mime: Option<&str>,
+        content: &[u8],
+    ) -> Result<(RETURN, bool), ParserError>
+    where
+        RETURN: DeserializeOwned,
+    {
+        let old_mime = self.old.mime();
+        let new_mime = self.new.mime();
+
+        match mime {
+            Some(stored) if old_mime != new_mime && stored == old_mime => {
+                Ok((self.old.deserialize_guarded(content)?, true))
+            }
+            Some(stored) if stored == new_mime => {
+                Ok((self.new.deserialize_guarded(content)?, false))
+            }
+            _ => self.new.deserialize_guarded(content).map_or_else(
+                |_| Ok((self.old.deserialize_guarded(content)?, true)),
+                |value| Ok((value, false)),
+            ),
+        }
+    }
+}
+
+impl<OLD, NEW> Parser for MigratingParser<OLD, NEW>
+where
+    OLD: ParserWhere,
+    NEW: ParserWhere,
+{
+    #[inline]
+    fn serialize_value<VALUE>(&self, value: &VALUE) -> Result<Vec<u8>, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        self.new.serialize_value(value)
+    }
+
+    #[inline]
+    fn deserialize_value<RETURN>(&self, content: &[u8]) -> Result<RETURN, ParserError>
+    where
+        RETURN: for<'content> Deserialize<'content>,
+    {
+        self.decode(None, content).map(|(value, _)| value)
+    }
+
+    #[inline]
+    fn mime(&self) -> String {
+        self.new.mime()
+    }
+
+    #[inline]
+    fn serialized_size<VALUE>(&self, value: &VALUE) -> Result<u64, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        self.new.serialized_size(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Display;
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::storage::cache::lru::Lru;
+    use crate::storage::copy::Cache;
+    use crate::storage::sink::chunked::Chunked;
+    use crate::storage::sink::encrypted::{Encrypted, KeyRing};
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::sink::size_limit::SizeLimit;
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct Doc {
+        id: u32,
+    }
+
+    enum Format {
+        Json(Json),
+        Toml(Toml),
+    }
+
+    impl Parser for Format {
+        fn serialize_value<VALUE>(&self, value: &VALUE) -> Result<Vec<u8>, ParserError>
+        where
+            VALUE: ValueWhere,
+        {
+            match *self {
+                Self::Json(ref json) => json.serialize_value(value),
+                Self::Toml(ref toml) => toml.serialize_value(value),
+            }
+        }
+
+        fn deserialize_value<CONTENT>(&self, content: &[u8]) -> Result<CONTENT, ParserError>
+        where
+            CONTENT: for<'content> Deserialize<'content>,
+        {
+            match *self {
+                Self::Json(ref json) => json.deserialize_value(content),
+                Self::Toml(ref toml) => toml.deserialize_value(content),
+            }
+        }
+
+        fn mime(&self) -> String {
+            match *self {
+                Self::Json(ref json) => json.mime(),
+                Self::Toml(ref toml) => toml.mime(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn mixed_formats() {
+        let registry = ParserRegistry::default()
+            .with_parser(Format::Json(Json))
+            .with_parser(Format::Toml(Toml));
+        let mut memory = Memory::default();
+        for (key, parser) in [("legacy", Format::Json(Json)), ("new", Format::Toml(Toml))] {
+            memory
+                .put_object_copy(
+                    &DKeyWithParserCopy::new(&key.to_owned(), &parser),
+                    &Doc { id: 1 },
+                )
+                .await
+                .unwrap();
+        }
+
+        for key in ["legacy", "new"] {
+            assert_eq!(
+                registry
+                    .get_object_auto::<Doc, _, _>(&memory, &key.to_owned())
+                    .await
+                    .unwrap(),
+                Some(Doc { id: 1 })
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_mime() {
+        let registry = ParserRegistry::default().with_parser(Json);
+        let mut memory = Memory::default();
+        memory
+            .put_bytes_copy(&"blob".to_owned(), "image/png".to_owned(), vec![])
+            .await
+            .unwrap();
+
+        assert!(registry
+            .get_object_auto::<Doc, _, _>(&memory, &"blob".to_owned())
+            .await
+            .is_err());
+        assert!(registry
+            .with_fallback("application/json")
+            .parser(None)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn migrate_on_read() {
+        let migrating = MigratingParser::new(Json, Toml).with_rewrite(true);
+        let key = "doc".to_owned();
+        let mut memory = Memory::default();
+        memory
+            .put_object_copy(&DKeyWithParserCopy::new(&key, &Json), &Doc { id: 1 })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            migrating
+                .get_object_migrating::<Doc, _, _>(&mut memory, &key)
+                .await
+                .unwrap(),
+            Some(Doc { id: 1 })
+        );
+        assert_eq!(
+            memory.head_inner("doc").and_then(|meta| meta.mime),
+            Some(Toml.mime()),
+            "old format must be rewritten"
+        );
+        assert_eq!(
+            memory
+                .get_object_copy::<Doc, _, _>(&DKeyWithParserCopy::new(&key, &migrating))
+                .await
+                .unwrap(),
+            Some(Doc { id: 1 })
+        );
+    }
+
+    #[test]
+    fn read_either_write_new() {
+        let migrating = MigratingParser::new(Json, Toml);
+        let doc = Doc { id: 2 };
+
+        assert_eq!(
+            migrating
+                .deserialize_value::<Doc>(&Json.serialize_value(&doc).unwrap())
+                .unwrap(),
+            doc
+        );
+        assert_eq!(
+            migrating.serialize_value(&doc).unwrap(),
+            Toml.serialize_value(&doc).unwrap()
+        );
+    }
+
+    #[test]
+    fn serialized_size_without_buffering() {
+        let doc = Doc { id: 12_345 };
+
+        for size in [
+            Json.serialized_size(&doc).unwrap(),
+            Toml.serialized_size(&doc).unwrap(),
+        ] {
+            assert!(size > 0);
+        }
+        assert_eq!(
+            Json.serialized_size(&doc).unwrap(),
+            Json.serialize_value(&doc).unwrap().len() as u64
+        );
+        assert_eq!(
+            Toml.serialized_size(&doc).unwrap(),
+            Toml.serialize_value(&doc).unwrap().len() as u64
+        );
+    }
+
+    #[tokio::test]
+    async fn preview_put() {
+        let mut memory = Memory::default();
+        let key = "doc".to_owned();
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+
+        let fresh = memory
+            .preview_put_object_copy(&key_with_parser, &Doc { id: 1 })
+            .await
+            .unwrap();
+        assert_eq!(fresh.size, 8);
+        assert_eq!(fresh.replaces, None);
+        assert_eq!(fresh.mime, Json.mime());
+        assert!(memory.is_empty(), "a preview must not write");
+
+        memory
+            .put_object_copy(&key_with_parser, &Doc { id: 1 })
+            .await
+            .unwrap();
+        let replacing = memory
+            .preview_put_object_copy(&key_with_parser, &Doc { id: 1_000 })
+            .await
+            .unwrap();
+        assert_eq!(replacing.replaces, Some(8));
+        assert_eq!(replacing.growth(), 3);
+    }
+
+    struct Panicking;
+
+    impl Parser for Panicking {
+        fn serialize_value<VALUE>(&self, _value: &VALUE) -> Result<Vec<u8>, ParserError>
+        where
+            VALUE: ValueWhere,
+        {
+            panic!("serializer bug")
+        }
+
+        fn deserialize_value<RETURN>(&self, _content: &[u8]) -> Result<RETURN, ParserError>
+        where
+            RETURN: for<'content> Deserialize<'content>,
+        {
+            panic!("{}", "deserializer bug".to_owned())
+        }
+
+        fn mime(&self) -> String {
+            "application/json".to_owned()
+        }
+    }
+
+    fn is_panicked<ERROR: Display>(result: Result<impl Sized, ERROR>) -> bool {
+        result.is_err_and(|err| err.to_string().contains("Parser panicked"))
+    }
+
+    async fn sink_survives_panics<SINK>(sink: &mut SINK)
+    where
+        SINK: Sink + Send + Sync,
+        SINK::Error: Display,
+    {
+        let key = "doc".to_owned();
+        let panicking = DKeyWithParserCopy::new(&key, &Panicking);
+        let json = DKeyWithParserCopy::new(&key, &Json);
+
+        assert!(is_panicked(
+            sink.put_object_copy(&panicking, &Doc { id: 1 }).await
+        ));
+        assert!(sink.put_object_copy(&json, &Doc { id: 1 }).await.is_ok());
+        assert!(is_panicked(
+            sink.get_object_copy::<Doc, _, _>(&panicking).await
+        ));
+        assert!(sink
+            .get_object_copy::<Doc, _, _>(&json)
+            .await
+            .is_ok_and(|doc| doc == Some(Doc { id: 1 })));
+    }
+
+    #[test]
+    fn panics_become_errors() {
+        assert!(matches!(
+            Panicking.serialize_guarded(&Doc { id: 1 }),
+            Err(ParserError::Panicked { ref operation, ref message })
+                if operation == "serialize" && message == "serializer bug"
+        ));
+        assert!(matches!(
+            Panicking.deserialize_guarded::<Doc>(b"{}"),
+            Err(ParserError::Panicked { ref message, .. }) if message == "deserializer bug"
+        ));
+    }
+
+    #[tokio::test]
+    async fn sinks_survive_panicking_parsers() {
+        let threshold = NonZeroUsize::new(4).unwrap();
+        sink_survives_panics(&mut Memory::default()).await;
+        sink_survives_panics(&mut Chunked::new(threshold, Memory::default())).await;
+        sink_survives_panics(&mut SizeLimit::new(NonZeroUsize::MAX, Memory::default())).await;
+        sink_survives_panics(&mut Encrypted::new(
+            KeyRing::new("k1", &[1; 32]),
+            Memory::default(),
+        ))
+        .await;
+    }
+
+    #[tokio::test]
+    async fn cache_survives_panicking_parsers() {
+        let mut lru = Lru::new(NonZeroUsize::new(2).unwrap(), Memory::default());
+        let key = "doc".to_owned();
+        let panicking = DKeyWithParserCopy::new(&key, &Panicking);
+        let json = DKeyWithParserCopy::new(&key, &Json);
+
+        assert!(is_panicked(
+            lru.put_object_copy(&panicking, &Doc { id: 1 })
+                .await
+                .map(|_| ())
+        ));
+        assert!(lru.put_object_copy(&json, &Doc { id: 1 }).await.is_ok());
+        assert!(is_panicked(
+            lru.get_object_copy::<Doc, _, _>(&panicking).await
+        ));
+        assert_eq!(
+            lru.get_object_copy::<Doc, _, _>(&json).await.unwrap(),
+            Some(Doc { id: 1 })
+        );
+    }
 }
@@ -0,0 +1,282 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use super::Parser;
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::{DKeyWhere, ParserError, ResultExt as _};
+
+const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+const DEFAULT_MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeserializeLimits {
+    pub max_bytes: usize,
+    pub max_depth: usize,
+}
+
+impl Default for DeserializeLimits {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+impl DeserializeLimits {
+    #[inline]
+    pub fn check_size(&self, size: u64) -> Result<(), ParserError> {
+        let limit = self.max_bytes as u64;
+        if size > limit {
+            return Err(ParserError::PayloadTooLarge {
+                dimension: "bytes".to_owned(),
+                size,
+                limit,
+            });
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn check_json_depth(&self, content: &[u8]) -> Result<(), ParserError> {
+        let mut depth = 0_usize;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        for &byte in content {
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match byte {
+                b'"' => in_string = true,
+                b'[' | b'{' => {
+                    depth += 1;
+                    if depth > self.max_depth {
+                        return Err(ParserError::PayloadTooLarge {
+                            dimension: "nesting levels".to_owned(),
+                            size: depth as u64,
+                            limit: self.max_depth as u64,
+                        });
+                    }
+                }
+                b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Limited<PARSER> {
+    parser: PARSER,
+    limits: DeserializeLimits,
+}
+
+impl<PARSER> Limited<PARSER>
+where
+    PARSER: Parser,
+{
+    #[inline]
+    pub const fn new(parser: PARSER, limits: DeserializeLimits) -> Self {
+        Self { parser, limits }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn limits(&self) -> &DeserializeLimits {
+        &self.limits
+    }
+}
+
+impl<PARSER> Parser for Limited<PARSER>
+where
+    PARSER: Parser,
+{
+    #[inline]
+    fn serialize_value<VALUE>(&self, value: &VALUE) -> Result<Vec<u8>, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        self.parser.serialize_value(value)
+    }
+
+    #[inline]
+    fn deserialize_value<RETURN>(&self, content: &[u8]) -> Result<RETURN, ParserError>
+    where
+        RETURN: for<'content> Deserialize<'content>,
+    {
+        self.limits.check_size(content.len() as u64)?;
+        if self.parser.mime().ends_with("json") {
+            self.limits.check_json_depth(content)?;
+        }
+        self.parser.deserialize_value(content)
+    }
+
+    #[inline]
+    fn mime(&self) -> String {
+        self.parser.mime()
+    }
+
+    #[inline]
+    fn serialized_size<VALUE>(&self, value: &VALUE) -> Result<u64, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        self.parser.serialized_size(value)
+    }
+}
+
+/// Refuses from the head before downloading. Decoding layers report their decoded size there
+/// and never produce more than they reported, `Compressed` stops inflating at its header size, so
+/// the limit also bounds what they decode and not only the parse step.
+#[inline]
+pub async fn get_object_limited<RETURN, DKEY, PARSER, SINK>(
+    sink: &SINK,
+    key_with_parser: &DKeyWithParserCopy<'_, DKEY, Limited<PARSER>>,
+) -> Result<Option<RETURN>, SINK::Error>
+where
+    RETURN: DeserializeOwned + Send + Sync,
+    DKEY: DKeyWhere,
+    PARSER: ParserWhere,
+    SINK: Sink + Sync,
+    SINK::Error: From<ParserError>,
+{
+    let Some(meta) = sink.head_copy(key_with_parser.key()).await? else {
+        return Ok(None);
+    };
+    key_with_parser
+        .parser()
+        .limits()
+        .check_size(meta.size)
+        .context("limited", "get_object", &key_with_parser.key().name())?;
+
+    sink.get_object_copy(key_with_parser).await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::storage::copy::parser::{Json, Toml};
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    fn limits() -> DeserializeLimits {
+        DeserializeLimits {
+            max_bytes: 32,
+            max_depth: 3,
+        }
+    }
+
+    #[test]
+    fn reject_deep_or_large_payloads() {
+        let parser = Limited::new(Json, limits());
+
+        assert!(parser
+            .deserialize_value::<Value>(br#"[[["]]]]"]]]"#)
+            .is_ok());
+        assert!(matches!(
+            parser.deserialize_value::<Value>(b"[[[[1]]]]"),
+            Err(ParserError::PayloadTooLarge {
+                size: 4,
+                limit: 3,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parser.deserialize_value::<Value>(&[b' '; 33]),
+            Err(ParserError::PayloadTooLarge {
+                size: 33,
+                limit: 32,
+                ..
+            })
+        ));
+        assert!(
+            Limited::new(Toml, limits())
+                .deserialize_value::<Value>(b"a = [[[[1]]]]")
+                .is_ok(),
+            "depth is only scanned for json"
+        );
+    }
+
+    #[tokio::test]
+    async fn refuse_before_download() {
+        let mut memory = Memory::default();
+        let key = "big".to_owned();
+        memory
+            .put_object_copy(&DKeyWithParserCopy::new(&key, &Json), &vec![0_u8; 64])
+            .await
+            .unwrap();
+
+        let parser = Limited::new(Json, limits());
+        let Err(MemoryError::Serde(err)) = get_object_limited::<Vec<u8>, _, _, _>(
+            &memory,
+            &DKeyWithParserCopy::new(&key, &parser),
+        )
+        .await
+        else {
+            panic!("a payload over the limit must be refused");
+        };
+        assert_eq!(err.key(), Some("big"));
+        assert!(matches!(*err.root(), ParserError::PayloadTooLarge { .. }));
+        assert!(get_object_limited::<Vec<u8>, _, _, _>(
+            &memory,
+            &DKeyWithParserCopy::new(&"missing".to_owned(), &parser)
+        )
+        .await
+        .unwrap()
+        .is_none());
+    }
+
+    #[cfg(feature = "deflate")]
+    #[tokio::test]
+    async fn refuse_compressed_bombs() {
+        use crate::storage::sink::compressed::{Compressed, Deflate};
+        use crate::storage::LayerError;
+
+        let parser = Limited::new(Json, limits());
+        let mut compressed = Compressed::new(Deflate::default(), Memory::default());
+        compressed
+            .put_bytes_copy(&"honest".to_owned(), Json.mime(), vec![b' '; 1 << 20])
+            .await
+            .unwrap();
+        let Err(MemoryError::Serde(err)) = get_object_limited::<Value, _, _, _>(
+            &compressed,
+            &DKeyWithParserCopy::new(&"honest".to_owned(), &parser),
+        )
+        .await
+        else {
+            panic!("the decoded size is checked before inflating");
+        };
+        assert!(matches!(*err.root(), ParserError::PayloadTooLarge { .. }));
+
+        let mut lying = b"NGC1\x07deflate".to_vec();
+        lying.extend(16_u64.to_be_bytes());
+        lying.extend(miniz_oxide::deflate::compress_to_vec_zlib(
+            &[b' '; 1 << 20],
+            10,
+        ));
+        compressed
+            .storage_mut()
+            .put_bytes_inner("lying", Json.mime(), lying)
+            .unwrap();
+        assert!(matches!(
+            get_object_limited::<Value, _, _, _>(
+                &compressed,
+                &DKeyWithParserCopy::new(&"lying".to_owned(), &parser),
+            )
+            .await,
+            Err(MemoryError::Layer(LayerError::Compression { .. }))
+        ));
+    }
+}
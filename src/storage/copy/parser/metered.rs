@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use super::Parser;
+use crate::storage::copy::ValueWhere;
+use crate::storage::metrics::{Direction, StorageMetrics};
+use crate::storage::ParserError;
+
+pub struct Metered<PARSER> {
+    parser: PARSER,
+    metrics: Arc<dyn StorageMetrics>,
+}
+
+impl<PARSER> Metered<PARSER>
+where
+    PARSER: Parser,
+{
+    #[inline]
+    pub fn new(parser: PARSER, metrics: Arc<dyn StorageMetrics>) -> Self {
+        Self { parser, metrics }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn parser(&self) -> &PARSER {
+        &self.parser
+    }
+}
+
+impl<PARSER> Parser for Metered<PARSER>
+where
+    PARSER: Parser,
+{
+    #[inline]
+    fn serialize_value<VALUE>(&self, value: &VALUE) -> Result<Vec<u8>, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        let content = self.parser.serialize_value(value)?;
+        self.metrics.record_payload(
+            &self.parser.mime(),
+            Direction::Serialize,
+            content.len() as u64,
+        );
+        Ok(content)
+    }
+
+    #[inline]
+    fn deserialize_value<RETURN>(&self, content: &[u8]) -> Result<RETURN, ParserError>
+    where
+        RETURN: for<'content> Deserialize<'content>,
+    {
+        let value = self.parser.deserialize_value(content)?;
+        self.metrics.record_payload(
+            &self.parser.mime(),
+            Direction::Deserialize,
+            content.len() as u64,
+        );
+        Ok(value)
+    }
+
+    #[inline]
+    fn mime(&self) -> String {
+        self.parser.mime()
+    }
+
+    // A size estimate is not a payload, it is left out of the histograms.
+    #[inline]
+    fn serialized_size<VALUE>(&self, value: &VALUE) -> Result<u64, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        self.parser.serialized_size(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::storage::copy::direct::DKeyWithParserCopy;
+    use crate::storage::copy::parser::{Json, Toml};
+    use crate::storage::copy::Sink as _;
+    use crate::storage::metrics::PayloadMetrics;
+    use crate::storage::sink::memory::Memory;
+
+    #[tokio::test]
+    async fn record_sizes_by_mime() {
+        let metrics = Arc::new(PayloadMetrics::default());
+        let json = Metered::new(Json, Arc::clone(&metrics) as Arc<dyn StorageMetrics>);
+        let toml = Metered::new(Toml, Arc::clone(&metrics) as Arc<dyn StorageMetrics>);
+        let mut memory = Memory::default();
+        let key = "report".to_owned();
+        let value = json!({ "name": "negentropy", "pages": 12 });
+
+        memory
+            .put_object_copy(&DKeyWithParserCopy::new(&key, &json), &value)
+            .await
+            .unwrap();
+        let read = memory
+            .get_object_copy::<Value, _, _>(&DKeyWithParserCopy::new(&key, &json))
+            .await
+            .unwrap();
+        assert_eq!(read, Some(value.clone()));
+        toml.serialize_value(&value).unwrap();
+        assert!(json.deserialize_value::<Value>(b"not json").is_err());
+
+        let written = metrics.histogram(&Json.mime(), Direction::Serialize);
+        let size = Json.serialize_value(&value).unwrap().len() as u64;
+        assert_eq!((written.count, written.total), (1, size));
+        assert_eq!(
+            metrics
+                .histogram(&Json.mime(), Direction::Deserialize)
+                .count,
+            1,
+            "a payload failing to parse is not recorded"
+        );
+        assert_eq!(metrics.counts()[&Json.mime()], 2);
+        assert_eq!(metrics.counts()[&Toml.mime()], 1);
+    }
+}
@@ -0,0 +1,479 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::Parser;
+use crate::storage::copy::ValueWhere;
+use crate::storage::{ParserError, ValidationError};
+
+const KEYWORDS: [&str; 13] = [
+    "type",
+    "enum",
+    "const",
+    "required",
+    "properties",
+    "additionalProperties",
+    "items",
+    "minItems",
+    "maxItems",
+    "minLength",
+    "maxLength",
+    "minimum",
+    "maximum",
+];
+const ANNOTATIONS: [&str; 10] = [
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+];
+
+#[derive(Debug, Clone)]
+pub struct JsonSchema {
+    schema: Value,
+}
+
+impl JsonSchema {
+    /// Refuse a schema using a keyword the validation does not check, it would pass values the
+    /// schema rejects.
+    #[inline]
+    pub fn new(schema: Value) -> Result<Self, ParserError> {
+        let mut errors = vec![];
+        unsupported(&schema, "", &mut errors);
+
+        if errors.is_empty() {
+            Ok(Self { schema })
+        } else {
+            Err(ParserError::Validation(errors))
+        }
+    }
+
+    #[inline]
+    pub fn validate(&self, value: &Value) -> Result<(), Vec<ValidationError>> {
+        let mut errors = vec![];
+        check(&self.schema, value, "", &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+pub struct Validated<PARSER> {
+    parser: PARSER,
+    schema: JsonSchema,
+}
+
+impl<PARSER> Validated<PARSER>
+where
+    PARSER: Parser,
+{
+    #[inline]
+    pub const fn new(parser: PARSER, schema: JsonSchema) -> Self {
+        Self { parser, schema }
+    }
+}
+
+impl<PARSER> Parser for Validated<PARSER>
+where
+    PARSER: Parser,
+{
+    #[inline]
+    fn serialize_value<VALUE>(&self, value: &VALUE) -> Result<Vec<u8>, ParserError>
+    where
+        VALUE: ValueWhere,
+    {
+        let json = serde_json::to_value(value).map_err(|err| ParserError::Serde {
+            internal: err.to_string(),
+        })?;
+        self.schema
+            .validate(&json)
+            .map_err(ParserError::Validation)?;
+        self.parser.serialize_value(value)
+    }
+
+    #[inline]
+    fn deserialize_value<RETURN>(&self, content: &[u8]) -> Result<RETURN, ParserError>
+    where
+        RETURN: for<'content> Deserialize<'content>,
+    {
+        let json = self.parser.deserialize_value::<Value>(content)?;
+        self.schema
+            .validate(&json)
+            .map_err(ParserError::Validation)?;
+        serde_json::from_value(json).map_err(|err| ParserError::Serde {
+            internal: err.to_string(),
+        })
+    }
+
+    #[inline]
+    fn mime(&self) -> String {
+        self.parser.mime()
+    }
+}
+
+fn unsupported(schema: &Value, pointer: &str, errors: &mut Vec<ValidationError>) {
+    let rules = match *schema {
+        Value::Bool(_) => return,
+        Value::Object(ref rules) => rules,
+        Value::Null | Value::Number(_) | Value::String(_) | Value::Array(_) => {
+            errors.push(error(pointer, "schema", "expected an object or a boolean"));
+            return;
+        }
+    };
+
+    for (keyword, rule) in rules {
+        let child = format!("{pointer}/{}", escape(keyword));
+        match keyword.as_str() {
+            "properties" => match *rule {
+                Value::Object(ref properties) => {
+                    for (name, property) in properties {
+                        unsupported(property, &format!("{child}/{}", escape(name)), errors);
+                    }
+                }
+                _ => errors.push(error(&child, keyword, "expected an object")),
+            },
+            "additionalProperties" | "items" => unsupported(rule, &child, errors),
+            known if KEYWORDS.contains(&known) || ANNOTATIONS.contains(&known) => {}
+            _ => errors.push(error(&child, keyword, "unsupported keyword")),
+        }
+    }
+}
+
+fn check(schema: &Value, value: &Value, pointer: &str, errors: &mut Vec<ValidationError>) {
+    let rules = match *schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(error(pointer, "false", "no value is allowed"));
+            return;
+        }
+        Value::Object(ref rules) => rules,
+        Value::Null | Value::Number(_) | Value::String(_) | Value::Array(_) => return,
+    };
+
+    check_type(rules, value, pointer, errors);
+    check_enum(rules, value, pointer, errors);
+
+    match *value {
+        Value::Object(ref object) => check_object(rules, object, pointer, errors),
+        Value::Array(ref items) => check_array(rules, items, pointer, errors),
+        Value::String(ref text) => {
+            let len = text.chars().count();
+            check_bound(rules, "minLength", len, pointer, errors, |len, min| {
+                len >= min
+            });
+            check_bound(rules, "maxLength", len, pointer, errors, |len, max| {
+                len <= max
+            });
+        }
+        Value::Number(ref number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(minimum) = rules.get("minimum").and_then(Value::as_f64) {
+                if number < minimum {
+                    errors.push(error(pointer, "minimum", &format!("must be >= {minimum}")));
+                }
+            }
+            if let Some(maximum) = rules.get("maximum").and_then(Value::as_f64) {
+                if number > maximum {
+                    errors.push(error(pointer, "maximum", &format!("must be <= {maximum}")));
+                }
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
+
+fn check_type(
+    rules: &Map<String, Value>,
+    value: &Value,
+    pointer: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let allowed = match rules.get("type") {
+        Some(Value::String(kind)) => vec![kind.as_str()],
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ => return,
+    };
+
+    if !allowed.iter().any(|&kind| is_type(kind, value)) {
+        errors.push(error(
+            pointer,
+            "type",
+            &format!("expected {}", allowed.join(" or ")),
+        ));
+    }
+}
+
+fn check_enum(
+    rules: &Map<String, Value>,
+    value: &Value,
+    pointer: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    if let Some(constant) = rules.get("const") {
+        if constant != value {
+            errors.push(error(pointer, "const", &format!("expected {constant}")));
+        }
+    }
+    if let Some(Value::Array(variants)) = rules.get("enum") {
+        if !variants.contains(value) {
+            errors.push(error(pointer, "enum", "value is not one of the variants"));
+        }
+    }
+}
+
+fn check_object(
+    rules: &Map<String, Value>,
+    object: &Map<String, Value>,
+    pointer: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    if let Some(Value::Array(required)) = rules.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(name) {
+                errors.push(error(
+                    pointer,
+                    "required",
+                    &format!("missing property {name:?}"),
+                ));
+            }
+        }
+    }
+
+    let properties = rules.get("properties").and_then(Value::as_object);
+    for (name, property) in object {
+        let child = format!("{pointer}/{}", escape(name));
+        match properties.and_then(|properties| properties.get(name)) {
+            Some(schema) => check(schema, property, &child, errors),
+            None => {
+                if let Some(additional) = rules.get("additionalProperties") {
+                    check(additional, property, &child, errors);
+                }
+            }
+        }
+    }
+}
+
+fn check_array(
+    rules: &Map<String, Value>,
+    items: &[Value],
+    pointer: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    check_bound(
+        rules,
+        "minItems",
+        items.len(),
+        pointer,
+        errors,
+        |len, min| len >= min,
+    );
+    check_bound(
+        rules,
+        "maxItems",
+        items.len(),
+        pointer,
+        errors,
+        |len, max| len <= max,
+    );
+
+    if let Some(schema) = rules.get("items") {
+        for (index, item) in items.iter().enumerate() {
+            check(schema, item, &format!("{pointer}/{index}"), errors);
+        }
+    }
+}
+
+fn check_bound<COMPARE>(
+    rules: &Map<String, Value>,
+    keyword: &str,
+    len: usize,
+    pointer: &str,
+    errors: &mut Vec<ValidationError>,
+    compare: COMPARE,
+) where
+    COMPARE: Fn(u64, u64) -> bool,
+{
+    if let Some(bound) = rules.get(keyword).and_then(Value::as_u64) {
+        if !compare(u64::try_from(len).unwrap_or(u64::MAX), bound) {
+            errors.push(error(
+                pointer,
+                keyword,
+                &format!("length {len} violates {bound}"),
+            ));
+        }
+    }
+}
+
+fn is_type(kind: &str, value: &Value) -> bool {
+    match (kind, value) {
+        ("null", &Value::Null)
+        | ("boolean", &Value::Bool(_))
+        | ("string", &Value::String(_))
+        | ("array", &Value::Array(_))
+        | ("object", &Value::Object(_))
+        | ("number", &Value::Number(_)) => true,
+        ("integer", Value::Number(number)) => {
+            number.is_i64()
+                || number.is_u64()
+                || number.as_f64().is_some_and(|float| float.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+fn error(pointer: &str, keyword: &str, message: &str) -> ValidationError {
+    ValidationError {
+        pointer: if pointer.is_empty() {
+            "/".to_owned()
+        } else {
+            pointer.to_owned()
+        },
+        keyword: keyword.to_owned(),
+        message: message.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::storage::copy::direct::DKeyWithParserCopy;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::copy::Sink;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct Event {
+        id: u32,
+        kind: String,
+    }
+
+    fn validated() -> Validated<Json> {
+        Validated::new(
+            Json,
+            JsonSchema::new(json!({
+                "type": "object",
+                "required": ["id", "kind"],
+                "properties": {
+                    "id": { "type": "integer", "minimum": 1 },
+                    "kind": { "enum": ["created", "deleted"] }
+                },
+                "additionalProperties": false
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn reject_unsupported_keywords() {
+        let Err(ParserError::Validation(errors)) = JsonSchema::new(json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Event",
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer", "exclusiveMinimum": 0 },
+                "kind": { "type": "string", "pattern": "^[a-z]+$" },
+                "tags": { "items": { "$ref": "#/$defs/tag" } }
+            },
+            "oneOf": [{ "required": ["id"] }]
+        })) else {
+            panic!("a schema with unchecked keywords must be refused");
+        };
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| (error.pointer.as_str(), error.keyword.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/oneOf", "oneOf"),
+                ("/properties/id/exclusiveMinimum", "exclusiveMinimum"),
+                ("/properties/kind/pattern", "pattern"),
+                ("/properties/tags/items/$ref", "$ref"),
+            ]
+        );
+        assert!(JsonSchema::new(json!({ "items": [true] })).is_err());
+        assert!(JsonSchema::new(json!(true)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_put() {
+        let parser = validated();
+        let key = "event".to_owned();
+        let mut memory = Memory::default();
+        let event = Event {
+            id: 0,
+            kind: "renamed".to_owned(),
+        };
+
+        let Err(MemoryError::Serde(err)) = memory
+            .put_object_copy(&DKeyWithParserCopy::new(&key, &parser), &event)
+            .await
+        else {
+            panic!("invalid event must be rejected");
+        };
+        assert_eq!(err.key(), Some("event"));
+        let ParserError::Validation(ref errors) = *err.root() else {
+            panic!("invalid event must be reported field by field");
+        };
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| (error.pointer.as_str(), error.keyword.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("/id", "minimum"), ("/kind", "enum")]
+        );
+        assert!(memory.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reject_corrupted_get() {
+        let parser = validated();
+        let key = "event".to_owned();
+        let mut memory = Memory::default();
+        memory
+            .put_bytes_copy(
+                &key,
+                Json.mime(),
+                br#"{"id":1,"kind":"created","x":1}"#.to_vec(),
+            )
+            .await
+            .unwrap();
+
+        assert!(memory
+            .get_object_copy::<Event, _, _>(&DKeyWithParserCopy::new(&key, &parser))
+            .await
+            .is_err());
+
+        let event = Event {
+            id: 1,
+            kind: "created".to_owned(),
+        };
+        memory
+            .put_object_copy(&DKeyWithParserCopy::new(&key, &parser), &event)
+            .await
+            .unwrap();
+        assert_eq!(
+            memory
+                .get_object_copy::<Event, _, _>(&DKeyWithParserCopy::new(&key, &parser))
+                .await
+                .unwrap(),
+            Some(event)
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use core::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::diff::walk_all;
+use super::{journal, Sink};
+use crate::storage::clock::Clock;
+use crate::storage::meta::ObjectMeta;
+use crate::storage::progress::{Progress, ProgressTracker};
+use crate::storage::ParserError;
+
+const DEFAULT_MIME: &str = "application/octet-stream";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetentionAction {
+    Delete,
+    Archive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionRule {
+    pub prefix: String,
+    pub keep: Duration,
+    pub action: RetentionAction,
+}
+
+impl RetentionRule {
+    #[inline]
+    #[must_use]
+    pub fn new(prefix: &str, keep: Duration) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+            keep,
+            action: RetentionAction::Delete,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn archive(mut self) -> Self {
+        self.action = RetentionAction::Archive;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_expired(&self, meta: &ObjectMeta, now: SystemTime) -> bool {
+        meta.last_modified
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age >= self.keep)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    pub rules: Vec<RetentionRule>,
+    pub hold_prefix: String,
+    pub audit_key: String,
+}
+
+impl RetentionPolicy {
+    #[inline]
+    #[must_use]
+    pub fn new(rules: Vec<RetentionRule>) -> Self {
+        Self {
+            rules,
+            hold_prefix: ".hold/".to_owned(),
+            audit_key: ".retention/audit.ndjson".to_owned(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn rule(&self, key: &str) -> Option<&RetentionRule> {
+        self.rules
+            .iter()
+            .filter(|rule| key.starts_with(&rule.prefix))
+            .max_by_key(|rule| rule.prefix.len())
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hold_key(&self, key: &str) -> String {
+        format!("{}{key}", self.hold_prefix)
+    }
+
+    fn is_internal(&self, key: &str) -> bool {
+        key == self.audit_key || key.starts_with(&self.hold_prefix)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetentionOutcome {
+    Deleted,
+    Archived,
+    Held,
+    Retained,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub key: String,
+    pub outcome: RetentionOutcome,
+    pub at: u64,
+}
+
+#[inline]
+pub async fn place_hold<SINK>(
+    sink: &mut SINK,
+    policy: &RetentionPolicy,
+    key: &str,
+) -> Result<(), SINK::Error>
+where
+    SINK: Sink + Send + Sync,
+{
+    sink.put_bytes_copy(&policy.hold_key(key), DEFAULT_MIME.to_owned(), vec![])
+        .await
+}
+
+#[inline]
+pub async fn release_hold<SINK>(
+    sink: &mut SINK,
+    policy: &RetentionPolicy,
+    key: &str,
+) -> Result<(), SINK::Error>
+where
+    SINK: Sink + Send + Sync,
+{
+    sink.delete_copy(&policy.hold_key(key)).await
+}
+
+#[inline]
+pub async fn delete_retained<SINK, CLOCK>(
+    sink: &mut SINK,
+    policy: &RetentionPolicy,
+    clock: &CLOCK,
+    key: &str,
+) -> Result<RetentionOutcome, SINK::Error>
+where
+    SINK: Sink + Send + Sync,
+    SINK::Error: From<ParserError>,
+    CLOCK: Clock,
+{
+    let now = clock.system_time();
+    let outcome = if sink.head_copy(&policy.hold_key(key)).await?.is_some() {
+        RetentionOutcome::Held
+    } else {
+        let meta = sink.head_copy(&key.to_owned()).await?.unwrap_or_default();
+        match policy.rule(key) {
+            Some(rule) if !rule.is_expired(&meta, now) => RetentionOutcome::Retained,
+            _ => {
+                sink.delete_copy(&key.to_owned()).await?;
+                RetentionOutcome::Deleted
+            }
+        }
+    };
+
+    journal::append(sink, &policy.audit_key, &[entry(key, outcome, now)]).await?;
+    Ok(outcome)
+}
+
+#[inline]
+pub async fn enforce<ERROR, SINK, ARCHIVE, CLOCK, PROGRESS>(
+    sink: &mut SINK,
+    mut archive: Option<&mut ARCHIVE>,
+    policy: &RetentionPolicy,
+    clock: &CLOCK,
+    progress: &PROGRESS,
+) -> Result<Vec<AuditEntry>, ERROR>
+where
+    SINK: Sink + Send + Sync,
+    SINK::Error: From<ParserError>,
+    ARCHIVE: Sink + Send + Sync,
+    CLOCK: Clock,
+    PROGRESS: Progress,
+    ERROR: From<SINK::Error> + From<ARCHIVE::Error>,
+{
+    let now = clock.system_time();
+    let mut entries = vec![];
+
+    let prefixes = policy
+        .rules
+        .iter()
+        .map(|rule| rule.prefix.as_str())
+        .collect();
+    let keys = walk_all(sink, prefixes).await?;
+    let mut tracker = ProgressTracker::new(progress, Some(keys.len() as u64));
+    for key in keys {
+        tracker.advance(&key, 0);
+        if policy.is_internal(&key) {
+            continue;
+        }
+        let Some(rule) = policy.rule(&key) else {
+            continue;
+        };
+        let Some(meta) = sink.head_copy(&key).await? else {
+            continue;
+        };
+        if !rule.is_expired(&meta, now) {
+            continue;
+        }
+        if sink.head_copy(&policy.hold_key(&key)).await?.is_some() {
+            audit(
+                sink,
+                policy,
+                &mut entries,
+                entry(&key, RetentionOutcome::Held, now),
+            )
+            .await?;
+            continue;
+        }
+
+        let outcome = match (rule.action, archive.as_deref_mut()) {
+            (RetentionAction::Archive, Some(target)) => {
+                let Some(value) = sink.get_bytes_copy(&key).await? else {
+                    continue;
+                };
+                let mime = meta.mime.unwrap_or_else(|| DEFAULT_MIME.to_owned());
+                target.put_bytes_copy(&key, mime, value).await?;
+                RetentionOutcome::Archived
+            }
+            (RetentionAction::Archive, None) => {
+                let retained = entry(&key, RetentionOutcome::Retained, now);
+                audit(sink, policy, &mut entries, retained).await?;
+                continue;
+            }
+            (RetentionAction::Delete, _) => RetentionOutcome::Deleted,
+        };
+        sink.delete_copy(&key).await?;
+        audit(sink, policy, &mut entries, entry(&key, outcome, now)).await?;
+    }
+
+    Ok(entries)
+}
+
+/// Journaled right after its action, an error further in the walk loses no deletion.
+async fn audit<SINK>(
+    sink: &mut SINK,
+    policy: &RetentionPolicy,
+    entries: &mut Vec<AuditEntry>,
+    entry: AuditEntry,
+) -> Result<(), SINK::Error>
+where
+    SINK: Sink + Send + Sync,
+    SINK::Error: From<ParserError>,
+{
+    journal::append(sink, &policy.audit_key, core::slice::from_ref(&entry)).await?;
+    entries.push(entry);
+    Ok(())
+}
+
+/// Every entry `enforce` and `delete_retained` appended to the audit log, oldest first.
+#[inline]
+pub async fn audit_entries<SINK>(
+    sink: &SINK,
+    policy: &RetentionPolicy,
+) -> Result<Vec<AuditEntry>, SINK::Error>
+where
+    SINK: Sink + Send + Sync,
+    SINK::Error: From<ParserError>,
+{
+    journal::read(sink, &policy.audit_key).await
+}
+
+fn entry(key: &str, outcome: RetentionOutcome, now: SystemTime) -> AuditEntry {
+    AuditEntry {
+        key: key.to_owned(),
+        outcome,
+        at: now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::clock::SystemClock;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    fn policy() -> RetentionPolicy {
+        RetentionPolicy::new(vec![
+            RetentionRule::new("tmp/", Duration::ZERO),
+            RetentionRule::new("tmp/archive/", Duration::ZERO).archive(),
+            RetentionRule::new("records/", DAY),
+        ])
+    }
+
+    async fn memory_with(keys: &[&str]) -> Memory {
+        let mut memory = Memory::default();
+        for key in keys {
+            memory
+                .put_bytes_copy(&(*key).to_owned(), String::new(), key.as_bytes().to_vec())
+                .await
+                .unwrap();
+        }
+        memory
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let policy = policy();
+
+        assert_eq!(
+            policy.rule("tmp/archive/a").map(|rule| rule.action),
+            Some(RetentionAction::Archive)
+        );
+        assert_eq!(
+            policy.rule("tmp/a").map(|rule| rule.action),
+            Some(RetentionAction::Delete)
+        );
+        assert!(policy.rule("other").is_none());
+        assert!(
+            !RetentionRule::new("", Duration::ZERO)
+                .is_expired(&ObjectMeta::default(), SystemTime::now()),
+            "unknown age is never expired"
+        );
+    }
+
+    #[tokio::test]
+    async fn enforce_with_holds_and_archive() {
+        let policy = policy();
+        let mut memory =
+            memory_with(&["tmp/a", "tmp/held", "tmp/archive/b", "records/c", "other"]).await;
+        place_hold(&mut memory, &policy, "tmp/held").await.unwrap();
+        let mut archive = Memory::default();
+
+        let entries = enforce::<MemoryError, _, _, _, _>(
+            &mut memory,
+            Some(&mut archive),
+            &policy,
+            &SystemClock,
+            &(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.key.as_str(), entry.outcome))
+                .collect::<Vec<_>>(),
+            [
+                ("tmp/a", RetentionOutcome::Deleted),
+                ("tmp/archive/b", RetentionOutcome::Archived),
+                ("tmp/held", RetentionOutcome::Held),
+            ]
+        );
+        assert!(!memory.exists_inner("tmp/a"));
+        assert!(memory.exists_inner("tmp/held"));
+        assert!(memory.exists_inner("records/c"));
+        assert!(memory.exists_inner("other"));
+        assert_eq!(
+            archive.get_bytes_inner("tmp/archive/b"),
+            Some(b"tmp/archive/b".to_vec())
+        );
+
+        assert_eq!(audit_entries(&memory, &policy).await.unwrap(), entries);
+    }
+
+    #[tokio::test]
+    async fn journal_deletions_before_a_failure() {
+        use crate::storage::meta::ObjectLock;
+
+        let policy = policy();
+        let mut memory = memory_with(&["tmp/a", "tmp/z"]).await;
+        memory
+            .lock_copy(
+                &"tmp/z".to_owned(),
+                ObjectLock::governance(SystemTime::now() + DAY),
+            )
+            .await
+            .unwrap();
+
+        assert!(enforce::<MemoryError, _, Memory, _, _>(
+            &mut memory,
+            None,
+            &policy,
+            &SystemClock,
+            &()
+        )
+        .await
+        .is_err());
+        assert!(!memory.exists_inner("tmp/a"));
+        assert_eq!(
+            audit_entries(&memory, &policy)
+                .await
+                .unwrap()
+                .iter()
+                .map(|entry| (entry.key.as_str(), entry.outcome))
+                .collect::<Vec<_>>(),
+            [("tmp/a", RetentionOutcome::Deleted)]
+        );
+    }
+
+    #[tokio::test]
+    async fn refuse_early_deletion() {
+        let policy = policy();
+        let mut memory = memory_with(&["records/c", "tmp/held"]).await;
+        place_hold(&mut memory, &policy, "tmp/held").await.unwrap();
+
+        assert_eq!(
+            delete_retained(&mut memory, &policy, &SystemClock, "records/c")
+                .await
+                .unwrap(),
+            RetentionOutcome::Retained
+        );
+        assert_eq!(
+            delete_retained(&mut memory, &policy, &SystemClock, "tmp/held")
+                .await
+                .unwrap(),
+            RetentionOutcome::Held
+        );
+        assert!(memory.exists_inner("records/c"));
+        assert!(memory.exists_inner("tmp/held"));
+
+        release_hold(&mut memory, &policy, "tmp/held")
+            .await
+            .unwrap();
+        assert_eq!(
+            delete_retained(&mut memory, &policy, &SystemClock, "tmp/held")
+                .await
+                .unwrap(),
+            RetentionOutcome::Deleted
+        );
+        assert!(!memory.exists_inner("tmp/held"));
+        assert_eq!(
+            audit_entries(&memory, &policy)
+                .await
+                .unwrap()
+                .iter()
+                .map(|entry| (entry.key.as_str(), entry.outcome))
+                .collect::<Vec<_>>(),
+            [
+                ("records/c", RetentionOutcome::Retained),
+                ("tmp/held", RetentionOutcome::Held),
+                ("tmp/held", RetentionOutcome::Deleted),
+            ],
+            "every call appends to the log"
+        );
+    }
+}
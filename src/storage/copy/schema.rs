@@ -0,0 +1,140 @@
+use super::diff::walk;
+use super::direct::DKeyWithParserCopy;
+use super::{ParserWhere, Sink, ValueWhere};
+use crate::storage::{DKeyWhere, PrefixedKey};
+
+const SCHEMA_PREFIX: &str = ".schemas/";
+const SCHEMA_MIME: &str = "text/plain";
+
+pub trait SchemaId {
+    fn schema_id() -> String;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaMismatch {
+    pub key: String,
+    pub stored: Option<String>,
+    pub expected: String,
+}
+
+#[inline]
+pub async fn put_object_with_schema<VALUE, DKEY, PARSER, SINK>(
+    sink: &mut SINK,
+    key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    value: &VALUE,
+) -> Result<(), SINK::Error>
+where
+    VALUE: ValueWhere + SchemaId,
+    DKEY: DKeyWhere,
+    PARSER: ParserWhere,
+    SINK: Sink + Send + Sync,
+{
+    sink.put_object_copy(key_with_parser, value).await?;
+    sink.put_bytes_copy(
+        &PrefixedKey::new(SCHEMA_PREFIX, key_with_parser.key()),
+        SCHEMA_MIME.to_owned(),
+        VALUE::schema_id().into_bytes(),
+    )
+    .await
+}
+
+#[inline]
+pub async fn validate_prefix<VALUE, SINK>(
+    sink: &SINK,
+    prefix: &str,
+) -> Result<Vec<SchemaMismatch>, SINK::Error>
+where
+    VALUE: SchemaId,
+    SINK: Sink + Sync,
+{
+    let expected = VALUE::schema_id();
+    let mut mismatches = vec![];
+
+    for relative in walk(sink, prefix).await? {
+        let key = format!("{prefix}{relative}");
+        if key.starts_with(SCHEMA_PREFIX) {
+            continue;
+        }
+
+        let stored = sink
+            .get_bytes_copy(&PrefixedKey::new(SCHEMA_PREFIX, &key))
+            .await?
+            .map(|value| String::from_utf8_lossy(&value).into_owned());
+
+        if stored.as_deref() != Some(expected.as_str()) {
+            mismatches.push(SchemaMismatch {
+                key,
+                stored,
+                expected: expected.clone(),
+            });
+        }
+    }
+
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::sink::memory::Memory;
+
+    #[derive(Serialize)]
+    struct UserV1 {
+        name: String,
+    }
+
+    impl SchemaId for UserV1 {
+        fn schema_id() -> String {
+            "user/1".to_owned()
+        }
+    }
+
+    struct UserV2;
+
+    impl SchemaId for UserV2 {
+        fn schema_id() -> String {
+            "user/2".to_owned()
+        }
+    }
+
+    #[tokio::test]
+    async fn flag_drift() {
+        let mut memory = Memory::default();
+        let key = "users/alice".to_owned();
+        put_object_with_schema(
+            &mut memory,
+            &DKeyWithParserCopy::new(&key, &Json),
+            &UserV1 {
+                name: "alice".to_owned(),
+            },
+        )
+        .await
+        .unwrap();
+        memory
+            .put_bytes_copy(&"users/untracked".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+
+        assert!(memory.exists_inner(".schemas/users/alice"));
+        assert_eq!(
+            validate_prefix::<UserV1, _>(&memory, "users/")
+                .await
+                .unwrap(),
+            vec![SchemaMismatch {
+                key: "users/untracked".to_owned(),
+                stored: None,
+                expected: "user/1".to_owned(),
+            }]
+        );
+        assert_eq!(
+            validate_prefix::<UserV2, _>(&memory, "users/")
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
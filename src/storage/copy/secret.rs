@@ -0,0 +1,164 @@
+use core::fmt;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::{env, fs};
+
+use serde::{Deserialize, Serialize};
+
+use super::instance::BuilderError;
+
+const SECRET_SCHEME: &str = "secret://";
+
+pub trait SecretSource {
+    fn secret(&self, name: &str) -> Result<Option<String>, BuilderError>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EnvSecretSource {
+    prefix: String,
+}
+
+impl EnvSecretSource {
+    #[inline]
+    #[must_use]
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+        }
+    }
+}
+
+impl SecretSource for EnvSecretSource {
+    #[inline]
+    fn secret(&self, name: &str) -> Result<Option<String>, BuilderError> {
+        let key = format!(
+            "{}{}",
+            self.prefix,
+            name.to_uppercase().replace(['-', '/', '.'], "_")
+        );
+        Ok(env::var(key).ok())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileSecretSource {
+    root: PathBuf,
+}
+
+impl FileSecretSource {
+    #[inline]
+    #[must_use]
+    pub const fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+impl SecretSource for FileSecretSource {
+    #[inline]
+    fn secret(&self, name: &str) -> Result<Option<String>, BuilderError> {
+        // Anything but plain names, `..`, `/` or a prefix, would leave the root.
+        let path = Path::new(name);
+        if name.is_empty()
+            || !path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(BuilderError::Secret(name.to_owned()));
+        }
+
+        match fs::read_to_string(self.root.join(path)) {
+            Ok(content) => Ok(Some(content.trim_end_matches(['\r', '\n']).to_owned())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(BuilderError::Io(err.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SecretRef(String);
+
+impl SecretRef {
+    #[inline]
+    #[must_use]
+    pub fn new(value: &str) -> Self {
+        Self(value.to_owned())
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.0.strip_prefix(SECRET_SCHEME)
+    }
+
+    #[inline]
+    pub fn resolve<SOURCE>(&self, source: &SOURCE) -> Result<String, BuilderError>
+    where
+        SOURCE: SecretSource,
+    {
+        match self.name() {
+            Some(name) => source
+                .secret(name)?
+                .ok_or_else(|| BuilderError::Secret(name.to_owned())),
+            None => Ok(self.0.clone()),
+        }
+    }
+}
+
+impl fmt::Debug for SecretRef {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "SecretRef({SECRET_SCHEME}{name})"),
+            None => write!(f, "SecretRef(<plaintext>)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn resolve_from_file() {
+        let root = env::temp_dir().join(format!("negentropy-secrets-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("storage-key"), "s3cr3t\n").unwrap();
+        let source = FileSecretSource::new(root.clone());
+
+        assert_eq!(
+            SecretRef::new("secret://storage-key")
+                .resolve(&source)
+                .unwrap(),
+            "s3cr3t"
+        );
+        assert!(matches!(
+            SecretRef::new("secret://missing").resolve(&source),
+            Err(BuilderError::Secret(_))
+        ));
+        assert!(matches!(
+            SecretRef::new("secret://../escape").resolve(&source),
+            Err(BuilderError::Secret(_))
+        ));
+        assert!(matches!(
+            SecretRef::new("secret:///etc/hostname").resolve(&source),
+            Err(BuilderError::Secret(_))
+        ));
+        assert!(matches!(
+            SecretRef::new("secret://./storage-key").resolve(&source),
+            Err(BuilderError::Secret(_))
+        ));
+        assert_eq!(
+            SecretRef::new("literal").resolve(&source).unwrap(),
+            "literal"
+        );
+        assert_eq!(
+            format!("{:?}", SecretRef::new("literal")),
+            "SecretRef(<plaintext>)"
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
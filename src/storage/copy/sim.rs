@@ -0,0 +1,506 @@
+use core::future::Future;
+use core::ops::Range;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::Wake;
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
+
+use super::direct::DKeyWithParserCopy;
+use super::parser::GuardedParser as _;
+use super::{ParserWhere, Sink, ValueWhere};
+use crate::storage::clock::{Clock, MockClock};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ListPage, ObjectLock, ObjectMeta};
+use crate::storage::sink::memory::{Memory, FLAT_PAGE_SIZE};
+use crate::storage::{DKeyWhere, ListKeyObjects, MemoryError, ResultExt as _};
+
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+type Timers = Arc<Mutex<Vec<(Instant, Waker)>>>;
+
+#[derive(Default)]
+struct Woken(AtomicBool);
+
+impl Wake for Woken {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+pub struct Simulation {
+    seed: u64,
+    handle: SimHandle,
+    tasks: Vec<(Task, Arc<Woken>)>,
+}
+
+impl Simulation {
+    #[inline]
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            handle: SimHandle {
+                clock: MockClock::new(),
+                timers: Arc::default(),
+            },
+            tasks: vec![],
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn handle(&self) -> SimHandle {
+        self.handle.clone()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn clock(&self) -> MockClock {
+        self.handle.clock.clone()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn nodes(&self, count: usize) -> Vec<Node> {
+        let storage = SharedMemory::default();
+        (0..count)
+            .map(|id| Node {
+                id,
+                storage: storage.clone(),
+                handle: self.handle(),
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub fn spawn<FUTURE>(&mut self, future: FUTURE)
+    where
+        FUTURE: Future<Output = ()> + Send + 'static,
+    {
+        let woken = Arc::new(Woken(AtomicBool::new(true)));
+        self.tasks.push((Box::pin(future), woken));
+    }
+
+    #[inline]
+    pub fn run(&mut self) -> bool {
+        while !self.tasks.is_empty() {
+            let ready = self
+                .tasks
+                .iter()
+                .enumerate()
+                .filter(|&(_, (_, woken))| woken.0.load(Ordering::SeqCst))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+
+            if ready.is_empty() {
+                if !self.advance_to_next_timer() {
+                    return false;
+                }
+                continue;
+            }
+
+            let picked = usize::try_from(self.next_random()).unwrap_or_default() % ready.len();
+            let Some(&index) = ready.get(picked) else {
+                return false;
+            };
+            let Some(&mut (ref mut task, ref woken)) = self.tasks.get_mut(index) else {
+                return false;
+            };
+
+            woken.0.store(false, Ordering::SeqCst);
+            let waker = Waker::from(Arc::clone(woken));
+            if task
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                drop(self.tasks.swap_remove(index));
+            }
+        }
+
+        true
+    }
+
+    fn advance_to_next_timer(&self) -> bool {
+        let mut timers = self.handle.timers();
+        let Some(deadline) = timers.iter().map(|&(deadline, _)| deadline).min() else {
+            return false;
+        };
+
+        let now = self.handle.clock.now();
+        self.handle
+            .clock
+            .advance(deadline.saturating_duration_since(now));
+        timers.retain(|&(timer, ref waker)| {
+            if timer <= deadline {
+                waker.wake_by_ref();
+                false
+            } else {
+                true
+            }
+        });
+        true
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.seed = self.seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut mixed = self.seed;
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        mixed ^ (mixed >> 31)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimHandle {
+    clock: MockClock,
+    timers: Timers,
+}
+
+impl SimHandle {
+    #[inline]
+    #[must_use]
+    pub fn clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep {
+            deadline: self.clock.now() + duration,
+            handle: self.clone(),
+        }
+    }
+
+    fn timers(&self) -> MutexGuard<'_, Vec<(Instant, Waker)>> {
+        self.timers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+pub struct Sleep {
+    deadline: Instant,
+    handle: SimHandle,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.handle.clock.now() >= self.deadline {
+            Poll::Ready(())
+        } else {
+            self.handle
+                .timers()
+                .push((self.deadline, context.waker().clone()));
+            Poll::Pending
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            context.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+pub struct Node {
+    pub id: usize,
+    pub storage: SharedMemory,
+    pub handle: SimHandle,
+}
+
+#[derive(Default, Clone)]
+pub struct SharedMemory {
+    inner: Arc<Mutex<Memory>>,
+}
+
+impl SharedMemory {
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.memory().len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.memory().is_empty()
+    }
+
+    fn memory(&self) -> MutexGuard<'_, Memory> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Sink for SharedMemory {
+    type Error = MemoryError;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        YieldNow::default().await;
+        Ok(self.memory().exists_inner(&key_with_parser.key().name()))
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "shared_memory",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        YieldNow::default().await;
+        self.memory().put_bytes_inner(
+            &key_with_parser.key().name(),
+            key_with_parser.parser().mime(),
+            serialize,
+        )
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        YieldNow::default().await;
+        self.memory().put_bytes_inner(&key.name(), mime, value)
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        YieldNow::default().await;
+        self.memory().delete_inner(&key.name())?;
+        Ok(())
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        YieldNow::default().await;
+        self.memory().lock_inner(&key.name(), lock)
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        YieldNow::default().await;
+        Ok(self.memory().touch_inner(&key.name()))
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        YieldNow::default().await;
+        self.memory().append_bytes_inner(&key.name(), &value)
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let content = self.get_bytes_copy(key_with_parser.key()).await?;
+        Ok(content
+            .map(|value| {
+                key_with_parser
+                    .parser()
+                    .deserialize_guarded(&value)
+                    .context("shared_memory", "get_object", &key_with_parser.key().name())
+            })
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        YieldNow::default().await;
+        Ok(self.memory().get_bytes_inner(&key.name()))
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        YieldNow::default().await;
+        Ok(self.memory().head_inner(&key.name()))
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        YieldNow::default().await;
+        Ok(self.memory().get_range_inner(&key.name(), &range))
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        YieldNow::default().await;
+        Ok(self.memory().list_objects_inner(prefix))
+    }
+
+    #[inline]
+    async fn list_flat_page_copy(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<ListPage, Self::Error> {
+        YieldNow::default().await;
+        Ok(self
+            .memory()
+            .list_flat_inner(prefix, continuation.as_deref(), FLAT_PAGE_SIZE))
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("shared_memory")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::copy::parser::Json;
+
+    fn trace(seed: u64) -> Vec<usize> {
+        let mut simulation = Simulation::new(seed);
+        let trace = Arc::new(Mutex::new(vec![]));
+
+        for id in 0..3 {
+            let trace = Arc::clone(&trace);
+            simulation.spawn(async move {
+                for _ in 0..3 {
+                    trace.lock().unwrap().push(id);
+                    YieldNow::default().await;
+                }
+            });
+        }
+
+        assert!(simulation.run());
+        let steps = trace.lock().unwrap().clone();
+        steps
+    }
+
+    #[test]
+    fn deterministic_schedule() {
+        assert_eq!(trace(7), trace(7));
+        assert!((0..16).any(|seed| trace(seed) != trace(7)));
+    }
+
+    #[test]
+    fn sleep_advances_mock_clock() {
+        let mut simulation = Simulation::new(0);
+        let handle = simulation.handle();
+        let start = simulation.clock().now();
+
+        simulation.spawn(async move {
+            handle.sleep(Duration::from_secs(3600)).await;
+        });
+
+        assert!(simulation.run());
+        assert_eq!(
+            simulation.clock().now().duration_since(start),
+            Duration::from_secs(3600)
+        );
+    }
+
+    #[test]
+    fn find_put_if_not_exists_race() {
+        let races = (0..32)
+            .filter(|&seed| {
+                let mut simulation = Simulation::new(seed);
+                let winners = Arc::new(Mutex::new(0));
+
+                for mut node in simulation.nodes(2) {
+                    let winners = Arc::clone(&winners);
+                    simulation.spawn(async move {
+                        let key = "leader".to_owned();
+                        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+                        if node
+                            .storage
+                            .put_object_if_not_exists_copy(&key_with_parser, &node.id)
+                            .await
+                            .unwrap()
+                        {
+                            *winners.lock().unwrap() += 1;
+                        }
+                    });
+                }
+
+                assert!(simulation.run());
+                let count = *winners.lock().unwrap();
+                count > 1
+            })
+            .count();
+
+        assert!(races > 0, "some interleaving must elect two leaders");
+    }
+}
@@ -1,2 +1,22 @@
+pub mod any;
+pub mod chunked;
+pub mod compressed;
+pub mod encoded;
+pub mod encrypted;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "ipfs")]
+pub mod ipfs;
+pub mod measured;
 pub mod memory;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod publish;
+#[cfg(feature = "test-util")]
+pub mod recording;
+pub mod router;
 pub mod s3;
+pub mod size_limit;
+#[cfg(feature = "tracing")]
+pub mod slow_op;
+pub mod tenant;
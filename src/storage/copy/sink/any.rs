@@ -0,0 +1,357 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::instance::Configuration;
+use crate::storage::copy::{ParserWhere, PutOptions, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ListPage, ObjectLock, ObjectMeta};
+use crate::storage::sink::any::AnyStorage;
+use crate::storage::{AnyError, DKeyWhere, ListKeyObjects};
+
+impl AnyStorage {
+    #[inline]
+    pub async fn from_configuration(configuration: &Configuration) -> Result<Self, AnyError> {
+        Self::from_backend_with_tls(
+            &configuration.backend.clone().unwrap_or_default(),
+            configuration.tls.as_ref(),
+        )
+        .await
+    }
+}
+
+impl Sink for AnyStorage {
+    type Error = AnyError;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        Ok(match *self {
+            Self::Memory(ref sink) => sink.exists_copy(key_with_parser).await?,
+            Self::S3(ref sink) => sink.exists_copy(key_with_parser).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref sink) => sink.exists_copy(key_with_parser).await?,
+        })
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        match *self {
+            Self::Memory(ref mut sink) => sink.put_object_copy(key_with_parser, value).await?,
+            Self::S3(ref mut sink) => sink.put_object_copy(key_with_parser, value).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref mut sink) => sink.put_object_copy(key_with_parser, value).await?,
+        }
+        Ok(())
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        match *self {
+            Self::Memory(ref mut sink) => sink.put_bytes_copy(key, mime, value).await?,
+            Self::S3(ref mut sink) => sink.put_bytes_copy(key, mime, value).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref mut sink) => sink.put_bytes_copy(key, mime, value).await?,
+        }
+        Ok(())
+    }
+
+    #[inline]
+    async fn put_bytes_with_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+        options: PutOptions,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        match *self {
+            Self::Memory(ref mut sink) => {
+                sink.put_bytes_with_copy(key, mime, value, options).await?;
+            }
+            Self::S3(ref mut sink) => sink.put_bytes_with_copy(key, mime, value, options).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref mut sink) => {
+                sink.put_bytes_with_copy(key, mime, value, options).await?;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        match *self {
+            Self::Memory(ref mut sink) => sink.lock_copy(key, lock).await?,
+            Self::S3(ref mut sink) => sink.lock_copy(key, lock).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref mut sink) => sink.lock_copy(key, lock).await?,
+        }
+        Ok(())
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        match *self {
+            Self::Memory(ref mut sink) => Ok(sink.touch_copy(key).await?),
+            Self::S3(ref mut sink) => Ok(sink.touch_copy(key).await?),
+            #[cfg(feature = "http")]
+            Self::Http(ref mut sink) => Ok(sink.touch_copy(key).await?),
+        }
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        match *self {
+            Self::Memory(ref mut sink) => sink.delete_copy(key).await?,
+            Self::S3(ref mut sink) => sink.delete_copy(key).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref mut sink) => sink.delete_copy(key).await?,
+        }
+        Ok(())
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        match *self {
+            Self::Memory(ref mut sink) => sink.append_bytes_copy(key, value).await?,
+            Self::S3(ref mut sink) => sink.append_bytes_copy(key, value).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref mut sink) => sink.append_bytes_copy(key, value).await?,
+        }
+        Ok(())
+    }
+
+    #[inline]
+    async fn put_bytes_meta_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<ObjectMeta, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(match *self {
+            Self::Memory(ref mut sink) => sink.put_bytes_meta_copy(key, mime, value).await?,
+            Self::S3(ref mut sink) => sink.put_bytes_meta_copy(key, mime, value).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref mut sink) => sink.put_bytes_meta_copy(key, mime, value).await?,
+        })
+    }
+
+    #[inline]
+    async fn append_bytes_meta_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<ObjectMeta, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(match *self {
+            Self::Memory(ref mut sink) => sink.append_bytes_meta_copy(key, value).await?,
+            Self::S3(ref mut sink) => sink.append_bytes_meta_copy(key, value).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref mut sink) => sink.append_bytes_meta_copy(key, value).await?,
+        })
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        Ok(match *self {
+            Self::Memory(ref sink) => sink.get_object_copy(key_with_parser).await?,
+            Self::S3(ref sink) => sink.get_object_copy(key_with_parser).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref sink) => sink.get_object_copy(key_with_parser).await?,
+        })
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(match *self {
+            Self::Memory(ref sink) => sink.get_bytes_copy(key).await?,
+            Self::S3(ref sink) => sink.get_bytes_copy(key).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref sink) => sink.get_bytes_copy(key).await?,
+        })
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(match *self {
+            Self::Memory(ref sink) => sink.head_copy(key).await?,
+            Self::S3(ref sink) => sink.head_copy(key).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref sink) => sink.head_copy(key).await?,
+        })
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(match *self {
+            Self::Memory(ref sink) => sink.get_range_copy(key, range).await?,
+            Self::S3(ref sink) => sink.get_range_copy(key, range).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref sink) => sink.get_range_copy(key, range).await?,
+        })
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        Ok(match *self {
+            Self::Memory(ref sink) => sink.list_objects_copy(prefix).await?,
+            Self::S3(ref sink) => sink.list_objects_copy(prefix).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref sink) => sink.list_objects_copy(prefix).await?,
+        })
+    }
+
+    #[inline]
+    async fn list_flat_page_copy(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<ListPage, Self::Error> {
+        Ok(match *self {
+            Self::Memory(ref sink) => sink.list_flat_page_copy(prefix, continuation).await?,
+            Self::S3(ref sink) => sink.list_flat_page_copy(prefix, continuation).await?,
+            #[cfg(feature = "http")]
+            Self::Http(ref sink) => sink.list_flat_page_copy(prefix, continuation).await?,
+        })
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        match *self {
+            Self::Memory(ref sink) => sink.health_copy().await,
+            Self::S3(ref sink) => sink.health_copy().await,
+            #[cfg(feature = "http")]
+            Self::Http(ref sink) => sink.health_copy().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+    use std::{env, fs, process};
+
+    use super::*;
+    use crate::storage::cache::lru::Lru;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::copy::Cache;
+    use crate::storage::sink::any::Backend;
+
+    #[tokio::test]
+    async fn pick_backend_from_configuration() {
+        let path = env::temp_dir().join(format!("negentropy-any-{}.toml", process::id()));
+        fs::write(
+            &path,
+            "[backend]\nkind = \"memory\"\n[tls]\nextra_roots = [\"/etc/negentropy/ca.pem\"]\n",
+        )
+        .unwrap();
+        let configuration = Configuration::default().load_from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(configuration.backend, Some(Backend::Memory));
+        assert_eq!(
+            configuration
+                .tls
+                .as_ref()
+                .map(|tls| tls.extra_roots.as_slice()),
+            Some(["/etc/negentropy/ca.pem".into()].as_slice())
+        );
+
+        let mut storage = AnyStorage::from_configuration(&configuration)
+            .await
+            .unwrap();
+        assert_eq!(storage.kind(), "memory");
+        let key = "any/key".to_owned();
+        storage
+            .put_object_copy(&DKeyWithParserCopy::new(&key, &Json), &42_u8)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .get_object_copy::<u8, _, _>(&DKeyWithParserCopy::new(&key, &Json))
+                .await
+                .unwrap(),
+            Some(42)
+        );
+        assert!(storage
+            .list_objects_copy("any/")
+            .await
+            .unwrap()
+            .contains("any/key"));
+
+        let mut lru = Lru::new(NonZeroUsize::MIN, storage);
+        assert_eq!(
+            Cache::get_object_copy::<u8, _, _>(&mut lru, &DKeyWithParserCopy::new(&key, &Json))
+                .await
+                .unwrap(),
+            Some(42)
+        );
+    }
+}
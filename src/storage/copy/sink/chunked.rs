@@ -0,0 +1,664 @@
+use core::ops::Range;
+
+use futures::stream::{self, Stream};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::{GuardedParser as _, Json};
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::chunked::{Chunked, MANIFEST_MIME};
+use crate::storage::{slice_range, DKeyWhere, ListKeyObjects, ParserError, ResultExt as _};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    pub size: u64,
+    pub chunk_size: usize,
+    pub chunks: usize,
+    pub mime: String,
+}
+
+impl ChunkManifest {
+    fn check(&self) -> Result<(), ParserError> {
+        let chunk_size = u64::try_from(self.chunk_size).unwrap_or(u64::MAX);
+        let expected = (chunk_size != 0).then(|| self.size.div_ceil(chunk_size));
+        if expected == u64::try_from(self.chunks).ok() {
+            Ok(())
+        } else {
+            Err(ParserError::Corrupted(format!(
+                "manifest announces {} chunks of {} bytes for {} bytes",
+                self.chunks, self.chunk_size, self.size
+            )))
+        }
+    }
+
+    /// Size the chunk at `index` must have, only the last one may be shorter.
+    fn chunk_len(&self, index: usize) -> u64 {
+        let chunk_size = u64::try_from(self.chunk_size).unwrap_or(u64::MAX);
+        let offset = u64::try_from(index)
+            .unwrap_or(u64::MAX)
+            .saturating_mul(chunk_size);
+        self.size.saturating_sub(offset).min(chunk_size)
+    }
+
+    fn check_chunk(&self, index: usize, chunk: Option<Vec<u8>>) -> Result<Vec<u8>, ParserError> {
+        let chunk =
+            chunk.ok_or_else(|| ParserError::Corrupted(format!("chunk {index} is missing")))?;
+        let expected = self.chunk_len(index);
+        if u64::try_from(chunk.len()).ok() == Some(expected) {
+            Ok(chunk)
+        } else {
+            Err(ParserError::Corrupted(format!(
+                "chunk {index} has {} bytes, manifest expects {expected}",
+                chunk.len()
+            )))
+        }
+    }
+}
+
+impl<STORAGE> Chunked<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+    <STORAGE as Sink>::Error: From<ParserError>,
+{
+    #[inline]
+    pub async fn get_stream<DKEY>(
+        &self,
+        key: &DKEY,
+    ) -> Result<
+        Option<impl Stream<Item = Result<Vec<u8>, <STORAGE as Sink>::Error>> + '_>,
+        <STORAGE as Sink>::Error,
+    >
+    where
+        DKEY: DKeyWhere,
+    {
+        let name = key.name().into_owned();
+        let manifest = self.manifest(key).await?;
+        let (manifest, single) = match manifest {
+            Some((manifest, _)) => (Some(manifest), None),
+            None => match self.storage().get_bytes_copy(key).await? {
+                Some(value) => (None, Some(value)),
+                None => return Ok(None),
+            },
+        };
+        let chunks = manifest.as_ref().map_or(0, |manifest| manifest.chunks);
+
+        let chunked = stream::unfold(0, move |index| {
+            let name = name.clone();
+            let manifest = manifest.clone();
+            async move {
+                let manifest = manifest.filter(|_| index < chunks)?;
+                let chunk = match self
+                    .storage()
+                    .get_bytes_copy(&Self::chunk_key_inner(&name, index))
+                    .await
+                {
+                    Ok(chunk) => manifest
+                        .check_chunk(index, chunk)
+                        .context("chunked", "get_stream", &name)
+                        .map_err(Into::into),
+                    Err(err) => Err(err),
+                };
+                // Nothing after a failed chunk, the stream would not be the object anymore.
+                let next = if chunk.is_ok() { index + 1 } else { chunks };
+                Some((chunk, next))
+            }
+        });
+
+        Ok(Some(futures::StreamExt::chain(
+            stream::iter(single.map(Ok)),
+            chunked,
+        )))
+    }
+
+    async fn manifest<DKEY>(
+        &self,
+        key: &DKEY,
+    ) -> Result<Option<(ChunkManifest, ObjectMeta)>, <STORAGE as Sink>::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let Some(meta) = self.storage().head_copy(key).await? else {
+            return Ok(None);
+        };
+        if meta.mime.as_deref() != Some(MANIFEST_MIME) {
+            return Ok(None);
+        }
+
+        let manifest = self
+            .storage()
+            .get_object_copy::<ChunkManifest, _, _>(&DKeyWithParserCopy::new(key, &Json))
+            .await?;
+        let Some(manifest) = manifest else {
+            return Ok(None);
+        };
+        manifest
+            .check()
+            .context("chunked", "manifest", &key.name())?;
+        Ok(Some((manifest, meta)))
+    }
+
+    async fn get_chunks(
+        &self,
+        name: &str,
+        manifest: &ChunkManifest,
+        chunks: Range<usize>,
+    ) -> Result<Vec<u8>, <STORAGE as Sink>::Error> {
+        let mut content = vec![];
+
+        for index in chunks {
+            let chunk = self
+                .storage()
+                .get_bytes_copy(&Self::chunk_key_inner(name, index))
+                .await?;
+            content.extend(manifest.check_chunk(index, chunk).context(
+                "chunked",
+                "get_chunks",
+                name,
+            )?);
+        }
+
+        Ok(content)
+    }
+
+    async fn is_manifest<DKEY>(&self, key: &DKEY) -> Result<bool, <STORAGE as Sink>::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(self
+            .storage()
+            .head_copy(key)
+            .await?
+            .is_some_and(|meta| meta.mime.as_deref() == Some(MANIFEST_MIME)))
+    }
+
+    /// Delete the chunks of `name` from index `first` on. They are listed rather than read from
+    /// the manifest, so the chunks an overwritten or corrupted manifest lost track of go too.
+    async fn delete_chunks_from(
+        &mut self,
+        name: &str,
+        first: usize,
+    ) -> Result<(), <STORAGE as Sink>::Error> {
+        let prefix = Self::chunks_prefix_inner(name);
+        let mut orphans = vec![];
+        let mut continuation = None;
+        loop {
+            let page = self
+                .storage()
+                .list_flat_page_copy(&prefix, continuation)
+                .await?;
+            orphans.extend(
+                page.entries
+                    .into_iter()
+                    .filter(|entry| {
+                        entry
+                            .key
+                            .strip_prefix(prefix.as_str())
+                            .and_then(|index| index.parse::<usize>().ok())
+                            .is_some_and(|index| index >= first)
+                    })
+                    .map(|entry| entry.key),
+            );
+            continuation = page.next;
+            if continuation.is_none() {
+                break;
+            }
+        }
+
+        for orphan in orphans {
+            self.storage_mut().delete_copy(&orphan).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<STORAGE> Sink for Chunked<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+    <STORAGE as Sink>::Error: From<ParserError>,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.storage().exists_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "chunked",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        self.put_bytes_copy(
+            key_with_parser.key(),
+            key_with_parser.parser().mime(),
+            serialize,
+        )
+        .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let chunk_size = self.threshold().get();
+        let name = key.name();
+        let was_chunked = self.is_manifest(key).await?;
+        if value.len() <= chunk_size {
+            self.storage_mut().put_bytes_copy(key, mime, value).await?;
+            if was_chunked {
+                self.delete_chunks_from(&name, 0).await?;
+            }
+            return Ok(());
+        }
+
+        let chunks = value.chunks(chunk_size);
+        let manifest = ChunkManifest {
+            size: u64::try_from(value.len()).unwrap_or(u64::MAX),
+            chunk_size,
+            chunks: chunks.len(),
+            mime: mime.clone(),
+        };
+        for (index, chunk) in chunks.enumerate() {
+            self.storage_mut()
+                .put_bytes_copy(
+                    &Self::chunk_key_inner(&name, index),
+                    mime.clone(),
+                    chunk.to_vec(),
+                )
+                .await?;
+        }
+
+        let count = manifest.chunks;
+        let manifest = Json.serialize_value(&manifest)?;
+        self.storage_mut()
+            .put_bytes_copy(key, MANIFEST_MIME.to_owned(), manifest)
+            .await?;
+        if was_chunked {
+            self.delete_chunks_from(&name, count).await?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        if self.is_manifest(key).await? {
+            self.delete_chunks_from(&key.name(), 0).await?;
+        }
+        self.storage_mut().delete_copy(key).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let manifest = self.manifest(key).await?;
+        if let Some((manifest, _)) = manifest {
+            let name = key.name();
+            for index in 0..manifest.chunks {
+                self.storage_mut()
+                    .lock_copy(&Self::chunk_key_inner(&name, index), lock)
+                    .await?;
+            }
+        }
+        self.storage_mut().lock_copy(key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let manifest = self.manifest(key).await?;
+        if let Some((manifest, _)) = manifest {
+            let name = key.name();
+            for index in 0..manifest.chunks {
+                self.storage_mut()
+                    .touch_copy(&Self::chunk_key_inner(&name, index))
+                    .await?;
+            }
+        }
+        self.storage_mut().touch_copy(key).await
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let content = self.get_bytes_copy(key_with_parser.key()).await?;
+        Ok(content
+            .map(|value| {
+                key_with_parser
+                    .parser()
+                    .deserialize_guarded(&value)
+                    .context("chunked", "get_object", &key_with_parser.key().name())
+            })
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let manifest = self.manifest(key).await?;
+        match manifest {
+            Some((manifest, _)) => Ok(Some(
+                self.get_chunks(&key.name(), &manifest, 0..manifest.chunks)
+                    .await?,
+            )),
+            None => self.storage().get_bytes_copy(key).await,
+        }
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let manifest = self.manifest(key).await?;
+        match manifest {
+            Some((manifest, meta)) => Ok(Some(ObjectMeta {
+                size: manifest.size,
+                etag: None,
+                checksum: None,
+                mime: Some(manifest.mime),
+                last_modified: meta.last_modified,
+            })),
+            None => self.storage().head_copy(key).await,
+        }
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let Some((manifest, _)) = self.manifest(key).await? else {
+            return self.storage().get_range_copy(key, range).await;
+        };
+
+        let chunk_size = u64::try_from(manifest.chunk_size).unwrap_or(u64::MAX);
+        let end = range.end.min(manifest.size);
+        let first = range.start / chunk_size;
+        let last = end.div_ceil(chunk_size).max(first);
+        let content = self
+            .get_chunks(
+                &key.name(),
+                &manifest,
+                usize::try_from(first).unwrap_or(usize::MAX)
+                    ..usize::try_from(last).unwrap_or(usize::MAX),
+            )
+            .await?;
+        let offset = first * chunk_size;
+
+        Ok(Some(slice_range(
+            &content,
+            &(range.start - offset..end.max(range.start) - offset),
+        )))
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        let mut objects = self.storage().list_objects_copy(prefix).await?;
+        objects.retain(|entry| !Self::is_chunk_inner(entry));
+        Ok(objects)
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("chunked").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use futures::StreamExt as _;
+
+    use super::*;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    async fn chunked() -> Chunked<Memory> {
+        let mut chunked = Chunked::new(NonZeroUsize::new(4).unwrap(), Memory::default());
+        chunked
+            .put_bytes_copy(
+                &"big".to_owned(),
+                "text/plain".to_owned(),
+                (0..10).collect(),
+            )
+            .await
+            .unwrap();
+        chunked
+            .put_bytes_copy(&"small".to_owned(), "text/plain".to_owned(), vec![1, 2])
+            .await
+            .unwrap();
+        chunked
+    }
+
+    #[tokio::test]
+    async fn split_and_reassemble() {
+        let chunked = chunked().await;
+        let big = "big".to_owned();
+
+        assert_eq!(
+            chunked.storage().len(),
+            5,
+            "three chunks, a manifest and the small value"
+        );
+        assert_eq!(
+            chunked.get_bytes_copy(&big).await.unwrap(),
+            Some((0..10).collect())
+        );
+        assert_eq!(
+            chunked.get_bytes_copy(&"small".to_owned()).await.unwrap(),
+            Some(vec![1, 2])
+        );
+
+        let meta = chunked.head_copy(&big).await.unwrap().unwrap();
+        assert_eq!(meta.size, 10);
+        assert_eq!(meta.mime.as_deref(), Some("text/plain"));
+        assert_eq!(
+            chunked.list_objects_copy("").await.unwrap(),
+            ["big".to_owned(), "small".to_owned()].into_iter().collect(),
+            "chunk keys must stay hidden"
+        );
+    }
+
+    #[tokio::test]
+    async fn range_across_chunks() {
+        let chunked = chunked().await;
+
+        assert_eq!(
+            chunked
+                .get_range_copy(&"big".to_owned(), 3..9)
+                .await
+                .unwrap(),
+            Some(vec![3, 4, 5, 6, 7, 8])
+        );
+        assert_eq!(
+            chunked
+                .get_range_copy(&"big".to_owned(), 8..20)
+                .await
+                .unwrap(),
+            Some(vec![8, 9])
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_chunks() {
+        let chunked = chunked().await;
+        let parts = chunked
+            .get_stream(&"big".to_owned())
+            .await
+            .unwrap()
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(parts, vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8, 9]]);
+        assert!(chunked
+            .get_stream(&"missing".to_owned())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn corrupted_manifest() {
+        let mut chunked = chunked().await;
+        let big = "big".to_owned();
+        let corrupted = ChunkManifest {
+            size: u64::MAX,
+            chunk_size: 0,
+            chunks: usize::MAX,
+            mime: "text/plain".to_owned(),
+        };
+        let manifest = Json.serialize_value(&corrupted).unwrap();
+        chunked
+            .storage_mut()
+            .put_bytes_inner("big", MANIFEST_MIME.to_owned(), manifest)
+            .unwrap();
+
+        assert!(chunked.get_bytes_copy(&big).await.is_err());
+        assert!(
+            chunked.get_range_copy(&big, 2..5).await.is_err(),
+            "a zero chunk size must not divide by zero"
+        );
+        assert!(chunked.get_stream(&big).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn overwrite_drops_orphan_chunks() {
+        let mut chunked = chunked().await;
+        let big = "big".to_owned();
+
+        chunked
+            .put_bytes_copy(&big, "text/plain".to_owned(), (0..6).collect())
+            .await
+            .unwrap();
+        assert!(!chunked
+            .storage()
+            .exists_inner(&Chunked::<Memory>::chunk_key_inner("big", 2)));
+        assert_eq!(
+            chunked.get_bytes_copy(&big).await.unwrap(),
+            Some((0..6).collect())
+        );
+
+        chunked
+            .put_bytes_copy(&big, "text/plain".to_owned(), vec![1])
+            .await
+            .unwrap();
+        assert_eq!(
+            chunked.storage().len(),
+            2,
+            "only the two plain values remain"
+        );
+
+        chunked
+            .put_bytes_copy(&big, "text/plain".to_owned(), (0..10).collect())
+            .await
+            .unwrap();
+        chunked
+            .storage_mut()
+            .put_bytes_inner(
+                &Chunked::<Memory>::chunk_key_inner("big", 7),
+                "text/plain".to_owned(),
+                vec![0],
+            )
+            .unwrap();
+        chunked.delete_copy(&big).await.unwrap();
+        assert_eq!(
+            chunked.storage().len(),
+            1,
+            "a delete drops the chunks the manifest does not know about"
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_chunk() {
+        let mut chunked = chunked().await;
+        let big = "big".to_owned();
+        chunked
+            .storage_mut()
+            .delete_inner(&Chunked::<Memory>::chunk_key_inner("big", 1))
+            .unwrap();
+
+        let err = chunked.get_bytes_copy(&big).await.unwrap_err();
+        assert!(matches!(
+            err,
+            MemoryError::Serde(ref err) if matches!(*err.root(), ParserError::Corrupted(_))
+        ));
+        assert_eq!(
+            chunked.get_range_copy(&big, 0..4).await.unwrap(),
+            Some(vec![0, 1, 2, 3]),
+            "a range before the missing chunk is still readable"
+        );
+        assert!(chunked.get_range_copy(&big, 2..6).await.is_err());
+        let parts = chunked
+            .get_stream(&big)
+            .await
+            .unwrap()
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(parts.len(), 2, "the stream ends on the missing chunk");
+        assert!(parts[1].is_err());
+
+        chunked
+            .storage_mut()
+            .put_bytes_inner(
+                &Chunked::<Memory>::chunk_key_inner("big", 1),
+                "text/plain".to_owned(),
+                vec![4],
+            )
+            .unwrap();
+        assert!(
+            chunked.get_bytes_copy(&big).await.is_err(),
+            "a truncated chunk does not add up to the manifest size"
+        );
+    }
+}
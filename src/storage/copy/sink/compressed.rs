@@ -0,0 +1,387 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::compressed::{header, Compressed, Compression, HEADER_MAX};
+use crate::storage::{
+    slice_range, DKeyWhere, LayerError, ListKeyObjects, ParserError, ResultExt as _,
+};
+
+impl<CODEC, STORAGE> Sink for Compressed<CODEC, STORAGE>
+where
+    CODEC: Compression,
+    STORAGE: Sink + Send + Sync,
+    <STORAGE as Sink>::Error: From<LayerError> + From<ParserError>,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.storage().exists_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "compressed",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        self.put_bytes_copy(
+            key_with_parser.key(),
+            key_with_parser.parser().mime(),
+            serialize,
+        )
+        .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let packed = self.pack_inner(&mime, value);
+        self.storage_mut().put_bytes_copy(key, mime, packed).await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().delete_copy(key).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().lock_copy(key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().touch_copy(key).await
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let content = self.get_bytes_copy(key_with_parser.key()).await?;
+        Ok(content
+            .map(|value| {
+                key_with_parser
+                    .parser()
+                    .deserialize_guarded(&value)
+                    .context("compressed", "get_object", &key_with_parser.key().name())
+            })
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let packed = self.storage().get_bytes_copy(key).await?;
+        Ok(packed
+            .map(|packed| self.unpack_inner(&key.name(), packed))
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let Some(meta) = self.storage().head_copy(key).await? else {
+            return Ok(None);
+        };
+        let prefix = self
+            .storage()
+            .get_range_copy(key, 0..HEADER_MAX)
+            .await?
+            .unwrap_or_default();
+
+        Ok(Some(match header(&prefix) {
+            Some((_, size, _)) => ObjectMeta {
+                size,
+                checksum: None,
+                ..meta
+            },
+            None => meta,
+        }))
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let content = self.get_bytes_copy(key).await?;
+        Ok(content.map(|value| slice_range(&value, &range)))
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.storage().list_objects_copy(prefix).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("compressed").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    struct RunLength;
+
+    impl Compression for RunLength {
+        fn encoding(&self) -> &str {
+            "rle"
+        }
+
+        fn compress(&self, value: &[u8]) -> Vec<u8> {
+            let mut compressed = vec![];
+            for chunk in value.chunk_by(|left, right| left == right) {
+                for run in chunk.chunks(255) {
+                    compressed.extend([run.len() as u8, run[0]]);
+                }
+            }
+            compressed
+        }
+
+        fn decompress(&self, value: &[u8], size: usize) -> Option<Vec<u8>> {
+            let mut content = vec![];
+            for pair in value.chunks(2) {
+                let &[len, byte] = pair else {
+                    return None;
+                };
+                content.extend(core::iter::repeat_n(byte, usize::from(len)));
+                if content.len() > size {
+                    return None;
+                }
+            }
+            Some(content)
+        }
+    }
+
+    fn compressed() -> Compressed<RunLength, Memory> {
+        Compressed::new(RunLength, Memory::default()).with_threshold(8)
+    }
+
+    async fn put(
+        compressed: &mut Compressed<RunLength, Memory>,
+        key: &str,
+        mime: &str,
+        value: Vec<u8>,
+    ) {
+        compressed
+            .put_bytes_copy(&key.to_owned(), mime.to_owned(), value)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn compress_above_threshold() {
+        let mut compressed = compressed();
+        put(&mut compressed, "big", "text/plain", vec![7; 100]).await;
+        put(&mut compressed, "small", "text/plain", vec![7; 4]).await;
+        put(&mut compressed, "photo", "image/png", vec![7; 100]).await;
+
+        let stored = |key: &str| compressed.storage().get_bytes_inner(key).unwrap();
+        assert!(stored("big").len() < 100);
+        assert_eq!(
+            header(&stored("big")).map(|(encoding, size, _)| (encoding, size)),
+            Some(("rle", 100))
+        );
+        assert_eq!(stored("small"), vec![7; 4], "small values are stored as is");
+        assert_eq!(
+            stored("photo"),
+            vec![7; 100],
+            "compressed mimes are skipped"
+        );
+
+        for (key, size) in [("big", 100), ("small", 4), ("photo", 100)] {
+            assert_eq!(
+                compressed.get_bytes_copy(&key.to_owned()).await.unwrap(),
+                Some(vec![7; size])
+            );
+            assert_eq!(
+                compressed
+                    .head_copy(&key.to_owned())
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .size,
+                size as u64
+            );
+        }
+    }
+
+    #[cfg(feature = "deflate")]
+    #[tokio::test]
+    async fn deflate_round_trip() {
+        use crate::storage::sink::compressed::Deflate;
+
+        let mut compressed = Compressed::new(Deflate::default(), Memory::default());
+        let value = b"negentropy ".repeat(200);
+        compressed
+            .put_bytes_copy(&"log".to_owned(), "text/plain".to_owned(), value.clone())
+            .await
+            .unwrap();
+
+        let stored = compressed.storage().get_bytes_inner("log").unwrap();
+        assert!(stored.len() < value.len() / 10);
+        assert_eq!(
+            header(&stored).map(|(encoding, size, _)| (encoding, size)),
+            Some(("deflate", value.len() as u64))
+        );
+        assert_eq!(
+            compressed.get_bytes_copy(&"log".to_owned()).await.unwrap(),
+            Some(value)
+        );
+        assert_eq!(Deflate::new(42).level(), 10);
+    }
+
+    #[cfg(feature = "deflate")]
+    #[tokio::test]
+    async fn refuse_inflating_past_the_header() {
+        use crate::storage::sink::compressed::Deflate;
+
+        let mut compressed = Compressed::new(Deflate::default(), Memory::default());
+        let bomb = miniz_oxide::deflate::compress_to_vec_zlib(&[0; 1 << 20], 10);
+        let mut stored = b"NGC1\x07deflate".to_vec();
+        stored.extend(16_u64.to_be_bytes());
+        stored.extend(bomb);
+        compressed
+            .storage_mut()
+            .put_bytes_inner("bomb", String::new(), stored)
+            .unwrap();
+
+        assert!(matches!(
+            compressed.get_bytes_copy(&"bomb".to_owned()).await,
+            Err(MemoryError::Layer(LayerError::Compression { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn keep_raw_values_unambiguous() {
+        let mut compressed = compressed();
+        let value = b"NGC1 looks like an envelope".to_vec();
+        put(&mut compressed, "tricky", "image/png", value.clone()).await;
+        put(&mut compressed, "noise", "text/plain", (0..64).collect()).await;
+
+        assert_eq!(
+            compressed
+                .get_bytes_copy(&"tricky".to_owned())
+                .await
+                .unwrap(),
+            Some(value)
+        );
+        assert_eq!(
+            compressed.storage().get_bytes_inner("noise"),
+            Some((0..64).collect()),
+            "incompressible values are stored as is"
+        );
+        compressed
+            .storage_mut()
+            .put_bytes_inner(
+                "other",
+                String::new(),
+                b"NGC1\x04zstd\0\0\0\0\0\0\0\x01x".to_vec(),
+            )
+            .unwrap();
+        assert!(matches!(
+            compressed.get_bytes_copy(&"other".to_owned()).await,
+            Err(MemoryError::Layer(LayerError::Compression { .. }))
+        ));
+        compressed
+            .storage_mut()
+            .put_bytes_inner(
+                "short",
+                String::new(),
+                b"NGC1\x03rle\0\0\0\0\0\0\0\x05\x04x".to_vec(),
+            )
+            .unwrap();
+        assert!(
+            matches!(
+                compressed.get_bytes_copy(&"short".to_owned()).await,
+                Err(MemoryError::Layer(LayerError::Compression { .. }))
+            ),
+            "a decoded size other than the announced one is corrupted"
+        );
+    }
+
+    #[tokio::test]
+    async fn list_flat_after_the_continuation() {
+        let mut compressed = compressed();
+        for index in 0..1002 {
+            put(&mut compressed, &format!("k/{index:04}"), "", vec![1]).await;
+        }
+
+        let first = compressed.list_flat_page_copy("k/", None).await.unwrap();
+        assert_eq!(first.entries.len(), 1000);
+        assert_eq!(first.next.as_deref(), Some("k/0999"));
+
+        let second = compressed
+            .list_flat_page_copy("k/", first.next)
+            .await
+            .unwrap();
+        assert_eq!(
+            second
+                .entries
+                .iter()
+                .map(|entry| entry.key.as_str())
+                .collect::<Vec<_>>(),
+            ["k/1000", "k/1001"]
+        );
+        assert_eq!(second.next, None);
+    }
+}
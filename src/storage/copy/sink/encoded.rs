@@ -0,0 +1,274 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::codec::KeyCodec;
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::encoded::Encoded;
+use crate::storage::{DKeyWhere, ListKeyObjects};
+
+impl<CODEC, STORAGE> Sink for Encoded<CODEC, STORAGE>
+where
+    CODEC: KeyCodec,
+    STORAGE: Sink + Send + Sync,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let key = self.encode_inner(&key_with_parser.key().name());
+        self.storage()
+            .exists_copy(&DKeyWithParserCopy::new(&key, key_with_parser.parser()))
+            .await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let key = self.encode_inner(&key_with_parser.key().name());
+        self.storage_mut()
+            .put_object_copy(
+                &DKeyWithParserCopy::new(&key, key_with_parser.parser()),
+                value,
+            )
+            .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let key = self.encode_inner(&key.name());
+        self.storage_mut().put_bytes_copy(&key, mime, value).await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let key = self.encode_inner(&key.name());
+        self.storage_mut().delete_copy(&key).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let key = self.encode_inner(&key.name());
+        self.storage_mut().lock_copy(&key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let key = self.encode_inner(&key.name());
+        self.storage_mut().touch_copy(&key).await
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let key = self.encode_inner(&key.name());
+        self.storage_mut().append_bytes_copy(&key, value).await
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let key = self.encode_inner(&key_with_parser.key().name());
+        self.storage()
+            .get_object_copy(&DKeyWithParserCopy::new(&key, key_with_parser.parser()))
+            .await
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage()
+            .get_bytes_copy(&self.encode_inner(&key.name()))
+            .await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage()
+            .head_copy(&self.encode_inner(&key.name()))
+            .await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage()
+            .get_range_copy(&self.encode_inner(&key.name()), range)
+            .await
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        let objects = self
+            .storage()
+            .list_objects_copy(&self.encode_inner(prefix))
+            .await?;
+        Ok(self.decode_inner(objects))
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("encoded").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::codec::{Deterministic, Hashed, Identity, Normalized};
+    use crate::storage::sink::memory::Memory;
+    use crate::HashSet;
+
+    async fn put<CODEC>(encoded: &mut Encoded<CODEC, Memory>, key: &str)
+    where
+        CODEC: KeyCodec,
+    {
+        encoded
+            .put_bytes_copy(&key.to_owned(), String::new(), key.as_bytes().to_vec())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn deterministic_roundtrip() {
+        let mut encoded = Encoded::new(Deterministic::new(&[7; 32]), Memory::default());
+        put(&mut encoded, "patients/jane@example.com/record").await;
+        put(&mut encoded, "patients/john@example.com/record").await;
+
+        assert!(encoded
+            .storage()
+            .list_objects_inner("")
+            .iter()
+            .all(|key| !key.contains("patients")));
+        assert_eq!(
+            encoded
+                .get_bytes_copy(&"patients/jane@example.com/record".to_owned())
+                .await
+                .unwrap(),
+            Some(b"patients/jane@example.com/record".to_vec())
+        );
+        assert_eq!(
+            encoded.list_objects_copy("patients/").await.unwrap(),
+            [
+                "patients/jane@example.com/".to_owned(),
+                "patients/john@example.com/".to_owned()
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            Encoded::new(Deterministic::new(&[8; 32]), Memory::default())
+                .decode_inner(encoded.storage().list_objects_inner("")),
+            encoded.storage().list_objects_inner(""),
+            "another secret must not decode"
+        );
+    }
+
+    #[tokio::test]
+    async fn hashed_is_one_way() {
+        let mut encoded = Encoded::new(Hashed::new(b"salt".to_vec()), Memory::default());
+        put(&mut encoded, "users/jane").await;
+
+        assert!(encoded
+            .exists_copy(&DKeyWithParserCopy::new(
+                &"users/jane".to_owned(),
+                &crate::storage::copy::parser::Json
+            ))
+            .await
+            .unwrap());
+        assert_ne!(
+            encoded.encode_inner("users/jane"),
+            Encoded::new(Hashed::new(vec![]), Memory::default()).encode_inner("users/jane")
+        );
+        assert!(encoded
+            .list_objects_copy("")
+            .await
+            .unwrap()
+            .iter()
+            .all(|key| !key.contains("users")));
+
+        let identity = Encoded::new(Identity, Memory::default());
+        assert_eq!(identity.encode_inner("users/jane/"), "users/jane/");
+    }
+
+    #[tokio::test]
+    async fn normalized_unicode_keys() {
+        let mut encoded = Encoded::new(Normalized, Memory::default());
+        put(&mut encoded, "caf\u{65}\u{301}/menu").await;
+
+        assert_eq!(
+            encoded
+                .get_bytes_copy(&"caf\u{e9}/menu".to_owned())
+                .await
+                .unwrap(),
+            Some("caf\u{65}\u{301}/menu".as_bytes().to_vec()),
+            "decomposed and composed forms address the same object"
+        );
+        assert_eq!(
+            encoded
+                .list_objects_copy("caf\u{65}\u{301}/")
+                .await
+                .unwrap(),
+            HashSet::from_iter(["caf\u{e9}/menu".to_owned()])
+        );
+    }
+}
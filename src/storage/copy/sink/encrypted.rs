@@ -0,0 +1,402 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::diff::walk;
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::encrypted::{key_id, overhead, Encrypted, HEADER_LEN};
+use crate::storage::{
+    slice_range, DKeyWhere, LayerError, ListKeyObjects, ParserError, ResultExt as _,
+};
+
+impl<STORAGE> Encrypted<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+    <STORAGE as Sink>::Error: From<LayerError> + From<ParserError>,
+{
+    #[inline]
+    pub async fn rewrap_prefix(&mut self, prefix: &str) -> Result<usize, <STORAGE as Sink>::Error> {
+        let keys = walk(self.storage(), prefix).await?;
+        let mut rewrapped = 0;
+
+        for relative in keys {
+            let key = format!("{prefix}{relative}");
+            let Some(envelope) = self.storage().get_bytes_copy(&key).await? else {
+                continue;
+            };
+            if key_id(&envelope) == Some(self.keys().current()) {
+                continue;
+            }
+
+            let mime = self
+                .storage()
+                .head_copy(&key)
+                .await?
+                .and_then(|meta| meta.mime)
+                .unwrap_or_default();
+            self.resolve_inner(&envelope).await?;
+            let sealed = self.seal_inner(&key, self.open_inner(&key, &envelope)?)?;
+            self.storage_mut()
+                .put_bytes_copy(&key, mime, sealed)
+                .await?;
+            rewrapped += 1;
+        }
+
+        Ok(rewrapped)
+    }
+}
+
+impl<STORAGE> Sink for Encrypted<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+    <STORAGE as Sink>::Error: From<LayerError> + From<ParserError>,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.storage().exists_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "encrypted",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        self.put_bytes_copy(
+            key_with_parser.key(),
+            key_with_parser.parser().mime(),
+            serialize,
+        )
+        .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let sealed = self.seal_inner(&key.name(), value)?;
+        self.storage_mut().put_bytes_copy(key, mime, sealed).await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().delete_copy(key).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().lock_copy(key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().touch_copy(key).await
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let content = self.get_bytes_copy(key_with_parser.key()).await?;
+        Ok(content
+            .map(|value| {
+                key_with_parser
+                    .parser()
+                    .deserialize_guarded(&value)
+                    .context("encrypted", "get_object", &key_with_parser.key().name())
+            })
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let Some(envelope) = self.storage().get_bytes_copy(key).await? else {
+            return Ok(None);
+        };
+        self.resolve_inner(&envelope).await?;
+        Ok(Some(self.open_inner(&key.name(), &envelope)?))
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let Some(meta) = self.storage().head_copy(key).await? else {
+            return Ok(None);
+        };
+        let header = self
+            .storage()
+            .get_range_copy(key, 0..HEADER_LEN)
+            .await?
+            .unwrap_or_default();
+
+        Ok(Some(ObjectMeta {
+            size: meta
+                .size
+                .saturating_sub(overhead(&header).unwrap_or_default()),
+            checksum: None,
+            ..meta
+        }))
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let content = self.get_bytes_copy(key).await?;
+        Ok(content.map(|value| slice_range(&value, &range)))
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.storage().list_objects_copy(prefix).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("encrypted").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::Arc;
+
+    use futures::future::BoxFuture;
+
+    use super::*;
+    use crate::storage::layer::Layer as _;
+    use crate::storage::sink::encrypted::{DataKey, KeyProvider, KeyRing};
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    /// Wraps by flipping every bit, counts its calls to `decrypt`.
+    #[derive(Default)]
+    struct Flip {
+        generated: AtomicU8,
+        decrypted: AtomicU8,
+    }
+
+    impl KeyProvider for Flip {
+        fn generate(&self) -> BoxFuture<'_, Result<DataKey, LayerError>> {
+            let secret = [self.generated.fetch_add(1, Ordering::SeqCst); 32];
+            Box::pin(async move {
+                Ok(DataKey {
+                    secret,
+                    wrapped: secret.iter().map(|byte| !byte).collect(),
+                })
+            })
+        }
+
+        fn decrypt<'call>(
+            &'call self,
+            wrapped: &'call [u8],
+        ) -> BoxFuture<'call, Result<[u8; 32], LayerError>> {
+            self.decrypted.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                let mut secret = [0; 32];
+                for (byte, wrapped) in secret.iter_mut().zip(wrapped) {
+                    *byte = !wrapped;
+                }
+                Ok(secret)
+            })
+        }
+    }
+
+    async fn put(encrypted: &mut Encrypted<Memory>, key: &str) {
+        encrypted
+            .put_bytes_copy(
+                &key.to_owned(),
+                "text/plain".to_owned(),
+                key.as_bytes().to_vec(),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn seal_and_open() {
+        let mut encrypted = Encrypted::new(KeyRing::new("k1", &[1; 32]), Memory::default());
+        put(&mut encrypted, "secret/a").await;
+
+        let stored = encrypted.storage().get_bytes_inner("secret/a").unwrap();
+        assert_eq!(key_id(&stored), Some("k1"));
+        assert!(!stored.windows(8).any(|window| window == b"secret/a"));
+        assert_eq!(
+            encrypted
+                .get_bytes_copy(&"secret/a".to_owned())
+                .await
+                .unwrap(),
+            Some(b"secret/a".to_vec())
+        );
+        assert_eq!(
+            encrypted
+                .head_copy(&"secret/a".to_owned())
+                .await
+                .unwrap()
+                .unwrap()
+                .size,
+            8
+        );
+        assert_eq!(
+            encrypted
+                .get_range_copy(&"secret/a".to_owned(), 2..6)
+                .await
+                .unwrap(),
+            Some(b"cret".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn unwrap_data_keys_of_other_instances() {
+        let provider = Arc::new(Flip::default());
+        let mut writer = Encrypted::from_provider(provider.clone(), Memory::default())
+            .await
+            .unwrap();
+        put(&mut writer, "a").await;
+        let first = writer.keys().current().to_owned();
+        let second = writer.rotate_data_key().await.unwrap().to_owned();
+        assert_ne!(first, second);
+        put(&mut writer, "b").await;
+        assert_eq!(
+            writer
+                .head_copy(&"b".to_owned())
+                .await
+                .unwrap()
+                .unwrap()
+                .size,
+            1
+        );
+
+        let mut reader = Encrypted::from_provider(provider.clone(), Memory::default())
+            .await
+            .unwrap();
+        for key in ["a", "b"] {
+            let stored = writer.storage().get_bytes_inner(key).unwrap();
+            reader
+                .storage_mut()
+                .put_bytes_inner(key, String::new(), stored)
+                .unwrap();
+        }
+        for _ in 0..2 {
+            for key in ["a", "b"] {
+                assert_eq!(
+                    reader.get_bytes_copy(&key.to_owned()).await.unwrap(),
+                    Some(key.as_bytes().to_vec())
+                );
+            }
+        }
+        assert_eq!(
+            provider.decrypted.load(Ordering::SeqCst),
+            2,
+            "each data key is unwrapped once"
+        );
+
+        let without_provider = Encrypted::new(KeyRing::new("k1", &[1; 32]), writer.into_inner());
+        assert!(matches!(
+            without_provider.get_bytes_copy(&"a".to_owned()).await,
+            Err(MemoryError::Layer(LayerError::Crypto { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn envelope_bound_to_key() {
+        let mut encrypted = Encrypted::new(KeyRing::new("k1", &[1; 32]), Memory::default());
+        put(&mut encrypted, "a").await;
+        let stored = encrypted.storage().get_bytes_inner("a").unwrap();
+        encrypted
+            .storage_mut()
+            .put_bytes_inner("b", String::new(), stored)
+            .unwrap();
+
+        assert!(matches!(
+            encrypted.get_bytes_copy(&"b".to_owned()).await,
+            Err(MemoryError::Layer(LayerError::Crypto { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rotate_and_rewrap() {
+        let mut encrypted = Encrypted::new(KeyRing::new("k1", &[1; 32]), Memory::default());
+        put(&mut encrypted, "logs/a").await;
+        put(&mut encrypted, "logs/b").await;
+        put(&mut encrypted, "other").await;
+
+        encrypted.set_keys(KeyRing::new("k1", &[1; 32]).rotate("k2", &[2; 32]));
+        put(&mut encrypted, "logs/c").await;
+        assert_eq!(
+            encrypted
+                .get_bytes_copy(&"logs/a".to_owned())
+                .await
+                .unwrap(),
+            Some(b"logs/a".to_vec()),
+            "older keys still decrypt"
+        );
+
+        assert_eq!(encrypted.rewrap_prefix("logs/").await.unwrap(), 2);
+        assert_eq!(encrypted.rewrap_prefix("logs/").await.unwrap(), 0);
+
+        encrypted.set_keys(KeyRing::new("k2", &[2; 32]));
+        for key in ["logs/a", "logs/b", "logs/c"] {
+            assert_eq!(
+                encrypted.get_bytes_copy(&key.to_owned()).await.unwrap(),
+                Some(key.as_bytes().to_vec())
+            );
+        }
+        assert!(encrypted.get_bytes_copy(&"other".to_owned()).await.is_err());
+    }
+}
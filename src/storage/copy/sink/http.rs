@@ -0,0 +1,198 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::http::{Auth, Http};
+use crate::storage::{DKeyWhere, HttpError, ListKeyObjects, ResultExt as _};
+
+impl<AUTH> Sink for Http<AUTH>
+where
+    AUTH: Auth,
+{
+    type Error = HttpError;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.exists_inner(&key_with_parser.key().name()).await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "http",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        self.put_bytes_inner(
+            &key_with_parser.key().name(),
+            key_with_parser.parser().mime(),
+            serialize,
+        )
+        .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.put_bytes_inner(&key.name(), mime, value).await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.delete(&key.name()).await?;
+        Ok(())
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, _lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Err(HttpError::Request {
+            operation: "lock".to_owned(),
+            key: key.name().into_owned(),
+            internal: "object lock is not supported over WebDAV".to_owned(),
+        })
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let content = self.get_bytes_inner(&key_with_parser.key().name()).await?;
+        Ok(content
+            .map(|value| {
+                key_with_parser
+                    .parser()
+                    .deserialize_guarded(&value)
+                    .context("http", "get_object", &key_with_parser.key().name())
+            })
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.get_bytes_inner(&key.name()).await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.head_inner(&key.name()).await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.get_range_inner(&key.name(), range).await
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.list_objects_inner(prefix).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        self.health_inner().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sink::http::{decode_path, encode_path, Basic};
+
+    #[test]
+    fn encode_keys_in_urls() {
+        let key = "reports/2024 Q1/#1+é.json";
+
+        assert_eq!(encode_path(key), "reports/2024%20Q1/%231%2B%C3%A9.json");
+        assert_eq!(decode_path(&encode_path(key)), key);
+        assert_eq!(decode_path("100%"), "100%");
+    }
+
+    #[test]
+    fn parse_multistatus() {
+        let http = Http::new("https://cloud.example.com/remote.php/dav/files/jane")
+            .unwrap()
+            .with_auth(Basic::new("jane", "secret"));
+        let body = r#"<?xml version="1.0"?>
+            <d:multistatus xmlns:d="DAV:">
+                <d:response><d:href>/remote.php/dav/files/jane/docs/</d:href></d:response>
+                <d:response><d:href>/remote.php/dav/files/jane/docs/a%20b.txt</d:href></d:response>
+                <d:response>
+                    <d:href>https://cloud.example.com/remote.php/dav/files/jane/docs/sub/</d:href>
+                </d:response>
+                <d:response><d:href>/elsewhere/x</d:href></d:response>
+            </d:multistatus>"#;
+
+        assert_eq!(
+            http.base(),
+            "https://cloud.example.com/remote.php/dav/files/jane/"
+        );
+        assert_eq!(
+            http.keys_from_multistatus(body),
+            vec![
+                "docs/".to_owned(),
+                "docs/a b.txt".to_owned(),
+                "docs/sub/".to_owned()
+            ]
+        );
+        assert!(Http::new("ftp://example.com").is_err());
+
+        let http = Http::new("https://cloud.example.com/dav/files/jane%20doe").unwrap();
+        let body = r#"<d:multistatus xmlns:d="DAV:">
+                <d:response><d:href>/dav/files/jane%20doe/a.txt</d:href></d:response>
+            </d:multistatus>"#;
+        assert_eq!(http.keys_from_multistatus(body), vec!["a.txt".to_owned()]);
+    }
+}
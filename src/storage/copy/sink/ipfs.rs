@@ -0,0 +1,186 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::ipfs::Ipfs;
+use crate::storage::{DKeyWhere, HttpError, ListKeyObjects, ResultExt as _};
+
+impl Sink for Ipfs {
+    type Error = HttpError;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.exists_inner(&key_with_parser.key().name()).await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "ipfs",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        self.put_bytes_inner(&key_with_parser.key().name(), serialize)
+            .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        _mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.put_bytes_inner(&key.name(), value).await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.delete_inner(&key.name()).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, _lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Err(HttpError::Request {
+            operation: "lock".to_owned(),
+            key: key.name().into_owned(),
+            internal: "content addressed objects are already immutable".to_owned(),
+        })
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let content = self.get_bytes_inner(&key_with_parser.key().name()).await?;
+        Ok(content
+            .map(|value| {
+                key_with_parser
+                    .parser()
+                    .deserialize_guarded(&value)
+                    .context("ipfs", "get_object", &key_with_parser.key().name())
+            })
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.get_bytes_inner(&key.name()).await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.head_inner(&key.name()).await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.get_range_inner(&key.name(), range).await
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.list_objects_inner(prefix).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        self.health_inner().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::sink::ipfs::{is_cid, multipart_body};
+
+    #[test]
+    fn recognize_cids() {
+        assert!(is_cid("QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG"));
+        assert!(is_cid(
+            "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"
+        ));
+        assert!(!is_cid("docs/readme.md"));
+        assert!(!is_cid("QmShort"));
+    }
+
+    #[test]
+    fn multipart_upload() {
+        assert_eq!(
+            String::from_utf8(multipart_body(b"hello")).unwrap(),
+            "--negentropy-ipfs-boundary\r\nContent-Disposition: form-data; name=\"file\"; \
+             filename=\"blob\"\r\nContent-Type: \
+             application/octet-stream\r\n\r\nhello\r\n--negentropy-ipfs-boundary--\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_non_cid_keys_without_calling_the_api() {
+        let ipfs = Ipfs::new("http://127.0.0.1:1").unwrap();
+
+        assert_eq!(
+            ipfs.get_bytes_copy(&"docs/readme.md".to_owned())
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(!ipfs
+            .exists_copy(&DKeyWithParserCopy::new(
+                &"docs/readme.md".to_owned(),
+                &Json
+            ))
+            .await
+            .unwrap());
+        assert!(Ipfs::new("https://ipfs.example.com").is_err());
+    }
+}
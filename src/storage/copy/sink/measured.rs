@@ -0,0 +1,271 @@
+use core::ops::Range;
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::measured::Measured;
+use crate::storage::{DKeyWhere, ListKeyObjects};
+
+impl<STORAGE> Sink for Measured<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let start = Instant::now();
+        let exists = self.storage().exists_copy(key_with_parser).await;
+        self.observe_inner(
+            "exists",
+            key_with_parser.key(),
+            start.elapsed(),
+            exists.is_ok(),
+        );
+        exists
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let start = Instant::now();
+        let put = self
+            .storage_mut()
+            .put_object_copy(key_with_parser, value)
+            .await;
+        self.observe_inner(
+            "put_object",
+            key_with_parser.key(),
+            start.elapsed(),
+            put.is_ok(),
+        );
+        put
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let put = self.storage_mut().put_bytes_copy(key, mime, value).await;
+        self.observe_inner("put_bytes", key, start.elapsed(), put.is_ok());
+        put
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let delete = self.storage_mut().delete_copy(key).await;
+        self.observe_inner("delete", key, start.elapsed(), delete.is_ok());
+        delete
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let locked = self.storage_mut().lock_copy(key, lock).await;
+        self.observe_inner("lock", key, start.elapsed(), locked.is_ok());
+        locked
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let touched = self.storage_mut().touch_copy(key).await;
+        self.observe_inner("touch", key, start.elapsed(), touched.is_ok());
+        touched
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let append = self.storage_mut().append_bytes_copy(key, value).await;
+        self.observe_inner("append_bytes", key, start.elapsed(), append.is_ok());
+        append
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let start = Instant::now();
+        let object = self.storage().get_object_copy(key_with_parser).await;
+        self.observe_inner(
+            "get_object",
+            key_with_parser.key(),
+            start.elapsed(),
+            object.is_ok(),
+        );
+        object
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let bytes = self.storage().get_bytes_copy(key).await;
+        self.observe_inner("get_bytes", key, start.elapsed(), bytes.is_ok());
+        bytes
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let meta = self.storage().head_copy(key).await;
+        self.observe_inner("head", key, start.elapsed(), meta.is_ok());
+        meta
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let bytes = self.storage().get_range_copy(key, range).await;
+        self.observe_inner("get_range", key, start.elapsed(), bytes.is_ok());
+        bytes
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        let start = Instant::now();
+        let objects = self.storage().list_objects_copy(prefix).await;
+        self.observe_inner(
+            "list_objects",
+            &prefix.to_owned(),
+            start.elapsed(),
+            objects.is_ok(),
+        );
+        objects
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("measured").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::sync::{Arc, Mutex, PoisonError};
+
+    use super::*;
+    use crate::storage::metrics::StorageMetrics;
+    use crate::storage::sink::memory::Memory;
+
+    #[derive(Default)]
+    struct Operations(Mutex<Vec<(String, String, String, bool)>>);
+
+    impl StorageMetrics for Operations {
+        fn record_operation(
+            &self,
+            backend: &str,
+            operation: &str,
+            key: &str,
+            _elapsed: Duration,
+            success: bool,
+        ) {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner).push((
+                backend.to_owned(),
+                operation.to_owned(),
+                key.to_owned(),
+                success,
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn record_each_operation() {
+        let operations = Arc::new(Operations::default());
+        let mut measured = Measured::new(
+            "memory",
+            Arc::clone(&operations) as Arc<dyn StorageMetrics>,
+            Memory::default(),
+        );
+        let key = "logs/one".to_owned();
+
+        measured
+            .put_bytes_copy(&key, String::new(), vec![1])
+            .await
+            .unwrap();
+        assert_eq!(measured.get_bytes_copy(&key).await.unwrap(), Some(vec![1]));
+        measured.list_objects_copy("logs/").await.unwrap();
+
+        let recorded = operations
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        assert_eq!(
+            recorded
+                .iter()
+                .map(|&(ref backend, ref operation, ref key, success)| {
+                    (backend.as_str(), operation.as_str(), key.as_str(), success)
+                })
+                .collect::<Vec<_>>(),
+            [
+                ("memory", "put_bytes", "logs/one", true),
+                ("memory", "get_bytes", "logs/one", true),
+                ("memory", "list_objects", "logs/", true),
+            ]
+        );
+    }
+}
@@ -1,9 +1,14 @@
+use core::ops::Range;
+
 use serde::de::DeserializeOwned;
 
 use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::GuardedParser as _;
 use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
-use crate::storage::sink::memory::Memory;
-use crate::storage::{DKeyWhere, ListKeyObjects, MemoryError};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ListPage, ObjectLock, ObjectMeta};
+use crate::storage::sink::memory::{Memory, FLAT_PAGE_SIZE};
+use crate::storage::{DKeyWhere, ListKeyObjects, MemoryError, ResultExt as _};
 
 impl Sink for Memory {
     type Error = MemoryError;
@@ -17,7 +22,7 @@ impl Sink for Memory {
         DKEY: DKeyWhere,
         PARSER: ParserWhere,
     {
-        let exists = self.exists_inner(key_with_parser.key().name().as_str());
+        let exists = self.exists_inner(&key_with_parser.key().name());
         Ok(exists)
     }
 
@@ -32,28 +37,70 @@ impl Sink for Memory {
         DKEY: DKeyWhere,
         PARSER: ParserWhere,
     {
-        self.put_object_inner(key_with_parser.key().name(), value, |value_to_serialize| {
-            let serialize_value = key_with_parser
-                .parser()
-                .serialize_value(value_to_serialize)?;
-            Ok(serialize_value)
-        })
+        self.put_object_inner(
+            &key_with_parser.key().name(),
+            key_with_parser.parser().mime(),
+            value,
+            |value_to_serialize| {
+                let serialize_value = key_with_parser
+                    .parser()
+                    .serialize_guarded(value_to_serialize)
+                    .context("memory", "put_object", &key_with_parser.key().name())?;
+                Ok(serialize_value)
+            },
+        )
     }
 
     #[inline]
     async fn put_bytes_copy<DKEY>(
         &mut self,
         key: &DKEY,
-        _mime: String,
+        mime: String,
         value: Vec<u8>,
     ) -> Result<(), Self::Error>
     where
         DKEY: DKeyWhere,
     {
-        self.put_bytes_inner(key.name(), value);
+        self.put_bytes_inner(&key.name(), mime, value)
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.delete_inner(&key.name())?;
         Ok(())
     }
 
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.lock_inner(&key.name(), lock)
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(self.touch_inner(&key.name()))
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.append_bytes_inner(&key.name(), &value)
+    }
+
     #[inline]
     async fn get_object_copy<RETURN, DKEY, PARSER>(
         &self,
@@ -65,20 +112,77 @@ impl Sink for Memory {
         PARSER: ParserWhere,
     {
         self.get_object_inner(&key_with_parser.key().name(), |content| {
-            let deserialize_value = key_with_parser.parser().deserialize_value(content)?;
+            let deserialize_value = key_with_parser
+                .parser()
+                .deserialize_guarded(content)
+                .context("memory", "get_object", &key_with_parser.key().name())?;
             Ok(deserialize_value)
         })
     }
 
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(self.get_bytes_inner(&key.name()))
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(self.head_inner(&key.name()))
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Ok(self.get_range_inner(&key.name(), &range))
+    }
+
     #[inline]
     async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
         Ok(self.list_objects_inner(prefix))
     }
+
+    #[inline]
+    async fn list_flat_page_copy(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<ListPage, Self::Error> {
+        Ok(self.list_flat_inner(prefix, continuation.as_deref(), FLAT_PAGE_SIZE))
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("memory")
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use core::time::Duration;
+    use std::borrow::Cow;
+    use std::time::SystemTime;
+
+    use futures::StreamExt as _;
+
     use super::*;
+    use crate::storage::copy::listing::list_flat;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::copy::{PutOptions, Required as _};
+    use crate::storage::intern::InternStats;
+    use crate::storage::meta::ListEntry;
+    use crate::storage::{radix_key, ParserError};
     use crate::{DKey, HashSet};
 
     enum TestKey {
@@ -89,13 +193,13 @@ mod tests {
     }
 
     impl DKey for TestKey {
-        fn name(&self) -> String {
-            match *self {
-                Self::One => "one".to_owned(),
-                Self::Long => "long/qux".to_owned(),
-                Self::Long2 => "long/baz".to_owned(),
-                Self::VeryLong => "long/verylong/buz".to_owned(),
-            }
+        fn name(&self) -> Cow<'_, str> {
+            Cow::Borrowed(match *self {
+                Self::One => "one",
+                Self::Long => "long/qux",
+                Self::Long2 => "long/baz",
+                Self::VeryLong => "long/verylong/buz",
+            })
         }
     }
 
@@ -129,6 +233,174 @@ mod tests {
         assert_eq!(memory.get_bytes(&TestKey::One).unwrap(), &vec![42, 0, 9]);
     }
 
+    #[tokio::test]
+    async fn append() {
+        let mut memory = Memory::default();
+        memory
+            .append_bytes_copy(&TestKey::One, vec![1])
+            .await
+            .unwrap();
+        memory
+            .append_bytes_copy(&TestKey::One, vec![2, 3])
+            .await
+            .unwrap();
+
+        assert_eq!(memory.len(), 1);
+        assert_eq!(memory.get_bytes(&TestKey::One).unwrap(), &vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn range() {
+        let mut memory = Memory::default();
+        memory
+            .put_bytes_copy(&TestKey::One, String::new(), vec![0, 1, 2, 3, 4])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            memory.get_range_copy(&TestKey::One, 1..3).await.unwrap(),
+            Some(vec![1, 2])
+        );
+        assert_eq!(
+            memory.get_range_copy(&TestKey::One, 3..10).await.unwrap(),
+            Some(vec![3, 4]),
+            "end must be clamped to the object size"
+        );
+        assert_eq!(
+            memory.get_range_copy(&TestKey::One, 8..10).await.unwrap(),
+            Some(vec![])
+        );
+        assert_eq!(
+            memory.get_range_copy(&TestKey::Long, 0..1).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_is_none() {
+        let memory = Memory::default();
+        let key_with_parser = DKeyWithParserCopy::new(&TestKey::One, &Json);
+
+        assert!(!memory.exists_copy(&key_with_parser).await.unwrap());
+        assert!(memory
+            .get_object_copy::<u8, _, _>(&key_with_parser)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(memory
+            .get_bytes_copy(&TestKey::One)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(memory.head_copy(&TestKey::One).await.unwrap().is_none());
+        assert!(memory.get_lazy_copy(&TestKey::One).await.unwrap().is_none());
+        assert!(memory
+            .get_range_copy(&TestKey::One, 0..1)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            memory
+                .get_bytes_copy(&TestKey::One)
+                .await
+                .required(|| MemoryError::Serde(ParserError::UnknownMime("one".to_owned()))),
+            Err(MemoryError::Serde(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn touch_keeps_content() {
+        let mut memory = Memory::default();
+        memory
+            .put_bytes_copy(&TestKey::One, "text/plain".to_owned(), vec![1, 2])
+            .await
+            .unwrap();
+        let before = memory.head_copy(&TestKey::One).await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        assert!(memory.touch_copy(&TestKey::One).await.unwrap());
+        assert!(!memory.touch_copy(&TestKey::Long).await.unwrap());
+
+        let after = memory.head_copy(&TestKey::One).await.unwrap().unwrap();
+        assert!(after.last_modified > before.last_modified);
+        assert_eq!(after.mime.as_deref(), Some("text/plain"));
+        assert_eq!(memory.get_bytes(&TestKey::One).unwrap(), &vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn get_map_keeps_errors_per_key() {
+        let mut memory = Memory::default();
+        memory
+            .put_object_copy(&DKeyWithParserCopy::new(&TestKey::One, &Json), &1_u8)
+            .await
+            .unwrap();
+        memory
+            .put_bytes_copy(&TestKey::Long, String::new(), b"not json".to_vec())
+            .await
+            .unwrap();
+
+        let objects = memory
+            .get_map_copy::<u8, _, _>(&[TestKey::One, TestKey::Long, TestKey::VeryLong], &Json)
+            .await;
+
+        assert_eq!(objects.len(), 2, "missing objects are left out");
+        assert_eq!(objects["one"].as_ref().ok(), Some(&1));
+        assert!(matches!(objects["long/qux"], Err(MemoryError::Serde(_))));
+    }
+
+    #[tokio::test]
+    async fn parser_errors_name_the_key() {
+        let mut memory = Memory::default();
+        memory
+            .put_bytes_copy(&TestKey::Long, String::new(), b"not json".to_vec())
+            .await
+            .unwrap();
+
+        let Err(MemoryError::Serde(err)) = memory
+            .get_object_copy::<u8, _, _>(&DKeyWithParserCopy::new(&TestKey::Long, &Json))
+            .await
+        else {
+            panic!("corrupted content must not parse");
+        };
+        assert!(matches!(
+            err,
+            ParserError::Context { ref backend, ref operation, ref key, .. }
+                if backend == "memory" && operation == "get_object" && key == "long/qux"
+        ));
+        assert!(matches!(*err.root(), ParserError::Serde { .. }));
+        assert!(err
+            .to_string()
+            .starts_with("memory get_object \"long/qux\": "));
+    }
+
+    #[tokio::test]
+    async fn put_returning_previous() {
+        let mut memory = Memory::default();
+        let key_with_parser = DKeyWithParserCopy::new(&TestKey::One, &Json);
+
+        assert_eq!(
+            memory
+                .put_object_returning_previous_copy::<u8, _, _, _>(&key_with_parser, &1_u8)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            memory
+                .put_object_returning_previous_copy::<u8, _, _, _>(&key_with_parser, &2_u8)
+                .await
+                .unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            memory
+                .get_object_copy::<u8, _, _>(&key_with_parser)
+                .await
+                .unwrap(),
+            Some(2)
+        );
+    }
+
     #[tokio::test]
     async fn list_root() {
         let mut memory = Memory::default();
@@ -210,7 +482,8 @@ mod tests {
 
         assert_eq!(
             memory.list_objects_copy("long").await.unwrap(),
-            vec![].into_iter().collect::<HashSet<_>>()
+            vec!["long/".to_owned()].into_iter().collect::<HashSet<_>>(),
+            "like S3, the delimiter right after the prefix makes a common prefix"
         );
         assert_eq!(
             memory.list_objects_copy("long/").await.unwrap(),
@@ -246,4 +519,179 @@ mod tests {
                 .collect::<HashSet<_>>()
         );
     }
+
+    #[tokio::test]
+    async fn intern_keys() {
+        let mut memory = Memory::default();
+        memory
+            .put_bytes_copy(&TestKey::One, "text/plain".to_owned(), vec![1])
+            .await
+            .unwrap();
+        memory
+            .put_bytes_copy(&TestKey::One, "text/plain".to_owned(), vec![2])
+            .await
+            .unwrap();
+        memory
+            .append_bytes_copy(&TestKey::Long, vec![3])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            memory.intern_stats(),
+            InternStats {
+                keys: 2,
+                bytes: 11,
+                references: 5,
+            },
+            "data, mime and modification maps must share one key allocation"
+        );
+
+        memory.delete_copy(&TestKey::Long).await.unwrap();
+        assert_eq!(memory.intern_stats().keys, 1);
+    }
+
+    #[tokio::test]
+    async fn flat_pages() {
+        let mut memory = Memory::default();
+        for index in 0..=FLAT_PAGE_SIZE {
+            memory
+                .put_bytes_copy(
+                    &format!("flat/{index:04}/value"),
+                    String::new(),
+                    vec![0; index % 3],
+                )
+                .await
+                .unwrap();
+        }
+        memory
+            .put_bytes_copy(&"other".to_owned(), String::new(), vec![])
+            .await
+            .unwrap();
+
+        let first = memory.list_flat_page_copy("flat/", None).await.unwrap();
+        assert_eq!(first.entries.len(), FLAT_PAGE_SIZE);
+        assert_eq!(first.next.as_deref(), Some("flat/0999/value"));
+
+        let entries = list_flat(&memory, "flat/")
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            entries.len(),
+            FLAT_PAGE_SIZE + 1,
+            "must follow the continuation"
+        );
+        assert_eq!(
+            entries.last(),
+            Some(&ListEntry {
+                key: "flat/1000/value".to_owned(),
+                size: 1,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn locked_objects_refuse_writes() {
+        let mut memory = Memory::default();
+        let until = SystemTime::now() + Duration::from_secs(3_600);
+        for (key, lock) in [
+            ("governed", ObjectLock::governance(until)),
+            ("compliant", ObjectLock::compliance(until)),
+        ] {
+            memory
+                .put_bytes_with_copy(
+                    &key.to_owned(),
+                    String::new(),
+                    vec![1],
+                    PutOptions::locked(lock),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert!(matches!(
+            memory.delete_copy(&"governed".to_owned()).await,
+            Err(MemoryError::Locked { .. })
+        ));
+        assert!(
+            matches!(
+                memory
+                    .lock_copy(
+                        &"compliant".to_owned(),
+                        ObjectLock::compliance(SystemTime::now())
+                    )
+                    .await,
+                Err(MemoryError::Locked { .. })
+            ),
+            "compliance retention cannot be shortened"
+        );
+        assert!(!memory.release_governance("compliant"));
+        assert!(memory.delete_copy(&"compliant".to_owned()).await.is_err());
+        assert!(matches!(
+            memory
+                .put_bytes_copy(&"compliant".to_owned(), String::new(), vec![2])
+                .await,
+            Err(MemoryError::Locked { .. })
+        ));
+        assert!(matches!(
+            memory
+                .append_bytes_copy(&"compliant".to_owned(), vec![2])
+                .await,
+            Err(MemoryError::Locked { .. })
+        ));
+        assert_eq!(memory.get_bytes_inner("compliant"), Some(vec![1]));
+        assert!(matches!(
+            memory
+                .lock_copy(&"missing".to_owned(), ObjectLock::governance(until))
+                .await,
+            Err(MemoryError::NotExists { .. })
+        ));
+
+        assert!(memory.release_governance("governed"));
+        memory.delete_copy(&"governed".to_owned()).await.unwrap();
+        assert!(!memory.exists_inner("governed"));
+
+        memory
+            .put_bytes_copy(&"expired".to_owned(), String::new(), vec![1])
+            .await
+            .unwrap();
+        memory
+            .lock_copy(
+                &"expired".to_owned(),
+                ObjectLock::compliance(SystemTime::UNIX_EPOCH),
+            )
+            .await
+            .unwrap();
+        memory.delete_copy(&"expired".to_owned()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn list_unicode_keys() {
+        let mut memory = Memory::default();
+        for key in ["\u{e9}t\u{e9}/a", "\u{e9}t\u{e9}/\u{1f600}/b", "\u{e9}"] {
+            memory
+                .put_bytes_copy(&key.to_owned(), String::new(), vec![])
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            memory.list_objects_copy("\u{e9}t\u{e9}/").await.unwrap(),
+            HashSet::from_iter([
+                "\u{e9}t\u{e9}/a".to_owned(),
+                "\u{e9}t\u{e9}/\u{1f600}/".to_owned()
+            ])
+        );
+        assert_eq!(
+            memory.list_objects_copy("\u{e9}").await.unwrap(),
+            HashSet::from_iter(["\u{e9}".to_owned(), "\u{e9}t\u{e9}/".to_owned()])
+        );
+        assert_eq!(
+            radix_key("\u{e9}", "a\u{e9}"),
+            None,
+            "a prefix ending inside a character of the key must not panic"
+        );
+        assert_eq!(radix_key("long/", "long"), None);
+        assert_eq!(radix_key("a/", "a//b"), Some("a//".to_owned()));
+    }
 }
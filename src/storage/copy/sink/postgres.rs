@@ -0,0 +1,193 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ListPage, ObjectLock, ObjectMeta};
+use crate::storage::sink::postgres::Postgres;
+use crate::storage::{DKeyWhere, ListKeyObjects, PostgresError, ResultExt as _};
+
+impl Sink for Postgres {
+    type Error = PostgresError;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.exists_inner(&key_with_parser.key().name()).await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "postgres",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        self.put_bytes_inner(
+            &key_with_parser.key().name(),
+            key_with_parser.parser().mime(),
+            serialize,
+        )
+        .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.put_bytes_inner(&key.name(), mime, value).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, _lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        Err(PostgresError::Unsupported {
+            operation: "lock".to_owned(),
+            key: key.name().into_owned(),
+        })
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.delete_inner(&key.name()).await
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.append_bytes_inner(&key.name(), value).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.touch_inner(&key.name()).await
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let content = self.get_bytes_inner(&key_with_parser.key().name()).await?;
+        Ok(content
+            .map(|value| {
+                key_with_parser
+                    .parser()
+                    .deserialize_guarded(&value)
+                    .context("postgres", "get_object", &key_with_parser.key().name())
+            })
+            .transpose()?)
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.get_bytes_inner(&key.name()).await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.head_inner(&key.name()).await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.get_range_inner(&key.name(), range).await
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.list_objects_inner(prefix).await
+    }
+
+    #[inline]
+    async fn list_flat_page_copy(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<ListPage, Self::Error> {
+        self.list_flat_inner(prefix, continuation).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        self.health_inner().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::sink::postgres::{is_table, like_prefix};
+
+    #[test]
+    fn plain_table_names() {
+        assert!(is_table("objects"));
+        assert!(is_table("storage.objects_v2"));
+        assert!(!is_table("objects; DROP TABLE users"));
+        assert!(!is_table("a.b.c"));
+        assert!(!is_table("2objects"));
+        assert!(!is_table(""));
+    }
+
+    #[test]
+    fn escape_like_wildcards() {
+        assert_eq!(like_prefix("logs/"), "logs/%");
+        assert_eq!(like_prefix("100%_done\\"), "100\\%\\_done\\\\%");
+        assert_eq!(like_prefix(""), "%");
+    }
+}
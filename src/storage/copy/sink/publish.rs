@@ -0,0 +1,335 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::publish::{ChangeOperation, Publisher, Publishing};
+use crate::storage::{DKeyWhere, ListKeyObjects, ParserError, ResultExt as _};
+
+impl<PUBLISHER, STORAGE> Sink for Publishing<PUBLISHER, STORAGE>
+where
+    PUBLISHER: Publisher,
+    STORAGE: Sink + Send + Sync,
+    <STORAGE as Sink>::Error: From<ParserError>,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.storage().exists_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "publishing",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        self.put_bytes_copy(
+            key_with_parser.key(),
+            key_with_parser.parser().mime(),
+            serialize,
+        )
+        .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let meta = self
+            .storage_mut()
+            .put_bytes_meta_copy(key, mime, value)
+            .await?;
+        self.publisher()
+            .publish(Self::event_inner(
+                key.name().into_owned(),
+                ChangeOperation::Put,
+                meta,
+            ))
+            .await;
+        Ok(())
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().delete_copy(key).await?;
+        self.publisher()
+            .publish(Self::event_inner(
+                key.name().into_owned(),
+                ChangeOperation::Delete,
+                ObjectMeta::default(),
+            ))
+            .await;
+        Ok(())
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().lock_copy(key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let touched = self.storage_mut().touch_copy(key).await?;
+        if touched {
+            let meta = self.storage().head_copy(key).await?.unwrap_or_default();
+            self.publisher()
+                .publish(Self::event_inner(
+                    key.name().into_owned(),
+                    ChangeOperation::Touch,
+                    meta,
+                ))
+                .await;
+        }
+        Ok(touched)
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let meta = self
+            .storage_mut()
+            .append_bytes_meta_copy(key, value)
+            .await?;
+        self.publisher()
+            .publish(Self::event_inner(
+                key.name().into_owned(),
+                ChangeOperation::Append,
+                meta,
+            ))
+            .await;
+        Ok(())
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.storage().get_object_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage().get_bytes_copy(key).await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage().head_copy(key).await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage().get_range_copy(key, range).await
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.storage().list_objects_copy(prefix).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("publishing").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::mpsc;
+    use futures::StreamExt as _;
+
+    use super::*;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::meta::checksum;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::sink::publish::ChangeEvent;
+
+    #[tokio::test]
+    async fn publish_after_mutations() {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut publishing = Publishing::new(sender, Memory::default());
+        let key = "events/one".to_owned();
+
+        publishing
+            .put_bytes_copy(&key, String::new(), vec![1, 2])
+            .await
+            .unwrap();
+        publishing.append_bytes_copy(&key, vec![3]).await.unwrap();
+        publishing
+            .put_object_copy(&DKeyWithParserCopy::new(&key, &Json), &42)
+            .await
+            .unwrap();
+        drop(publishing);
+
+        assert_eq!(
+            receiver.collect::<Vec<_>>().await,
+            vec![
+                ChangeEvent {
+                    key: key.clone(),
+                    operation: ChangeOperation::Put,
+                    etag: None,
+                    checksum: Some(checksum(&[1, 2])),
+                    size: 2,
+                },
+                ChangeEvent {
+                    key: key.clone(),
+                    operation: ChangeOperation::Append,
+                    etag: None,
+                    checksum: Some(checksum(&[1, 2, 3])),
+                    size: 3,
+                },
+                ChangeEvent {
+                    key,
+                    operation: ChangeOperation::Put,
+                    etag: None,
+                    checksum: Some(checksum(b"42")),
+                    size: 2,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn publish_touches() {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut publishing = Publishing::new(sender, Memory::default());
+        let key = "events/one".to_owned();
+
+        assert!(!publishing.touch_copy(&key).await.unwrap());
+        publishing
+            .put_bytes_copy(&key, String::new(), vec![1, 2])
+            .await
+            .unwrap();
+        assert!(publishing.touch_copy(&key).await.unwrap());
+        drop(publishing);
+
+        let events = receiver.collect::<Vec<_>>().await;
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| event.operation)
+                .collect::<Vec<_>>(),
+            [ChangeOperation::Put, ChangeOperation::Touch]
+        );
+        assert_eq!(events[1].size, 2);
+        assert_eq!(events[1].checksum, Some(checksum(&[1, 2])));
+    }
+
+    #[cfg(feature = "kafka")]
+    #[test]
+    fn kafka_record_of_event() {
+        use rskafka::chrono::DateTime;
+
+        use crate::storage::sink::publish::kafka::event_record;
+
+        let event = ChangeEvent {
+            key: "events/one".to_owned(),
+            operation: ChangeOperation::Touch,
+            etag: Some("etag".to_owned()),
+            checksum: None,
+            size: 2,
+        };
+        let record = event_record(&event, DateTime::UNIX_EPOCH).unwrap();
+        assert_eq!(record.key.as_deref(), Some(b"events/one".as_slice()));
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&record.value.unwrap()).unwrap(),
+            serde_json::json!({
+                "key": "events/one",
+                "operation": "touch",
+                "etag": "etag",
+                "checksum": null,
+                "size": 2,
+            })
+        );
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn publish_without_heading() {
+        use crate::storage::sink::recording::RecordingSink;
+
+        let (sender, receiver) = mpsc::unbounded();
+        let mut publishing = Publishing::new(sender, RecordingSink::new(Memory::default()));
+        publishing
+            .put_object_copy(&DKeyWithParserCopy::new(&"one".to_owned(), &Json), &42)
+            .await
+            .unwrap();
+        publishing
+            .put_bytes_copy(&"two".to_owned(), String::new(), vec![1])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            publishing.storage().operations(),
+            ["put_bytes", "put_bytes"]
+        );
+        drop(receiver);
+        publishing
+            .put_bytes_copy(&"three".to_owned(), String::new(), vec![1])
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,309 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::{ParserWhere, PutOptions, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ListPage, ObjectLock, ObjectMeta};
+use crate::storage::sink::recording::{Call, RecordingSink};
+use crate::storage::{DKeyWhere, ListKeyObjects};
+
+impl<STORAGE> Sink for RecordingSink<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.record_inner(Call::Exists {
+            key: key_with_parser.key().name().into_owned(),
+        });
+        self.storage().exists_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn put_object_if_not_exists_copy<DKEY, PARSER, VALUE>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+        VALUE: ValueWhere,
+    {
+        self.record_inner(Call::PutObjectIfNotExists {
+            key: key_with_parser.key().name().into_owned(),
+            mime: key_with_parser.parser().mime(),
+        });
+        self.storage_mut()
+            .put_object_if_not_exists_copy(key_with_parser, value)
+            .await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.record_inner(Call::PutObject {
+            key: key_with_parser.key().name().into_owned(),
+            mime: key_with_parser.parser().mime(),
+        });
+        self.storage_mut()
+            .put_object_copy(key_with_parser, value)
+            .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.record_inner(Call::PutBytes {
+            key: key.name().into_owned(),
+            mime: mime.clone(),
+            size: value.len(),
+        });
+        self.storage_mut().put_bytes_copy(key, mime, value).await
+    }
+
+    #[inline]
+    async fn put_bytes_with_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+        options: PutOptions,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.record_inner(Call::PutBytesWith {
+            key: key.name().into_owned(),
+            mime: mime.clone(),
+            size: value.len(),
+            lock: options.lock,
+        });
+        self.storage_mut()
+            .put_bytes_with_copy(key, mime, value, options)
+            .await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.record_inner(Call::Delete {
+            key: key.name().into_owned(),
+        });
+        self.storage_mut().delete_copy(key).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.record_inner(Call::Lock {
+            key: key.name().into_owned(),
+            lock,
+        });
+        self.storage_mut().lock_copy(key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.record_inner(Call::Touch {
+            key: key.name().into_owned(),
+        });
+        self.storage_mut().touch_copy(key).await
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.record_inner(Call::AppendBytes {
+            key: key.name().into_owned(),
+            size: value.len(),
+        });
+        self.storage_mut().append_bytes_copy(key, value).await
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.record_inner(Call::GetObject {
+            key: key_with_parser.key().name().into_owned(),
+        });
+        self.storage().get_object_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.record_inner(Call::GetBytes {
+            key: key.name().into_owned(),
+        });
+        self.storage().get_bytes_copy(key).await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.record_inner(Call::Head {
+            key: key.name().into_owned(),
+        });
+        self.storage().head_copy(key).await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.record_inner(Call::GetRange {
+            key: key.name().into_owned(),
+            range: range.clone(),
+        });
+        self.storage().get_range_copy(key, range).await
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.record_inner(Call::ListObjects {
+            prefix: prefix.to_owned(),
+        });
+        self.storage().list_objects_copy(prefix).await
+    }
+
+    #[inline]
+    async fn list_flat_page_copy(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<ListPage, Self::Error> {
+        self.record_inner(Call::ListFlatPage {
+            prefix: prefix.to_owned(),
+            continuation: continuation.clone(),
+        });
+        self.storage()
+            .list_flat_page_copy(prefix, continuation)
+            .await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("recording").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::storage::cache::lru::Lru;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::copy::Cache as _;
+    use crate::storage::layer::Layer as _;
+    use crate::storage::sink::memory::Memory;
+
+    #[tokio::test]
+    async fn record_calls_in_order() {
+        let mut recording = RecordingSink::new(Memory::default());
+        let key = "one".to_owned();
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+
+        assert!(recording
+            .put_object_if_not_exists_copy(&key_with_parser, &1_u8)
+            .await
+            .unwrap());
+        recording.get_bytes_copy(&key).await.unwrap();
+
+        assert_eq!(
+            recording.calls(),
+            [
+                Call::PutObjectIfNotExists {
+                    key: key.clone(),
+                    mime: Json.mime(),
+                },
+                Call::GetBytes { key: key.clone() },
+            ]
+        );
+        assert_eq!(recording.take().len(), 2);
+        assert!(recording.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn observe_cache_coherency() {
+        let mut lru = Lru::new(
+            NonZeroUsize::new(4).unwrap(),
+            RecordingSink::new(Memory::default()),
+        );
+        let key = "one".to_owned();
+        let key_with_parser = DKeyWithParserCopy::new(&key, &Json);
+
+        lru.put_object_if_not_exists_copy(&key_with_parser, &1_u8)
+            .await
+            .unwrap();
+        lru.get_object_copy::<u8, _, _>(&key_with_parser)
+            .await
+            .unwrap();
+
+        let recording = lru.into_inner();
+        assert!(recording.called_before("exists", "put_bytes"));
+        assert!(!recording.called_before("put_bytes", "exists"));
+        assert_eq!(
+            recording.count("get_object"),
+            0,
+            "the put must warm the cache"
+        );
+        assert!(recording.calls().iter().all(|call| call.key() == "one"));
+    }
+}
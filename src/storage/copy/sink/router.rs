@@ -0,0 +1,235 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::router::Router;
+use crate::storage::{DKeyWhere, ListKeyObjects};
+
+impl<SINK> Sink for Router<SINK>
+where
+    SINK: Sink + Send + Sync,
+{
+    type Error = <SINK as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.route_inner(&key_with_parser.key().name())
+            .exists_copy(key_with_parser)
+            .await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.route_mut_inner(&key_with_parser.key().name())
+            .put_object_copy(key_with_parser, value)
+            .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.route_mut_inner(&key.name())
+            .put_bytes_copy(key, mime, value)
+            .await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.route_mut_inner(&key.name()).delete_copy(key).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.route_mut_inner(&key.name()).lock_copy(key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.route_mut_inner(&key.name()).touch_copy(key).await
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.route_mut_inner(&key.name())
+            .append_bytes_copy(key, value)
+            .await
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.route_inner(&key_with_parser.key().name())
+            .get_object_copy(key_with_parser)
+            .await
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.route_inner(&key.name()).get_bytes_copy(key).await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.route_inner(&key.name()).head_copy(key).await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.route_inner(&key.name())
+            .get_range_copy(key, range)
+            .await
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        let mut objects = self.route_inner(prefix).list_objects_copy(prefix).await?;
+
+        for sink in self.nested_inner(prefix) {
+            objects.extend(sink.list_objects_copy(prefix).await?);
+        }
+
+        Ok(objects)
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        let mut report = HealthReport::new("router");
+        for sink in self.sinks_inner() {
+            report = report.with_inner(sink.health_copy().await);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sink::memory::Memory;
+    use crate::HashSet;
+
+    fn router() -> Router<Memory> {
+        Router::new(Memory::default())
+            .with_route("cache/", Memory::default())
+            .with_route("cache/hot/", Memory::default())
+    }
+
+    #[tokio::test]
+    async fn dispatch_by_longest_prefix() {
+        let mut router = router();
+        for key in ["cache/a", "cache/hot/b", "other"] {
+            router
+                .put_bytes_copy(&key.to_owned(), String::new(), vec![1])
+                .await
+                .unwrap();
+        }
+
+        assert!(router.route_inner("other").exists_inner("other"));
+        assert!(!router.route_inner("other").exists_inner("cache/a"));
+        assert!(router.route_inner("cache/a").exists_inner("cache/a"));
+        assert!(router
+            .route_inner("cache/hot/b")
+            .exists_inner("cache/hot/b"));
+        assert!(!router.route_inner("cache/a").exists_inner("cache/hot/b"));
+        assert_eq!(
+            router
+                .get_bytes_copy(&"cache/hot/b".to_owned())
+                .await
+                .unwrap(),
+            Some(vec![1])
+        );
+    }
+
+    #[tokio::test]
+    async fn list_merges_nested_routes() {
+        let mut router = router();
+        for key in ["cache/a", "cache/hot/b", "other"] {
+            router
+                .put_bytes_copy(&key.to_owned(), String::new(), vec![])
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            router.list_objects_copy("").await.unwrap(),
+            vec!["cache/".to_owned(), "other".to_owned()]
+                .into_iter()
+                .collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            router.list_objects_copy("cache/").await.unwrap(),
+            vec!["cache/a".to_owned(), "cache/hot/".to_owned()]
+                .into_iter()
+                .collect::<HashSet<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn health_covers_every_sink() {
+        let health = router().health_copy().await;
+        assert_eq!(health.inner.len(), 3);
+        assert!(health.is_healthy());
+    }
+}
@@ -1,9 +1,14 @@
+use core::ops::Range;
+
 use serde::de::DeserializeOwned;
 
 use crate::storage::copy::direct::DKeyWithParserCopy;
-use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::{ParserWhere, PutOptions, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ListPage, ObjectLock, ObjectMeta};
 use crate::storage::sink::s3::S3;
-use crate::storage::{DKeyWhere, ListKeyObjects, S3Error};
+use crate::storage::{DKeyWhere, ListKeyObjects, ResultExt as _, S3Error};
 
 impl Sink for S3 {
     type Error = S3Error;
@@ -17,7 +22,8 @@ impl Sink for S3 {
         DKEY: DKeyWhere,
         PARSER: ParserWhere,
     {
-        self.exists_inner(key_with_parser.key().name()).await
+        self.exists_inner(key_with_parser.key().name().into_owned())
+            .await
     }
 
     #[inline]
@@ -32,13 +38,14 @@ impl Sink for S3 {
         PARSER: ParserWhere,
     {
         self.put_object_inner(
-            key_with_parser.key().name(),
+            key_with_parser.key().name().into_owned(),
             key_with_parser.parser().mime(),
             value,
             |value_to_serialize| {
                 Ok(key_with_parser
                     .parser()
-                    .serialize_value(value_to_serialize)?)
+                    .serialize_guarded(value_to_serialize)
+                    .context("s3", "put_object", &key_with_parser.key().name())?)
             },
         )
         .await
@@ -54,7 +61,88 @@ impl Sink for S3 {
     where
         DKEY: DKeyWhere,
     {
-        self.put_bytes_inner(key.name(), mime, value).await
+        self.put_bytes_inner(key.name().into_owned(), mime, value)
+            .await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.delete_inner(key.name().into_owned()).await
+    }
+
+    #[inline]
+    async fn put_bytes_with_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+        options: PutOptions,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.put_bytes_locked_inner(key.name().into_owned(), mime, value, options.lock)
+            .await?;
+        Ok(())
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.lock_inner(key.name().into_owned(), lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.touch_inner(key.name().into_owned()).await
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.append_bytes_inner(key.name().into_owned(), value)
+            .await
+    }
+
+    #[inline]
+    async fn put_bytes_meta_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<ObjectMeta, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.put_bytes_meta_inner(key.name().into_owned(), mime, value)
+            .await
+    }
+
+    #[inline]
+    async fn append_bytes_meta_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<ObjectMeta, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.append_bytes_meta_inner(key.name().into_owned(), value)
+            .await
     }
 
     #[inline]
@@ -67,14 +155,203 @@ impl Sink for S3 {
         DKEY: DKeyWhere,
         PARSER: ParserWhere,
     {
-        self.get_object_inner(key_with_parser.key().name(), |content| {
-            Ok(key_with_parser.parser().deserialize_value(content)?)
+        self.get_object_inner(key_with_parser.key().name().into_owned(), |content| {
+            Ok(key_with_parser
+                .parser()
+                .deserialize_guarded(content)
+                .context("s3", "get_object", &key_with_parser.key().name())?)
         })
         .await
     }
 
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.get_bytes_inner(key.name().into_owned()).await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.head_inner(key.name().into_owned()).await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.get_range_inner(key.name().into_owned(), &range).await
+    }
+
     #[inline]
     async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
         self.list_objects_inner(prefix).await
     }
+
+    #[inline]
+    async fn list_flat_page_copy(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<ListPage, Self::Error> {
+        self.list_flat_inner(prefix, continuation).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        self.health_inner().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Output;
+    use aws_sdk_s3::types::{CommonPrefix, Object};
+
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::sink::s3::{collect_list_pages, handle_list_objects};
+    use crate::storage::S3Error;
+
+    const ALPHABET: [&str; 4] = ["a", "b", "\u{e9}", "/"];
+
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            usize::try_from(self.0 % bound as u64).unwrap()
+        }
+
+        fn key(&mut self) -> String {
+            (0..=self.next(6))
+                .map(|_| ALPHABET[self.next(ALPHABET.len())])
+                .collect()
+        }
+    }
+
+    /// Delimiter listing as documented for `ListObjectsV2`, kept naive on purpose.
+    fn bucket_listing(keys: &BTreeSet<String>, prefix: &str) -> ListObjectsV2Output {
+        let mut contents = vec![];
+        let mut common_prefixes = BTreeSet::new();
+
+        for key in keys.iter().filter(|key| key.starts_with(prefix)) {
+            let rest = key.get(prefix.len()..).unwrap();
+            match rest.find('/') {
+                Some(index) => {
+                    common_prefixes.insert(format!("{prefix}{}", &rest[..=index]));
+                }
+                None => contents.push(Object::builder().key(key).build()),
+            }
+        }
+
+        ListObjectsV2Output::builder()
+            .set_contents(Some(contents))
+            .set_common_prefixes(Some(
+                common_prefixes
+                    .into_iter()
+                    .map(|common| CommonPrefix::builder().prefix(common).build())
+                    .collect(),
+            ))
+            .build()
+    }
+
+    #[test]
+    fn memory_matches_s3_delimiter_semantics() {
+        let mut random = XorShift(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..500 {
+            let keys = (0..random.next(12))
+                .map(|_| random.key())
+                .collect::<BTreeSet<_>>();
+            let mut memory = Memory::default();
+            for key in &keys {
+                memory.put_bytes_inner(key, String::new(), vec![]).unwrap();
+            }
+
+            let candidate = random.key();
+            let cut = candidate
+                .char_indices()
+                .map(|(index, _)| index)
+                .nth(random.next(candidate.chars().count()))
+                .unwrap_or_default();
+            for prefix in ["", &candidate[..cut], &candidate] {
+                assert_eq!(
+                    memory.list_objects_inner(prefix),
+                    handle_list_objects(prefix, bucket_listing(&keys, prefix)),
+                    "keys {keys:?} listed under {prefix:?}"
+                );
+            }
+        }
+    }
+
+    /// Splits a listing in pages of 1000 entries, the token is the index of the next entry.
+    fn page(list: ListObjectsV2Output, continuation: Option<&str>) -> ListObjectsV2Output {
+        let entries = list
+            .contents()
+            .iter()
+            .filter_map(|object| object.key())
+            .map(|key| (key.to_owned(), true))
+            .chain(
+                list.common_prefixes()
+                    .iter()
+                    .filter_map(|common| common.prefix())
+                    .map(|common| (common.to_owned(), false)),
+            )
+            .collect::<Vec<_>>();
+        let start = continuation.map_or(0, |token| token.parse().unwrap());
+        let end = entries.len().min(start + 1000);
+        let (contents, common_prefixes): (Vec<_>, Vec<_>) =
+            entries[start..end].iter().partition(|&&(_, object)| object);
+
+        ListObjectsV2Output::builder()
+            .set_contents(Some(
+                contents
+                    .into_iter()
+                    .map(|(key, _)| Object::builder().key(key).build())
+                    .collect(),
+            ))
+            .set_common_prefixes(Some(
+                common_prefixes
+                    .into_iter()
+                    .map(|(common, _)| CommonPrefix::builder().prefix(common).build())
+                    .collect(),
+            ))
+            .set_next_continuation_token((end < entries.len()).then(|| end.to_string()))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn merge_list_pages() {
+        let keys = (0..1500)
+            .map(|index| format!("logs/{index:04}"))
+            .chain((0..600).map(|index| format!("logs/dir{index:04}/key")))
+            .collect::<BTreeSet<_>>();
+        let mut calls = 0;
+
+        let listed = collect_list_pages("logs/", |continuation| {
+            calls += 1;
+            let list = page(bucket_listing(&keys, "logs/"), continuation.as_deref());
+            async move { Ok::<_, S3Error>(list) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(calls, 3);
+        assert_eq!(listed.len(), 2100);
+        assert!(listed.contains("logs/1499"));
+        assert!(listed.contains("logs/dir0599/"));
+    }
 }
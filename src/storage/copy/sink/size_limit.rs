@@ -0,0 +1,229 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::parser::GuardedParser as _;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::size_limit::{OversizePolicy, SizeLimit};
+use crate::storage::{DKeyWhere, LayerError, ListKeyObjects, ParserError, ResultExt as _};
+
+impl<STORAGE> Sink for SizeLimit<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+    <STORAGE as Sink>::Error: From<LayerError> + From<ParserError>,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.storage().exists_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let serialize = key_with_parser.parser().serialize_guarded(value).context(
+            "size_limit",
+            "put_object",
+            &key_with_parser.key().name(),
+        )?;
+        self.put_bytes_copy(
+            key_with_parser.key(),
+            key_with_parser.parser().mime(),
+            serialize,
+        )
+        .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.check_inner(&key.name(), value.len() as u64)?;
+        self.storage_mut().put_bytes_copy(key, mime, value).await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().delete_copy(key).await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().lock_copy(key, lock).await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage_mut().touch_copy(key).await
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        if self.policy() == OversizePolicy::Reject {
+            let current = self
+                .storage()
+                .head_copy(key)
+                .await?
+                .map(|meta| meta.size)
+                .unwrap_or_default();
+            self.check_inner(&key.name(), current + value.len() as u64)?;
+        }
+        self.storage_mut().append_bytes_copy(key, value).await
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        self.storage().get_object_copy(key_with_parser).await
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage().get_bytes_copy(key).await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage().head_copy(key).await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage().get_range_copy(key, range).await
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        self.storage().list_objects_copy(prefix).await
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("size_limit").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::storage::copy::parser::Json;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    fn is_too_large(result: Result<(), MemoryError>) -> bool {
+        matches!(
+            result,
+            Err(MemoryError::Layer(LayerError::ObjectTooLarge {
+                limit: 8,
+                ..
+            }))
+        )
+    }
+
+    #[tokio::test]
+    async fn reject_oversized_puts() {
+        let mut limited = SizeLimit::new(NonZeroUsize::new(8).unwrap(), Memory::default());
+        let key = "doc".to_owned();
+
+        limited
+            .put_bytes_copy(&key, String::new(), vec![0; 8])
+            .await
+            .unwrap();
+        assert!(is_too_large(
+            limited
+                .put_bytes_copy(&key, String::new(), vec![0; 9])
+                .await
+        ));
+        assert!(is_too_large(limited.append_bytes_copy(&key, vec![0]).await));
+        assert!(is_too_large(
+            limited
+                .put_object_copy(&DKeyWithParserCopy::new(&key, &Json), &"a long json string")
+                .await
+        ));
+        assert_eq!(limited.storage().get_bytes_inner("doc"), Some(vec![0; 8]));
+    }
+
+    #[tokio::test]
+    async fn route_oversized_puts_to_chunks() {
+        let mut limited = SizeLimit::chunking(NonZeroUsize::new(8).unwrap(), Memory::default());
+        let key = "big".to_owned();
+
+        limited
+            .put_bytes_copy(&key, String::new(), (0..20).collect())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            limited.storage().storage().len(),
+            4,
+            "three chunks and a manifest"
+        );
+        assert_eq!(
+            limited.get_bytes_copy(&key).await.unwrap(),
+            Some((0..20).collect())
+        );
+    }
+}
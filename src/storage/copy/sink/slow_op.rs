@@ -0,0 +1,226 @@
+use core::ops::Range;
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ObjectLock, ObjectMeta};
+use crate::storage::sink::slow_op::SlowOpLogger;
+use crate::storage::{DKeyWhere, ListKeyObjects};
+
+impl<STORAGE> Sink for SlowOpLogger<STORAGE>
+where
+    STORAGE: Sink + Send + Sync,
+{
+    type Error = <STORAGE as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let start = Instant::now();
+        let exists = self.storage().exists_copy(key_with_parser).await;
+        self.observe_inner("exists", key_with_parser.key(), None, start.elapsed());
+        exists
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let start = Instant::now();
+        let put = self
+            .storage_mut()
+            .put_object_copy(key_with_parser, value)
+            .await;
+        self.observe_inner("put_object", key_with_parser.key(), None, start.elapsed());
+        put
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let size = value.len();
+        let start = Instant::now();
+        let put = self.storage_mut().put_bytes_copy(key, mime, value).await;
+        self.observe_inner("put_bytes", key, Some(size), start.elapsed());
+        put
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let delete = self.storage_mut().delete_copy(key).await;
+        self.observe_inner("delete", key, None, start.elapsed());
+        delete
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let locked = self.storage_mut().lock_copy(key, lock).await;
+        self.observe_inner("lock", key, None, start.elapsed());
+        locked
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let touched = self.storage_mut().touch_copy(key).await;
+        self.observe_inner("touch", key, None, start.elapsed());
+        touched
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let size = value.len();
+        let start = Instant::now();
+        let append = self.storage_mut().append_bytes_copy(key, value).await;
+        self.observe_inner("append_bytes", key, Some(size), start.elapsed());
+        append
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let start = Instant::now();
+        let object = self.storage().get_object_copy(key_with_parser).await;
+        self.observe_inner("get_object", key_with_parser.key(), None, start.elapsed());
+        object
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let bytes = self.storage().get_bytes_copy(key).await;
+        let size = bytes
+            .as_ref()
+            .ok()
+            .and_then(|value| value.as_ref().map(Vec::len));
+        self.observe_inner("get_bytes", key, size, start.elapsed());
+        bytes
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let meta = self.storage().head_copy(key).await;
+        self.observe_inner("head", key, None, start.elapsed());
+        meta
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let start = Instant::now();
+        let bytes = self.storage().get_range_copy(key, range).await;
+        let size = bytes
+            .as_ref()
+            .ok()
+            .and_then(|value| value.as_ref().map(Vec::len));
+        self.observe_inner("get_range", key, size, start.elapsed());
+        bytes
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        let start = Instant::now();
+        let objects = self.storage().list_objects_copy(prefix).await;
+        let size = objects.as_ref().ok().map(ListKeyObjects::len);
+        self.observe_inner("list_objects", &prefix.to_owned(), size, start.elapsed());
+        objects
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("slow_op_logger").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::storage::sink::memory::Memory;
+
+    #[tokio::test]
+    async fn pass_through() {
+        let mut logger = SlowOpLogger::new(Duration::from_secs(60), Memory::default());
+        logger
+            .put_bytes_copy(&"one".to_owned(), String::new(), vec![1])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            logger.get_bytes_copy(&"one".to_owned()).await.unwrap(),
+            Some(vec![1])
+        );
+        assert!(logger.storage().exists_inner("one"));
+    }
+
+    #[test]
+    fn threshold() {
+        let logger = SlowOpLogger::new(Duration::from_millis(10), Memory::default());
+        let key = "one".to_owned();
+
+        assert!(!logger.observe_inner("get_bytes", &key, None, Duration::from_millis(5)));
+        assert!(logger.observe_inner("get_bytes", &key, Some(1), Duration::from_millis(15)));
+    }
+}
@@ -0,0 +1,286 @@
+use core::ops::Range;
+
+use serde::de::DeserializeOwned;
+
+use crate::storage::copy::direct::DKeyWithParserCopy;
+use crate::storage::copy::{ParserWhere, Sink, ValueWhere};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ListPage, ObjectLock, ObjectMeta};
+use crate::storage::sink::tenant::Tenant;
+use crate::storage::{DKeyWhere, ListKeyObjects, PrefixedKey};
+
+impl<SINK> Sink for Tenant<SINK>
+where
+    SINK: Sink + Send + Sync,
+{
+    type Error = <SINK as Sink>::Error;
+
+    #[inline]
+    async fn exists_copy<DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let key = PrefixedKey::new(self.prefix(), key_with_parser.key());
+        self.storage()
+            .exists_copy(&DKeyWithParserCopy::new(&key, key_with_parser.parser()))
+            .await
+    }
+
+    #[inline]
+    async fn put_object_copy<VALUE, DKEY, PARSER>(
+        &mut self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+        value: &VALUE,
+    ) -> Result<(), Self::Error>
+    where
+        VALUE: ValueWhere,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let prefix = self.prefix().to_owned();
+        let key = PrefixedKey::new(&prefix, key_with_parser.key());
+        self.storage_mut()
+            .put_object_copy(
+                &DKeyWithParserCopy::new(&key, key_with_parser.parser()),
+                value,
+            )
+            .await
+    }
+
+    #[inline]
+    async fn put_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let prefix = self.prefix().to_owned();
+        self.storage_mut()
+            .put_bytes_copy(&PrefixedKey::new(&prefix, key), mime, value)
+            .await
+    }
+
+    #[inline]
+    async fn delete_copy<DKEY>(&mut self, key: &DKEY) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let prefix = self.prefix().to_owned();
+        self.storage_mut()
+            .delete_copy(&PrefixedKey::new(&prefix, key))
+            .await
+    }
+
+    #[inline]
+    async fn lock_copy<DKEY>(&mut self, key: &DKEY, lock: ObjectLock) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let prefix = self.prefix().to_owned();
+        self.storage_mut()
+            .lock_copy(&PrefixedKey::new(&prefix, key), lock)
+            .await
+    }
+
+    #[inline]
+    async fn touch_copy<DKEY>(&mut self, key: &DKEY) -> Result<bool, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let prefix = self.prefix().to_owned();
+        self.storage_mut()
+            .touch_copy(&PrefixedKey::new(&prefix, key))
+            .await
+    }
+
+    #[inline]
+    async fn append_bytes_copy<DKEY>(
+        &mut self,
+        key: &DKEY,
+        value: Vec<u8>,
+    ) -> Result<(), Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        let prefix = self.prefix().to_owned();
+        self.storage_mut()
+            .append_bytes_copy(&PrefixedKey::new(&prefix, key), value)
+            .await
+    }
+
+    #[inline]
+    async fn get_object_copy<RETURN, DKEY, PARSER>(
+        &self,
+        key_with_parser: &DKeyWithParserCopy<'_, DKEY, PARSER>,
+    ) -> Result<Option<RETURN>, Self::Error>
+    where
+        RETURN: DeserializeOwned + Send + Sync,
+        DKEY: DKeyWhere,
+        PARSER: ParserWhere,
+    {
+        let key = PrefixedKey::new(self.prefix(), key_with_parser.key());
+        self.storage()
+            .get_object_copy(&DKeyWithParserCopy::new(&key, key_with_parser.parser()))
+            .await
+    }
+
+    #[inline]
+    async fn get_bytes_copy<DKEY>(&self, key: &DKEY) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage()
+            .get_bytes_copy(&PrefixedKey::new(self.prefix(), key))
+            .await
+    }
+
+    #[inline]
+    async fn head_copy<DKEY>(&self, key: &DKEY) -> Result<Option<ObjectMeta>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage()
+            .head_copy(&PrefixedKey::new(self.prefix(), key))
+            .await
+    }
+
+    #[inline]
+    async fn get_range_copy<DKEY>(
+        &self,
+        key: &DKEY,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, Self::Error>
+    where
+        DKEY: DKeyWhere,
+    {
+        self.storage()
+            .get_range_copy(&PrefixedKey::new(self.prefix(), key), range)
+            .await
+    }
+
+    #[inline]
+    async fn list_objects_copy(&self, prefix: &str) -> Result<ListKeyObjects, Self::Error> {
+        let objects = self
+            .storage()
+            .list_objects_copy(&format!("{}{prefix}", self.prefix()))
+            .await?;
+        Ok(self.strip_inner(objects))
+    }
+
+    #[inline]
+    async fn list_flat_page_copy(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<ListPage, Self::Error> {
+        let page = self
+            .storage()
+            .list_flat_page_copy(&format!("{}{prefix}", self.prefix()), continuation)
+            .await?;
+        Ok(self.strip_page_inner(page))
+    }
+
+    #[inline]
+    async fn health_copy(&self) -> HealthReport {
+        HealthReport::new("tenant").with_inner(self.storage().health_copy().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::sink::tenant::{TenantId, TenantStore};
+    use crate::storage::TenantError;
+    use crate::HashSet;
+
+    #[test]
+    fn reject_invalid_id() {
+        assert!(TenantId::new("acme").is_ok());
+        assert!(TenantId::new("").is_err());
+        assert!(TenantId::new("acme/other").is_err());
+        assert!(TenantId::new("..").is_err());
+    }
+
+    #[tokio::test]
+    async fn namespaced_view() {
+        let acme = TenantId::new("acme").unwrap();
+        let store = TenantStore::new(|_| Memory::default()).with_encryption_key(&acme, [7; 32]);
+        let mut tenant = store.tenant(&acme).unwrap();
+        tenant
+            .put_bytes_copy(&"docs/a".to_owned(), String::new(), vec![42])
+            .await
+            .unwrap();
+
+        let raw = tenant
+            .storage()
+            .storage()
+            .get_bytes_inner("tenants/acme/docs/a");
+        assert!(raw.is_some_and(|sealed| sealed != [42]));
+        assert_eq!(
+            tenant.get_bytes_copy(&"docs/a".to_owned()).await.unwrap(),
+            Some(vec![42])
+        );
+        assert_eq!(
+            tenant.list_objects_copy("").await.unwrap(),
+            vec!["docs/".to_owned()].into_iter().collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            tenant
+                .list_flat_page_copy("", None)
+                .await
+                .unwrap()
+                .entries
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn tenant_requires_a_key() {
+        let acme = TenantId::new("acme").unwrap();
+        let store = TenantStore::new(|_| Memory::default());
+
+        assert!(matches!(
+            store.tenant(&acme),
+            Err(TenantError::MissingKey(ref id)) if id == "acme"
+        ));
+        assert_eq!(store.plaintext_tenant(&acme).prefix(), "tenants/acme/");
+    }
+
+    #[test]
+    fn environment_prefix() {
+        let acme = TenantId::new("acme").unwrap();
+        let store = TenantStore::new(|_| Memory::default()).with_key_prefix("staging/");
+
+        assert_eq!(
+            store.plaintext_tenant(&acme).prefix(),
+            "staging/tenants/acme/"
+        );
+        assert_eq!(
+            store.with_root("orgs/").plaintext_tenant(&acme).prefix(),
+            "staging/orgs/acme/"
+        );
+    }
+
+    #[tokio::test]
+    async fn dedicated_bucket() {
+        let acme = TenantId::new("acme").unwrap();
+        let other = TenantId::new("other").unwrap();
+        let store = TenantStore::new(|bucket: Option<&str>| bucket.map(ToOwned::to_owned))
+            .with_bucket(&acme, "acme-bucket");
+
+        assert_eq!(
+            store.plaintext_tenant(&acme).storage(),
+            &Some("acme-bucket".to_owned())
+        );
+        assert_eq!(store.plaintext_tenant(&other).storage(), &None);
+    }
+}
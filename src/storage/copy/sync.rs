@@ -0,0 +1,338 @@
+use core::time::Duration;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::diff::walk;
+use super::direct::DKeyWithParserCopy;
+use super::parser::Json;
+use super::Sink;
+use crate::storage::progress::{Progress, ProgressTracker};
+use crate::storage::task::CancellationToken;
+use crate::storage::Throttled;
+
+const DEFAULT_MIME: &str = "application/octet-stream";
+
+/// Checkpoint of a sync in progress, removed from the target once the sync completes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncManifest {
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub source_prefix: String,
+    #[serde(default)]
+    pub target_prefix: String,
+    pub completed: u64,
+    pub cursors: BTreeMap<String, String>,
+}
+
+impl SyncManifest {
+    #[inline]
+    #[must_use]
+    pub fn new(source: &str, source_prefix: &str, target_prefix: &str) -> Self {
+        Self {
+            source: source.to_owned(),
+            source_prefix: source_prefix.to_owned(),
+            target_prefix: target_prefix.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the checkpoint was left by a sync between the same source and prefixes.
+    #[inline]
+    #[must_use]
+    pub fn resumes(&self, other: &Self) -> bool {
+        self.source == other.source
+            && self.source_prefix == other.source_prefix
+            && self.target_prefix == other.target_prefix
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_done(&self, key: &str) -> bool {
+        self.cursors
+            .get(shard(key))
+            .is_some_and(|cursor| key <= cursor.as_str())
+    }
+
+    fn complete(&mut self, key: &str) {
+        self.completed += 1;
+        self.cursors.insert(shard(key).to_owned(), key.to_owned());
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    pub manifest_key: String,
+    /// Names the source in the manifest, a checkpoint of another source is not resumed.
+    pub source: String,
+    pub checkpoint_every: u64,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_retries: u32,
+    pub cancellation: CancellationToken,
+}
+
+impl SyncOptions {
+    #[inline]
+    #[must_use]
+    pub fn new(manifest_key: &str) -> Self {
+        Self {
+            manifest_key: manifest_key.to_owned(),
+            source: String::new(),
+            checkpoint_every: 100,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            max_retries: 10,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_source(mut self, source: &str) -> Self {
+        source.clone_into(&mut self.source);
+        self
+    }
+}
+
+#[inline]
+pub async fn sync<ERROR, SOURCE, TARGET, PROGRESS>(
+    source: &SOURCE,
+    source_prefix: &str,
+    target: &mut TARGET,
+    target_prefix: &str,
+    options: &SyncOptions,
+    progress: &PROGRESS,
+) -> Result<SyncManifest, ERROR>
+where
+    SOURCE: Sink + Sync,
+    TARGET: Sink + Send + Sync,
+    TARGET::Error: Throttled,
+    PROGRESS: Progress,
+    ERROR: From<SOURCE::Error> + From<TARGET::Error>,
+{
+    let manifest_key = options.manifest_key.clone();
+    let manifest_with_parser = DKeyWithParserCopy::new(&manifest_key, &Json);
+    let fresh = SyncManifest::new(&options.source, source_prefix, target_prefix);
+    let mut manifest = target
+        .get_object_copy::<SyncManifest, _, _>(&manifest_with_parser)
+        .await?
+        .filter(|stored| stored.resumes(&fresh))
+        .unwrap_or(fresh);
+    let mut pace = Duration::ZERO;
+    let mut since_checkpoint = 0;
+
+    let keys = walk(source, source_prefix).await?;
+    let mut tracker = ProgressTracker::new(progress, Some(keys.len() as u64));
+    for key in keys {
+        if options.cancellation.is_cancelled() {
+            target
+                .put_object_copy(&manifest_with_parser, &manifest)
+                .await?;
+            return Ok(manifest);
+        }
+        let target_key = format!("{target_prefix}{key}");
+        if manifest.is_done(&key) || target_key == manifest_key {
+            tracker.advance(&key, 0);
+            continue;
+        }
+
+        let source_key = format!("{source_prefix}{key}");
+        let mut bytes = 0;
+        if let Some(value) = source.get_bytes_copy(&source_key).await? {
+            bytes = value.len() as u64;
+            let mime = source
+                .head_copy(&source_key)
+                .await?
+                .and_then(|meta| meta.mime)
+                .unwrap_or_else(|| DEFAULT_MIME.to_owned());
+            pace = put_with_backoff(target, &target_key, mime, value, pace, options).await?;
+        }
+
+        manifest.complete(&key);
+        tracker.advance(&key, bytes);
+        since_checkpoint += 1;
+        if since_checkpoint >= options.checkpoint_every {
+            target
+                .put_object_copy(&manifest_with_parser, &manifest)
+                .await?;
+            since_checkpoint = 0;
+        }
+    }
+
+    target.delete_copy(&manifest_key).await?;
+    Ok(manifest)
+}
+
+async fn put_with_backoff<TARGET>(
+    target: &mut TARGET,
+    key: &String,
+    mime: String,
+    value: Vec<u8>,
+    pace: Duration,
+    options: &SyncOptions,
+) -> Result<Duration, TARGET::Error>
+where
+    TARGET: Sink + Send + Sync,
+    TARGET::Error: Throttled,
+{
+    let mut delay = pace;
+    let mut retries = 0;
+
+    loop {
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        match target
+            .put_bytes_copy(key, mime.clone(), value.clone())
+            .await
+        {
+            Ok(()) => {
+                let pace = delay / 2;
+                return Ok(if pace < options.initial_backoff {
+                    Duration::ZERO
+                } else {
+                    pace
+                });
+            }
+            Err(err) if err.is_throttled() && retries < options.max_retries => {
+                retries += 1;
+                delay = (delay * 2)
+                    .max(options.initial_backoff)
+                    .min(options.max_backoff);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn shard(key: &str) -> &str {
+    key.find('/')
+        .and_then(|index| key.get(..=index))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt as _;
+
+    use super::*;
+    use crate::storage::progress::{ChannelProgress, ProgressUpdate};
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+
+    async fn source() -> Memory {
+        let mut memory = Memory::default();
+        for key in ["a/1", "a/2", "a/3", "b/1", "root"] {
+            memory
+                .put_bytes_copy(&key.to_owned(), String::new(), key.as_bytes().to_vec())
+                .await
+                .unwrap();
+        }
+        memory
+    }
+
+    #[tokio::test]
+    async fn copy_and_record_manifest() {
+        let source = source().await;
+        let mut target = Memory::default();
+        let options = SyncOptions::new(".sync/manifest.json");
+        let (progress, updates) = ChannelProgress::new();
+
+        let manifest =
+            sync::<MemoryError, _, _, _>(&source, "", &mut target, "copy/", &options, &progress)
+                .await
+                .unwrap();
+        drop(progress);
+
+        assert_eq!(manifest.completed, 5);
+        assert_eq!(manifest.cursors.get("a/"), Some(&"a/3".to_owned()));
+        assert_eq!(target.len(), 5, "a finished sync drops its manifest");
+        assert_eq!(target.get_bytes_inner("copy/b/1"), Some(b"b/1".to_vec()));
+        assert_eq!(
+            updates.collect::<Vec<_>>().await.last(),
+            Some(&ProgressUpdate {
+                done: 5,
+                total: Some(5),
+                bytes: 16,
+                key: Some("root".to_owned()),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_from_manifest() {
+        let source = source().await;
+        let mut target = Memory::default();
+        let options = SyncOptions::new(".sync/manifest.json");
+        let interrupted = SyncManifest {
+            completed: 2,
+            cursors: BTreeMap::from([("a/".to_owned(), "a/2".to_owned())]),
+            ..SyncManifest::new("", "", "")
+        };
+        target
+            .put_object_copy(
+                &DKeyWithParserCopy::new(&options.manifest_key, &Json),
+                &interrupted,
+            )
+            .await
+            .unwrap();
+
+        let manifest = sync::<MemoryError, _, _, _>(&source, "", &mut target, "", &options, &())
+            .await
+            .unwrap();
+
+        assert_eq!(manifest.completed, 5);
+        assert!(!target.exists_inner("a/1"), "already synced before resume");
+        assert!(target.exists_inner("a/3"));
+        assert!(target.exists_inner("b/1"));
+        assert!(!target.exists_inner(&options.manifest_key));
+    }
+
+    #[tokio::test]
+    async fn ignore_manifest_of_another_sync() {
+        let source = source().await;
+        let mut target = Memory::default();
+        let options = SyncOptions::new(".sync/manifest.json").with_source("primary");
+        let other = SyncManifest {
+            completed: 4,
+            cursors: BTreeMap::from([
+                ("a/".to_owned(), "a/3".to_owned()),
+                (String::new(), "root".to_owned()),
+            ]),
+            ..SyncManifest::new("primary", "", "backup/")
+        };
+        target
+            .put_object_copy(
+                &DKeyWithParserCopy::new(&options.manifest_key, &Json),
+                &other,
+            )
+            .await
+            .unwrap();
+
+        let manifest = sync::<MemoryError, _, _, _>(&source, "", &mut target, "", &options, &())
+            .await
+            .unwrap();
+
+        assert_eq!(manifest.completed, 5, "the other checkpoint is not resumed");
+        assert!(target.exists_inner("a/1"));
+        assert!(target.exists_inner("root"));
+    }
+
+    #[tokio::test]
+    async fn cancelled_sync_keeps_checkpoint() {
+        let source = source().await;
+        let mut target = Memory::default();
+        let options = SyncOptions::new(".sync/manifest.json");
+        options.cancellation.cancel();
+
+        let manifest = sync::<MemoryError, _, _, _>(&source, "", &mut target, "", &options, &())
+            .await
+            .unwrap();
+
+        assert_eq!(manifest.completed, 0);
+        assert_eq!(target.len(), 1, "only the manifest");
+    }
+}
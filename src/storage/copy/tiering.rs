@@ -0,0 +1,377 @@
+use core::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::diff::walk_all;
+use super::{journal, Sink};
+use crate::storage::clock::Clock;
+use crate::storage::meta::ObjectMeta;
+use crate::storage::task::CancellationToken;
+use crate::storage::ParserError;
+
+const DEFAULT_MIME: &str = "application/octet-stream";
+
+pub trait AccessFrequency {
+    fn reads(&self, key: &str) -> Option<u64>;
+}
+
+impl AccessFrequency for () {
+    #[inline]
+    fn reads(&self, _key: &str) -> Option<u64> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TierRule {
+    pub name: String,
+    pub prefix: String,
+    pub min_age: Option<Duration>,
+    pub min_size: Option<u64>,
+    pub max_reads: Option<u64>,
+}
+
+impl TierRule {
+    #[inline]
+    #[must_use]
+    pub fn new(name: &str, prefix: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            prefix: prefix.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn matches(
+        &self,
+        key: &str,
+        meta: &ObjectMeta,
+        reads: Option<u64>,
+        now: SystemTime,
+    ) -> bool {
+        let old_enough = self.min_age.is_none_or(|min_age| {
+            meta.last_modified
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age >= min_age)
+        });
+        let big_enough = self.min_size.is_none_or(|min_size| meta.size >= min_size);
+        let cold_enough = self
+            .max_reads
+            .is_none_or(|max_reads| reads.unwrap_or_default() <= max_reads);
+
+        key.starts_with(&self.prefix) && old_enough && big_enough && cold_enough
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Movement {
+    pub key: String,
+    pub rule: String,
+    pub size: u64,
+    pub moved_at: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TieringOptions {
+    pub manifest_key: String,
+    pub rules: Vec<TierRule>,
+    pub interval: Duration,
+}
+
+impl TieringOptions {
+    #[inline]
+    #[must_use]
+    pub fn new(manifest_key: &str, rules: Vec<TierRule>) -> Self {
+        Self {
+            manifest_key: manifest_key.to_owned(),
+            rules,
+            interval: Duration::from_secs(3_600),
+        }
+    }
+}
+
+/// One pass over the rule prefixes, stops before the next object once `token` is cancelled.
+/// Each movement is appended to the manifest as soon as the object left hot, so an error or a
+/// cancellation later in the pass loses none of them.
+#[inline]
+pub async fn tier<ERROR, HOT, COLD, ACCESS, CLOCK>(
+    hot: &mut HOT,
+    cold: &mut COLD,
+    access: &ACCESS,
+    clock: &CLOCK,
+    options: &TieringOptions,
+    token: &CancellationToken,
+) -> Result<Vec<Movement>, ERROR>
+where
+    HOT: Sink + Send + Sync,
+    HOT::Error: From<ParserError>,
+    COLD: Sink + Send + Sync,
+    ACCESS: AccessFrequency + Sync,
+    CLOCK: Clock,
+    ERROR: From<HOT::Error> + From<COLD::Error>,
+{
+    let now = clock.system_time();
+    let moved_at = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let mut movements = vec![];
+
+    let prefixes = options
+        .rules
+        .iter()
+        .map(|rule| rule.prefix.as_str())
+        .collect();
+    let keys = walk_all(hot, prefixes).await?;
+    for key in keys {
+        if token.is_cancelled() {
+            break;
+        }
+        if key == options.manifest_key {
+            continue;
+        }
+        let Some(meta) = hot.head_copy(&key).await? else {
+            continue;
+        };
+        let reads = access.reads(&key);
+        let Some(rule) = options
+            .rules
+            .iter()
+            .find(|rule| rule.matches(&key, &meta, reads, now))
+        else {
+            continue;
+        };
+        let Some(value) = hot.get_bytes_copy(&key).await? else {
+            continue;
+        };
+
+        let mime = meta.mime.unwrap_or_else(|| DEFAULT_MIME.to_owned());
+        cold.put_bytes_copy(&key, mime, value).await?;
+        hot.delete_copy(&key).await?;
+        let movement = Movement {
+            key,
+            rule: rule.name.clone(),
+            size: meta.size,
+            moved_at,
+        };
+        journal::append(hot, &options.manifest_key, core::slice::from_ref(&movement)).await?;
+        movements.push(movement);
+    }
+
+    Ok(movements)
+}
+
+/// Every movement recorded in the manifest, oldest first.
+#[inline]
+pub async fn movements<HOT>(
+    hot: &HOT,
+    options: &TieringOptions,
+) -> Result<Vec<Movement>, HOT::Error>
+where
+    HOT: Sink + Send + Sync,
+    HOT::Error: From<ParserError>,
+{
+    journal::read(hot, &options.manifest_key).await
+}
+
+#[inline]
+pub async fn run<ERROR, HOT, COLD, ACCESS, CLOCK>(
+    hot: &mut HOT,
+    cold: &mut COLD,
+    access: &ACCESS,
+    clock: &CLOCK,
+    options: &TieringOptions,
+    token: &CancellationToken,
+) -> Result<(), ERROR>
+where
+    HOT: Sink + Send + Sync,
+    HOT::Error: From<ParserError>,
+    COLD: Sink + Send + Sync,
+    ACCESS: AccessFrequency + Sync,
+    CLOCK: Clock,
+    ERROR: From<HOT::Error> + From<COLD::Error>,
+{
+    while !token.is_cancelled() {
+        tier::<ERROR, _, _, _, _>(hot, cold, access, clock, options, token).await?;
+        if !token.sleep(options.interval).await {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::clock::MockClock;
+    use crate::storage::sink::memory::Memory;
+    use crate::storage::MemoryError;
+    use crate::HashMap;
+
+    impl AccessFrequency for HashMap<String, u64> {
+        fn reads(&self, key: &str) -> Option<u64> {
+            self.get(key).copied()
+        }
+    }
+
+    #[test]
+    fn match_every_criterion() {
+        let now = UNIX_EPOCH + Duration::from_secs(100);
+        let rule = TierRule {
+            min_age: Some(Duration::from_secs(30)),
+            min_size: Some(10),
+            max_reads: Some(1),
+            ..TierRule::new("archive", "logs/")
+        };
+        let meta = ObjectMeta {
+            size: 10,
+            last_modified: Some(UNIX_EPOCH + Duration::from_secs(60)),
+            ..ObjectMeta::default()
+        };
+
+        assert!(rule.matches("logs/a", &meta, None, now));
+        assert!(!rule.matches("data/a", &meta, None, now));
+        assert!(!rule.matches("logs/a", &meta, Some(2), now));
+        assert!(!rule.matches("logs/a", &meta, None, UNIX_EPOCH + Duration::from_secs(80)));
+        assert!(
+            !rule.matches(
+                "logs/a",
+                &ObjectMeta {
+                    size: 10,
+                    ..ObjectMeta::default()
+                },
+                None,
+                now
+            ),
+            "unknown age never satisfies min_age"
+        );
+    }
+
+    #[tokio::test]
+    async fn move_and_record() {
+        let mut hot = Memory::default();
+        for (key, value) in [
+            ("logs/big", vec![0; 16]),
+            ("logs/small", vec![0; 2]),
+            ("logs/popular", vec![0; 16]),
+            ("data/big", vec![0; 16]),
+        ] {
+            hot.put_bytes_copy(&key.to_owned(), "text/plain".to_owned(), value)
+                .await
+                .unwrap();
+        }
+        let mut cold = Memory::default();
+        let access = HashMap::from_iter([("logs/popular".to_owned(), 5)]);
+        let options = TieringOptions::new(
+            ".tiering/manifest.ndjson",
+            vec![TierRule {
+                min_size: Some(8),
+                max_reads: Some(1),
+                ..TierRule::new("cold-logs", "logs/")
+            }],
+        );
+
+        let moved = tier::<MemoryError, _, _, _, _>(
+            &mut hot,
+            &mut cold,
+            &access,
+            &MockClock::new(),
+            &options,
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            moved,
+            vec![Movement {
+                key: "logs/big".to_owned(),
+                rule: "cold-logs".to_owned(),
+                size: 16,
+                moved_at: 0,
+            }]
+        );
+        assert!(!hot.exists_inner("logs/big"));
+        assert_eq!(cold.get_bytes_inner("logs/big"), Some(vec![0; 16]));
+        assert!(hot.exists_inner("logs/small"));
+        assert!(hot.exists_inner("logs/popular"));
+        assert!(hot.exists_inner("data/big"));
+
+        let again = tier::<MemoryError, _, _, _, _>(
+            &mut hot,
+            &mut cold,
+            &access,
+            &MockClock::new(),
+            &options,
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+        assert!(again.is_empty());
+        assert_eq!(movements(&hot, &options).await.unwrap(), moved);
+
+        hot.put_bytes_copy(&"logs/late".to_owned(), String::new(), vec![0; 16])
+            .await
+            .unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        let cancelled = tier::<MemoryError, _, _, _, _>(
+            &mut hot,
+            &mut cold,
+            &access,
+            &MockClock::new(),
+            &options,
+            &token,
+        )
+        .await
+        .unwrap();
+        assert!(cancelled.is_empty());
+        assert!(hot.exists_inner("logs/late"));
+    }
+
+    #[tokio::test]
+    async fn record_movements_before_a_failure() {
+        use std::time::SystemTime;
+
+        use crate::storage::meta::ObjectLock;
+
+        let mut hot = Memory::default();
+        for key in ["logs/a", "logs/z"] {
+            hot.put_bytes_copy(&key.to_owned(), String::new(), vec![0; 4])
+                .await
+                .unwrap();
+        }
+        hot.lock_copy(
+            &"logs/z".to_owned(),
+            ObjectLock::governance(SystemTime::now() + Duration::from_secs(60)),
+        )
+        .await
+        .unwrap();
+        let options = TieringOptions::new(
+            ".tiering/manifest.ndjson",
+            vec![TierRule::new("cold-logs", "logs/")],
+        );
+
+        assert!(tier::<MemoryError, _, _, _, _>(
+            &mut hot,
+            &mut Memory::default(),
+            &HashMap::default(),
+            &MockClock::new(),
+            &options,
+            &CancellationToken::new(),
+        )
+        .await
+        .is_err());
+        assert_eq!(
+            movements(&hot, &options)
+                .await
+                .unwrap()
+                .iter()
+                .map(|movement| movement.key.as_str())
+                .collect::<Vec<_>>(),
+            ["logs/a"]
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use serde_json::Value;
+
+use super::diff::walk;
+use super::parser::GuardedParser as _;
+use super::{ParserWhere, Sink};
+use crate::storage::progress::{Progress, ProgressTracker};
+use crate::storage::{DKeyWhere, ParserError, ResultExt as _};
+
+/// Rewrite one object from the `from` format to the `to` format, `false` when absent.
+///
+/// `Parser` exposes no serde `Deserializer` or `Serializer`, so the value goes through a
+/// `serde_json::Value` instead of being streamed: map keys come out sorted, numbers must fit an
+/// `i64`, `u64` or `f64`, and types private to a format, such as TOML datetimes, turn into their
+/// serde representation rather than a native value of the target format.
+#[inline]
+pub async fn transcode<DKEY, FROM, TO, SINK>(
+    sink: &mut SINK,
+    key: &DKEY,
+    from: &FROM,
+    to: &TO,
+) -> Result<bool, SINK::Error>
+where
+    DKEY: DKeyWhere,
+    FROM: ParserWhere,
+    TO: ParserWhere,
+    SINK: Sink + Send + Sync,
+    SINK::Error: From<ParserError>,
+{
+    let Some(content) = sink.get_bytes_copy(key).await? else {
+        return Ok(false);
+    };
+
+    let name = key.name();
+    let value =
+        from.deserialize_guarded::<Value>(&content)
+            .context("transcode", "deserialize", &name)?;
+    let transcoded = to
+        .serialize_guarded(&value)
+        .context("transcode", "serialize", &name)?;
+    sink.put_bytes_copy(key, to.mime(), transcoded).await?;
+    Ok(true)
+}
+
+#[inline]
+pub async fn transcode_prefix<FROM, TO, SINK, PROGRESS>(
+    sink: &mut SINK,
+    prefix: &str,
+    from: &FROM,
+    to: &TO,
+    progress: &PROGRESS,
+) -> Result<usize, SINK::Error>
+where
+    FROM: ParserWhere,
+    TO: ParserWhere,
+    SINK: Sink + Send + Sync,
+    SINK::Error: From<ParserError>,
+    PROGRESS: Progress,
+{
+    let mut count = 0;
+    let mime = to.mime();
+
+    let keys = walk(sink, prefix).await?;
+    let mut tracker = ProgressTracker::new(progress, Some(keys.len() as u64));
+    for key in keys {
+        let key = format!("{prefix}{key}");
+        tracker.advance(&key, 0);
+        let meta = sink.head_copy(&key).await?;
+        if meta.is_some_and(|meta| meta.mime.as_ref() == Some(&mime)) {
+            continue;
+        }
+        if transcode(sink, &key, from, to).await? {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::storage::copy::direct::DKeyWithParserCopy;
+    use crate::storage::copy::parser::{Json, Toml};
+    use crate::storage::sink::memory::Memory;
+
+    #[tokio::test]
+    async fn json_to_toml_prefix() {
+        let mut memory = Memory::default();
+        for key in ["config/a", "config/nested/b"] {
+            memory
+                .put_object_copy(
+                    &DKeyWithParserCopy::new(&key.to_owned(), &Json),
+                    &json!({ "name": key, "retries": 3 }),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            transcode_prefix(&mut memory, "config/", &Json, &Toml, &())
+                .await
+                .unwrap(),
+            2
+        );
+
+        let key = "config/nested/b".to_owned();
+        assert_eq!(memory.head_inner(&key).unwrap().mime, Some(Toml.mime()));
+        assert_eq!(
+            memory
+                .get_object_copy::<Value, _, _>(&DKeyWithParserCopy::new(&key, &Toml))
+                .await
+                .unwrap(),
+            Some(json!({ "name": "config/nested/b", "retries": 3 }))
+        );
+        assert!(!transcode(&mut memory, &"missing".to_owned(), &Json, &Toml)
+            .await
+            .unwrap());
+        assert_eq!(
+            transcode_prefix(&mut memory, "config/", &Json, &Toml, &())
+                .await
+                .unwrap(),
+            0,
+            "objects already in the target format are skipped"
+        );
+    }
+
+    #[tokio::test]
+    async fn transcode_through_json_values() {
+        let mut memory = Memory::default();
+        let key = "config/c".to_owned();
+        memory
+            .put_bytes_copy(
+                &key,
+                Toml.mime(),
+                b"zone = \"b\"\nat = 1979-05-27T07:32:00Z\n".to_vec(),
+            )
+            .await
+            .unwrap();
+
+        assert!(transcode(&mut memory, &key, &Toml, &Json).await.unwrap());
+        assert_eq!(
+            String::from_utf8(memory.get_bytes_copy(&key).await.unwrap().unwrap()).unwrap(),
+            r#"{"at":{"$__toml_private_datetime":"1979-05-27T07:32:00Z"},"zone":"b"}"#,
+            "keys are sorted and the datetime keeps its serde representation"
+        );
+
+        memory
+            .put_bytes_copy(&key, Json.mime(), b"{\"big\": 1e400}".to_vec())
+            .await
+            .unwrap();
+        assert!(
+            transcode(&mut memory, &key, &Json, &Toml).await.is_err(),
+            "a number past f64 has no Value"
+        );
+    }
+}
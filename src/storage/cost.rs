@@ -0,0 +1,57 @@
+use crate::storage::metrics::prefix_bucket;
+use crate::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Get,
+    Put,
+    List,
+    Head,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OperationCost {
+    pub count: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostReport {
+    pub operations: HashMap<Operation, OperationCost>,
+    pub prefixes: HashMap<String, HashMap<Operation, OperationCost>>,
+}
+
+impl CostReport {
+    #[inline]
+    #[must_use]
+    pub fn operation(&self, operation: Operation) -> OperationCost {
+        self.operations.get(&operation).copied().unwrap_or_default()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn prefix(&self, prefix: &str, operation: Operation) -> OperationCost {
+        self.prefixes
+            .get(prefix)
+            .and_then(|operations| operations.get(&operation))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn record(&mut self, operation: Operation, key: &str, bytes: u64) {
+        let prefix = prefix_bucket(key);
+
+        for cost in [
+            self.operations.entry(operation).or_default(),
+            self.prefixes
+                .entry(prefix.to_owned())
+                .or_default()
+                .entry(operation)
+                .or_default(),
+        ] {
+            cost.count += 1;
+            cost.bytes += bytes;
+        }
+    }
+}
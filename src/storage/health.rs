@@ -0,0 +1,45 @@
+use core::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    pub backend: String,
+    pub reachable: bool,
+    pub latency: Option<Duration>,
+    pub bucket_exists: Option<bool>,
+    pub credentials_valid: Option<bool>,
+    pub error: Option<String>,
+    pub inner: Vec<HealthReport>,
+}
+
+impl HealthReport {
+    #[inline]
+    #[must_use]
+    pub fn new(backend: &str) -> Self {
+        Self {
+            backend: backend.to_owned(),
+            reachable: true,
+            latency: None,
+            bucket_exists: None,
+            credentials_valid: None,
+            error: None,
+            inner: vec![],
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_inner(mut self, inner: Self) -> Self {
+        self.inner.push(inner);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.reachable
+            && self.error.is_none()
+            && self.bucket_exists != Some(false)
+            && self.credentials_valid != Some(false)
+            && self.inner.iter().all(Self::is_healthy)
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use crate::HashSet;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InternStats {
+    pub keys: usize,
+    pub bytes: usize,
+    pub references: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Interner {
+    keys: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    #[inline]
+    pub fn intern(&mut self, key: &str) -> Arc<str> {
+        if let Some(interned) = self.keys.get(key) {
+            return Arc::clone(interned);
+        }
+
+        let interned = Arc::<str>::from(key);
+        self.keys.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// Drops the key once no map references it anymore.
+    #[inline]
+    pub fn release(&mut self, key: &str) {
+        if self
+            .keys
+            .get(key)
+            .is_some_and(|interned| Arc::strong_count(interned) == 1)
+        {
+            self.keys.remove(key);
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn stats(&self) -> InternStats {
+        self.keys
+            .iter()
+            .fold(InternStats::default(), |stats, key| InternStats {
+                keys: stats.keys + 1,
+                bytes: stats.bytes + key.len(),
+                references: stats.references + Arc::strong_count(key) - 1,
+            })
+    }
+}
@@ -0,0 +1,28 @@
+use core::any::Any;
+
+pub trait Layer {
+    type Inner;
+
+    fn inner(&self) -> &Self::Inner;
+
+    fn inner_mut(&mut self) -> &mut Self::Inner;
+
+    fn into_inner(self) -> Self::Inner;
+
+    #[inline]
+    fn as_any(&self) -> &dyn Any
+    where
+        Self: Sized + 'static,
+    {
+        self
+    }
+
+    #[inline]
+    fn downcast_inner<TARGET>(&self) -> Option<&TARGET>
+    where
+        Self::Inner: 'static,
+        TARGET: 'static,
+    {
+        (self.inner() as &dyn Any).downcast_ref()
+    }
+}
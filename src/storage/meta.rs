@@ -0,0 +1,134 @@
+use core::fmt::Write as _;
+use std::time::SystemTime;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectMeta {
+    pub size: u64,
+    pub etag: Option<String>,
+    pub checksum: Option<String>,
+    pub mime: Option<String>,
+    pub last_modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    Governance,
+    Compliance,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectLock {
+    pub mode: LockMode,
+    pub retain_until: SystemTime,
+}
+
+impl ObjectLock {
+    #[inline]
+    #[must_use]
+    pub const fn governance(retain_until: SystemTime) -> Self {
+        Self {
+            mode: LockMode::Governance,
+            retain_until,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn compliance(retain_until: SystemTime) -> Self {
+        Self {
+            mode: LockMode::Compliance,
+            retain_until,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_active(&self, now: SystemTime) -> bool {
+        self.retain_until > now
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListEntry {
+    pub key: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListPage {
+    pub entries: Vec<ListEntry>,
+    pub next: Option<String>,
+}
+
+impl ObjectMeta {
+    #[inline]
+    #[must_use]
+    pub fn from_bytes(value: &[u8]) -> Self {
+        Self {
+            size: u64::try_from(value.len()).unwrap_or(u64::MAX),
+            checksum: Some(checksum(value)),
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn same_content(&self, other: &Self) -> Option<bool> {
+        if self.size != other.size {
+            return Some(false);
+        }
+
+        match (self, other) {
+            (
+                &Self {
+                    checksum: Some(ref left),
+                    ..
+                },
+                &Self {
+                    checksum: Some(ref right),
+                    ..
+                },
+            ) => Some(left == right),
+            (
+                &Self {
+                    etag: Some(ref left),
+                    ..
+                },
+                &Self {
+                    etag: Some(ref right),
+                    ..
+                },
+            ) => Some(left == right),
+            _ => None,
+        }
+    }
+}
+
+#[inline]
+#[must_use]
+pub fn checksum(value: &[u8]) -> String {
+    hex(&Sha256::digest(value))
+}
+
+/// Converts a base64 SHA256, as S3 reports it, to the hex form of [`checksum`].
+/// Composite checksums of multipart uploads are not digests of the content and give `None`.
+#[inline]
+#[must_use]
+pub fn checksum_from_base64(encoded: &str) -> Option<String> {
+    STANDARD
+        .decode(encoded)
+        .ok()
+        .filter(|digest| digest.len() == 32)
+        .map(|digest| hex(&digest))
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
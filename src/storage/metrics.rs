@@ -0,0 +1,152 @@
+use core::time::Duration;
+use std::sync::{Mutex, PoisonError};
+
+use crate::HashMap;
+
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Serialize,
+    Deserialize,
+}
+
+impl Direction {
+    #[inline]
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Serialize => "serialize",
+            Self::Deserialize => "deserialize",
+        }
+    }
+}
+
+/// Receives what the storage observes, every record is a no-op unless overridden.
+pub trait StorageMetrics: Send + Sync {
+    /// One operation a backend answered, `key` is the object key or the listed prefix.
+    #[inline]
+    fn record_operation(
+        &self,
+        _backend: &str,
+        _operation: &str,
+        _key: &str,
+        _elapsed: Duration,
+        _success: bool,
+    ) {
+    }
+
+    /// Size of one payload a parser produced or read back.
+    #[inline]
+    fn record_payload(&self, _mime: &str, _direction: Direction, _size: u64) {}
+}
+
+/// First segment of a key with its `/`, empty for a key at the root. Keeps the label
+/// cardinality of a metric bound by the layout of the bucket, not by its object count.
+#[inline]
+#[must_use]
+pub fn prefix_bucket(key: &str) -> &str {
+    key.find('/')
+        .and_then(|index| key.get(..=index))
+        .unwrap_or_default()
+}
+
+/// Power of two buckets, `buckets[index]` counts the payloads of at most `2^index` bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeHistogram {
+    pub count: u64,
+    pub total: u64,
+    pub buckets: Vec<u64>,
+}
+
+impl SizeHistogram {
+    #[inline]
+    pub fn record(&mut self, size: u64) {
+        let index = (u64::BITS - size.saturating_sub(1).leading_zeros()) as usize;
+        if self.buckets.len() <= index {
+            self.buckets.resize(index + 1, 0);
+        }
+        self.buckets[index] += 1;
+        self.count += 1;
+        self.total = self.total.saturating_add(size);
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn mean(&self) -> Option<u64> {
+        self.total.checked_div(self.count)
+    }
+}
+
+/// In memory payload sizes per mime, enough to compare formats before and after a migration.
+#[derive(Debug, Default)]
+pub struct PayloadMetrics {
+    sizes: Mutex<HashMap<(String, Direction), SizeHistogram>>,
+}
+
+impl PayloadMetrics {
+    #[inline]
+    #[must_use]
+    pub fn histogram(&self, mime: &str, direction: Direction) -> SizeHistogram {
+        self.sizes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(mime.to_owned(), direction))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// How many payloads each mime went through, both directions together.
+    #[inline]
+    #[must_use]
+    pub fn counts(&self) -> HashMap<String, u64> {
+        let mut counts = HashMap::default();
+        for ((mime, _), histogram) in self
+            .sizes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            *counts.entry(mime.clone()).or_default() += histogram.count;
+        }
+        counts
+    }
+}
+
+impl StorageMetrics for PayloadMetrics {
+    #[inline]
+    fn record_payload(&self, mime: &str, direction: Direction, size: u64) {
+        self.sizes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry((mime.to_owned(), direction))
+            .or_default()
+            .record(size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_of_two_buckets() {
+        let mut histogram = SizeHistogram::default();
+        for size in [0, 1, 2, 3, 4, 5, 1024] {
+            histogram.record(size);
+        }
+
+        assert_eq!(histogram.buckets[..4], [2, 1, 2, 1]);
+        assert_eq!(histogram.buckets[10], 1);
+        assert_eq!(histogram.buckets.len(), 11);
+        assert_eq!(histogram.mean(), Some(1039 / 7));
+        assert_eq!(SizeHistogram::default().mean(), None);
+    }
+
+    #[test]
+    fn first_segment_as_prefix() {
+        assert_eq!(prefix_bucket("patients/jane/record"), "patients/");
+        assert_eq!(prefix_bucket("index"), "");
+    }
+}
@@ -0,0 +1,125 @@
+use core::time::Duration;
+
+use prometheus::{
+    exponential_buckets, Error, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry,
+};
+
+use super::{prefix_bucket, Direction, StorageMetrics};
+
+/// From 64 B to 16 MiB, a bucket every factor of 4.
+const PAYLOAD_BUCKETS: (f64, f64, usize) = (64.0, 4.0, 10);
+
+/// `StorageMetrics` backed by a `prometheus::Registry`. The prefix label is only on the
+/// operation counter, the histograms stay at one series per backend and operation.
+#[derive(Debug, Clone)]
+pub struct PrometheusMetrics {
+    operations: IntCounterVec,
+    durations: HistogramVec,
+    payloads: HistogramVec,
+}
+
+impl PrometheusMetrics {
+    #[inline]
+    pub fn register(registry: &Registry) -> Result<Self, Error> {
+        let (start, factor, count) = PAYLOAD_BUCKETS;
+        let metrics = Self {
+            operations: IntCounterVec::new(
+                Opts::new(
+                    "negentropy_storage_operations_total",
+                    "Storage operations answered by a backend",
+                ),
+                &["backend", "operation", "prefix", "outcome"],
+            )?,
+            durations: HistogramVec::new(
+                HistogramOpts::new(
+                    "negentropy_storage_operation_duration_seconds",
+                    "Time a backend took to answer a storage operation",
+                ),
+                &["backend", "operation"],
+            )?,
+            payloads: HistogramVec::new(
+                HistogramOpts::new(
+                    "negentropy_storage_payload_bytes",
+                    "Size of the payloads going through a parser",
+                )
+                .buckets(exponential_buckets(start, factor, count)?),
+                &["mime", "direction"],
+            )?,
+        };
+
+        registry.register(Box::new(metrics.operations.clone()))?;
+        registry.register(Box::new(metrics.durations.clone()))?;
+        registry.register(Box::new(metrics.payloads.clone()))?;
+        Ok(metrics)
+    }
+}
+
+impl StorageMetrics for PrometheusMetrics {
+    #[inline]
+    fn record_operation(
+        &self,
+        backend: &str,
+        operation: &str,
+        key: &str,
+        elapsed: Duration,
+        success: bool,
+    ) {
+        let outcome = if success { "success" } else { "error" };
+        self.operations
+            .with_label_values(&[backend, operation, prefix_bucket(key), outcome])
+            .inc();
+        self.durations
+            .with_label_values(&[backend, operation])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    #[inline]
+    fn record_payload(&self, mime: &str, direction: Direction, size: u64) {
+        self.payloads
+            .with_label_values(&[mime, direction.as_str()])
+            .observe(size as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_and_record() {
+        let registry = Registry::new();
+        let metrics = PrometheusMetrics::register(&registry).unwrap();
+        assert!(
+            PrometheusMetrics::register(&registry).is_err(),
+            "the same collectors can not be registered twice"
+        );
+
+        metrics.record_operation(
+            "s3",
+            "get_object",
+            "patients/jane",
+            Duration::from_millis(3),
+            true,
+        );
+        metrics.record_operation("s3", "get_object", "patients/john", Duration::ZERO, false);
+        metrics.record_payload("application/json", Direction::Serialize, 100);
+
+        let families = registry.gather();
+        let operations = families
+            .iter()
+            .find(|family| family.get_name() == "negentropy_storage_operations_total")
+            .unwrap();
+        assert_eq!(operations.get_metric().len(), 2);
+        assert!(operations.get_metric().iter().all(|metric| metric
+            .get_label()
+            .iter()
+            .any(|label| label.get_name() == "prefix" && label.get_value() == "patients/")));
+        assert_eq!(
+            metrics
+                .payloads
+                .with_label_values(&["application/json", "serialize"])
+                .get_sample_count(),
+            1
+        );
+    }
+}
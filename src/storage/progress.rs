@@ -0,0 +1,110 @@
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgressUpdate {
+    pub done: u64,
+    pub total: Option<u64>,
+    pub bytes: u64,
+    pub key: Option<String>,
+}
+
+pub trait Progress: Send + Sync {
+    fn update(&self, update: &ProgressUpdate);
+}
+
+impl Progress for () {
+    #[inline]
+    fn update(&self, _update: &ProgressUpdate) {}
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelProgress {
+    sender: UnboundedSender<ProgressUpdate>,
+}
+
+impl ChannelProgress {
+    #[inline]
+    #[must_use]
+    pub fn new() -> (Self, UnboundedReceiver<ProgressUpdate>) {
+        let (sender, receiver) = unbounded();
+        (Self { sender }, receiver)
+    }
+}
+
+impl Progress for ChannelProgress {
+    #[inline]
+    fn update(&self, update: &ProgressUpdate) {
+        // A dropped receiver means nobody is watching anymore, the job keeps going.
+        self.sender.unbounded_send(update.clone()).ok();
+    }
+}
+
+#[derive(Debug)]
+pub struct ProgressTracker<'progress, PROGRESS: ?Sized> {
+    progress: &'progress PROGRESS,
+    state: ProgressUpdate,
+}
+
+impl<'progress, PROGRESS> ProgressTracker<'progress, PROGRESS>
+where
+    PROGRESS: Progress + ?Sized,
+{
+    #[inline]
+    pub fn new(progress: &'progress PROGRESS, total: Option<u64>) -> Self {
+        Self {
+            progress,
+            state: ProgressUpdate {
+                total,
+                ..ProgressUpdate::default()
+            },
+        }
+    }
+
+    #[inline]
+    pub fn advance(&mut self, key: &str, bytes: u64) {
+        self.state.done += 1;
+        self.state.bytes += bytes;
+        self.state.key = Some(key.to_owned());
+        self.progress.update(&self.state);
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn state(&self) -> &ProgressUpdate {
+        &self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt as _;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn channel_receives_updates() {
+        let (progress, receiver) = ChannelProgress::new();
+        let mut tracker = ProgressTracker::new(&progress, Some(2));
+        tracker.advance("a", 3);
+        tracker.advance("b", 4);
+        drop(progress);
+
+        assert_eq!(
+            receiver.collect::<Vec<_>>().await,
+            [
+                ProgressUpdate {
+                    done: 1,
+                    total: Some(2),
+                    bytes: 3,
+                    key: Some("a".to_owned()),
+                },
+                ProgressUpdate {
+                    done: 2,
+                    total: Some(2),
+                    bytes: 7,
+                    key: Some("b".to_owned()),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(feature = "copy")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "http")]
+use crate::storage::sink::http::Http;
+use crate::storage::sink::memory::Memory;
+use crate::storage::sink::s3::{BucketMode, S3Config, S3};
+use crate::storage::tls::TlsConfig;
+use crate::storage::AnyError;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "copy", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "copy", serde(tag = "kind", rename_all = "lowercase"))]
+pub enum Backend {
+    #[default]
+    Memory,
+    S3 {
+        bucket: String,
+        #[cfg_attr(feature = "copy", serde(default))]
+        mode: BucketMode,
+        #[cfg_attr(feature = "copy", serde(default))]
+        accelerate: bool,
+        #[cfg_attr(feature = "copy", serde(default))]
+        dual_stack: bool,
+        #[cfg_attr(feature = "copy", serde(default))]
+        anonymous: bool,
+    },
+    #[cfg(feature = "http")]
+    Http { base: String },
+}
+
+pub enum AnyStorage {
+    Memory(Memory),
+    S3(S3),
+    #[cfg(feature = "http")]
+    Http(Http),
+}
+
+impl AnyStorage {
+    #[inline]
+    pub async fn from_backend(backend: &Backend) -> Result<Self, AnyError> {
+        Self::from_backend_with_tls(backend, None).await
+    }
+
+    #[inline]
+    pub async fn from_backend_with_tls(
+        backend: &Backend,
+        tls: Option<&TlsConfig>,
+    ) -> Result<Self, AnyError> {
+        Ok(match *backend {
+            Backend::Memory => Self::Memory(Memory::default()),
+            Backend::S3 {
+                ref bucket,
+                mode,
+                accelerate,
+                dual_stack,
+                anonymous,
+            } => {
+                let mut config = S3Config::new(bucket.clone())
+                    .with_mode(mode)
+                    .with_accelerate(accelerate)
+                    .with_dual_stack(dual_stack)
+                    .with_anonymous(anonymous);
+                config.tls = tls.cloned();
+                Self::S3(S3::from_config(config).await?)
+            }
+            #[cfg(feature = "http")]
+            Backend::Http { ref base } => Self::Http(match tls {
+                Some(tls) => Http::with_tls(base, tls)?,
+                None => Http::new(base)?,
+            }),
+        })
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match *self {
+            Self::Memory(_) => "memory",
+            Self::S3(_) => "s3",
+            #[cfg(feature = "http")]
+            Self::Http(_) => "http",
+        }
+    }
+}
+
+impl From<Memory> for AnyStorage {
+    #[inline]
+    fn from(value: Memory) -> Self {
+        Self::Memory(value)
+    }
+}
+
+impl From<S3> for AnyStorage {
+    #[inline]
+    fn from(value: S3) -> Self {
+        Self::S3(value)
+    }
+}
+
+#[cfg(feature = "http")]
+impl From<Http> for AnyStorage {
+    #[inline]
+    fn from(value: Http) -> Self {
+        Self::Http(value)
+    }
+}
@@ -0,0 +1,66 @@
+use core::num::NonZeroUsize;
+
+use crate::storage::layer::Layer;
+
+pub(crate) const MANIFEST_MIME: &str = "application/vnd.negentropy.chunked+json";
+const CHUNKS_SUFFIX: &str = ".chunks/";
+
+pub struct Chunked<STORAGE> {
+    threshold: NonZeroUsize,
+    storage: STORAGE,
+}
+
+impl<STORAGE> Chunked<STORAGE>
+where
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub const fn new(threshold: NonZeroUsize, storage: STORAGE) -> Self {
+        Self { threshold, storage }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn threshold(&self) -> NonZeroUsize {
+        self.threshold
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn chunk_key_inner(key: &str, index: usize) -> String {
+        format!("{}{index:08}", Self::chunks_prefix_inner(key))
+    }
+
+    pub(crate) fn chunks_prefix_inner(key: &str) -> String {
+        format!("{key}{CHUNKS_SUFFIX}")
+    }
+
+    pub(crate) fn is_chunk_inner(entry: &str) -> bool {
+        entry.contains(CHUNKS_SUFFIX)
+    }
+}
+
+impl<STORAGE> Layer for Chunked<STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
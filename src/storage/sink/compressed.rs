@@ -0,0 +1,233 @@
+use crate::storage::compat::{Envelope, COMPRESSED_V1};
+use crate::storage::layer::Layer;
+use crate::storage::LayerError;
+
+const IDENTITY: &str = "identity";
+pub(crate) const HEADER_MAX: u64 = COMPRESSED_V1.len() as u64 + 1 + 255 + 8;
+const DEFAULT_THRESHOLD: usize = 1024;
+const COMPRESSED_MIMES: [&str; 7] = [
+    "image/",
+    "video/",
+    "audio/",
+    "application/zip",
+    "application/gzip",
+    "application/zstd",
+    "application/x-7z-compressed",
+];
+
+pub trait Compression: Send + Sync {
+    fn encoding(&self) -> &str;
+
+    fn compress(&self, value: &[u8]) -> Vec<u8>;
+
+    /// `size` comes from the envelope, decoding must stop once it produced more than that.
+    fn decompress(&self, value: &[u8], size: usize) -> Option<Vec<u8>>;
+}
+
+/// Zlib wrapped deflate, what `Content-Encoding: deflate` means over HTTP.
+#[cfg(feature = "deflate")]
+#[derive(Debug, Clone, Copy)]
+pub struct Deflate {
+    level: u8,
+}
+
+#[cfg(feature = "deflate")]
+impl Deflate {
+    /// From 0, no compression, to 10, the smallest and slowest.
+    #[inline]
+    #[must_use]
+    pub const fn new(level: u8) -> Self {
+        Self {
+            level: if level > 10 { 10 } else { level },
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn level(&self) -> u8 {
+        self.level
+    }
+}
+
+#[cfg(feature = "deflate")]
+impl Default for Deflate {
+    #[inline]
+    fn default() -> Self {
+        Self::new(6)
+    }
+}
+
+#[cfg(feature = "deflate")]
+impl Compression for Deflate {
+    #[inline]
+    fn encoding(&self) -> &str {
+        "deflate"
+    }
+
+    #[inline]
+    fn compress(&self, value: &[u8]) -> Vec<u8> {
+        miniz_oxide::deflate::compress_to_vec_zlib(value, self.level)
+    }
+
+    #[inline]
+    fn decompress(&self, value: &[u8], size: usize) -> Option<Vec<u8>> {
+        miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(value, size).ok()
+    }
+}
+
+pub struct Compressed<CODEC, STORAGE> {
+    codec: CODEC,
+    threshold: usize,
+    skipped_mimes: Vec<String>,
+    storage: STORAGE,
+}
+
+impl<CODEC, STORAGE> Compressed<CODEC, STORAGE>
+where
+    CODEC: Compression,
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub fn new(codec: CODEC, storage: STORAGE) -> Self {
+        Self {
+            codec,
+            threshold: DEFAULT_THRESHOLD,
+            skipped_mimes: COMPRESSED_MIMES.map(ToOwned::to_owned).to_vec(),
+            storage,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_skipped_mime(mut self, mime: &str) -> Self {
+        self.skipped_mimes.push(mime.to_owned());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn codec(&self) -> &CODEC {
+        &self.codec
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn should_compress(&self, mime: &str, size: usize) -> bool {
+        size > self.threshold
+            && !self
+                .skipped_mimes
+                .iter()
+                .any(|skipped| mime.starts_with(skipped.as_str()))
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn pack_inner(&self, mime: &str, value: Vec<u8>) -> Vec<u8> {
+        if self.should_compress(mime, value.len()) {
+            let compressed = self.codec.compress(&value);
+            if compressed.len() < value.len() {
+                return envelope(self.codec.encoding(), value.len(), compressed);
+            }
+        }
+
+        if value.starts_with(COMPRESSED_V1) {
+            envelope(IDENTITY, value.len(), value)
+        } else {
+            value
+        }
+    }
+
+    pub(crate) fn unpack_inner(&self, key: &str, value: Vec<u8>) -> Result<Vec<u8>, LayerError> {
+        let Some((encoding, size, body)) = header(&value) else {
+            return Ok(value);
+        };
+        let size = usize::try_from(size).map_err(|_err| compression_error(key, "too large"))?;
+
+        let content = if encoding == IDENTITY {
+            body.to_vec()
+        } else if encoding == self.codec.encoding() {
+            self.codec
+                .decompress(body, size)
+                .ok_or_else(|| compression_error(key, "corrupted payload"))?
+        } else {
+            return Err(compression_error(
+                key,
+                &format!("unknown encoding {encoding}"),
+            ));
+        };
+
+        if content.len() == size {
+            Ok(content)
+        } else {
+            Err(compression_error(
+                key,
+                &format!("{} bytes decoded, {size} announced", content.len()),
+            ))
+        }
+    }
+}
+
+impl<CODEC, STORAGE> Layer for Compressed<CODEC, STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
+
+#[must_use]
+pub(crate) fn header(value: &[u8]) -> Option<(&str, u64, &[u8])> {
+    match Envelope::read(value) {
+        Envelope::Compressed {
+            encoding,
+            size,
+            body,
+            ..
+        } => Some((encoding, size, body)),
+        Envelope::Plain(_) | Envelope::Encrypted { .. } => None,
+    }
+}
+
+fn envelope(encoding: &str, size: usize, body: Vec<u8>) -> Vec<u8> {
+    let encoding = encoding.get(..encoding.len().min(255)).unwrap_or_default();
+    let mut envelope =
+        Vec::with_capacity(COMPRESSED_V1.len() + 1 + encoding.len() + 8 + body.len());
+    envelope.extend_from_slice(COMPRESSED_V1);
+    envelope.push(u8::try_from(encoding.len()).unwrap_or(u8::MAX));
+    envelope.extend_from_slice(encoding.as_bytes());
+    envelope.extend_from_slice(&(size as u64).to_be_bytes());
+    envelope.extend(body);
+    envelope
+}
+
+fn compression_error(key: &str, internal: &str) -> LayerError {
+    LayerError::Compression {
+        operation: "decompress".to_owned(),
+        key: key.to_owned(),
+        internal: internal.to_owned(),
+    }
+}
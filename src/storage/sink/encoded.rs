@@ -0,0 +1,63 @@
+use crate::storage::codec::{decode_key, encode_key, KeyCodec};
+use crate::storage::layer::Layer;
+use crate::storage::ListKeyObjects;
+
+pub struct Encoded<CODEC, STORAGE> {
+    codec: CODEC,
+    storage: STORAGE,
+}
+
+impl<CODEC, STORAGE> Encoded<CODEC, STORAGE>
+where
+    CODEC: KeyCodec,
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub const fn new(codec: CODEC, storage: STORAGE) -> Self {
+        Self { codec, storage }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn codec(&self) -> &CODEC {
+        &self.codec
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn encode_inner(&self, key: &str) -> String {
+        encode_key(&self.codec, key)
+    }
+
+    pub(crate) fn decode_inner(&self, objects: ListKeyObjects) -> ListKeyObjects {
+        objects
+            .into_iter()
+            .map(|encoded| decode_key(&self.codec, &encoded).unwrap_or(encoded))
+            .collect()
+    }
+}
+
+impl<CODEC, STORAGE> Layer for Encoded<CODEC, STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
@@ -0,0 +1,340 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use futures::future::BoxFuture;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom as _, SystemRandom};
+
+use crate::storage::compat::{Envelope, ENCRYPTED_V1, ENCRYPTED_V2};
+use crate::storage::layer::Layer;
+use crate::storage::meta::checksum;
+use crate::storage::LayerError;
+use crate::HashMap;
+
+/// Enough of an envelope to know its overhead, whatever its version.
+pub(crate) const HEADER_LEN: u64 = ENCRYPTED_V2.len() as u64 + 3;
+
+/// A data key in clear, along with the form its provider wrapped it in.
+pub struct DataKey {
+    pub secret: [u8; 32],
+    pub wrapped: Vec<u8>,
+}
+
+/// Hands out data keys without the instances holding a raw key, only the wrapped form of a
+/// data key is stored, in the envelope of every object it sealed.
+pub trait KeyProvider: Send + Sync {
+    fn generate(&self) -> BoxFuture<'_, Result<DataKey, LayerError>>;
+
+    fn decrypt<'call>(
+        &'call self,
+        wrapped: &'call [u8],
+    ) -> BoxFuture<'call, Result<[u8; 32], LayerError>>;
+}
+
+pub struct KeyRing {
+    current: String,
+    keys: Vec<(String, LessSafeKey)>,
+    wrapped: HashMap<String, Vec<u8>>,
+}
+
+impl KeyRing {
+    #[inline]
+    #[must_use]
+    pub fn new(id: &str, secret: &[u8; 32]) -> Self {
+        Self {
+            current: id.to_owned(),
+            keys: vec![(id.to_owned(), cipher(secret))],
+            wrapped: HashMap::default(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_key(mut self, id: &str, secret: &[u8; 32]) -> Self {
+        self.insert(id, secret);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn rotate(mut self, id: &str, secret: &[u8; 32]) -> Self {
+        self.insert(id, secret);
+        id.clone_into(&mut self.current);
+        self
+    }
+
+    /// Envelopes sealed with the key `id` carry `wrapped`, for other instances to unwrap it.
+    #[inline]
+    #[must_use]
+    pub fn with_wrapped(mut self, id: &str, wrapped: Vec<u8>) -> Self {
+        self.wrapped.insert(id.to_owned(), wrapped);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn current(&self) -> &str {
+        &self.current
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn ids(&self) -> Vec<&str> {
+        self.keys.iter().map(|(id, _)| id.as_str()).collect()
+    }
+
+    fn key(&self, id: &str) -> Option<&LessSafeKey> {
+        self.keys
+            .iter()
+            .find(|&(known, _)| known == id)
+            .map(|(_, key)| key)
+    }
+
+    fn insert(&mut self, id: &str, secret: &[u8; 32]) {
+        self.keys.retain(|(known, _)| known != id);
+        self.wrapped.remove(id);
+        self.keys.push((id.to_owned(), cipher(secret)));
+    }
+}
+
+pub struct Encrypted<STORAGE> {
+    keys: KeyRing,
+    provider: Option<Arc<dyn KeyProvider>>,
+    unwrapped: Mutex<HashMap<String, LessSafeKey>>,
+    random: SystemRandom,
+    storage: STORAGE,
+}
+
+impl<STORAGE> Encrypted<STORAGE>
+where
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub fn new(keys: KeyRing, storage: STORAGE) -> Self {
+        Self {
+            keys,
+            provider: None,
+            unwrapped: Mutex::default(),
+            random: SystemRandom::new(),
+            storage,
+        }
+    }
+
+    /// Seals with a data key of `provider`, its wrapped form is stored in every envelope.
+    #[inline]
+    pub async fn from_provider(
+        provider: Arc<dyn KeyProvider>,
+        storage: STORAGE,
+    ) -> Result<Self, LayerError> {
+        let data_key = provider.generate().await?;
+        let id = data_key_id(&data_key.wrapped);
+        let keys = KeyRing::new(&id, &data_key.secret).with_wrapped(&id, data_key.wrapped);
+        Ok(Self::new(keys, storage).with_provider(provider))
+    }
+
+    /// Opens the envelopes sealed with a data key missing from the ring by unwrapping it.
+    #[inline]
+    #[must_use]
+    pub fn with_provider(mut self, provider: Arc<dyn KeyProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn keys(&self) -> &KeyRing {
+        &self.keys
+    }
+
+    #[inline]
+    pub fn set_keys(&mut self, keys: KeyRing) {
+        self.keys = keys;
+    }
+
+    /// Seals with a new data key from now on, the previous ones still open older envelopes.
+    #[inline]
+    pub async fn rotate_data_key(&mut self) -> Result<&str, LayerError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or_else(|| crypto_error("rotate", "", "no key provider"))?;
+        let data_key = provider.generate().await?;
+        let id = data_key_id(&data_key.wrapped);
+        self.keys.insert(&id, &data_key.secret);
+        self.keys.wrapped.insert(id.clone(), data_key.wrapped);
+        self.keys.current = id;
+        Ok(self.keys.current())
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn seal_inner(&self, key: &str, mut value: Vec<u8>) -> Result<Vec<u8>, LayerError> {
+        let id = self.keys.current();
+        let cipher = self
+            .keys
+            .key(id)
+            .ok_or_else(|| crypto_error("seal", key, "current key is missing"))?;
+        let mut nonce = [0; NONCE_LEN];
+        self.random
+            .fill(&mut nonce)
+            .map_err(|_| crypto_error("seal", key, "no randomness available"))?;
+        let id_len = u8::try_from(id.len())
+            .map_err(|_| crypto_error("seal", key, "key id longer than 255 bytes"))?;
+        let wrapped = self.keys.wrapped.get(id);
+        let wrapped_len = u16::try_from(wrapped.map_or(0, Vec::len))
+            .map_err(|_| crypto_error("seal", key, "wrapped key longer than 65535 bytes"))?;
+
+        cipher
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(key.as_bytes()),
+                &mut value,
+            )
+            .map_err(|_| crypto_error("seal", key, "encryption failed"))?;
+
+        let mut envelope = Vec::with_capacity(
+            ENCRYPTED_V2.len() + 3 + id.len() + usize::from(wrapped_len) + NONCE_LEN + value.len(),
+        );
+        if let Some(wrapped) = wrapped {
+            envelope.extend_from_slice(ENCRYPTED_V2);
+            envelope.extend_from_slice(&wrapped_len.to_be_bytes());
+            envelope.push(id_len);
+            envelope.extend_from_slice(id.as_bytes());
+            envelope.extend_from_slice(wrapped);
+        } else {
+            envelope.extend_from_slice(ENCRYPTED_V1);
+            envelope.push(id_len);
+            envelope.extend_from_slice(id.as_bytes());
+        }
+        envelope.extend_from_slice(&nonce);
+        envelope.extend(value);
+        Ok(envelope)
+    }
+
+    pub(crate) fn open_inner(&self, key: &str, envelope: &[u8]) -> Result<Vec<u8>, LayerError> {
+        let Envelope::Encrypted {
+            key_id: id, sealed, ..
+        } = Envelope::read(envelope)
+        else {
+            return Err(crypto_error("open", key, "not an envelope"));
+        };
+        let unwrapped = self.unwrapped();
+        let cipher = self
+            .keys
+            .key(id)
+            .or_else(|| unwrapped.get(id))
+            .ok_or_else(|| crypto_error("open", key, &format!("unknown key id {id}")))?;
+        let (nonce, sealed) = sealed
+            .split_at_checked(NONCE_LEN)
+            .ok_or_else(|| crypto_error("open", key, "truncated envelope"))?;
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| crypto_error("open", key, "truncated envelope"))?;
+        let mut sealed = sealed.to_vec();
+
+        let plain_len = cipher
+            .open_in_place(nonce, Aad::from(key.as_bytes()), &mut sealed)
+            .map_err(|_| crypto_error("open", key, "decryption failed"))?
+            .len();
+        sealed.truncate(plain_len);
+        Ok(sealed)
+    }
+
+    /// Asks the provider once for the data key of an envelope sealed by another instance.
+    pub(crate) async fn resolve_inner(&self, envelope: &[u8]) -> Result<(), LayerError> {
+        let Envelope::Encrypted {
+            key_id: id,
+            wrapped_key,
+            ..
+        } = Envelope::read(envelope)
+        else {
+            return Ok(());
+        };
+        let Some(ref provider) = self.provider else {
+            return Ok(());
+        };
+        if wrapped_key.is_empty()
+            || self.keys.key(id).is_some()
+            || self.unwrapped().contains_key(id)
+        {
+            return Ok(());
+        }
+
+        let secret = provider.decrypt(wrapped_key).await?;
+        self.unwrapped().insert(id.to_owned(), cipher(&secret));
+        Ok(())
+    }
+
+    fn unwrapped(&self) -> MutexGuard<'_, HashMap<String, LessSafeKey>> {
+        self.unwrapped
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<STORAGE> Layer for Encrypted<STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
+
+#[must_use]
+pub(crate) fn overhead(header: &[u8]) -> Option<u64> {
+    let sealing = NONCE_LEN + AES_256_GCM.tag_len();
+    if let Some(rest) = header.strip_prefix(ENCRYPTED_V1) {
+        let &id_len = rest.first()?;
+        return Some((ENCRYPTED_V1.len() + 1 + usize::from(id_len) + sealing) as u64);
+    }
+
+    let (wrapped_len, rest) = header
+        .strip_prefix(ENCRYPTED_V2)?
+        .split_first_chunk::<2>()?;
+    let &id_len = rest.first()?;
+    let wrapped_len = usize::from(u16::from_be_bytes(*wrapped_len));
+    Some((ENCRYPTED_V2.len() + 3 + usize::from(id_len) + wrapped_len + sealing) as u64)
+}
+
+#[must_use]
+pub(crate) fn key_id(envelope: &[u8]) -> Option<&str> {
+    match Envelope::read(envelope) {
+        Envelope::Encrypted { key_id, .. } => Some(key_id),
+        Envelope::Plain(_) | Envelope::Compressed { .. } => None,
+    }
+}
+
+/// Derived from the wrapped key, the same data key gets the same id on every instance.
+fn data_key_id(wrapped: &[u8]) -> String {
+    format!("dk-{}", checksum(wrapped).get(..16).unwrap_or_default())
+}
+
+fn cipher(secret: &[u8; 32]) -> LessSafeKey {
+    LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, secret)
+            .unwrap_or_else(|_| unreachable!("secret is 32 bytes")),
+    )
+}
+
+fn crypto_error(operation: &str, key: &str, internal: &str) -> LayerError {
+    LayerError::Crypto {
+        operation: operation.to_owned(),
+        key: key.to_owned(),
+        internal: internal.to_owned(),
+    }
+}
@@ -0,0 +1,550 @@
+use core::ops::Range;
+#[cfg(feature = "tracing")]
+use std::sync::Arc;
+use std::time::Instant;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use hyper::client::HttpConnector;
+use hyper::header::{self, HeaderMap, HeaderValue};
+use hyper::{Body, Client, Method, Request, StatusCode, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+
+use crate::storage::health::HealthReport;
+use crate::storage::meta::ObjectMeta;
+#[cfg(feature = "tracing")]
+use crate::storage::telemetry::Propagator;
+use crate::storage::tls::TlsConfig;
+use crate::storage::{radix_key, slice_range, HttpError, ListKeyObjects};
+
+const PROPFIND_BODY: &[u8] = br#"<?xml version="1.0" encoding="utf-8"?><propfind xmlns="DAV:"><prop><resourcetype/></prop></propfind>"#;
+
+pub trait Auth: Send + Sync {
+    fn authorization(&self) -> Option<String>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoAuth;
+
+impl Auth for NoAuth {
+    #[inline]
+    fn authorization(&self) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Basic {
+    header: String,
+}
+
+impl Basic {
+    #[inline]
+    #[must_use]
+    pub fn new(user: &str, password: &str) -> Self {
+        Self {
+            header: format!("Basic {}", STANDARD.encode(format!("{user}:{password}"))),
+        }
+    }
+}
+
+impl Auth for Basic {
+    #[inline]
+    fn authorization(&self) -> Option<String> {
+        Some(self.header.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bearer {
+    token: String,
+}
+
+impl Bearer {
+    #[inline]
+    #[must_use]
+    pub fn new(token: &str) -> Self {
+        Self {
+            token: token.to_owned(),
+        }
+    }
+}
+
+impl Auth for Bearer {
+    #[inline]
+    fn authorization(&self) -> Option<String> {
+        Some(format!("Bearer {}", self.token))
+    }
+}
+
+struct Response {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+pub struct Http<AUTH = NoAuth> {
+    client: Client<HttpsConnector<HttpConnector>>,
+    base: String,
+    base_path: String,
+    auth: AUTH,
+    #[cfg(feature = "tracing")]
+    propagator: Option<Arc<dyn Propagator>>,
+}
+
+impl Http {
+    #[inline]
+    pub fn new(base: &str) -> Result<Self, HttpError> {
+        let connector = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        Self::with_connector(base, connector)
+    }
+
+    #[inline]
+    pub fn with_tls(base: &str, tls: &TlsConfig) -> Result<Self, HttpError> {
+        Self::with_connector(base, tls.https_connector()?)
+    }
+
+    fn with_connector(
+        base: &str,
+        connector: HttpsConnector<HttpConnector>,
+    ) -> Result<Self, HttpError> {
+        let base = if base.ends_with('/') {
+            base.to_owned()
+        } else {
+            format!("{base}/")
+        };
+        let uri = base
+            .parse::<Uri>()
+            .map_err(|err| HttpError::Url(format!("{base}: {err}")))?;
+        if !matches!(uri.scheme_str(), Some("http" | "https")) {
+            return Err(HttpError::Url(format!("{base}: expected http or https")));
+        }
+
+        Ok(Self {
+            client: Client::builder().build(connector),
+            base_path: uri.path().to_owned(),
+            base,
+            auth: NoAuth,
+            #[cfg(feature = "tracing")]
+            propagator: None,
+        })
+    }
+}
+
+impl<AUTH> Http<AUTH>
+where
+    AUTH: Auth,
+{
+    #[inline]
+    pub fn with_auth<OTHER>(self, auth: OTHER) -> Http<OTHER>
+    where
+        OTHER: Auth,
+    {
+        Http {
+            client: self.client,
+            base: self.base,
+            base_path: self.base_path,
+            auth,
+            #[cfg(feature = "tracing")]
+            propagator: self.propagator,
+        }
+    }
+
+    /// Writes the trace context of the current span in the headers of every request.
+    #[cfg(feature = "tracing")]
+    #[inline]
+    #[must_use]
+    pub fn with_propagator<PROPAGATOR>(mut self, propagator: PROPAGATOR) -> Self
+    where
+        PROPAGATOR: Propagator + 'static,
+    {
+        self.propagator = Some(Arc::new(propagator));
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub(crate) async fn exists_inner(&self, key: &str) -> Result<bool, HttpError> {
+        Ok(self.head_inner(key).await?.is_some())
+    }
+
+    pub(crate) async fn head_inner(&self, key: &str) -> Result<Option<ObjectMeta>, HttpError> {
+        let response = self.send(Method::HEAD, key, vec![], vec![]).await?;
+
+        match response.status {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let header = |name| {
+                    response
+                        .headers
+                        .get(name)
+                        .and_then(|value: &HeaderValue| value.to_str().ok())
+                        .map(ToOwned::to_owned)
+                };
+                Ok(Some(ObjectMeta {
+                    size: header(header::CONTENT_LENGTH)
+                        .and_then(|len| len.parse().ok())
+                        .unwrap_or_default(),
+                    etag: header(header::ETAG),
+                    checksum: None,
+                    mime: header(header::CONTENT_TYPE),
+                    last_modified: None,
+                }))
+            }
+            status => Err(status_error("head", key, status)),
+        }
+    }
+
+    pub(crate) async fn get_bytes_inner(&self, key: &str) -> Result<Option<Vec<u8>>, HttpError> {
+        let response = self.send(Method::GET, key, vec![], vec![]).await?;
+
+        match response.status {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(response.body)),
+            status => Err(status_error("get_bytes", key, status)),
+        }
+    }
+
+    pub(crate) async fn get_range_inner(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, HttpError> {
+        if range.start >= range.end {
+            return Ok(self.head_inner(key).await?.map(|_| vec![]));
+        }
+
+        let bytes = format!("bytes={}-{}", range.start, range.end - 1);
+        let response = self
+            .send(Method::GET, key, vec![(header::RANGE, bytes)], vec![])
+            .await?;
+
+        match response.status {
+            StatusCode::NOT_FOUND => Ok(None),
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(Some(vec![])),
+            StatusCode::PARTIAL_CONTENT => Ok(Some(response.body)),
+            status if status.is_success() => Ok(Some(slice_range(&response.body, &range))),
+            status => Err(status_error("get_range", key, status)),
+        }
+    }
+
+    pub(crate) async fn put_bytes_inner(
+        &self,
+        key: &str,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), HttpError> {
+        let headers = if mime.is_empty() {
+            vec![]
+        } else {
+            vec![(header::CONTENT_TYPE, mime)]
+        };
+        let response = self
+            .send(Method::PUT, key, headers.clone(), value.clone())
+            .await?;
+
+        let status = if response.status == StatusCode::CONFLICT {
+            self.create_collections(key).await?;
+            self.send(Method::PUT, key, headers, value).await?.status
+        } else {
+            response.status
+        };
+
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(status_error("put_bytes", key, status))
+        }
+    }
+
+    #[inline]
+    pub async fn delete(&self, key: &str) -> Result<bool, HttpError> {
+        let response = self.send(Method::DELETE, key, vec![], vec![]).await?;
+
+        match response.status {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(status_error("delete", key, status)),
+        }
+    }
+
+    pub(crate) async fn list_objects_inner(
+        &self,
+        prefix: &str,
+    ) -> Result<ListKeyObjects, HttpError> {
+        let collection = prefix
+            .rfind('/')
+            .and_then(|index| prefix.get(..=index))
+            .unwrap_or_default();
+        let response = self
+            .send(
+                webdav_method("PROPFIND")?,
+                collection,
+                vec![
+                    (header::HeaderName::from_static("depth"), "1".to_owned()),
+                    (header::CONTENT_TYPE, "application/xml".to_owned()),
+                ],
+                PROPFIND_BODY.to_vec(),
+            )
+            .await?;
+
+        match response.status {
+            StatusCode::NOT_FOUND => Ok(ListKeyObjects::default()),
+            status if status.is_success() => Ok(self
+                .keys_from_multistatus(&String::from_utf8_lossy(&response.body))
+                .into_iter()
+                .filter(|key| key != collection && key.starts_with(prefix))
+                .filter_map(|key| radix_key(prefix, &key))
+                .collect()),
+            status => Err(status_error("list_objects", prefix, status)),
+        }
+    }
+
+    pub(crate) async fn health_inner(&self) -> HealthReport {
+        let mut report = HealthReport::new("http");
+        let start = Instant::now();
+        let response = match webdav_method("PROPFIND") {
+            Ok(method) => {
+                self.send(
+                    method,
+                    "",
+                    vec![(header::HeaderName::from_static("depth"), "0".to_owned())],
+                    PROPFIND_BODY.to_vec(),
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+        report.latency = Some(start.elapsed());
+
+        match response {
+            Ok(response) if response.status.is_success() => {
+                report.bucket_exists = Some(true);
+                report.credentials_valid = Some(true);
+            }
+            Ok(response) => {
+                match response.status {
+                    StatusCode::NOT_FOUND => report.bucket_exists = Some(false),
+                    StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                        report.credentials_valid = Some(false);
+                    }
+                    _ => {}
+                }
+                report.error = Some(format!(
+                    "PROPFIND {} returned {}",
+                    self.base, response.status
+                ));
+            }
+            Err(err) => {
+                report.reachable = false;
+                report.error = Some(err.to_string());
+            }
+        }
+
+        report
+    }
+
+    pub(crate) fn keys_from_multistatus(&self, body: &str) -> Vec<String> {
+        let base_path = decode_path(&self.base_path);
+        hrefs(body)
+            .into_iter()
+            .filter_map(|href| {
+                let path = if href.starts_with("http://") || href.starts_with("https://") {
+                    href.parse::<Uri>().ok()?.path().to_owned()
+                } else {
+                    href
+                };
+                decode_path(&path)
+                    .strip_prefix(base_path.as_str())
+                    .map(ToOwned::to_owned)
+            })
+            .collect()
+    }
+
+    async fn create_collections(&self, key: &str) -> Result<(), HttpError> {
+        let mkcol = webdav_method("MKCOL")?;
+        let mut collection = String::new();
+
+        for segment in key
+            .split('/')
+            .rev()
+            .skip(1)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            collection.push_str(segment);
+            collection.push('/');
+            let status = self
+                .send(mkcol.clone(), &collection, vec![], vec![])
+                .await?
+                .status;
+            if !status.is_success() && status != StatusCode::METHOD_NOT_ALLOWED {
+                return Err(status_error("create_collection", &collection, status));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        headers: Vec<(header::HeaderName, String)>,
+        body: Vec<u8>,
+    ) -> Result<Response, HttpError> {
+        let operation = method.to_string();
+        let request_error = |internal: String| HttpError::Request {
+            operation: operation.clone(),
+            key: key.to_owned(),
+            internal,
+        };
+
+        let mut request =
+            Request::builder()
+                .method(method)
+                .uri(format!("{}{}", self.base, encode_path(key)));
+        if let Some(authorization) = self.auth.authorization() {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        #[cfg(feature = "tracing")]
+        if let Some(ref propagator) = self.propagator {
+            let mut propagated = vec![];
+            propagator.inject(&tracing::Span::current(), &mut propagated);
+            for (name, value) in propagated {
+                request = request.header(name, value);
+            }
+        }
+        let request = request
+            .body(Body::from(body))
+            .map_err(|err| request_error(err.to_string()))?;
+
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(|err| request_error(err.to_string()))?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|err| request_error(err.to_string()))?;
+
+        Ok(Response {
+            status,
+            headers,
+            body: body.to_vec(),
+        })
+    }
+}
+
+fn webdav_method(name: &str) -> Result<Method, HttpError> {
+    Method::from_bytes(name.as_bytes()).map_err(|err| HttpError::Request {
+        operation: name.to_owned(),
+        key: String::new(),
+        internal: err.to_string(),
+    })
+}
+
+fn status_error(operation: &str, key: &str, status: StatusCode) -> HttpError {
+    if matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) {
+        HttpError::Throttled {
+            operation: operation.to_owned(),
+            key: key.to_owned(),
+        }
+    } else {
+        HttpError::Status {
+            operation: operation.to_owned(),
+            key: key.to_owned(),
+            status: status.as_u16(),
+        }
+    }
+}
+
+pub(crate) fn encode_path(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+
+    for byte in key.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
+pub(crate) fn decode_path(path: &str) -> String {
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| core::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = tail.get(2..).unwrap_or_default();
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn hrefs(body: &str) -> Vec<String> {
+    let mut hrefs = vec![];
+    let mut rest = body;
+
+    while let Some(start) = rest.find('<') {
+        rest = rest.get(start + 1..).unwrap_or_default();
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = rest.get(..end).unwrap_or_default();
+        rest = rest.get(end + 1..).unwrap_or_default();
+
+        let name = tag
+            .split_whitespace()
+            .next()
+            .and_then(|name| name.rsplit(':').next())
+            .unwrap_or_default();
+        if name.eq_ignore_ascii_case("href") && !tag.starts_with('/') {
+            let content = rest.get(..rest.find('<').unwrap_or(rest.len()));
+            hrefs.push(unescape_xml(content.unwrap_or_default().trim()));
+        }
+    }
+
+    hrefs
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,312 @@
+use core::ops::Range;
+use std::time::Instant;
+
+use hyper::client::HttpConnector;
+use hyper::{header, Body, Client, Method, Request, StatusCode, Uri};
+use serde::Deserialize;
+
+use crate::storage::health::HealthReport;
+use crate::storage::meta::ObjectMeta;
+use crate::storage::sink::http::encode_path;
+use crate::storage::{HttpError, ListKeyObjects, ParserError, ResultExt as _};
+
+const BOUNDARY: &str = "negentropy-ipfs-boundary";
+
+#[derive(Deserialize)]
+struct Added {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct Stat {
+    #[serde(rename = "Size")]
+    size: u64,
+}
+
+#[derive(Deserialize)]
+struct Pins {
+    #[serde(rename = "Keys", default)]
+    keys: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    #[serde(rename = "Message", default)]
+    message: String,
+}
+
+pub struct Ipfs {
+    client: Client<HttpConnector>,
+    api: String,
+}
+
+impl Ipfs {
+    #[inline]
+    pub fn new(api: &str) -> Result<Self, HttpError> {
+        let api = format!("{}/api/v0/", api.trim_end_matches('/'));
+        let uri = api
+            .parse::<Uri>()
+            .map_err(|err| HttpError::Url(format!("{api}: {err}")))?;
+        if uri.scheme_str() != Some("http") {
+            return Err(HttpError::Url(format!("{api}: expected http")));
+        }
+
+        Ok(Self {
+            client: Client::new(),
+            api,
+        })
+    }
+
+    #[inline]
+    pub async fn add(&self, value: Vec<u8>) -> Result<String, HttpError> {
+        self.add_with("cid-version=1&pin=true", &value).await
+    }
+
+    /// CID the node would give `value`, without storing nor pinning it.
+    #[inline]
+    pub async fn hash(&self, value: &[u8]) -> Result<String, HttpError> {
+        self.add_with("cid-version=1&only-hash=true&pin=false", value)
+            .await
+    }
+
+    pub(crate) async fn exists_inner(&self, cid: &str) -> Result<bool, HttpError> {
+        Ok(self.head_inner(cid).await?.is_some())
+    }
+
+    pub(crate) async fn head_inner(&self, cid: &str) -> Result<Option<ObjectMeta>, HttpError> {
+        if !is_cid(cid) {
+            return Ok(None);
+        }
+        let arg = format!("arg=/ipfs/{}&offline=true", encode_path(cid));
+
+        match self.call("files/stat", &arg, None, cid).await? {
+            Ok(response) => Ok(Some(ObjectMeta {
+                size: parse_json::<Stat>(&response)
+                    .context("ipfs", "head", cid)?
+                    .size,
+                etag: Some(cid.to_owned()),
+                ..ObjectMeta::default()
+            })),
+            Err(StatusCode::NOT_FOUND) => Ok(None),
+            Err(status) => Err(status_error("head", cid, status)),
+        }
+    }
+
+    pub(crate) async fn get_bytes_inner(&self, cid: &str) -> Result<Option<Vec<u8>>, HttpError> {
+        self.cat(cid, None).await
+    }
+
+    pub(crate) async fn get_range_inner(
+        &self,
+        cid: &str,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, HttpError> {
+        self.cat(cid, Some(range)).await
+    }
+
+    /// Only pins `value` once its CID is known to be `cid`, a mismatch leaves no pin behind.
+    pub(crate) async fn put_bytes_inner(&self, cid: &str, value: Vec<u8>) -> Result<(), HttpError> {
+        let hashed = self.hash(&value).await?;
+        if hashed != cid {
+            return Err(HttpError::Request {
+                operation: "put_bytes".to_owned(),
+                key: cid.to_owned(),
+                internal: format!("content is addressed by {hashed}"),
+            });
+        }
+
+        let added = self.add(value).await?;
+        if added != cid {
+            self.delete_inner(&added).await?;
+            return Err(HttpError::Request {
+                operation: "put_bytes".to_owned(),
+                key: cid.to_owned(),
+                internal: format!("content was pinned as {added}"),
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn delete_inner(&self, cid: &str) -> Result<(), HttpError> {
+        if !is_cid(cid) {
+            return Ok(());
+        }
+        let arg = format!("arg={}", encode_path(cid));
+
+        match self.call("pin/rm", &arg, None, cid).await? {
+            Ok(_) | Err(StatusCode::NOT_FOUND) => Ok(()),
+            Err(status) => Err(status_error("delete", cid, status)),
+        }
+    }
+
+    pub(crate) async fn list_objects_inner(
+        &self,
+        prefix: &str,
+    ) -> Result<ListKeyObjects, HttpError> {
+        let response = self
+            .call("pin/ls", "type=recursive", None, prefix)
+            .await?
+            .map_err(|status| status_error("list_objects", prefix, status))?;
+
+        Ok(parse_json::<Pins>(&response)
+            .context("ipfs", "list_objects", prefix)?
+            .keys
+            .into_iter()
+            .map(|(cid, _)| cid)
+            .filter(|cid| cid.starts_with(prefix))
+            .collect())
+    }
+
+    pub(crate) async fn health_inner(&self) -> HealthReport {
+        let mut report = HealthReport::new("ipfs");
+        let start = Instant::now();
+        let version = self.call("version", "", None, "").await;
+        report.latency = Some(start.elapsed());
+
+        match version {
+            Ok(Ok(_)) => {}
+            Ok(Err(status)) => report.error = Some(format!("version returned {status}")),
+            Err(err) => {
+                report.reachable = false;
+                report.error = Some(err.to_string());
+            }
+        }
+
+        report
+    }
+
+    async fn add_with(&self, query: &str, value: &[u8]) -> Result<String, HttpError> {
+        let response = self
+            .call("add", query, Some(multipart_body(value)), "")
+            .await?
+            .map_err(|status| status_error("add", "", status))?;
+
+        Ok(parse_json::<Added>(&response)
+            .context("ipfs", "add", "")?
+            .hash)
+    }
+
+    async fn cat(
+        &self,
+        cid: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<Option<Vec<u8>>, HttpError> {
+        if !is_cid(cid) {
+            return Ok(None);
+        }
+        let mut arg = format!("arg={}&offline=true", encode_path(cid));
+        if let Some(range) = range {
+            arg.push_str(&format!(
+                "&offset={}&length={}",
+                range.start,
+                range.end.saturating_sub(range.start)
+            ));
+        }
+
+        match self.call("cat", &arg, None, cid).await? {
+            Ok(content) => Ok(Some(content)),
+            Err(StatusCode::NOT_FOUND) => Ok(None),
+            Err(status) => Err(status_error("get_bytes", cid, status)),
+        }
+    }
+
+    async fn call(
+        &self,
+        command: &str,
+        query: &str,
+        body: Option<Vec<u8>>,
+        key: &str,
+    ) -> Result<Result<Vec<u8>, StatusCode>, HttpError> {
+        let request_error = |internal: String| HttpError::Request {
+            operation: command.to_owned(),
+            key: key.to_owned(),
+            internal,
+        };
+
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("{}{command}?{query}", self.api));
+        if body.is_some() {
+            request = request.header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            );
+        }
+        let request = request
+            .body(Body::from(body.unwrap_or_default()))
+            .map_err(|err| request_error(err.to_string()))?;
+
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(|err| request_error(err.to_string()))?;
+        let status = response.status();
+        let content = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|err| request_error(err.to_string()))?
+            .to_vec();
+
+        if status.is_success() {
+            Ok(Ok(content))
+        } else {
+            Ok(Err(api_status(status, &content)))
+        }
+    }
+}
+
+#[must_use]
+pub(crate) fn is_cid(key: &str) -> bool {
+    let base58 =
+        |byte: u8| byte.is_ascii_alphanumeric() && !matches!(byte, b'0' | b'O' | b'I' | b'l');
+
+    if key.len() == 46 && key.starts_with("Qm") {
+        key.bytes().all(base58)
+    } else if let Some(rest) = key.strip_prefix('b') {
+        rest.len() >= 32
+            && rest
+                .bytes()
+                .all(|byte| byte.is_ascii_lowercase() || matches!(byte, b'2'..=b'7'))
+    } else {
+        false
+    }
+}
+
+pub(crate) fn multipart_body(value: &[u8]) -> Vec<u8> {
+    let mut body = format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; \
+         filename=\"blob\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(value);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    body
+}
+
+fn api_status(status: StatusCode, content: &[u8]) -> StatusCode {
+    let not_found = serde_json::from_slice::<ApiError>(content)
+        .is_ok_and(|err| err.message.contains("not found"));
+    if not_found {
+        StatusCode::NOT_FOUND
+    } else {
+        status
+    }
+}
+
+fn parse_json<RETURN>(content: &[u8]) -> Result<RETURN, ParserError>
+where
+    RETURN: for<'content> Deserialize<'content>,
+{
+    serde_json::from_slice(content).map_err(|err| ParserError::Serde {
+        internal: err.to_string(),
+    })
+}
+
+fn status_error(operation: &str, key: &str, status: StatusCode) -> HttpError {
+    HttpError::Status {
+        operation: operation.to_owned(),
+        key: key.to_owned(),
+        status: status.as_u16(),
+    }
+}
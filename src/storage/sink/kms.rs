@@ -0,0 +1,99 @@
+use aws_config::BehaviorVersion;
+use aws_sdk_kms::primitives::Blob;
+use aws_sdk_kms::types::DataKeySpec;
+use aws_sdk_kms::Client;
+use futures::future::BoxFuture;
+
+use crate::storage::sink::encrypted::{DataKey, KeyProvider};
+use crate::storage::LayerError;
+
+/// Data keys generated by GenerateDataKey under one KMS key, unwrapped by Decrypt on read.
+#[derive(Debug, Clone)]
+pub struct KmsKeyProvider {
+    client: Client,
+    key_id: String,
+}
+
+impl KmsKeyProvider {
+    #[inline]
+    #[must_use]
+    pub fn new(client: Client, key_id: &str) -> Self {
+        Self {
+            client,
+            key_id: key_id.to_owned(),
+        }
+    }
+
+    /// Client from the environment, like the S3 sink loads its own.
+    #[inline]
+    pub async fn from_env(key_id: &str) -> Self {
+        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+        Self::new(Client::new(&config), key_id)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    async fn generate_inner(&self) -> Result<DataKey, LayerError> {
+        let output = self
+            .client
+            .generate_data_key()
+            .key_id(&self.key_id)
+            .key_spec(DataKeySpec::Aes256)
+            .send()
+            .await
+            .map_err(|err| kms_error("generate", &self.key_id, &err.to_string()))?;
+
+        let secret = output
+            .plaintext()
+            .and_then(|plaintext| <[u8; 32]>::try_from(plaintext.as_ref()).ok())
+            .ok_or_else(|| kms_error("generate", &self.key_id, "no 32 bytes data key"))?;
+        let wrapped = output
+            .ciphertext_blob()
+            .map(|wrapped| wrapped.as_ref().to_vec())
+            .ok_or_else(|| kms_error("generate", &self.key_id, "no wrapped data key"))?;
+        Ok(DataKey { secret, wrapped })
+    }
+
+    async fn decrypt_inner(&self, wrapped: &[u8]) -> Result<[u8; 32], LayerError> {
+        let output = self
+            .client
+            .decrypt()
+            .key_id(&self.key_id)
+            .ciphertext_blob(Blob::new(wrapped))
+            .send()
+            .await
+            .map_err(|err| kms_error("decrypt", &self.key_id, &err.to_string()))?;
+
+        output
+            .plaintext()
+            .and_then(|plaintext| <[u8; 32]>::try_from(plaintext.as_ref()).ok())
+            .ok_or_else(|| kms_error("decrypt", &self.key_id, "no 32 bytes data key"))
+    }
+}
+
+impl KeyProvider for KmsKeyProvider {
+    #[inline]
+    fn generate(&self) -> BoxFuture<'_, Result<DataKey, LayerError>> {
+        Box::pin(self.generate_inner())
+    }
+
+    #[inline]
+    fn decrypt<'call>(
+        &'call self,
+        wrapped: &'call [u8],
+    ) -> BoxFuture<'call, Result<[u8; 32], LayerError>> {
+        Box::pin(self.decrypt_inner(wrapped))
+    }
+}
+
+fn kms_error(operation: &str, key_id: &str, internal: &str) -> LayerError {
+    LayerError::Crypto {
+        operation: operation.to_owned(),
+        key: key_id.to_owned(),
+        internal: internal.to_owned(),
+    }
+}
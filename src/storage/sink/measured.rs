@@ -0,0 +1,72 @@
+use core::time::Duration;
+use std::sync::Arc;
+
+use crate::storage::layer::Layer;
+use crate::storage::metrics::StorageMetrics;
+use crate::storage::DKey;
+
+pub struct Measured<STORAGE> {
+    backend: String,
+    metrics: Arc<dyn StorageMetrics>,
+    storage: STORAGE,
+}
+
+impl<STORAGE> Measured<STORAGE>
+where
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub fn new(backend: &str, metrics: Arc<dyn StorageMetrics>, storage: STORAGE) -> Self {
+        Self {
+            backend: backend.to_owned(),
+            metrics,
+            storage,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn observe_inner<DKEY>(
+        &self,
+        operation: &str,
+        key: &DKEY,
+        elapsed: Duration,
+        success: bool,
+    ) where
+        DKEY: DKey + ?Sized,
+    {
+        self.metrics
+            .record_operation(&self.backend, operation, &key.name(), elapsed, success);
+    }
+}
+
+impl<STORAGE> Layer for Measured<STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
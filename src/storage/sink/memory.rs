@@ -1,9 +1,21 @@
-use crate::storage::{radix_key, DKeyWhere, ListKeyObjects, MemoryError, ParserError};
+use core::ops::Range;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::storage::intern::{InternStats, Interner};
+use crate::storage::meta::{ListEntry, ListPage, LockMode, ObjectLock, ObjectMeta};
+use crate::storage::{radix_key, slice_range, DKeyWhere, ListKeyObjects, MemoryError};
 use crate::HashMap;
 
+pub(crate) const FLAT_PAGE_SIZE: usize = 1_000;
+
 #[derive(Default)]
 pub struct Memory {
-    data: HashMap<String, Vec<u8>>,
+    keys: Interner,
+    data: HashMap<Arc<str>, Vec<u8>>,
+    mimes: HashMap<Arc<str>, String>,
+    modified: HashMap<Arc<str>, SystemTime>,
+    locks: HashMap<Arc<str>, ObjectLock>,
 }
 
 impl Memory {
@@ -19,20 +31,126 @@ impl Memory {
         self.data.is_empty()
     }
 
+    #[inline]
+    #[must_use]
+    pub fn intern_stats(&self) -> InternStats {
+        self.keys.stats()
+    }
+
     #[inline]
     pub fn get_bytes<DKEY>(&mut self, key: &DKEY) -> Option<&Vec<u8>>
     where
         DKEY: DKeyWhere,
     {
-        self.data.get(&key.name())
+        self.data.get(key.name().as_ref())
+    }
+
+    pub(crate) fn get_bytes_inner(&self, key: &str) -> Option<Vec<u8>> {
+        self.data.get(key).cloned()
+    }
+
+    pub(crate) fn append_bytes_inner(
+        &mut self,
+        key: &str,
+        value: &[u8],
+    ) -> Result<(), MemoryError> {
+        self.refuse_locked(key)?;
+        let key = self.keys.intern(key);
+        self.modified.insert(Arc::clone(&key), SystemTime::now());
+        if let Some(content) = self.data.get_mut(&key) {
+            content.extend_from_slice(value);
+        } else {
+            self.data.insert(key, value.to_vec());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_range_inner(&self, key: &str, range: &Range<u64>) -> Option<Vec<u8>> {
+        self.data.get(key).map(|value| slice_range(value, range))
     }
 
     pub(crate) fn exists_inner(&self, key: &str) -> bool {
         self.data.contains_key(key)
     }
 
-    pub(crate) fn put_bytes_inner(&mut self, key: String, value: Vec<u8>) {
+    pub(crate) fn head_inner(&self, key: &str) -> Option<ObjectMeta> {
+        self.data.get(key).map(|value| ObjectMeta {
+            mime: self.mimes.get(key).cloned(),
+            last_modified: self.modified.get(key).copied(),
+            ..ObjectMeta::from_bytes(value)
+        })
+    }
+
+    pub(crate) fn put_bytes_inner(
+        &mut self,
+        key: &str,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), MemoryError> {
+        self.refuse_locked(key)?;
+        let key = self.keys.intern(key);
+        if mime.is_empty() {
+            self.mimes.remove(&key);
+        } else {
+            self.mimes.insert(Arc::clone(&key), mime);
+        }
+        self.modified.insert(Arc::clone(&key), SystemTime::now());
         self.data.insert(key, value);
+        Ok(())
+    }
+
+    pub(crate) fn touch_inner(&mut self, key: &str) -> bool {
+        let Some(modified) = self.modified.get_mut(key) else {
+            return false;
+        };
+        *modified = SystemTime::now();
+        true
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn lock(&self, key: &str) -> Option<ObjectLock> {
+        self.locks.get(key).copied()
+    }
+
+    #[inline]
+    pub fn release_governance(&mut self, key: &str) -> bool {
+        let governed = self
+            .locks
+            .get(key)
+            .is_some_and(|lock| lock.mode == LockMode::Governance);
+        if governed {
+            self.locks.remove(key);
+        }
+        governed
+    }
+
+    pub(crate) fn lock_inner(&mut self, key: &str, lock: ObjectLock) -> Result<(), MemoryError> {
+        if !self.data.contains_key(key) {
+            return Err(MemoryError::NotExists {
+                key: key.to_owned(),
+            });
+        }
+        if let Some(held) = self.locks.get(key) {
+            if held.mode == LockMode::Compliance && lock.retain_until < held.retain_until {
+                return Err(MemoryError::Locked {
+                    key: key.to_owned(),
+                    until: held.retain_until,
+                });
+            }
+        }
+        self.locks.insert(self.keys.intern(key), lock);
+        Ok(())
+    }
+
+    pub(crate) fn delete_inner(&mut self, key: &str) -> Result<bool, MemoryError> {
+        self.refuse_locked(key)?;
+        self.locks.remove(key);
+        self.mimes.remove(key);
+        self.modified.remove(key);
+        let removed = self.data.remove(key).is_some();
+        self.keys.release(key);
+        Ok(removed)
     }
 
     pub(crate) fn list_objects_inner(&self, prefix: &str) -> ListKeyObjects {
@@ -44,28 +162,62 @@ impl Memory {
             .collect()
     }
 
+    pub(crate) fn list_flat_inner(
+        &self,
+        prefix: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> ListPage {
+        let mut keys = self
+            .data
+            .keys()
+            .filter(|key| key.starts_with(prefix) && after.is_none_or(|after| &***key > after))
+            .collect::<Vec<_>>();
+        keys.sort_unstable();
+
+        let next = (keys.len() > limit)
+            .then(|| {
+                keys.get(limit.saturating_sub(1))
+                    .map(|key| (***key).to_owned())
+            })
+            .flatten();
+        let entries = keys
+            .into_iter()
+            .take(limit)
+            .map(|key| ListEntry {
+                key: (**key).to_owned(),
+                size: self
+                    .data
+                    .get(key)
+                    .map_or(0, |value| u64::try_from(value.len()).unwrap_or(u64::MAX)),
+            })
+            .collect();
+
+        ListPage { entries, next }
+    }
+
     pub(crate) fn put_object_inner<VALUE, PARSER>(
         &mut self,
-        key: String,
+        key: &str,
+        mime: String,
         value: &VALUE,
         parser: PARSER,
     ) -> Result<(), MemoryError>
     where
         PARSER: Fn(&VALUE) -> Result<Vec<u8>, MemoryError>,
     {
-        let serialize = parser(value);
+        let serialize = parser(value)?;
+        self.put_bytes_inner(key, mime, serialize)
+    }
 
-        match serialize {
-            Ok(res) => {
-                self.put_bytes_inner(key, res);
-                Ok(())
-            }
-            Err(err) => {
-                let memory_error = MemoryError::from(ParserError::Serde {
-                    internal: err.to_string(),
-                });
-                Err(memory_error)
-            }
+    // An object under an active retention can be neither overwritten, appended nor deleted.
+    fn refuse_locked(&self, key: &str) -> Result<(), MemoryError> {
+        match self.locks.get(key) {
+            Some(held) if held.is_active(SystemTime::now()) => Err(MemoryError::Locked {
+                key: key.to_owned(),
+                until: held.retain_until,
+            }),
+            _ => Ok(()),
         }
     }
 
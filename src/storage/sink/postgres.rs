@@ -0,0 +1,328 @@
+use core::ops::Range;
+use core::time::Duration;
+use std::time::{Instant, UNIX_EPOCH};
+
+use sqlx::postgres::{PgPool, PgRow};
+use sqlx::Row as _;
+
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{ListEntry, ListPage, ObjectMeta};
+use crate::storage::{radix_key, ListKeyObjects, PostgresError};
+
+const DEFAULT_TABLE: &str = "negentropy_objects";
+const PAGE_SIZE: i64 = 1000;
+
+/// Objects stored in one table, `key` is ordered bytewise so listings match the other sinks.
+pub struct Postgres {
+    pool: PgPool,
+    table: String,
+}
+
+impl Postgres {
+    #[inline]
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            table: DEFAULT_TABLE.to_owned(),
+        }
+    }
+
+    #[inline]
+    pub async fn connect(url: &str) -> Result<Self, PostgresError> {
+        let pool = PgPool::connect(url)
+            .await
+            .map_err(|err| query_error("connect", "", &err))?;
+        Ok(Self::new(pool))
+    }
+
+    /// `table` may be qualified by its schema, each part must be a plain identifier.
+    #[inline]
+    pub fn with_table(mut self, table: &str) -> Result<Self, PostgresError> {
+        if !is_table(table) {
+            return Err(PostgresError::Table(table.to_owned()));
+        }
+        table.clone_into(&mut self.table);
+        Ok(self)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    #[inline]
+    pub async fn migrate(&self) -> Result<(), PostgresError> {
+        let statement = format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                key TEXT COLLATE \"C\" PRIMARY KEY,
+                bytes BYTEA NOT NULL,
+                mime TEXT NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+            self.table
+        );
+        sqlx::query(&statement)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| query_error("migrate", &self.table, &err))?;
+        Ok(())
+    }
+
+    pub(crate) async fn exists_inner(&self, key: &str) -> Result<bool, PostgresError> {
+        let statement = format!("SELECT 1 FROM {} WHERE key = $1", self.table);
+        let row = sqlx::query(&statement)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| query_error("exists", key, &err))?;
+        Ok(row.is_some())
+    }
+
+    pub(crate) async fn head_inner(&self, key: &str) -> Result<Option<ObjectMeta>, PostgresError> {
+        let statement = format!(
+            "SELECT octet_length(bytes)::int8 AS size, md5(bytes) AS etag, mime,
+                extract(epoch FROM updated_at)::float8 AS updated_at
+            FROM {} WHERE key = $1",
+            self.table
+        );
+        let row = sqlx::query(&statement)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| query_error("head", key, &err))?;
+        row.map(|row| parse_meta(&row).map_err(|err| query_error("head", key, &err)))
+            .transpose()
+    }
+
+    pub(crate) async fn get_bytes_inner(
+        &self,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>, PostgresError> {
+        let statement = format!("SELECT bytes FROM {} WHERE key = $1", self.table);
+        sqlx::query_scalar(&statement)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| query_error("get_bytes", key, &err))
+    }
+
+    pub(crate) async fn get_range_inner(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, PostgresError> {
+        // `substring` counts from 1 and takes a length, both as `int4`.
+        let statement = format!(
+            "SELECT substring(bytes FROM $2 FOR $3) FROM {} WHERE key = $1",
+            self.table
+        );
+        let start = i32::try_from(range.start.saturating_add(1)).unwrap_or(i32::MAX);
+        let length = i32::try_from(range.end.saturating_sub(range.start)).unwrap_or(i32::MAX);
+        sqlx::query_scalar(&statement)
+            .bind(key)
+            .bind(start)
+            .bind(length)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| query_error("get_range", key, &err))
+    }
+
+    pub(crate) async fn put_bytes_inner(
+        &self,
+        key: &str,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<(), PostgresError> {
+        let statement = format!(
+            "INSERT INTO {} (key, bytes, mime) VALUES ($1, $2, $3)
+            ON CONFLICT (key) DO UPDATE
+            SET bytes = EXCLUDED.bytes, mime = EXCLUDED.mime, updated_at = now()",
+            self.table
+        );
+        sqlx::query(&statement)
+            .bind(key)
+            .bind(value)
+            .bind(mime)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| query_error("put_bytes", key, &err))?;
+        Ok(())
+    }
+
+    /// Concatenates in place, a missing object is created without a mime.
+    pub(crate) async fn append_bytes_inner(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+    ) -> Result<(), PostgresError> {
+        let statement = format!(
+            "INSERT INTO {0} (key, bytes, mime) VALUES ($1, $2, '')
+            ON CONFLICT (key) DO UPDATE
+            SET bytes = {0}.bytes || EXCLUDED.bytes, updated_at = now()",
+            self.table
+        );
+        sqlx::query(&statement)
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| query_error("append_bytes", key, &err))?;
+        Ok(())
+    }
+
+    pub(crate) async fn touch_inner(&self, key: &str) -> Result<bool, PostgresError> {
+        let statement = format!(
+            "UPDATE {} SET updated_at = now() WHERE key = $1",
+            self.table
+        );
+        let result = sqlx::query(&statement)
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| query_error("touch", key, &err))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub(crate) async fn delete_inner(&self, key: &str) -> Result<(), PostgresError> {
+        let statement = format!("DELETE FROM {} WHERE key = $1", self.table);
+        sqlx::query(&statement)
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| query_error("delete", key, &err))?;
+        Ok(())
+    }
+
+    pub(crate) async fn list_objects_inner(
+        &self,
+        prefix: &str,
+    ) -> Result<ListKeyObjects, PostgresError> {
+        let statement = format!(
+            "SELECT key FROM {} WHERE key LIKE $1 ESCAPE '\\'",
+            self.table
+        );
+        let keys: Vec<String> = sqlx::query_scalar(&statement)
+            .bind(like_prefix(prefix))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| query_error("list_objects", prefix, &err))?;
+
+        Ok(keys
+            .iter()
+            .filter_map(|key| radix_key(prefix, key))
+            .collect())
+    }
+
+    pub(crate) async fn list_flat_inner(
+        &self,
+        prefix: &str,
+        after: Option<String>,
+    ) -> Result<ListPage, PostgresError> {
+        let statement = format!(
+            "SELECT key, octet_length(bytes)::int8 AS size FROM {}
+            WHERE key LIKE $1 ESCAPE '\\' AND ($2::text IS NULL OR key > $2)
+            ORDER BY key LIMIT $3",
+            self.table
+        );
+        let rows = sqlx::query(&statement)
+            .bind(like_prefix(prefix))
+            .bind(after)
+            .bind(PAGE_SIZE + 1)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| query_error("list_flat", prefix, &err))?;
+
+        let mut entries = rows
+            .iter()
+            .map(|row| {
+                Ok(ListEntry {
+                    key: row.try_get("key")?,
+                    size: row.try_get::<i64, _>("size")?.unsigned_abs(),
+                })
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()
+            .map_err(|err| query_error("list_flat", prefix, &err))?;
+        let next = (entries.len() as u64 > PAGE_SIZE.unsigned_abs())
+            .then(|| {
+                entries.truncate(PAGE_SIZE.unsigned_abs() as usize);
+                entries.last().map(|entry| entry.key.clone())
+            })
+            .flatten();
+
+        Ok(ListPage { entries, next })
+    }
+
+    pub(crate) async fn health_inner(&self) -> HealthReport {
+        let mut report = HealthReport::new("postgres");
+        let start = Instant::now();
+        let statement = format!("SELECT 1 FROM {} LIMIT 1", self.table);
+        let probe = sqlx::query(&statement).fetch_optional(&self.pool).await;
+        report.latency = Some(start.elapsed());
+
+        match probe {
+            Ok(_) => report.bucket_exists = Some(true),
+            Err(sqlx::Error::Database(err)) if err.code().as_deref() == Some("42P01") => {
+                report.bucket_exists = Some(false);
+            }
+            Err(err) => {
+                report.reachable = false;
+                report.error = Some(err.to_string());
+            }
+        }
+
+        report
+    }
+}
+
+/// Plain identifiers only, the table name is interpolated in every statement.
+#[must_use]
+pub(crate) fn is_table(table: &str) -> bool {
+    let identifier = |part: &str| {
+        part.chars()
+            .next()
+            .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+            && part
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '_')
+    };
+
+    let mut parts = table.split('.');
+    parts.clone().count() <= 2 && parts.all(identifier)
+}
+
+#[must_use]
+pub(crate) fn like_prefix(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for char in prefix.chars() {
+        if matches!(char, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(char);
+    }
+    pattern.push('%');
+    pattern
+}
+
+fn parse_meta(row: &PgRow) -> Result<ObjectMeta, sqlx::Error> {
+    let updated_at = row.try_get::<f64, _>("updated_at")?;
+    let mime = row.try_get::<String, _>("mime")?;
+    Ok(ObjectMeta {
+        size: row.try_get::<i64, _>("size")?.unsigned_abs(),
+        etag: Some(row.try_get("etag")?),
+        checksum: None,
+        mime: (!mime.is_empty()).then_some(mime),
+        last_modified: Duration::try_from_secs_f64(updated_at)
+            .ok()
+            .map(|since| UNIX_EPOCH + since),
+    })
+}
+
+fn query_error(operation: &str, key: &str, err: &sqlx::Error) -> PostgresError {
+    PostgresError::Query {
+        operation: operation.to_owned(),
+        key: key.to_owned(),
+        internal: err.to_string(),
+    }
+}
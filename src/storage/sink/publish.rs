@@ -0,0 +1,132 @@
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+
+use core::future::Future;
+
+use futures::channel::mpsc::UnboundedSender;
+#[cfg(feature = "copy")]
+use serde::Serialize;
+
+use crate::storage::layer::Layer;
+use crate::storage::meta::ObjectMeta;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "copy", derive(Serialize))]
+#[cfg_attr(feature = "copy", serde(rename_all = "lowercase"))]
+pub enum ChangeOperation {
+    Put,
+    Append,
+    Delete,
+    Touch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "copy", derive(Serialize))]
+pub struct ChangeEvent {
+    pub key: String,
+    pub operation: ChangeOperation,
+    pub etag: Option<String>,
+    pub checksum: Option<String>,
+    pub size: u64,
+}
+
+pub trait Publisher: Send + Sync {
+    fn publish(&self, event: ChangeEvent) -> impl Future<Output = ()> + Send;
+}
+
+impl Publisher for UnboundedSender<ChangeEvent> {
+    #[inline]
+    fn publish(&self, event: ChangeEvent) -> impl Future<Output = ()> + Send {
+        match self.unbounded_send(event) {
+            Ok(()) => {}
+            #[cfg(feature = "tracing")]
+            Err(err) => {
+                let event = err.into_inner();
+                tracing::warn!(key = %event.key, "change event dropped, the receiver is gone");
+            }
+            #[cfg(not(feature = "tracing"))]
+            Err(_) => {}
+        }
+        async {}
+    }
+}
+
+/// JSON body shared by the broker publishers.
+#[cfg(any(feature = "kafka", feature = "nats"))]
+pub(crate) fn event_payload(event: &ChangeEvent) -> Option<Vec<u8>> {
+    match serde_json::to_vec(event) {
+        Ok(payload) => Some(payload),
+        #[cfg(feature = "tracing")]
+        Err(err) => {
+            tracing::warn!(key = %event.key, %err, "change event dropped, it does not serialize");
+            None
+        }
+        #[cfg(not(feature = "tracing"))]
+        Err(_) => None,
+    }
+}
+
+pub struct Publishing<PUBLISHER, STORAGE> {
+    publisher: PUBLISHER,
+    storage: STORAGE,
+}
+
+impl<PUBLISHER, STORAGE> Publishing<PUBLISHER, STORAGE>
+where
+    PUBLISHER: Publisher,
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub const fn new(publisher: PUBLISHER, storage: STORAGE) -> Self {
+        Self { publisher, storage }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn publisher(&self) -> &PUBLISHER {
+        &self.publisher
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn event_inner(
+        key: String,
+        operation: ChangeOperation,
+        meta: ObjectMeta,
+    ) -> ChangeEvent {
+        ChangeEvent {
+            key,
+            operation,
+            etag: meta.etag,
+            checksum: meta.checksum,
+            size: meta.size,
+        }
+    }
+}
+
+impl<PUBLISHER, STORAGE> Layer for Publishing<PUBLISHER, STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rskafka::chrono::{DateTime, Utc};
+use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+use rskafka::client::ClientBuilder;
+use rskafka::record::Record;
+
+use super::{event_payload, ChangeEvent, Publisher};
+use crate::storage::LayerError;
+
+/// Produce each change event as a JSON record keyed by the object key on one partition of a
+/// topic, so the events of a key stay ordered.
+#[derive(Debug)]
+pub struct KafkaPublisher {
+    partition: PartitionClient,
+}
+
+impl KafkaPublisher {
+    #[inline]
+    #[must_use]
+    pub const fn new(partition: PartitionClient) -> Self {
+        Self { partition }
+    }
+
+    #[inline]
+    pub async fn connect(brokers: Vec<String>, topic: &str) -> Result<Self, LayerError> {
+        let client = ClientBuilder::new(brokers)
+            .build()
+            .await
+            .map_err(|err| kafka_error("connect", topic, &err.to_string()))?;
+        let partition = client
+            .partition_client(topic, 0, UnknownTopicHandling::Retry)
+            .await
+            .map_err(|err| kafka_error("partition", topic, &err.to_string()))?;
+        Ok(Self::new(partition))
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn topic(&self) -> &str {
+        self.partition.topic()
+    }
+}
+
+impl Publisher for KafkaPublisher {
+    #[inline]
+    async fn publish(&self, event: ChangeEvent) {
+        let Some(record) = event_record(&event, now()) else {
+            return;
+        };
+        match self
+            .partition
+            .produce(vec![record], Compression::NoCompression)
+            .await
+        {
+            Ok(_) => {}
+            #[cfg(feature = "tracing")]
+            Err(err) => {
+                tracing::warn!(key = %event.key, %err, "change event dropped, Kafka refused it");
+            }
+            #[cfg(not(feature = "tracing"))]
+            Err(_) => {}
+        }
+    }
+}
+
+pub(crate) fn event_record(event: &ChangeEvent, timestamp: DateTime<Utc>) -> Option<Record> {
+    Some(Record {
+        key: Some(event.key.clone().into_bytes()),
+        value: Some(event_payload(event)?),
+        headers: BTreeMap::new(),
+        timestamp,
+    })
+}
+
+/// `chrono` is built without its clock.
+fn now() -> DateTime<Utc> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .and_then(|elapsed| i64::try_from(elapsed.as_millis()).ok())
+        .and_then(DateTime::from_timestamp_millis)
+        .unwrap_or_default()
+}
+
+fn kafka_error(operation: &str, topic: &str, internal: &str) -> LayerError {
+    LayerError::Queue {
+        operation: operation.to_owned(),
+        key: topic.to_owned(),
+        internal: internal.to_owned(),
+    }
+}
@@ -0,0 +1,62 @@
+use async_nats::Client;
+
+use super::{event_payload, ChangeEvent, Publisher};
+use crate::storage::LayerError;
+
+/// Publish each change event as a JSON message on one NATS subject.
+#[derive(Debug, Clone)]
+pub struct NatsPublisher {
+    client: Client,
+    subject: String,
+}
+
+impl NatsPublisher {
+    #[inline]
+    #[must_use]
+    pub fn new(client: Client, subject: &str) -> Self {
+        Self {
+            client,
+            subject: subject.to_owned(),
+        }
+    }
+
+    #[inline]
+    pub async fn connect(address: &str, subject: &str) -> Result<Self, LayerError> {
+        let client = async_nats::connect(address)
+            .await
+            .map_err(|err| LayerError::Queue {
+                operation: "connect".to_owned(),
+                key: subject.to_owned(),
+                internal: err.to_string(),
+            })?;
+        Ok(Self::new(client, subject))
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+}
+
+impl Publisher for NatsPublisher {
+    #[inline]
+    async fn publish(&self, event: ChangeEvent) {
+        let Some(payload) = event_payload(&event) else {
+            return;
+        };
+        match self
+            .client
+            .publish(self.subject.clone(), payload.into())
+            .await
+        {
+            Ok(()) => {}
+            #[cfg(feature = "tracing")]
+            Err(err) => {
+                tracing::warn!(key = %event.key, %err, "change event dropped, NATS refused it");
+            }
+            #[cfg(not(feature = "tracing"))]
+            Err(_) => {}
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use core::ops::Range;
+use std::sync::{Mutex, PoisonError};
+
+use crate::storage::layer::Layer;
+use crate::storage::meta::ObjectLock;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Call {
+    Exists {
+        key: String,
+    },
+    PutObjectIfNotExists {
+        key: String,
+        mime: String,
+    },
+    PutObject {
+        key: String,
+        mime: String,
+    },
+    PutBytes {
+        key: String,
+        mime: String,
+        size: usize,
+    },
+    PutBytesWith {
+        key: String,
+        mime: String,
+        size: usize,
+        lock: Option<ObjectLock>,
+    },
+    Lock {
+        key: String,
+        lock: ObjectLock,
+    },
+    Delete {
+        key: String,
+    },
+    AppendBytes {
+        key: String,
+        size: usize,
+    },
+    Touch {
+        key: String,
+    },
+    GetObject {
+        key: String,
+    },
+    GetBytes {
+        key: String,
+    },
+    Head {
+        key: String,
+    },
+    GetRange {
+        key: String,
+        range: Range<u64>,
+    },
+    ListObjects {
+        prefix: String,
+    },
+    ListFlatPage {
+        prefix: String,
+        continuation: Option<String>,
+    },
+}
+
+impl Call {
+    #[inline]
+    #[must_use]
+    pub const fn operation(&self) -> &'static str {
+        match *self {
+            Self::Exists { .. } => "exists",
+            Self::PutObjectIfNotExists { .. } => "put_object_if_not_exists",
+            Self::PutObject { .. } => "put_object",
+            Self::PutBytes { .. } => "put_bytes",
+            Self::PutBytesWith { .. } => "put_bytes_with",
+            Self::Lock { .. } => "lock",
+            Self::Delete { .. } => "delete",
+            Self::AppendBytes { .. } => "append_bytes",
+            Self::Touch { .. } => "touch",
+            Self::GetObject { .. } => "get_object",
+            Self::GetBytes { .. } => "get_bytes",
+            Self::Head { .. } => "head",
+            Self::GetRange { .. } => "get_range",
+            Self::ListObjects { .. } => "list_objects",
+            Self::ListFlatPage { .. } => "list_flat_page",
+        }
+    }
+
+    /// The key of the call, or the prefix for listings.
+    #[inline]
+    #[must_use]
+    pub fn key(&self) -> &str {
+        match *self {
+            Self::Exists { ref key }
+            | Self::PutObjectIfNotExists { ref key, .. }
+            | Self::PutObject { ref key, .. }
+            | Self::PutBytes { ref key, .. }
+            | Self::PutBytesWith { ref key, .. }
+            | Self::Lock { ref key, .. }
+            | Self::Delete { ref key }
+            | Self::AppendBytes { ref key, .. }
+            | Self::Touch { ref key }
+            | Self::GetObject { ref key }
+            | Self::GetBytes { ref key }
+            | Self::Head { ref key }
+            | Self::GetRange { ref key, .. } => key,
+            Self::ListObjects { ref prefix } | Self::ListFlatPage { ref prefix, .. } => prefix,
+        }
+    }
+}
+
+/// Forward every operation to the wrapped storage and remember the calls in the order they were
+/// made, for tests asserting what a cache or a conditional write actually sent to the backend.
+pub struct RecordingSink<STORAGE> {
+    calls: Mutex<Vec<Call>>,
+    storage: STORAGE,
+}
+
+impl<STORAGE> RecordingSink<STORAGE>
+where
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub const fn new(storage: STORAGE) -> Self {
+        Self {
+            calls: Mutex::new(vec![]),
+            storage,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn operations(&self) -> Vec<&'static str> {
+        self.calls
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(Call::operation)
+            .collect()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn count(&self, operation: &str) -> usize {
+        self.calls
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|call| call.operation() == operation)
+            .count()
+    }
+
+    /// `true` when the first `first` call happened before the first `then` call, `false` when
+    /// either was never made.
+    #[inline]
+    #[must_use]
+    pub fn called_before(&self, first: &str, then: &str) -> bool {
+        let operations = self.operations();
+        let position = |operation| {
+            operations
+                .iter()
+                .position(|&recorded| recorded == operation)
+        };
+        position(first)
+            .zip(position(then))
+            .is_some_and(|(first, then)| first < then)
+    }
+
+    /// Return the calls recorded so far and start over with an empty log.
+    #[inline]
+    pub fn take(&self) -> Vec<Call> {
+        core::mem::take(&mut *self.calls.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    pub(crate) fn record_inner(&self, call: Call) {
+        self.calls
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(call);
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+}
+
+impl<STORAGE> Layer for RecordingSink<STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
@@ -0,0 +1,74 @@
+pub struct Router<SINK> {
+    routes: Vec<(String, SINK)>,
+    fallback: SINK,
+}
+
+impl<SINK> Router<SINK>
+where
+    SINK: Send + Sync,
+{
+    #[inline]
+    pub const fn new(fallback: SINK) -> Self {
+        Self {
+            routes: vec![],
+            fallback,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_route(mut self, prefix: &str, sink: SINK) -> Self {
+        self.routes.retain(|(route, _)| route != prefix);
+        let position = self
+            .routes
+            .iter()
+            .position(|(route, _)| route.len() < prefix.len())
+            .unwrap_or(self.routes.len());
+        self.routes.insert(position, (prefix.to_owned(), sink));
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    pub(crate) fn route_inner(&self, key: &str) -> &SINK {
+        self.routes
+            .iter()
+            .find(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .map_or(&self.fallback, |(_, sink)| sink)
+    }
+
+    pub(crate) fn route_mut_inner(&mut self, key: &str) -> &mut SINK {
+        match self
+            .routes
+            .iter_mut()
+            .find(|&&mut (ref prefix, _)| key.starts_with(prefix.as_str()))
+        {
+            Some(&mut (_, ref mut sink)) => sink,
+            None => &mut self.fallback,
+        }
+    }
+
+    pub(crate) fn nested_inner<'router>(
+        &'router self,
+        prefix: &'router str,
+    ) -> impl Iterator<Item = &'router SINK> {
+        self.routes
+            .iter()
+            .filter(move |(route, _)| route.len() > prefix.len() && route.starts_with(prefix))
+            .map(|(_, sink)| sink)
+    }
+
+    pub(crate) fn sinks_inner(&self) -> impl Iterator<Item = &SINK> {
+        core::iter::once(&self.fallback).chain(self.routes.iter().map(|(_, sink)| sink))
+    }
+}
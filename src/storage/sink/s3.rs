@@ -1,96 +1,1308 @@
+use core::error::Error;
+use core::future::Future;
+use core::ops::Range;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
 use std::env;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::time::{Instant, SystemTime};
 
 use aws_config::{BehaviorVersion, Region};
-use aws_sdk_s3::config::Builder;
-use aws_sdk_s3::error::SdkError;
+use aws_sdk_s3::config::endpoint::{ResolveEndpoint, SharedEndpointResolver};
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::config::interceptors::BeforeTransmitInterceptorContextMut;
+use aws_sdk_s3::config::{
+    Builder, ConfigBag, HttpClient, Intercept, IntoShared as _, RuntimeComponents, SharedHttpClient,
+};
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
 use aws_sdk_s3::operation::get_object::{GetObjectError, GetObjectOutput};
-use aws_sdk_s3::operation::head_object::HeadObjectError;
+use aws_sdk_s3::operation::head_object::{HeadObjectError, HeadObjectOutput};
 use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Output;
-use aws_sdk_s3::primitives::{AggregatedBytes, ByteStream};
+use aws_sdk_s3::operation::{RequestId as _, RequestIdExt as _};
+use aws_sdk_s3::primitives::{AggregatedBytes, ByteStream, DateTime, SdkBody};
+use aws_sdk_s3::types::{
+    self, BucketInfo, BucketLifecycleConfiguration, BucketLocationConstraint, BucketType,
+    BucketVersioningStatus, ChecksumAlgorithm, CompletedMultipartUpload, CompletedPart,
+    CreateBucketConfiguration, DataRedundancy, ExpirationStatus, LifecycleExpiration,
+    LifecycleRuleFilter, LocationInfo, LocationType, ObjectLockMode, ObjectLockRetention,
+    ObjectLockRetentionMode, RequestPayer, Transition, TransitionStorageClass,
+    VersioningConfiguration,
+};
 use aws_sdk_s3::Client;
+#[cfg(feature = "tls")]
+use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
+#[cfg(feature = "tls")]
+use hyper::client::HttpConnector;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+#[cfg(feature = "copy")]
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+use uuid::Uuid;
 
+use crate::storage::clock::{Clock, SystemClock};
+use crate::storage::cost::{CostReport, Operation};
+use crate::storage::health::HealthReport;
+use crate::storage::meta::{
+    checksum_from_base64, ListEntry, ListPage, LockMode, ObjectLock, ObjectMeta,
+};
+#[cfg(feature = "tracing")]
+use crate::storage::telemetry::Propagator;
+use crate::storage::tls::TlsConfig;
 use crate::storage::{
-    DeserializeWhere, ListKeyObjects, ReturnWhere, S3Error, SerializeWhere, ValueWhere,
+    DeserializeWhere, ListKeyObjects, RequestIds, ReturnWhere, S3Error, SerializeWhere, TlsError,
+    ValueWhere,
 };
+use crate::HashMap;
+
+const SELFCHECK_PREFIX: &str = ".negentropy/selfcheck/";
+const SELFCHECK_CONTENT: &[u8] = b"negentropy selfcheck";
+const BUCKET_POLICY_KEY: &str = ".negentropy/bucket-policy.toml";
+const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_PART_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+const HEAD_CACHE_MAX: usize = 10_000;
+const DIRECTORY_SUFFIX: &str = "--x-s3";
+const RECONNECT_ATTEMPTS: u32 = 4;
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(200);
+/// Everything but the unreserved characters and the `/` separators is encoded in a copy source.
+const COPY_SOURCE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// How the bucket is addressed. A directory bucket (S3 Express One Zone) is named
+/// `<base>--<zone-id>--x-s3`, authenticates with short lived sessions the SDK opens on demand,
+/// only lists under prefixes ending with `/` and returns keys in no particular order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "copy", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "copy", serde(rename_all = "lowercase"))]
+pub enum BucketMode {
+    #[default]
+    General,
+    Directory,
+}
+
+impl BucketMode {
+    #[inline]
+    #[must_use]
+    pub fn from_bucket(bucket: &str) -> Self {
+        if bucket.ends_with(DIRECTORY_SUFFIX) {
+            Self::Directory
+        } else {
+            Self::General
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn zone_id(bucket: &str) -> Option<&str> {
+        let (_, zone_id) = bucket.strip_suffix(DIRECTORY_SUFFIX)?.rsplit_once("--")?;
+        (!zone_id.is_empty()).then_some(zone_id)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BucketPolicy {
+    pub versioning: bool,
+    pub lifecycle_rules: Vec<LifecycleRule>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LifecycleRule {
+    pub id: String,
+    pub prefix: String,
+    pub expiration_days: Option<i32>,
+    pub transition_days: Option<i32>,
+    pub transition_storage_class: Option<String>,
+}
+
+/// Connection reuse of the S3 client, unset fields keep the hyper defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Idle connections kept open per host, the rest are closed once released.
+    pub max_idle_per_host: Option<usize>,
+    /// How long an idle connection waits in the pool before being closed.
+    pub idle_timeout: Option<Duration>,
+    /// Interval of the TCP keep-alive probes, so middleboxes do not drop quiet connections.
+    pub tcp_keepalive: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub bucket: String,
+    pub mode: BucketMode,
+    /// Route transfers through the nearest edge location, for uploads from far away networks.
+    pub accelerate: bool,
+    /// Resolve endpoints answering over IPv6 as well as IPv4.
+    pub dual_stack: bool,
+    /// Send unsigned requests, enough to read a public bucket without any credentials.
+    pub anonymous: bool,
+    /// Connector carrying the requests, to go through a proxy or trust a private CA.
+    pub http_client: Option<SharedHttpClient>,
+    /// Picks the endpoint of each request instead of `S3_ENDPOINT` and the AWS rules.
+    pub endpoint_resolver: Option<SharedEndpointResolver>,
+    pub tls: Option<TlsConfig>,
+    pub pool: Option<PoolConfig>,
+    /// Writes the trace context of each call in its request headers.
+    #[cfg(feature = "tracing")]
+    pub propagator: Option<Arc<dyn Propagator>>,
+}
+
+impl S3Config {
+    #[inline]
+    #[must_use]
+    pub const fn new(bucket: String) -> Self {
+        Self {
+            bucket,
+            mode: BucketMode::General,
+            accelerate: false,
+            dual_stack: false,
+            anonymous: false,
+            http_client: None,
+            endpoint_resolver: None,
+            tls: None,
+            pool: None,
+            #[cfg(feature = "tracing")]
+            propagator: None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_mode(mut self, mode: BucketMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_accelerate(mut self, accelerate: bool) -> Self {
+        self.accelerate = accelerate;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_dual_stack(mut self, dual_stack: bool) -> Self {
+        self.dual_stack = dual_stack;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_anonymous(mut self, anonymous: bool) -> Self {
+        self.anonymous = anonymous;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_http_client<CLIENT>(mut self, http_client: CLIENT) -> Self
+    where
+        CLIENT: HttpClient + 'static,
+    {
+        self.http_client = Some(http_client.into_shared());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_endpoint_resolver<RESOLVER>(mut self, endpoint_resolver: RESOLVER) -> Self
+    where
+        RESOLVER: ResolveEndpoint + 'static,
+    {
+        self.endpoint_resolver = Some(endpoint_resolver.into_shared_resolver());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_pool(mut self, pool: PoolConfig) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    #[cfg(feature = "tracing")]
+    #[inline]
+    #[must_use]
+    pub fn with_propagator<PROPAGATOR>(mut self, propagator: PROPAGATOR) -> Self
+    where
+        PROPAGATOR: Propagator + 'static,
+    {
+        self.propagator = Some(Arc::new(propagator));
+        self
+    }
+
+    #[inline]
+    pub fn validate(&self) -> Result<(), S3Error> {
+        let bucket = &self.bucket;
+        if self.mode == BucketMode::Directory && BucketMode::zone_id(bucket).is_none() {
+            return Err(S3Error::EnvConfig(format!(
+                "directory bucket {bucket} must be named <base>--<zone-id>{DIRECTORY_SUFFIX}"
+            )));
+        }
+        if self.accelerate && self.mode == BucketMode::Directory {
+            return Err(S3Error::EnvConfig(format!(
+                "transfer acceleration is not available on directory bucket {bucket}"
+            )));
+        }
+        if self.anonymous && self.mode == BucketMode::Directory {
+            return Err(S3Error::EnvConfig(format!(
+                "directory bucket {bucket} needs credentials to open a session"
+            )));
+        }
+        if (self.tls.is_some() || self.pool.is_some()) && self.http_client.is_some() {
+            return Err(S3Error::EnvConfig(format!(
+                "TLS and pool settings of {bucket} only apply to the default HTTP client"
+            )));
+        }
+        if (self.accelerate || self.dual_stack) && self.endpoint_resolver.is_some() {
+            return Err(S3Error::EnvConfig(format!(
+                "a custom endpoint resolver for {bucket} replaces transfer acceleration and \
+                 dual-stack"
+            )));
+        }
+        // The accelerate endpoint is virtual hosted, a dot would break the bucket certificate.
+        if self.accelerate && bucket.contains('.') {
+            return Err(S3Error::EnvConfig(format!(
+                "transfer acceleration needs a bucket name without dots, got {bucket}"
+            )));
+        }
+        Ok(())
+    }
+
+    const fn uses_aws_endpoint(&self) -> bool {
+        self.accelerate || self.dual_stack || matches!(self.mode, BucketMode::Directory)
+    }
+}
+
+/// Extra request settings applied to every operation of a client made by
+/// [`S3::with_op_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpOptions {
+    /// Accept the transfer charges of a requester pays bucket.
+    pub request_payer: Option<RequestPayer>,
+    pub headers: Vec<(String, String)>,
+}
+
+impl OpOptions {
+    #[inline]
+    #[must_use]
+    pub fn requester_pays() -> Self {
+        Self {
+            request_payer: Some(RequestPayer::Requester),
+            headers: vec![],
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    fn request_headers(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.request_payer
+            .iter()
+            .map(|payer| ("x-amz-request-payer".to_owned(), payer.as_str().to_owned()))
+            .chain(self.headers.iter().cloned())
+    }
+}
+
+#[derive(Debug)]
+struct OpHeaders(OpOptions);
+
+impl Intercept for OpHeaders {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "NegentropyOpHeaders"
+    }
+
+    // Before signing so the extra headers are covered by the signature.
+    #[inline]
+    fn modify_before_signing(
+        &self,
+        context: &mut BeforeTransmitInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let headers = context.request_mut().headers_mut();
+        for (name, value) in self.0.request_headers() {
+            headers.try_insert(name, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tracing")]
+#[derive(Debug)]
+struct Propagated(Arc<dyn Propagator>);
+
+#[cfg(feature = "tracing")]
+impl Intercept for Propagated {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "NegentropyPropagated"
+    }
+
+    // The request is sent inside the span of `traced`, it is the current one here.
+    #[inline]
+    fn modify_before_signing(
+        &self,
+        context: &mut BeforeTransmitInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut propagated = vec![];
+        self.0.inject(&tracing::Span::current(), &mut propagated);
+        let headers = context.request_mut().headers_mut();
+        for (name, value) in propagated {
+            headers.try_insert(name, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeadCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+#[derive(Debug)]
+struct HeadCache {
+    ttl: Option<Duration>,
+    // `None` remembers a missing object, the common answer before a conditional put.
+    entries: HashMap<String, (Instant, Option<ObjectMeta>)>,
+    hits: u64,
+    misses: u64,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for HeadCache {
+    fn default() -> Self {
+        Self {
+            ttl: None,
+            entries: HashMap::default(),
+            hits: 0,
+            misses: 0,
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl HeadCache {
+    fn is_fresh(&self, cached_at: Instant, ttl: Duration) -> bool {
+        self.clock.now().saturating_duration_since(cached_at) < ttl
+    }
+
+    fn get(&mut self, key: &str) -> Option<Option<ObjectMeta>> {
+        let ttl = self.ttl?;
+        let cached = self
+            .entries
+            .get(key)
+            .filter(|&&(cached_at, _)| self.is_fresh(cached_at, ttl))
+            .map(|(_, meta)| meta.clone());
+        if cached.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        cached
+    }
+
+    fn insert(&mut self, key: &str, meta: Option<ObjectMeta>) {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+        let now = self.clock.now();
+        if self.entries.len() >= HEAD_CACHE_MAX {
+            self.entries
+                .retain(|_, &mut (cached_at, _)| now.saturating_duration_since(cached_at) < ttl);
+        }
+        if self.entries.len() < HEAD_CACHE_MAX {
+            self.entries.insert(key.to_owned(), (now, meta));
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Connection {
+    config: S3Config,
+    options: Vec<OpOptions>,
+    // The generation tells a request whether the client it failed with was already replaced.
+    client: RwLock<(u64, Client)>,
+    reconnections: AtomicU64,
+}
+
+impl Connection {
+    fn new(config: S3Config, options: Vec<OpOptions>, client: Client) -> Self {
+        Self {
+            config,
+            options,
+            client: RwLock::new((0, client)),
+            reconnections: AtomicU64::new(0),
+        }
+    }
+
+    fn current(&self) -> (u64, Client) {
+        self.client
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Rebuild the client from the credentials chain, unless a concurrent request already did.
+    async fn reconnect(&self, stale: u64) -> bool {
+        for attempt in 0..RECONNECT_ATTEMPTS {
+            if self.current().0 != stale {
+                return true;
+            }
+            match create_client(&self.config).await {
+                Ok(client) => {
+                    let mut current = self.client.write().unwrap_or_else(PoisonError::into_inner);
+                    if current.0 == stale {
+                        *current = (stale + 1, layered(client, &self.options));
+                        self.reconnections.fetch_add(1, Ordering::Relaxed);
+                    }
+                    return true;
+                }
+                Err(_) => tokio::time::sleep(reconnect_backoff(attempt)).await,
+            }
+        }
+        false
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct S3 {
-    inner: Client,
+    connection: Arc<Connection>,
     bucket: String,
+    mode: BucketMode,
+    costs: Arc<Mutex<CostReport>>,
+    heads: Arc<Mutex<HeadCache>>,
 }
 
 impl S3 {
     #[inline]
     pub async fn new(bucket: String) -> Result<Self, S3Error> {
+        Self::from_config(S3Config::new(bucket)).await
+    }
+
+    #[inline]
+    pub async fn from_config(config: S3Config) -> Result<Self, S3Error> {
+        config.validate()?;
+
+        let client = create_client(&config).await?;
         Ok(Self {
-            inner: create_client().await?,
-            bucket,
+            bucket: config.bucket.clone(),
+            mode: config.mode,
+            connection: Arc::new(Connection::new(config, vec![], client)),
+            costs: Arc::default(),
+            heads: Arc::default(),
+        })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_bucket(&self, bucket: &str) -> Self {
+        Self {
+            connection: Arc::clone(&self.connection),
+            bucket: bucket.to_owned(),
+            mode: self.mode,
+            costs: Arc::default(),
+            heads: Arc::new(Mutex::new({
+                let heads = self.heads();
+                HeadCache {
+                    ttl: heads.ttl,
+                    clock: Arc::clone(&heads.clock),
+                    ..HeadCache::default()
+                }
+            })),
+        }
+    }
+
+    /// A client on the same bucket sending `options` with each request, for a requester pays
+    /// read or the audit headers a gateway expects. Costs and cached HEADs stay shared.
+    #[inline]
+    #[must_use]
+    pub fn with_op_options(&self, options: OpOptions) -> Self {
+        let client = layered(self.client(), core::slice::from_ref(&options));
+        let mut layers = self.connection.options.clone();
+        layers.push(options);
+        Self {
+            connection: Arc::new(Connection::new(
+                self.connection.config.clone(),
+                layers,
+                client,
+            )),
+            bucket: self.bucket.clone(),
+            mode: self.mode,
+            costs: Arc::clone(&self.costs),
+            heads: Arc::clone(&self.heads),
+        }
+    }
+
+    /// Remember HEAD answers for `ttl`, so a loop of conditional puts or exists checks on the
+    /// same keys does not pay a request each time. Writes going through this client invalidate
+    /// their key, writes from other clients stay unseen until the entry expires.
+    #[inline]
+    #[must_use]
+    pub fn with_head_cache(self, ttl: Duration) -> Self {
+        self.heads().ttl = Some(ttl);
+        self
+    }
+
+    /// Clock aging the cached HEAD answers.
+    #[inline]
+    #[must_use]
+    pub fn with_clock<CLOCK>(self, clock: CLOCK) -> Self
+    where
+        CLOCK: Clock + 'static,
+    {
+        self.heads().clock = Arc::new(clock);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn mode(&self) -> BucketMode {
+        self.mode
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn head_cache_stats(&self) -> HeadCacheStats {
+        let heads = self.heads();
+        HeadCacheStats {
+            hits: heads.hits,
+            misses: heads.misses,
+            entries: heads.entries.len(),
+        }
+    }
+
+    /// How many times the client was rebuilt after its credentials expired.
+    #[inline]
+    #[must_use]
+    pub fn reconnections(&self) -> u64 {
+        self.connection.reconnections.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn cost_report(&self) -> CostReport {
+        self.costs().clone()
+    }
+
+    #[inline]
+    pub fn reset_cost_report(&self) -> CostReport {
+        core::mem::take(&mut *self.costs())
+    }
+
+    fn client(&self) -> Client {
+        self.connection.current().1
+    }
+
+    /// Send the request built by `request`, once more on a fresh client when the credentials
+    /// of the current one expired, an assumed role session otherwise fails until a restart.
+    #[expect(
+        clippy::result_large_err,
+        reason = "the SDK error is handed back as the SDK returns it"
+    )]
+    async fn send<OUTPUT, ERROR, REQUEST, FUTURE>(
+        &self,
+        method: &str,
+        key: &str,
+        request: REQUEST,
+    ) -> Result<OUTPUT, SdkError<ERROR, HttpResponse>>
+    where
+        REQUEST: Fn(Client) -> FUTURE,
+        FUTURE: Future<Output = Result<OUTPUT, SdkError<ERROR, HttpResponse>>>,
+        ERROR: ProvideErrorMetadata,
+    {
+        let (generation, client) = self.connection.current();
+        let sent = traced(method, &self.bucket, key, request(client)).await;
+        match sent {
+            Err(ref err) if is_expired(err) && self.connection.reconnect(generation).await => {
+                traced(method, &self.bucket, key, request(self.client())).await
+            }
+            sent => sent,
+        }
+    }
+
+    fn costs(&self) -> MutexGuard<'_, CostReport> {
+        self.costs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn heads(&self) -> MutexGuard<'_, HeadCache> {
+        self.heads.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn forget_head(&self, key: &str) {
+        self.heads().entries.remove(key);
+    }
+
+    fn refuse_lock(&self, key: &str) -> Result<(), S3Error> {
+        if self.mode == BucketMode::Directory {
+            return Err(S3Error::S3Object {
+                operation: "lock".to_owned(),
+                key: key.to_owned(),
+                internal: "object lock is not available on directory buckets".to_owned(),
+                request: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Directory buckets only list under a prefix ending with `/`, so a partial name lists its
+    /// parent directory and the keys are filtered afterwards.
+    fn list_prefix<'prefix>(&self, prefix: &'prefix str) -> &'prefix str {
+        match self.mode {
+            BucketMode::General => prefix,
+            BucketMode::Directory => prefix
+                .rfind('/')
+                .map_or("", |slash| prefix.get(..=slash).unwrap_or_default()),
+        }
+    }
+
+    fn record(&self, operation: Operation, key: &str, bytes: usize) {
+        self.costs()
+            .record(operation, key, u64::try_from(bytes).unwrap_or(u64::MAX));
+    }
+
+    #[inline]
+    pub async fn validate(&self) -> Result<(), S3Error> {
+        self.record(Operation::Head, "", 0);
+        self.send("HeadBucket", "", |client| {
+            client.head_bucket().bucket(&self.bucket).send()
+        })
+        .await
+        .map_err(|err| classify_error("validate_head_bucket", &self.bucket, &self.bucket, err))?;
+
+        let key = format!("{SELFCHECK_PREFIX}{}", Uuid::new_v4());
+        self.record(Operation::Put, &key, SELFCHECK_CONTENT.len());
+        self.send("PutObject", &key, |client| {
+            client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(&key)
+                .body(ByteStream::from_static(SELFCHECK_CONTENT))
+                .send()
+        })
+        .await
+        .map_err(|err| classify_error("validate_put", &self.bucket, &key, err))?;
+
+        self.record(Operation::Get, &key, SELFCHECK_CONTENT.len());
+        let object = self
+            .send("GetObject", &key, |client| {
+                client.get_object().bucket(&self.bucket).key(&key).send()
+            })
+            .await
+            .map_err(|err| classify_error("validate_get", &self.bucket, &key, err))?;
+        let content = object
+            .body
+            .collect()
+            .await
+            .map_err(|err| S3Error::S3Object {
+                operation: "validate_get".to_owned(),
+                key: key.clone(),
+                internal: err.to_string(),
+                request: None,
+            })?;
+
+        self.record(Operation::Delete, &key, 0);
+        self.send("DeleteObject", &key, |client| {
+            client.delete_object().bucket(&self.bucket).key(&key).send()
+        })
+        .await
+        .map_err(|err| classify_error("validate_delete", &self.bucket, &key, err))?;
+
+        if content.to_vec() == SELFCHECK_CONTENT {
+            Ok(())
+        } else {
+            Err(S3Error::S3Object {
+                operation: "validate_get".to_owned(),
+                key,
+                internal: "probe content mismatch".to_owned(),
+                request: None,
+            })
+        }
+    }
+
+    #[inline]
+    pub async fn ensure_bucket(
+        &self,
+        create_if_missing: bool,
+        policy: &BucketPolicy,
+    ) -> Result<bool, S3Error> {
+        self.record(Operation::Head, "", 0);
+        let created = match self
+            .send("HeadBucket", "", |client| {
+                client.head_bucket().bucket(&self.bucket).send()
+            })
+            .await
+        {
+            Ok(_) => false,
+            Err(err) => match classify_error("ensure_head_bucket", &self.bucket, &self.bucket, err)
+            {
+                S3Error::NotExistsBucket(_) if create_if_missing => {
+                    self.create_bucket().await?;
+                    true
+                }
+                other => return Err(other),
+            },
+        };
+
+        if policy.versioning && self.mode == BucketMode::Directory {
+            return Err(S3Error::S3Bucket {
+                operation: "ensure_versioning".to_owned(),
+                bucket: self.bucket.clone(),
+                internal: "versioning is not available on directory buckets".to_owned(),
+                request: None,
+            });
+        }
+        if policy.versioning {
+            self.record(Operation::Put, "", 0);
+            self.send("PutBucketVersioning", "", |client| {
+                client
+                    .put_bucket_versioning()
+                    .bucket(&self.bucket)
+                    .versioning_configuration(
+                        VersioningConfiguration::builder()
+                            .status(BucketVersioningStatus::Enabled)
+                            .build(),
+                    )
+                    .send()
+            })
+            .await
+            .map_err(|err| classify_error("ensure_versioning", &self.bucket, &self.bucket, err))?;
+        }
+
+        if !policy.lifecycle_rules.is_empty() {
+            let rules = policy
+                .lifecycle_rules
+                .iter()
+                .map(build_lifecycle_rule)
+                .collect::<Result<Vec<_>, _>>()?;
+            let lifecycle = BucketLifecycleConfiguration::builder()
+                .set_rules(Some(rules))
+                .build()
+                .map_err(|err| S3Error::S3Bucket {
+                    operation: "ensure_lifecycle".to_owned(),
+                    bucket: self.bucket.clone(),
+                    internal: err.to_string(),
+                    request: None,
+                })?;
+            self.record(Operation::Put, "", 0);
+            self.send("PutBucketLifecycleConfiguration", "", |client| {
+                client
+                    .put_bucket_lifecycle_configuration()
+                    .bucket(&self.bucket)
+                    .lifecycle_configuration(lifecycle.clone())
+                    .send()
+            })
+            .await
+            .map_err(|err| classify_error("ensure_lifecycle", &self.bucket, &self.bucket, err))?;
+        }
+
+        self.put_bytes_inner(
+            BUCKET_POLICY_KEY.to_owned(),
+            "application/toml".to_owned(),
+            policy_to_toml(policy).into_bytes(),
+        )
+        .await?;
+
+        Ok(created)
+    }
+
+    async fn create_bucket(&self) -> Result<(), S3Error> {
+        if let Some(zone_id) =
+            BucketMode::zone_id(&self.bucket).filter(|_| self.mode == BucketMode::Directory)
+        {
+            let configuration = CreateBucketConfiguration::builder()
+                .location(
+                    LocationInfo::builder()
+                        .r#type(LocationType::AvailabilityZone)
+                        .name(zone_id)
+                        .build(),
+                )
+                .bucket(
+                    BucketInfo::builder()
+                        .r#type(BucketType::Directory)
+                        .data_redundancy(DataRedundancy::SingleAvailabilityZone)
+                        .build(),
+                )
+                .build();
+            self.record(Operation::Put, "", 0);
+            self.send("CreateBucket", "", |client| {
+                client
+                    .create_bucket()
+                    .bucket(&self.bucket)
+                    .create_bucket_configuration(configuration.clone())
+                    .send()
+            })
+            .await
+            .map_err(|err| classify_error("ensure_create", &self.bucket, &self.bucket, err))?;
+            return Ok(());
+        }
+
+        let region = self
+            .client()
+            .config()
+            .region()
+            .map(ToString::to_string)
+            .filter(|region| region != "us-east-1");
+        let configuration = region.map(|location| {
+            CreateBucketConfiguration::builder()
+                .location_constraint(BucketLocationConstraint::from(location.as_str()))
+                .build()
+        });
+
+        self.record(Operation::Put, "", 0);
+        self.send("CreateBucket", "", |client| {
+            client
+                .create_bucket()
+                .bucket(&self.bucket)
+                .set_create_bucket_configuration(configuration.clone())
+                .send()
         })
+        .await
+        .map_err(|err| classify_error("ensure_create", &self.bucket, &self.bucket, err))?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn health_inner(&self) -> HealthReport {
+        let mut report = HealthReport::new("s3");
+        let start = Instant::now();
+        let head_bucket = self
+            .send("HeadBucket", "", |client| {
+                client.head_bucket().bucket(&self.bucket).send()
+            })
+            .await;
+        report.latency = Some(start.elapsed());
+        self.record(Operation::Head, "", 0);
+
+        match head_bucket {
+            Ok(_) => {
+                report.bucket_exists = Some(true);
+                report.credentials_valid = Some(true);
+            }
+            Err(err) => {
+                match err.raw_response().map(|raw| raw.status().as_u16()) {
+                    Some(404) => report.bucket_exists = Some(false),
+                    Some(401 | 403) => report.credentials_valid = Some(false),
+                    Some(_) => {}
+                    None => report.reachable = false,
+                }
+                report.error = Some(err.to_string());
+            }
+        }
+
+        report
     }
 
     pub(crate) async fn exists_inner(&self, key: String) -> Result<bool, S3Error> {
+        if let Some(cached) = self.heads().get(&key) {
+            return Ok(cached.is_some());
+        }
+
         let head_object = self
-            .inner
-            .head_object()
-            .bucket(&self.bucket)
-            .key(&key)
-            .send()
+            .send("HeadObject", &key, |client| {
+                client.head_object().bucket(&self.bucket).key(&key).send()
+            })
             .await;
+        self.record(Operation::Head, &key, 0);
 
         match head_object {
-            Ok(_) => Ok(true),
+            Ok(output) => {
+                self.heads().insert(&key, Some(head_meta(&output)));
+                Ok(true)
+            }
             Err(SdkError::ServiceError(err))
                 if matches!(err.err(), &HeadObjectError::NotFound(_)) =>
             {
+                self.heads().insert(&key, None);
                 Ok(false)
             }
             Err(err) => Err(S3Error::S3Exists {
                 operation: "exists".to_owned(),
                 key,
                 internal: err.to_string(),
+                request: request_ids(&err),
+            }),
+        }
+    }
+
+    async fn retained_until(&self, key: &str) -> Result<Option<SystemTime>, S3Error> {
+        // Object lock does not exist on directory buckets, skip the extra HEAD.
+        if self.mode == BucketMode::Directory {
+            return Ok(None);
+        }
+        let head_object = self
+            .send("HeadObject", key, |client| {
+                client.head_object().bucket(&self.bucket).key(key).send()
+            })
+            .await;
+        self.record(Operation::Head, key, 0);
+
+        match head_object {
+            Ok(output) => Ok(output
+                .object_lock_retain_until_date()
+                .and_then(|date| SystemTime::try_from(*date).ok())),
+            Err(SdkError::ServiceError(err))
+                if matches!(err.err(), &HeadObjectError::NotFound(_)) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(S3Error::S3Exists {
+                operation: "delete".to_owned(),
+                key: key.to_owned(),
+                internal: err.to_string(),
+                request: request_ids(&err),
             }),
         }
     }
 
+    pub(crate) async fn head_inner(&self, key: String) -> Result<Option<ObjectMeta>, S3Error> {
+        if let Some(cached) = self.heads().get(&key) {
+            return Ok(cached);
+        }
+
+        let head_object = self
+            .send("HeadObject", &key, |client| {
+                client.head_object().bucket(&self.bucket).key(&key).send()
+            })
+            .await;
+        self.record(Operation::Head, &key, 0);
+
+        let meta = match head_object {
+            Ok(output) => Some(head_meta(&output)),
+            Err(SdkError::ServiceError(err))
+                if matches!(err.err(), &HeadObjectError::NotFound(_)) =>
+            {
+                None
+            }
+            Err(err) => {
+                return Err(S3Error::S3Exists {
+                    operation: "head".to_owned(),
+                    key,
+                    internal: err.to_string(),
+                    request: request_ids(&err),
+                })
+            }
+        };
+        self.heads().insert(&key, meta.clone());
+        Ok(meta)
+    }
+
     pub(crate) async fn put_bytes_inner(
         &self,
         key: String,
         mime: String,
         value: Vec<u8>,
     ) -> Result<(), S3Error> {
-        self.inner
-            .put_object()
-            .bucket(&self.bucket)
-            .key(&key)
-            .body(ByteStream::from(value))
-            .set_content_type(Some(mime))
-            .send()
+        self.put_bytes_locked_inner(key, mime, value, None).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn put_bytes_meta_inner(
+        &self,
+        key: String,
+        mime: String,
+        value: Vec<u8>,
+    ) -> Result<ObjectMeta, S3Error> {
+        self.put_bytes_locked_inner(key, mime, value, None).await
+    }
+
+    pub(crate) async fn put_bytes_locked_inner(
+        &self,
+        key: String,
+        mime: String,
+        value: Vec<u8>,
+        lock: Option<ObjectLock>,
+    ) -> Result<ObjectMeta, S3Error> {
+        if lock.is_some() {
+            self.refuse_lock(&key)?;
+        }
+        self.forget_head(&key);
+        self.record(Operation::Put, &key, value.len());
+        let size = u64::try_from(value.len()).unwrap_or(u64::MAX);
+        let body = SdkBody::from(value);
+        let output = self
+            .send("PutObject", &key, |client| {
+                let mut put_object = client
+                    .put_object()
+                    .bucket(&self.bucket)
+                    .key(&key)
+                    .body(replayable(&body))
+                    .set_content_type(Some(mime.clone()));
+                if let Some(lock) = lock {
+                    put_object = put_object
+                        .checksum_algorithm(ChecksumAlgorithm::Sha256)
+                        .object_lock_mode(match lock.mode {
+                            LockMode::Governance => ObjectLockMode::Governance,
+                            LockMode::Compliance => ObjectLockMode::Compliance,
+                        })
+                        .object_lock_retain_until_date(DateTime::from(lock.retain_until));
+                }
+                put_object.send()
+            })
             .await
-            .map_err(|err| S3Error::S3Object {
-                operation: "put_bytes".to_owned(),
-                key,
-                internal: err.to_string(),
+            .map_err(|err| {
+                if is_throttled(&err) {
+                    S3Error::Throttled {
+                        operation: "put_bytes".to_owned(),
+                        key: key.clone(),
+                        request: request_ids(&err),
+                    }
+                } else {
+                    S3Error::S3Object {
+                        operation: "put_bytes".to_owned(),
+                        key: key.clone(),
+                        internal: err.to_string(),
+                        request: request_ids(&err),
+                    }
+                }
             })?;
 
+        Ok(ObjectMeta {
+            size,
+            etag: output.e_tag().map(|etag| etag.trim_matches('"').to_owned()),
+            checksum: output.checksum_sha256().and_then(checksum_from_base64),
+            mime: (!mime.is_empty()).then_some(mime),
+            last_modified: None,
+        })
+    }
+
+    pub(crate) async fn lock_inner(&self, key: String, lock: ObjectLock) -> Result<(), S3Error> {
+        self.refuse_lock(&key)?;
+        self.record(Operation::Put, &key, 0);
+        let retention = ObjectLockRetention::builder()
+            .mode(match lock.mode {
+                LockMode::Governance => ObjectLockRetentionMode::Governance,
+                LockMode::Compliance => ObjectLockRetentionMode::Compliance,
+            })
+            .retain_until_date(DateTime::from(lock.retain_until))
+            .build();
+        self.send("PutObjectRetention", &key, |client| {
+            client
+                .put_object_retention()
+                .bucket(&self.bucket)
+                .key(&key)
+                .retention(retention.clone())
+                .send()
+        })
+        .await
+        .map_err(|err| classify_error("lock", &self.bucket, &key, err))?;
+        self.forget_head(&key);
+
         Ok(())
     }
 
+    pub(crate) async fn delete_inner(&self, key: String) -> Result<(), S3Error> {
+        let retained_until = self.retained_until(&key).await?;
+        if let Some(until) = retained_until.filter(|&until| until > SystemTime::now()) {
+            return Err(S3Error::Locked { key, until });
+        }
+
+        self.forget_head(&key);
+        self.record(Operation::Delete, &key, 0);
+        self.send("DeleteObject", &key, |client| {
+            client.delete_object().bucket(&self.bucket).key(&key).send()
+        })
+        .await
+        .map_err(|err| classify_error("delete_object", &self.bucket, &key, err))?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn append_bytes_inner(
+        &self,
+        key: String,
+        value: Vec<u8>,
+    ) -> Result<(), S3Error> {
+        self.append_bytes_meta_inner(key, value).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn append_bytes_meta_inner(
+        &self,
+        key: String,
+        value: Vec<u8>,
+    ) -> Result<ObjectMeta, S3Error> {
+        let Some(meta) = self.head_inner(key.clone()).await? else {
+            return self.put_bytes_meta_inner(key, String::new(), value).await;
+        };
+        self.forget_head(&key);
+        let mime = meta.mime.unwrap_or_default();
+
+        if meta.size < MIN_PART_SIZE {
+            let mut content = self.get_bytes_inner(key.clone()).await?.unwrap_or_default();
+            content.extend(value);
+            return self.put_bytes_meta_inner(key, mime, content).await;
+        }
+
+        let size = meta
+            .size
+            .saturating_add(u64::try_from(value.len()).unwrap_or(u64::MAX));
+        self.record(Operation::Put, &key, value.len());
+        let upload = self
+            .send("CreateMultipartUpload", &key, |client| {
+                client
+                    .create_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(&key)
+                    .set_content_type(Some(mime.clone()))
+                    .send()
+            })
+            .await
+            .map_err(|err| append_error(&key, &err))?;
+        let upload_id = upload.upload_id().unwrap_or_default();
+
+        match self.append_parts(&key, upload_id, meta.size, value).await {
+            Ok(etag) => Ok(ObjectMeta {
+                size,
+                etag,
+                checksum: None,
+                mime: (!mime.is_empty()).then_some(mime),
+                last_modified: None,
+            }),
+            Err(err) => {
+                self.record(Operation::Delete, &key, 0);
+                let _aborted = self
+                    .send("AbortMultipartUpload", &key, |client| {
+                        client
+                            .abort_multipart_upload()
+                            .bucket(&self.bucket)
+                            .key(&key)
+                            .upload_id(upload_id)
+                            .send()
+                    })
+                    .await;
+                Err(err)
+            }
+        }
+    }
+
+    pub(crate) async fn touch_inner(&self, key: String) -> Result<bool, S3Error> {
+        let head_object = self
+            .send("HeadObject", &key, |client| {
+                client.head_object().bucket(&self.bucket).key(&key).send()
+            })
+            .await;
+        self.record(Operation::Head, &key, 0);
+        let head = match head_object {
+            Ok(output) => output,
+            Err(SdkError::ServiceError(err))
+                if matches!(err.err(), &HeadObjectError::NotFound(_)) =>
+            {
+                self.heads().insert(&key, None);
+                return Ok(false);
+            }
+            Err(err) => {
+                return Err(S3Error::S3Exists {
+                    operation: "touch".to_owned(),
+                    key,
+                    internal: err.to_string(),
+                    request: request_ids(&err),
+                })
+            }
+        };
+
+        // S3 refuses a copy onto itself unless the metadata is replaced, everything a replace
+        // would reset is carried over from the HEAD.
+        self.forget_head(&key);
+        self.record(Operation::Put, &key, 0);
+        self.send("CopyObject", &key, |client| {
+            client
+                .copy_object()
+                .bucket(&self.bucket)
+                .key(&key)
+                .copy_source(copy_source(&self.bucket, &key))
+                .metadata_directive(types::MetadataDirective::Replace)
+                .set_metadata(head.metadata().cloned())
+                .set_content_type(head.content_type().map(ToOwned::to_owned))
+                .set_content_encoding(head.content_encoding().map(ToOwned::to_owned))
+                .set_content_disposition(head.content_disposition().map(ToOwned::to_owned))
+                .set_content_language(head.content_language().map(ToOwned::to_owned))
+                .set_cache_control(head.cache_control().map(ToOwned::to_owned))
+                .set_storage_class(head.storage_class().cloned())
+                .set_server_side_encryption(head.server_side_encryption().cloned())
+                .set_ssekms_key_id(head.ssekms_key_id().map(ToOwned::to_owned))
+                .send()
+        })
+        .await
+        .map_err(|err| classify_error("touch", &self.bucket, &key, err))?;
+
+        Ok(true)
+    }
+
     pub(crate) async fn list_objects_inner(&self, prefix: &str) -> Result<ListKeyObjects, S3Error> {
+        collect_list_pages(prefix, |continuation| async move {
+            let list = self
+                .send("ListObjectsV2", prefix, |client| {
+                    client
+                        .list_objects_v2()
+                        .bucket(&self.bucket)
+                        .prefix(self.list_prefix(prefix))
+                        .set_delimiter(Some("/".to_owned()))
+                        .set_continuation_token(continuation.clone())
+                        .send()
+                })
+                .await;
+            self.record(Operation::List, prefix, 0);
+
+            list.map_err(|err| S3Error::S3List {
+                operation: "list_objects".to_owned(),
+                prefix: prefix.to_owned(),
+                internal: Some(err.to_string()),
+                request: request_ids(&err),
+            })
+        })
+        .await
+    }
+
+    pub(crate) async fn list_flat_inner(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<ListPage, S3Error> {
         let list = self
-            .inner
-            .list_objects_v2()
-            .bucket(&self.bucket)
-            .prefix(prefix)
-            .set_delimiter(Some("/".to_owned()))
-            .send()
+            .send("ListObjectsV2", prefix, |client| {
+                client
+                    .list_objects_v2()
+                    .bucket(&self.bucket)
+                    .prefix(self.list_prefix(prefix))
+                    .set_continuation_token(continuation.clone())
+                    .send()
+            })
             .await;
+        self.record(Operation::List, prefix, 0);
 
+        // Pages of a directory bucket are unordered and may come back empty once filtered, only
+        // a missing `next` ends the listing.
         match list {
-            Ok(list_output) => handle_list_objects(list_output),
+            Ok(list_output) => {
+                let mut page = handle_list_flat(list_output);
+                page.entries.retain(|entry| entry.key.starts_with(prefix));
+                Ok(page)
+            }
             Err(err) => Err(S3Error::S3List {
-                operation: "list_objects".to_owned(),
+                operation: "list_flat".to_owned(),
                 prefix: prefix.to_owned(),
                 internal: Some(err.to_string()),
+                request: request_ids(&err),
             }),
         }
     }
@@ -114,26 +1326,77 @@ impl S3 {
                 operation: "put_object".to_owned(),
                 key,
                 internal: err.to_string(),
+                request: None,
             }),
         }
     }
 
+    pub(crate) async fn get_bytes_inner(&self, key: String) -> Result<Option<Vec<u8>>, S3Error> {
+        self.get_object_inner(key, |content| Ok(content.to_vec()))
+            .await
+    }
+
+    pub(crate) async fn get_range_inner(
+        &self,
+        key: String,
+        range: &Range<u64>,
+    ) -> Result<Option<Vec<u8>>, S3Error> {
+        if range.is_empty() {
+            return Ok(self.head_inner(key).await?.map(|_| vec![]));
+        }
+
+        let header = format!("bytes={}-{}", range.start, range.end - 1);
+        match self
+            .fetch_inner(key.clone(), Some(header), |content| Ok(content.to_vec()))
+            .await
+        {
+            Err(S3Error::S3Object { ref internal, .. }) if internal.contains("InvalidRange") => {
+                Ok(self.head_inner(key).await?.map(|_| vec![]))
+            }
+            fetched => fetched,
+        }
+    }
+
     pub(crate) async fn get_object_inner<RETURN, PARSER>(
         &self,
         key: String,
         parser: PARSER,
     ) -> Result<Option<RETURN>, S3Error>
+    where
+        RETURN: ReturnWhere,
+        PARSER: DeserializeWhere<RETURN, S3Error>,
+    {
+        self.fetch_inner(key, None, parser).await
+    }
+
+    async fn fetch_inner<RETURN, PARSER>(
+        &self,
+        key: String,
+        range: Option<String>,
+        parser: PARSER,
+    ) -> Result<Option<RETURN>, S3Error>
     where
         RETURN: ReturnWhere,
         PARSER: DeserializeWhere<RETURN, S3Error>,
     {
         let object = self
-            .inner
-            .get_object()
-            .bucket(&self.bucket)
-            .key(&key)
-            .send()
+            .send("GetObject", &key, |client| {
+                client
+                    .get_object()
+                    .bucket(&self.bucket)
+                    .key(&key)
+                    .set_range(range.clone())
+                    .send()
+            })
             .await;
+        let bytes = object.as_ref().map_or(0, |object_output| {
+            object_output.content_length().unwrap_or_default()
+        });
+        self.record(
+            Operation::Get,
+            &key,
+            usize::try_from(bytes).unwrap_or_default(),
+        );
 
         match object {
             Ok(object_output) => parse_s3_object(object_output, key, parser).await,
@@ -142,27 +1405,388 @@ impl S3 {
             {
                 Ok(None)
             }
+            Err(err) if is_throttled(&err) => Err(S3Error::Throttled {
+                operation: "get_object".to_owned(),
+                key,
+                request: request_ids(&err),
+            }),
             Err(err) => Err(S3Error::S3Object {
                 operation: "get_object".to_owned(),
                 key,
                 internal: err.to_string(),
+                request: request_ids(&err),
             }),
         }
     }
+
+    async fn append_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        size: u64,
+        value: Vec<u8>,
+    ) -> Result<Option<String>, S3Error> {
+        let ranges = copy_ranges(size);
+        let mut parts = CompletedMultipartUpload::builder();
+        let mut part_number = 0;
+        for range in &ranges {
+            part_number += 1;
+            let copy_range =
+                (ranges.len() > 1).then(|| format!("bytes={}-{}", range.start, range.end - 1));
+            let copied = self
+                .send("UploadPartCopy", key, |client| {
+                    client
+                        .upload_part_copy()
+                        .bucket(&self.bucket)
+                        .key(key)
+                        .upload_id(upload_id)
+                        .part_number(part_number)
+                        .copy_source(copy_source(&self.bucket, key))
+                        .set_copy_source_range(copy_range.clone())
+                        .send()
+                })
+                .await
+                .map_err(|err| append_error(key, &err))?;
+            parts = parts.parts(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(
+                        copied
+                            .copy_part_result()
+                            .and_then(|part| part.e_tag())
+                            .map(ToOwned::to_owned),
+                    )
+                    .build(),
+            );
+        }
+
+        part_number += 1;
+        let body = SdkBody::from(value);
+        let uploaded = self
+            .send("UploadPart", key, |client| {
+                client
+                    .upload_part()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .upload_id(upload_id)
+                    .part_number(part_number)
+                    .body(replayable(&body))
+                    .send()
+            })
+            .await
+            .map_err(|err| append_error(key, &err))?;
+
+        let parts = parts
+            .parts(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(uploaded.e_tag().map(ToOwned::to_owned))
+                    .build(),
+            )
+            .build();
+        let completed = self
+            .send("CompleteMultipartUpload", key, |client| {
+                client
+                    .complete_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .upload_id(upload_id)
+                    .multipart_upload(parts.clone())
+                    .send()
+            })
+            .await
+            .map_err(|err| append_error(key, &err))?;
+
+        Ok(completed
+            .e_tag()
+            .map(|etag| etag.trim_matches('"').to_owned()))
+    }
+}
+
+#[cfg(feature = "tracing")]
+async fn traced<FUTURE>(method: &str, bucket: &str, key: &str, future: FUTURE) -> FUTURE::Output
+where
+    FUTURE: Future,
+{
+    use tracing::Instrument;
+
+    let span = tracing::info_span!(
+        "s3",
+        otel.name = format!("S3.{method}"),
+        otel.kind = "client",
+        rpc.system = "aws-api",
+        rpc.service = "S3",
+        rpc.method = method,
+        aws.s3.bucket = bucket,
+        aws.s3.key = key,
+    );
+    future.instrument(span).await
+}
+
+#[cfg(not(feature = "tracing"))]
+async fn traced<FUTURE>(_method: &str, _bucket: &str, _key: &str, future: FUTURE) -> FUTURE::Output
+where
+    FUTURE: Future,
+{
+    future.await
+}
+
+fn is_throttled<ERROR>(err: &SdkError<ERROR, HttpResponse>) -> bool
+where
+    ERROR: ProvideErrorMetadata,
+{
+    matches!(
+        err.code(),
+        Some("SlowDown" | "Throttling" | "ThrottlingException" | "RequestLimitExceeded")
+    ) || err.raw_response().map(|raw| raw.status().as_u16()) == Some(503)
+}
+
+fn is_expired<ERROR>(err: &SdkError<ERROR, HttpResponse>) -> bool
+where
+    ERROR: ProvideErrorMetadata,
+{
+    matches!(
+        err.code(),
+        Some("ExpiredToken" | "ExpiredTokenException" | "TokenRefreshRequired")
+    )
+}
+
+/// Exponential backoff with up to as much random jitter, so a fleet whose sessions expired
+/// together does not hit the credentials endpoint in lockstep.
+fn reconnect_backoff(attempt: u32) -> Duration {
+    let base = RECONNECT_BACKOFF.saturating_mul(1 << attempt.min(16));
+    let jitter = Uuid::new_v4().as_u128() % base.as_millis().max(1);
+    base + Duration::from_millis(u64::try_from(jitter).unwrap_or_default())
+}
+
+// An in memory body always clones, the request can be sent again after a reconnection.
+fn replayable(body: &SdkBody) -> ByteStream {
+    ByteStream::new(body.try_clone().unwrap_or_else(SdkBody::empty))
+}
+
+fn layered(client: Client, options: &[OpOptions]) -> Client {
+    if options.is_empty() {
+        return client;
+    }
+    let mut config = client.config().to_builder();
+    for option in options {
+        config = config.interceptor(OpHeaders(option.clone()));
+    }
+    Client::from_conf(config.build())
+}
+
+/// Byte ranges to copy an object of `size` bytes in parts S3 accepts. They are kept even so no
+/// part but the appended one ends under the minimum part size.
+fn copy_ranges(size: u64) -> Vec<Range<u64>> {
+    let count = size.div_ceil(MAX_PART_SIZE).max(1);
+    let part_size = size.div_ceil(count);
+    (0..count)
+        .map(|index| index * part_size..((index + 1) * part_size).min(size))
+        .collect()
+}
+
+fn copy_source(bucket: &str, key: &str) -> String {
+    format!("{bucket}/{}", utf8_percent_encode(key, COPY_SOURCE))
+}
+
+fn append_error<ERROR>(key: &str, err: &SdkError<ERROR, HttpResponse>) -> S3Error {
+    S3Error::S3Object {
+        operation: "append_bytes".to_owned(),
+        key: key.to_owned(),
+        internal: err.to_string(),
+        request: request_ids(err),
+    }
+}
+
+// Without a response, a timeout or a dispatch failure, AWS has nothing to trace.
+fn request_ids<ERROR>(err: &SdkError<ERROR, HttpResponse>) -> Option<Box<RequestIds>> {
+    let ids = RequestIds {
+        request_id: err.request_id().map(ToOwned::to_owned),
+        extended_request_id: err.extended_request_id().map(ToOwned::to_owned),
+    };
+    (ids != RequestIds::default()).then(|| Box::new(ids))
+}
+
+fn head_meta(output: &HeadObjectOutput) -> ObjectMeta {
+    ObjectMeta {
+        size: output
+            .content_length()
+            .and_then(|size| u64::try_from(size).ok())
+            .unwrap_or_default(),
+        etag: output.e_tag().map(|etag| etag.trim_matches('"').to_owned()),
+        checksum: output.checksum_sha256().and_then(checksum_from_base64),
+        mime: output.content_type().map(ToOwned::to_owned),
+        last_modified: output
+            .last_modified()
+            .and_then(|date| SystemTime::try_from(*date).ok()),
+    }
+}
+
+fn classify_error<ERROR>(
+    operation: &str,
+    bucket: &str,
+    key: &str,
+    err: SdkError<ERROR, HttpResponse>,
+) -> S3Error
+where
+    ERROR: ProvideErrorMetadata + Error + 'static,
+{
+    let status = err.raw_response().map(|raw| raw.status().as_u16());
+
+    if is_throttled(&err) {
+        return S3Error::Throttled {
+            operation: operation.to_owned(),
+            key: key.to_owned(),
+            request: request_ids(&err),
+        };
+    }
+
+    match (status, err.code()) {
+        (_, Some("NoSuchBucket")) => S3Error::NotExistsBucket(bucket.to_owned()),
+        (Some(404), _) if operation.ends_with("head_bucket") => {
+            S3Error::NotExistsBucket(bucket.to_owned())
+        }
+        (Some(401 | 403), _) | (_, Some("AccessDenied")) => S3Error::PermissionDenied {
+            operation: operation.to_owned(),
+            key: key.to_owned(),
+            request: request_ids(&err),
+        },
+        _ => S3Error::S3Bucket {
+            operation: operation.to_owned(),
+            bucket: bucket.to_owned(),
+            internal: err.to_string(),
+            request: request_ids(&err),
+        },
+    }
 }
 
-#[expect(clippy::single_call_fn, reason = "code readability")]
-fn handle_list_objects(list: ListObjectsV2Output) -> Result<ListKeyObjects, S3Error> {
-    list.contents
-        .map_or(Err(S3Error::S3ListHandle), |contents| {
-            Ok(contents
-                .into_iter()
-                .filter_map(|content| content.key)
-                .collect())
+fn build_lifecycle_rule(rule: &LifecycleRule) -> Result<types::LifecycleRule, S3Error> {
+    let expiration = rule
+        .expiration_days
+        .map(|days| LifecycleExpiration::builder().days(days).build());
+    let transition = rule.transition_days.map(|days| {
+        Transition::builder()
+            .days(days)
+            .set_storage_class(
+                rule.transition_storage_class
+                    .as_deref()
+                    .map(TransitionStorageClass::from),
+            )
+            .build()
+    });
+
+    types::LifecycleRule::builder()
+        .id(&rule.id)
+        .filter(LifecycleRuleFilter::Prefix(rule.prefix.clone()))
+        .status(ExpirationStatus::Enabled)
+        .set_expiration(expiration)
+        .set_transitions(transition.map(|value| vec![value]))
+        .build()
+        .map_err(|err| S3Error::S3Bucket {
+            operation: "ensure_lifecycle".to_owned(),
+            bucket: rule.id.clone(),
+            internal: err.to_string(),
+            request: None,
+        })
+}
+
+fn policy_to_toml(policy: &BucketPolicy) -> String {
+    let rules = policy
+        .lifecycle_rules
+        .iter()
+        .map(|rule| {
+            let mut table = Table::new();
+            table.insert("id".to_owned(), Value::String(rule.id.clone()));
+            table.insert("prefix".to_owned(), Value::String(rule.prefix.clone()));
+            if let Some(days) = rule.expiration_days {
+                table.insert("expiration_days".to_owned(), Value::Integer(days.into()));
+            }
+            if let Some(days) = rule.transition_days {
+                table.insert("transition_days".to_owned(), Value::Integer(days.into()));
+            }
+            if let Some(ref storage_class) = rule.transition_storage_class {
+                table.insert(
+                    "transition_storage_class".to_owned(),
+                    Value::String(storage_class.clone()),
+                );
+            }
+            Value::Table(table)
         })
+        .collect();
+
+    let mut table = Table::new();
+    table.insert("versioning".to_owned(), Value::Boolean(policy.versioning));
+    table.insert("lifecycle_rules".to_owned(), Value::Array(rules));
+    table.to_string()
+}
+
+#[cfg_attr(not(test), expect(clippy::single_call_fn, reason = "code readability"))]
+pub(crate) fn handle_list_objects(prefix: &str, list: ListObjectsV2Output) -> ListKeyObjects {
+    let keys = list
+        .contents
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|content| content.key);
+    let common_prefixes = list
+        .common_prefixes
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|common| common.prefix);
+
+    // The bucket may return names that no longer match once normalized, skip them.
+    keys.chain(common_prefixes)
+        .filter(|key| key.starts_with(prefix))
+        .collect()
+}
+
+/// A delimited listing stops at 1000 keys and prefixes, the pages are merged until the bucket
+/// stops handing out a continuation token.
+pub(crate) async fn collect_list_pages<FETCH, FUTURE>(
+    prefix: &str,
+    mut fetch: FETCH,
+) -> Result<ListKeyObjects, S3Error>
+where
+    FETCH: FnMut(Option<String>) -> FUTURE,
+    FUTURE: Future<Output = Result<ListObjectsV2Output, S3Error>>,
+{
+    let mut keys = ListKeyObjects::default();
+    let mut continuation = None;
+    loop {
+        let list = fetch(continuation.take()).await?;
+        continuation = list.next_continuation_token().map(ToOwned::to_owned);
+        keys.extend(handle_list_objects(prefix, list));
+        if continuation.is_none() {
+            return Ok(keys);
+        }
+    }
 }
 
 #[expect(clippy::single_call_fn, reason = "code readability")]
+fn handle_list_flat(list: ListObjectsV2Output) -> ListPage {
+    let entries = list
+        .contents
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|content| {
+            Some(ListEntry {
+                size: content
+                    .size
+                    .and_then(|size| u64::try_from(size).ok())
+                    .unwrap_or_default(),
+                key: content.key?,
+            })
+        })
+        .collect();
+    let next = if list.is_truncated.unwrap_or_default() {
+        list.next_continuation_token
+    } else {
+        None
+    };
+
+    ListPage { entries, next }
+}
+
+#[cfg_attr(not(test), expect(clippy::single_call_fn, reason = "code readability"))]
 async fn parse_s3_object<RETURN, PARSER>(
     object: GetObjectOutput,
     key: String,
@@ -172,19 +1796,17 @@ where
     RETURN: ReturnWhere,
     PARSER: DeserializeWhere<RETURN, S3Error>,
 {
-    if object.content_length().unwrap_or_default() == 0 {
-        Ok(None)
-    } else {
-        let try_decoding = object.body.collect().await;
+    // An empty object exists, only a `NoSuchKey` from the request means absent.
+    let try_decoding = object.body.collect().await;
 
-        match try_decoding {
-            Ok(content) => Ok(Some(parse_aggregated_bytes(content, parser)?)),
-            Err(err) => Err(S3Error::S3Object {
-                operation: "parse_s3_object".to_owned(),
-                key,
-                internal: err.to_string(),
-            }),
-        }
+    match try_decoding {
+        Ok(content) => Ok(Some(parse_aggregated_bytes(content, parser)?)),
+        Err(err) => Err(S3Error::S3Object {
+            operation: "parse_s3_object".to_owned(),
+            key,
+            internal: err.to_string(),
+            request: None,
+        }),
     }
 }
 
@@ -202,17 +1824,263 @@ where
 }
 
 #[expect(clippy::single_call_fn, reason = "code readability")]
-async fn create_client() -> Result<Client, S3Error> {
-    let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-    let config = Builder::from(&sdk_config)
-        .endpoint_url(
-            env::var("S3_ENDPOINT")
-                .map_err(|err| S3Error::EnvConfig(format!("S3_ENDPOINT {err}")))?,
-        )
+#[cfg(feature = "tls")]
+fn custom_http_client(tls: &TlsConfig, pool: PoolConfig) -> Result<SharedHttpClient, TlsError> {
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    http.set_keepalive(pool.tcp_keepalive);
+
+    let mut hyper_builder = hyper::Client::builder();
+    if let Some(max_idle) = pool.max_idle_per_host {
+        hyper_builder.pool_max_idle_per_host(max_idle);
+    }
+    if let Some(idle_timeout) = pool.idle_timeout {
+        hyper_builder.pool_idle_timeout(idle_timeout);
+    }
+
+    Ok(HyperClientBuilder::new()
+        .hyper_builder(hyper_builder)
+        .build(tls.wrap_connector(http)?))
+}
+
+#[cfg(not(feature = "tls"))]
+const fn custom_http_client(
+    _tls: &TlsConfig,
+    _pool: PoolConfig,
+) -> Result<SharedHttpClient, TlsError> {
+    Err(TlsError::Disabled)
+}
+
+async fn create_client(config: &S3Config) -> Result<Client, S3Error> {
+    let loader = aws_config::defaults(BehaviorVersion::latest());
+    let sdk_config = if config.anonymous {
+        loader.no_credentials().load().await
+    } else {
+        loader.load().await
+    };
+    let mut builder = Builder::from(&sdk_config)
         .region(Region::new(
             env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_owned()),
         ))
-        .force_path_style(true)
-        .build();
+        .accelerate(config.accelerate)
+        .use_dual_stack(config.dual_stack);
+    if let Some(ref http_client) = config.http_client {
+        builder.set_http_client(Some(http_client.clone()));
+    }
+    if config.tls.is_some() || config.pool.is_some() {
+        builder.set_http_client(Some(custom_http_client(
+            &config.tls.clone().unwrap_or_default(),
+            config.pool.unwrap_or_default(),
+        )?));
+    }
+    let endpoint = env::var("S3_ENDPOINT");
+    if let Some(ref resolver) = config.endpoint_resolver {
+        // The resolver still receives `S3_ENDPOINT` and the path style in its parameters.
+        builder.set_endpoint_resolver(Some(resolver.clone()));
+        builder.set_endpoint_url(endpoint.ok());
+        builder = builder.force_path_style(config.mode == BucketMode::General);
+    } else if config.uses_aws_endpoint() {
+        if (config.accelerate || config.dual_stack) && endpoint.is_ok() {
+            return Err(S3Error::EnvConfig(
+                "S3_ENDPOINT overrides transfer acceleration and dual-stack, unset one of them"
+                    .to_owned(),
+            ));
+        }
+        // Zonal endpoints are resolved from the bucket name and only answer virtual hosted
+        // requests signed with a session, `S3_ENDPOINT` stays an override for local emulators.
+        builder.set_endpoint_url(endpoint.ok());
+        builder = builder.disable_s3_express_session_auth(false);
+    } else {
+        builder = builder
+            .endpoint_url(endpoint.map_err(|err| S3Error::EnvConfig(format!("S3_ENDPOINT {err}")))?)
+            .force_path_style(true);
+    }
+    #[cfg(feature = "tracing")]
+    if let Some(ref propagator) = config.propagator {
+        builder = builder.interceptor(Propagated(Arc::clone(propagator)));
+    }
+    let config = builder.build();
     Ok(aws_sdk_s3::Client::from_conf(config))
 }
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_s3::config::endpoint::{Endpoint, EndpointFuture, Params};
+    use aws_sdk_s3::error::ErrorMetadata;
+
+    use super::*;
+    use crate::storage::clock::MockClock;
+
+    #[derive(Debug)]
+    struct StaticResolver;
+
+    impl ResolveEndpoint for StaticResolver {
+        fn resolve_endpoint<'params>(
+            &'params self,
+            _params: &'params Params,
+        ) -> EndpointFuture<'params> {
+            EndpointFuture::ready(Ok(Endpoint::builder()
+                .url("https://minio.internal")
+                .build()))
+        }
+    }
+
+    #[test]
+    fn detect_directory_buckets() {
+        assert_eq!(
+            BucketMode::from_bucket("logs--usw2-az1--x-s3"),
+            BucketMode::Directory
+        );
+        assert_eq!(BucketMode::from_bucket("logs"), BucketMode::General);
+        assert_eq!(
+            BucketMode::zone_id("my--logs--usw2-az1--x-s3"),
+            Some("usw2-az1")
+        );
+        assert_eq!(BucketMode::zone_id("logs--x-s3"), None);
+        assert_eq!(BucketMode::zone_id("logs----x-s3"), None);
+    }
+
+    #[test]
+    fn reconnect_backoff_grows() {
+        for attempt in 0..RECONNECT_ATTEMPTS {
+            let base = RECONNECT_BACKOFF * (1 << attempt);
+            let delay = reconnect_backoff(attempt);
+            assert!(delay >= base && delay < base * 2, "{attempt}: {delay:?}");
+        }
+    }
+
+    #[test]
+    fn head_cache_ages_with_the_clock() {
+        let clock = MockClock::new();
+        let mut heads = HeadCache {
+            ttl: Some(Duration::from_secs(10)),
+            clock: Arc::new(clock.clone()),
+            ..HeadCache::default()
+        };
+        heads.insert("gone", None);
+        assert_eq!(heads.get("gone"), Some(None));
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(heads.get("gone"), None);
+        assert_eq!((heads.hits, heads.misses), (1, 1));
+    }
+
+    #[test]
+    fn encode_copy_source() {
+        assert_eq!(
+            copy_source("uploads", "reports/2024 q1/\u{e9}t\u{e9}+draft.json"),
+            "uploads/reports/2024%20q1/%C3%A9t%C3%A9%2Bdraft.json"
+        );
+        assert_eq!(copy_source("uploads", "a-b_c.d~e"), "uploads/a-b_c.d~e");
+    }
+
+    #[test]
+    fn split_large_copy_sources() {
+        assert_eq!(copy_ranges(MIN_PART_SIZE), vec![0..MIN_PART_SIZE]);
+        assert_eq!(copy_ranges(MAX_PART_SIZE), vec![0..MAX_PART_SIZE]);
+
+        let size = 2 * MAX_PART_SIZE + 1;
+        let ranges = copy_ranges(size);
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges.first().map(|range| range.start), Some(0));
+        assert_eq!(ranges.last().map(|range| range.end), Some(size));
+        assert!(ranges.windows(2).all(|pair| pair[0].end == pair[1].start));
+        assert!(ranges.iter().all(|range| {
+            let len = range.end - range.start;
+            (MIN_PART_SIZE..=MAX_PART_SIZE).contains(&len)
+        }));
+    }
+
+    #[tokio::test]
+    async fn empty_object_exists() {
+        let object = GetObjectOutput::builder()
+            .content_length(0)
+            .body(ByteStream::from_static(b""))
+            .build();
+
+        let parsed = parse_s3_object(object, "empty".to_owned(), |content: &[u8]| {
+            Ok(content.to_vec())
+        })
+        .await
+        .unwrap();
+        assert_eq!(parsed, Some(vec![]));
+    }
+
+    #[test]
+    fn keep_request_ids() {
+        let mut raw = HttpResponse::new(500.try_into().unwrap(), SdkBody::empty());
+        raw.headers_mut()
+            .insert("x-amz-request-id", "4442587FB7D0A2F9");
+        raw.headers_mut()
+            .insert("x-amz-id-2", "vlR7PnpV2Ce81l0PRw6jlUpck7Jo5ZsQ");
+        let err = SdkError::service_error(
+            GetObjectError::generic(ErrorMetadata::builder().code("InternalError").build()),
+            raw,
+        );
+
+        let failed = classify_error("get_object", "uploads", "report.json", err);
+        assert_eq!(
+            failed.request_ids(),
+            Some(&RequestIds {
+                request_id: Some("4442587FB7D0A2F9".to_owned()),
+                extended_request_id: Some("vlR7PnpV2Ce81l0PRw6jlUpck7Jo5ZsQ".to_owned()),
+            })
+        );
+        assert_eq!(
+            append_error(
+                "report.json",
+                &SdkError::<GetObjectError, _>::timeout_error("slow")
+            )
+            .request_ids(),
+            None
+        );
+    }
+
+    #[test]
+    fn op_options_headers() {
+        let options = OpOptions::requester_pays().with_header("x-audit-id", "42");
+
+        assert_eq!(
+            options.request_headers().collect::<Vec<_>>(),
+            [
+                ("x-amz-request-payer".to_owned(), "requester".to_owned()),
+                ("x-audit-id".to_owned(), "42".to_owned()),
+            ]
+        );
+        assert_eq!(OpOptions::default().request_headers().count(), 0);
+    }
+
+    #[test]
+    fn refuse_incompatible_endpoints() {
+        let general = S3Config::new("uploads".to_owned());
+        assert!(general
+            .clone()
+            .with_accelerate(true)
+            .with_dual_stack(true)
+            .validate()
+            .is_ok());
+        assert!(general.clone().with_anonymous(true).validate().is_ok());
+        assert!(S3Config::new("uploads.example".to_owned())
+            .with_accelerate(true)
+            .validate()
+            .is_err());
+
+        let directory =
+            S3Config::new("uploads--usw2-az1--x-s3".to_owned()).with_mode(BucketMode::Directory);
+        assert!(directory.clone().with_dual_stack(true).validate().is_ok());
+        assert!(directory.clone().with_anonymous(true).validate().is_err());
+        assert!(directory.with_accelerate(true).validate().is_err());
+        assert!(general
+            .clone()
+            .with_endpoint_resolver(StaticResolver)
+            .validate()
+            .is_ok());
+        assert!(general
+            .clone()
+            .with_endpoint_resolver(StaticResolver)
+            .with_dual_stack(true)
+            .validate()
+            .is_err());
+        assert!(general.with_mode(BucketMode::Directory).validate().is_err());
+    }
+}
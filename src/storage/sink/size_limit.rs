@@ -0,0 +1,93 @@
+use core::num::NonZeroUsize;
+
+use crate::storage::layer::Layer;
+use crate::storage::sink::chunked::Chunked;
+use crate::storage::LayerError;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OversizePolicy {
+    #[default]
+    Reject,
+    Forward,
+}
+
+pub struct SizeLimit<STORAGE> {
+    limit: NonZeroUsize,
+    policy: OversizePolicy,
+    storage: STORAGE,
+}
+
+impl<STORAGE> SizeLimit<STORAGE>
+where
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub const fn new(limit: NonZeroUsize, storage: STORAGE) -> Self {
+        Self {
+            limit,
+            policy: OversizePolicy::Reject,
+            storage,
+        }
+    }
+
+    #[inline]
+    pub const fn chunking(limit: NonZeroUsize, storage: STORAGE) -> SizeLimit<Chunked<STORAGE>> {
+        SizeLimit {
+            limit,
+            policy: OversizePolicy::Forward,
+            storage: Chunked::new(limit, storage),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn limit(&self) -> NonZeroUsize {
+        self.limit
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn policy(&self) -> OversizePolicy {
+        self.policy
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn check_inner(&self, key: &str, size: u64) -> Result<(), LayerError> {
+        let limit = self.limit.get() as u64;
+        if self.policy == OversizePolicy::Reject && size > limit {
+            Err(LayerError::ObjectTooLarge {
+                key: key.to_owned(),
+                size,
+                limit,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<STORAGE> Layer for SizeLimit<STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
@@ -0,0 +1,79 @@
+use core::any::type_name;
+use core::time::Duration;
+
+use crate::storage::layer::Layer;
+use crate::storage::DKey;
+
+pub struct SlowOpLogger<STORAGE> {
+    threshold: Duration,
+    storage: STORAGE,
+}
+
+impl<STORAGE> SlowOpLogger<STORAGE>
+where
+    STORAGE: Send + Sync,
+{
+    #[inline]
+    pub const fn new(threshold: Duration, storage: STORAGE) -> Self {
+        Self { threshold, storage }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    pub(crate) const fn storage(&self) -> &STORAGE {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut STORAGE {
+        &mut self.storage
+    }
+
+    pub(crate) fn observe_inner<DKEY>(
+        &self,
+        operation: &str,
+        key: &DKEY,
+        size: Option<usize>,
+        elapsed: Duration,
+    ) -> bool
+    where
+        DKEY: DKey + ?Sized,
+    {
+        let slow = elapsed > self.threshold;
+
+        if slow {
+            tracing::warn!(
+                operation,
+                key = &*key.name(),
+                backend = type_name::<STORAGE>(),
+                duration = ?elapsed,
+                size,
+                "slow storage operation"
+            );
+        }
+
+        slow
+    }
+}
+
+impl<STORAGE> Layer for SlowOpLogger<STORAGE> {
+    type Inner = STORAGE;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
@@ -0,0 +1,244 @@
+use core::fmt;
+
+use crate::storage::layer::Layer;
+use crate::storage::meta::{ListEntry, ListPage};
+use crate::storage::sink::encrypted::{Encrypted, KeyRing};
+use crate::storage::{ListKeyObjects, TenantError};
+use crate::HashMap;
+
+pub(crate) const TENANT_ROOT: &str = "tenants/";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "copy",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct TenantId(String);
+
+impl TenantId {
+    #[inline]
+    pub fn new(id: &str) -> Result<Self, TenantError> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_');
+
+        if valid {
+            Ok(Self(id.to_owned()))
+        } else {
+            Err(TenantError::InvalidId(id.to_owned()))
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn prefix(&self) -> String {
+        self.prefix_in(TENANT_ROOT)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn prefix_in(&self, root: &str) -> String {
+        format!("{root}{}/", self.0)
+    }
+}
+
+impl fmt::Display for TenantId {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<String> for TenantId {
+    type Error = TenantError;
+
+    #[inline]
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(&value)
+    }
+}
+
+impl From<TenantId> for String {
+    #[inline]
+    fn from(value: TenantId) -> Self {
+        value.0
+    }
+}
+
+#[derive(Default, Clone)]
+struct TenantOptions {
+    bucket: Option<String>,
+    encryption_key: Option<[u8; 32]>,
+}
+
+pub struct TenantStore<FACTORY> {
+    key_prefix: String,
+    root: String,
+    options: HashMap<TenantId, TenantOptions>,
+    factory: FACTORY,
+}
+
+impl<SINK, FACTORY> TenantStore<FACTORY>
+where
+    FACTORY: Fn(Option<&str>) -> SINK,
+    SINK: Send + Sync,
+{
+    #[inline]
+    pub fn new(factory: FACTORY) -> Self {
+        Self {
+            key_prefix: String::new(),
+            root: TENANT_ROOT.to_owned(),
+            options: HashMap::default(),
+            factory,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_root(mut self, root: &str) -> Self {
+        root.clone_into(&mut self.root);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_key_prefix(mut self, key_prefix: &str) -> Self {
+        key_prefix.clone_into(&mut self.key_prefix);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_bucket(mut self, tenant: &TenantId, bucket: &str) -> Self {
+        self.options.entry(tenant.clone()).or_default().bucket = Some(bucket.to_owned());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_encryption_key(mut self, tenant: &TenantId, key: [u8; 32]) -> Self {
+        self.options
+            .entry(tenant.clone())
+            .or_default()
+            .encryption_key = Some(key);
+        self
+    }
+
+    /// The view of a tenant, every object it writes is sealed with the tenant's own key.
+    #[inline]
+    pub fn tenant(&self, id: &TenantId) -> Result<Tenant<Encrypted<SINK>>, TenantError> {
+        let options = self.options.get(id).cloned().unwrap_or_default();
+        let secret = options
+            .encryption_key
+            .ok_or_else(|| TenantError::MissingKey(id.to_string()))?;
+
+        Ok(Tenant {
+            id: id.clone(),
+            prefix: self.prefix(id),
+            storage: Encrypted::new(
+                KeyRing::new(id.as_str(), &secret),
+                (self.factory)(options.bucket.as_deref()),
+            ),
+        })
+    }
+
+    /// The view of a tenant without encryption, isolated by its prefix only.
+    #[inline]
+    pub fn plaintext_tenant(&self, id: &TenantId) -> Tenant<SINK> {
+        let options = self.options.get(id).cloned().unwrap_or_default();
+
+        Tenant {
+            id: id.clone(),
+            prefix: self.prefix(id),
+            storage: (self.factory)(options.bucket.as_deref()),
+        }
+    }
+
+    fn prefix(&self, id: &TenantId) -> String {
+        format!("{}{}", self.key_prefix, id.prefix_in(&self.root))
+    }
+}
+
+pub struct Tenant<SINK> {
+    id: TenantId,
+    prefix: String,
+    storage: SINK,
+}
+
+impl<SINK> Tenant<SINK>
+where
+    SINK: Send + Sync,
+{
+    #[inline]
+    #[must_use]
+    pub const fn id(&self) -> &TenantId {
+        &self.id
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub(crate) const fn storage(&self) -> &SINK {
+        &self.storage
+    }
+
+    pub(crate) fn storage_mut(&mut self) -> &mut SINK {
+        &mut self.storage
+    }
+
+    pub(crate) fn strip_inner(&self, objects: ListKeyObjects) -> ListKeyObjects {
+        objects
+            .into_iter()
+            .filter_map(|key| {
+                key.strip_prefix(self.prefix.as_str())
+                    .map(ToOwned::to_owned)
+            })
+            .collect()
+    }
+
+    pub(crate) fn strip_page_inner(&self, page: ListPage) -> ListPage {
+        ListPage {
+            entries: page
+                .entries
+                .into_iter()
+                .filter_map(|entry| {
+                    Some(ListEntry {
+                        key: entry.key.strip_prefix(self.prefix.as_str())?.to_owned(),
+                        ..entry
+                    })
+                })
+                .collect(),
+            ..page
+        }
+    }
+}
+
+impl<SINK> Layer for Tenant<SINK> {
+    type Inner = SINK;
+
+    #[inline]
+    fn inner(&self) -> &Self::Inner {
+        &self.storage
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        &mut self.storage
+    }
+
+    #[inline]
+    fn into_inner(self) -> Self::Inner {
+        self.storage
+    }
+}
@@ -0,0 +1,456 @@
+use core::fmt::Display;
+use core::future::Future;
+use core::time::Duration;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tokio::task::JoinHandle;
+use tokio::time::{timeout_at, Instant};
+use tokio_util::sync;
+
+use crate::storage::health::HealthReport;
+
+/// `tokio_util` token with a cancellable `sleep`, clones share the same cancellation.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    token: sync::CancellationToken,
+}
+
+impl CancellationToken {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    #[inline]
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + '_ {
+        self.token.cancelled()
+    }
+
+    #[inline]
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        tokio::select! {
+            () = tokio::time::sleep(duration) => !self.is_cancelled(),
+            () = self.cancelled() => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    pub drained: Vec<String>,
+    pub panicked: Vec<String>,
+    pub aborted: Vec<String>,
+}
+
+impl ShutdownReport {
+    #[inline]
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.panicked.is_empty() && self.aborted.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TaskSet {
+    token: CancellationToken,
+    tasks: Vec<(String, JoinHandle<()>)>,
+}
+
+impl TaskSet {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    #[inline]
+    pub fn spawn<TASK, FUTURE>(&mut self, name: &str, task: TASK)
+    where
+        TASK: FnOnce(CancellationToken) -> FUTURE,
+        FUTURE: Future<Output = ()> + Send + 'static,
+    {
+        let handle = tokio::spawn(task(self.token.clone()));
+        self.tasks.push((name.to_owned(), handle));
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.tasks.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    #[inline]
+    pub async fn shutdown(&mut self, grace: Duration) -> ShutdownReport {
+        self.token.cancel();
+        let deadline = Instant::now() + grace;
+        let mut report = ShutdownReport::default();
+
+        for (name, mut handle) in self.tasks.drain(..) {
+            match timeout_at(deadline, &mut handle).await {
+                Ok(Ok(())) => report.drained.push(name),
+                Ok(Err(_)) => report.panicked.push(name),
+                Err(_) => {
+                    handle.abort();
+                    report.aborted.push(name);
+                }
+            }
+        }
+
+        report
+    }
+}
+
+impl Drop for TaskSet {
+    #[inline]
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_restarts: Option<u32>,
+    /// A run lasting this long counts as healthy, its failure starts again from the initial
+    /// backoff with no restart spent.
+    pub healthy_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_restarts: None,
+            healthy_after: Duration::from_secs(300),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    Running,
+    Restarting,
+    Stopped,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskStatus {
+    pub state: TaskState,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+type StatusBoard = Arc<Mutex<BTreeMap<String, TaskStatus>>>;
+
+struct AbortOnDrop(JoinHandle<Result<(), String>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Restart the tasks of a `TaskSet` that fail, with a backoff, and report their state.
+#[derive(Debug, Default)]
+pub struct Supervisor {
+    tasks: TaskSet,
+    status: StatusBoard,
+}
+
+impl Supervisor {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn tasks(&self) -> &TaskSet {
+        &self.tasks
+    }
+
+    /// Spawn a task that is not restarted.
+    #[inline]
+    pub fn spawn<TASK, FUTURE>(&mut self, name: &str, task: TASK)
+    where
+        TASK: FnOnce(CancellationToken) -> FUTURE,
+        FUTURE: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.spawn(name, task);
+    }
+
+    #[inline]
+    pub fn supervise<TASK, FUTURE, ERROR>(&mut self, name: &str, policy: RestartPolicy, task: TASK)
+    where
+        TASK: Fn(CancellationToken) -> FUTURE + Send + 'static,
+        FUTURE: Future<Output = Result<(), ERROR>> + Send + 'static,
+        ERROR: Display,
+    {
+        let status = Arc::clone(&self.status);
+        let name = name.to_owned();
+        update(&status, &name, |task| task.state = TaskState::Running);
+
+        self.tasks.spawn(&name.clone(), move |token| async move {
+            let mut backoff = policy.initial_backoff;
+            loop {
+                let started = Instant::now();
+                let run = task(token.clone());
+                let mut attempt = AbortOnDrop(tokio::spawn(async move {
+                    run.await.map_err(|err| err.to_string())
+                }));
+                let error = match (&mut attempt.0).await {
+                    Ok(Ok(())) => None,
+                    Ok(Err(err)) => Some(err),
+                    Err(_) => Some("panicked".to_owned()),
+                };
+
+                let Some(error) = error.filter(|_| !token.is_cancelled()) else {
+                    update(&status, &name, |task| task.state = TaskState::Stopped);
+                    return;
+                };
+                let healthy = started.elapsed() >= policy.healthy_after;
+                if healthy {
+                    backoff = policy.initial_backoff;
+                }
+                let exhausted = update(&status, &name, |task| {
+                    task.last_error = Some(error);
+                    if healthy {
+                        task.restarts = 0;
+                    }
+                    if policy.max_restarts.is_some_and(|max| task.restarts >= max) {
+                        task.state = TaskState::Failed;
+                    } else {
+                        task.restarts += 1;
+                        task.state = TaskState::Restarting;
+                    }
+                    task.state == TaskState::Failed
+                });
+                if exhausted {
+                    return;
+                }
+                if !token.sleep(backoff).await {
+                    update(&status, &name, |task| task.state = TaskState::Stopped);
+                    return;
+                }
+
+                backoff = backoff.saturating_mul(2).min(policy.max_backoff);
+                update(&status, &name, |task| task.state = TaskState::Running);
+            }
+        });
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn status(&self, name: &str) -> Option<TaskStatus> {
+        self.status
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(name)
+            .cloned()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn health(&self) -> HealthReport {
+        let status = self.status.lock().unwrap_or_else(PoisonError::into_inner);
+        status
+            .iter()
+            .fold(HealthReport::new("supervisor"), |report, (name, task)| {
+                let mut inner = HealthReport::new(name);
+                inner.reachable = task.state != TaskState::Failed;
+                if task.state != TaskState::Running {
+                    inner.error.clone_from(&task.last_error);
+                }
+                report.with_inner(inner)
+            })
+    }
+
+    #[inline]
+    pub async fn shutdown(&mut self, grace: Duration) -> ShutdownReport {
+        self.tasks.shutdown(grace).await
+    }
+}
+
+fn update<RETURN>(
+    status: &StatusBoard,
+    name: &str,
+    change: impl FnOnce(&mut TaskStatus) -> RETURN,
+) -> RETURN {
+    let mut status = status.lock().unwrap_or_else(PoisonError::into_inner);
+    let task = status.entry(name.to_owned()).or_insert(TaskStatus {
+        state: TaskState::Running,
+        restarts: 0,
+        last_error: None,
+    });
+    change(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn cancel_wakes_waiters() {
+        let token = CancellationToken::new();
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.cancelled().await }
+        });
+        tokio::task::yield_now().await;
+
+        assert!(!token.is_cancelled());
+        token.cancel();
+        waiter.await.unwrap();
+        assert!(!token.sleep(Duration::from_secs(60)).await);
+    }
+
+    #[tokio::test]
+    async fn shutdown_drains_in_order() {
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let mut tasks = TaskSet::new();
+        tasks.spawn("ticker", |token| {
+            let ticks = Arc::clone(&ticks);
+            async move {
+                while token.sleep(Duration::from_millis(1)).await {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        });
+        tasks.spawn("stubborn", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+        tasks.spawn("crashing", |_| async { panic!("task failed") });
+        assert_eq!(tasks.names(), ["ticker", "stubborn", "crashing"]);
+
+        let report = tasks.shutdown(Duration::from_millis(20)).await;
+
+        assert_eq!(report.drained, ["ticker"]);
+        assert_eq!(report.aborted, ["stubborn"]);
+        assert_eq!(report.panicked, ["crashing"]);
+        assert!(!report.is_clean());
+        assert!(tasks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn supervisor_restarts_with_backoff() {
+        let attempts = Arc::new(Mutex::new(0_u32));
+        let mut supervisor = Supervisor::new();
+        let policy = RestartPolicy {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+            max_restarts: Some(2),
+            healthy_after: Duration::from_secs(60),
+        };
+        supervisor.supervise("gc", policy, {
+            let attempts = Arc::clone(&attempts);
+            move |_| {
+                let attempts = Arc::clone(&attempts);
+                async move {
+                    *attempts.lock().unwrap() += 1;
+                    Err::<(), _>("bucket unreachable")
+                }
+            }
+        });
+        supervisor.supervise("heartbeat", policy, |token| async move {
+            token.cancelled().await;
+            Ok::<(), String>(())
+        });
+
+        while supervisor.status("gc").unwrap().state != TaskState::Failed {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(*attempts.lock().unwrap(), 3);
+        assert_eq!(supervisor.status("gc").unwrap().restarts, 2);
+        let health = supervisor.health();
+        assert!(!health.is_healthy());
+        assert_eq!(
+            health.inner.first().and_then(|gc| gc.error.as_deref()),
+            Some("bucket unreachable")
+        );
+
+        let report = supervisor.shutdown(Duration::from_secs(1)).await;
+        assert_eq!(report.drained, ["gc", "heartbeat"]);
+        assert_eq!(
+            supervisor.status("heartbeat").unwrap().state,
+            TaskState::Stopped
+        );
+    }
+
+    #[tokio::test]
+    async fn supervisor_forgets_restarts_after_a_healthy_run() {
+        let attempts = Arc::new(Mutex::new(0_u32));
+        let mut supervisor = Supervisor::new();
+        let policy = RestartPolicy {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::MAX,
+            max_restarts: Some(1),
+            healthy_after: Duration::from_millis(5),
+        };
+        supervisor.supervise("sync", policy, {
+            let attempts = Arc::clone(&attempts);
+            move |_| {
+                let attempts = Arc::clone(&attempts);
+                async move {
+                    let attempt = {
+                        let mut attempts = attempts.lock().unwrap();
+                        *attempts += 1;
+                        *attempts
+                    };
+                    if attempt <= 3 {
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                    }
+                    Err::<(), _>("connection reset")
+                }
+            }
+        });
+
+        while supervisor.status("sync").unwrap().state != TaskState::Failed {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(
+            *attempts.lock().unwrap(),
+            4,
+            "each healthy run resets the restarts, the quick ones then exhaust them"
+        );
+        assert_eq!(supervisor.status("sync").unwrap().restarts, 1);
+        supervisor.shutdown(Duration::from_secs(1)).await;
+    }
+}
@@ -0,0 +1,17 @@
+use core::fmt::Debug;
+
+use tracing::Span;
+
+/// Writes the trace context of `span` as request headers, called on every outbound request of
+/// the S3 and HTTP sinks it is given to.
+pub trait Propagator: Debug + Send + Sync {
+    fn inject(&self, span: &Span, headers: &mut Vec<(String, String)>);
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopPropagator;
+
+impl Propagator for NoopPropagator {
+    #[inline]
+    fn inject(&self, _span: &Span, _headers: &mut Vec<(String, String)>) {}
+}
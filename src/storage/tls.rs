@@ -0,0 +1,177 @@
+#[cfg(feature = "tls")]
+use std::fs;
+#[cfg(feature = "tls")]
+use std::io::BufReader;
+#[cfg(feature = "tls")]
+use std::path::Path;
+use std::path::PathBuf;
+
+#[cfg(feature = "tls")]
+use hyper::client::HttpConnector;
+#[cfg(feature = "tls")]
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+#[cfg(feature = "tls")]
+use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore};
+#[cfg(feature = "tls")]
+use rustls_pemfile::Item;
+#[cfg(feature = "copy")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "tls")]
+use crate::storage::TlsError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "copy", derive(Serialize, Deserialize))]
+pub struct ClientIdentity {
+    /// PEM chain presented to the server, leaf first.
+    pub certificate: PathBuf,
+    /// PEM private key of the leaf, PKCS#8, PKCS#1 or SEC1.
+    pub private_key: PathBuf,
+}
+
+/// TLS settings of the remote backends, for endpoints signed by a private PKI.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "copy", derive(Serialize, Deserialize))]
+pub struct TlsConfig {
+    /// PEM files of authorities trusted on top of the system roots.
+    #[cfg_attr(feature = "copy", serde(default))]
+    pub extra_roots: Vec<PathBuf>,
+    /// Identity sent to endpoints asking for mutual TLS.
+    #[cfg_attr(feature = "copy", serde(default))]
+    pub identity: Option<ClientIdentity>,
+}
+
+#[cfg(feature = "tls")]
+impl TlsConfig {
+    #[inline]
+    pub fn client_config(&self) -> Result<ClientConfig, TlsError> {
+        let mut roots = RootCertStore::empty();
+        // A host without a system store still works when the extra roots are enough.
+        for native in rustls_native_certs::load_native_certs().unwrap_or_default() {
+            let _skipped = roots.add(&Certificate(native.0));
+        }
+        for path in &self.extra_roots {
+            for certificate in read_certificates(path)? {
+                roots
+                    .add(&certificate)
+                    .map_err(|err| TlsError::Certificate {
+                        path: path.clone(),
+                        internal: err.to_string(),
+                    })?;
+            }
+        }
+        if roots.is_empty() {
+            return Err(TlsError::Config("no trusted root certificate".to_owned()));
+        }
+
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+        match self.identity {
+            Some(ref identity) => builder
+                .with_client_auth_cert(
+                    read_certificates(&identity.certificate)?,
+                    read_private_key(&identity.private_key)?,
+                )
+                .map_err(|err| TlsError::Certificate {
+                    path: identity.certificate.clone(),
+                    internal: err.to_string(),
+                }),
+            None => Ok(builder.with_no_client_auth()),
+        }
+    }
+
+    #[inline]
+    pub fn https_connector(&self) -> Result<HttpsConnector<HttpConnector>, TlsError> {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        self.wrap_connector(http)
+    }
+
+    #[inline]
+    pub fn wrap_connector(
+        &self,
+        http: HttpConnector,
+    ) -> Result<HttpsConnector<HttpConnector>, TlsError> {
+        Ok(HttpsConnectorBuilder::new()
+            .with_tls_config(self.client_config()?)
+            .https_or_http()
+            .enable_http1()
+            .wrap_connector(http))
+    }
+}
+
+#[cfg(feature = "tls")]
+fn read_items(path: &Path) -> Result<Vec<Item>, TlsError> {
+    let content = fs::read(path).map_err(|err| TlsError::Read {
+        path: path.to_owned(),
+        internal: err.to_string(),
+    })?;
+    rustls_pemfile::read_all(&mut BufReader::new(content.as_slice())).map_err(|err| {
+        TlsError::Certificate {
+            path: path.to_owned(),
+            internal: err.to_string(),
+        }
+    })
+}
+
+#[cfg(feature = "tls")]
+fn read_certificates(path: &Path) -> Result<Vec<Certificate>, TlsError> {
+    let certificates = read_items(path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::X509Certificate(der) => Some(Certificate(der)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if certificates.is_empty() {
+        return Err(TlsError::Certificate {
+            path: path.to_owned(),
+            internal: "no PEM certificate".to_owned(),
+        });
+    }
+    Ok(certificates)
+}
+
+#[cfg(feature = "tls")]
+fn read_private_key(path: &Path) -> Result<PrivateKey, TlsError> {
+    read_items(path)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(der) | Item::RSAKey(der) | Item::ECKey(der) => Some(PrivateKey(der)),
+            _ => None,
+        })
+        .ok_or_else(|| TlsError::Certificate {
+            path: path.to_owned(),
+            internal: "no PEM private key".to_owned(),
+        })
+}
+
+#[cfg(all(test, feature = "tls"))]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn refuse_unreadable_roots() {
+        let missing = TlsConfig {
+            extra_roots: vec![PathBuf::from("/nonexistent/negentropy-ca.pem")],
+            identity: None,
+        };
+        assert!(matches!(
+            missing.client_config(),
+            Err(TlsError::Read { .. })
+        ));
+
+        let path = env::temp_dir().join(format!("negentropy-tls-{}.pem", std::process::id()));
+        fs::write(&path, "not a certificate\n").unwrap();
+        let garbage = TlsConfig {
+            extra_roots: vec![path.clone()],
+            identity: None,
+        };
+        let result = garbage.client_config();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(TlsError::Certificate { .. })));
+    }
+}